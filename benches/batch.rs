@@ -0,0 +1,26 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use enr::{batch::build_many, k256::ecdsa::SigningKey, Enr};
+use std::net::Ipv4Addr;
+
+fn batch(c: &mut Criterion) {
+    let keys: Vec<_> = (0..256)
+        .map(|_| SigningKey::random(&mut rand::thread_rng()))
+        .collect();
+    let mut template = Enr::builder();
+    template.ip4(Ipv4Addr::LOCALHOST).udp4(30303).tcp4(30303);
+
+    c.bench_function("build sequential", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(template.clone().build(key).unwrap());
+            }
+        })
+    });
+
+    c.bench_function("build_many", |b| {
+        b.iter(|| black_box(build_many(&keys, &template)))
+    });
+}
+
+criterion_group!(benches, batch);
+criterion_main!(benches);
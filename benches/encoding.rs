@@ -0,0 +1,53 @@
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use enr::{k256::ecdsa::SigningKey, Enr};
+use std::net::Ipv4Addr;
+
+fn records(count: usize) -> Vec<Enr<SigningKey>> {
+    (0..count)
+        .map(|_| {
+            let key = SigningKey::random(&mut rand::thread_rng());
+            Enr::builder()
+                .ip4(Ipv4Addr::LOCALHOST)
+                .udp4(30303)
+                .tcp4(30303)
+                .add_value("eth2", &vec![0_u8; 16])
+                .build(&key)
+                .unwrap()
+        })
+        .collect()
+}
+
+fn encoding(c: &mut Criterion) {
+    let records = records(16);
+
+    c.bench_function("rlp::encode", |b| {
+        b.iter(|| {
+            for enr in &records {
+                black_box(rlp::encode(enr));
+            }
+        })
+    });
+
+    c.bench_function("encode_to", |b| {
+        let mut buffer = BytesMut::new();
+        b.iter(|| {
+            buffer.clear();
+            for enr in &records {
+                enr.encode_to(&mut buffer);
+            }
+            black_box(&buffer);
+        })
+    });
+
+    c.bench_function("size", |b| {
+        b.iter(|| {
+            for enr in &records {
+                black_box(enr.size());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, encoding);
+criterion_main!(benches);
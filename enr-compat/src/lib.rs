@@ -0,0 +1,28 @@
+//! Read access to Ethereum Node Records, independent of the version of the `enr` crate.
+//!
+//! Applications may depend on two versions of `enr` at once, for example directly and through
+//! `discv5`. A trait defined in `enr` itself would be a distinct trait in each version, so code
+//! written against one version could not accept the records of the other. [`RecordView`] lives in
+//! this crate instead, which only uses `std` types and does not change with `enr`, so that every
+//! version of `enr` implementing it implements the same trait.
+//!
+//! With its `bridge` feature, `enr` implements [`RecordView`] for its records and turns any
+//! [`RecordView`] back into its own record type, verifying the signature.
+
+/// Read access to a record, independent of the version of the crate that implements it.
+pub trait RecordView {
+    /// The sequence number of the record.
+    fn seq(&self) -> u64;
+
+    /// The raw node id of the record.
+    fn node_id(&self) -> [u8; 32];
+
+    /// The raw RLP encoded value stored under `key`.
+    fn get_raw_rlp(&self, key: &[u8]) -> Option<&[u8]>;
+
+    /// The keys of the record, in ascending order.
+    fn keys(&self) -> Vec<&[u8]>;
+
+    /// The RLP encoding of the record, including its signature.
+    fn to_rlp(&self) -> Vec<u8>;
+}
@@ -0,0 +1,191 @@
+//! The RLP codec of records, shared by [`Enr`](crate::Enr) and the record types of the [`enr`]
+//! crate.
+//!
+//! A record is encoded as the RLP list `[signature, seq, k, v, ...]` of at most [`MAX_ENR_SIZE`]
+//! bytes, with its keys unique and sorted byte-wise. Values are stored as their raw RLP encoding.
+//! The identity scheme signs the content of the record: the same list without the signature.
+//!
+//! [`enr`]: https://docs.rs/enr
+
+use crate::KeyScheme;
+use bytes::BufMut;
+use rlp::{DecoderError, Rlp};
+
+/// The maximum size of an encoded record, in bytes.
+pub const MAX_ENR_SIZE: usize = 300;
+
+/// The first byte of the RLP header of a byte string.
+pub const RLP_STRING_OFFSET: u8 = 0x80;
+/// The first byte of the RLP header of a list.
+pub const RLP_LIST_OFFSET: u8 = 0xc0;
+
+/// The keys of the specification holding a port, encoded as a big-endian `u16`.
+const PORT_KEYS: [&[u8]; 4] = [b"tcp", b"tcp6", b"udp", b"udp6"];
+
+/// Decodes the structure of an RLP encoded record, returning its signature and sequence number.
+///
+/// This checks the size of the record, that it is a list without trailing data holding an even
+/// number of items and that its keys are sorted, and calls `field` with each key and its value in
+/// the order they are encoded. It neither checks the values, see [`check_value`], nor verifies the
+/// signature.
+pub fn decode_fields<'a>(
+    rlp: &Rlp<'a>,
+    field: impl FnMut(&'a [u8], &Rlp<'a>) -> Result<(), DecoderError>,
+) -> Result<(&'a [u8], u64), DecoderError> {
+    if rlp.as_raw().len() > MAX_ENR_SIZE {
+        return Err(DecoderError::Custom("enr exceeds max size"));
+    }
+    decode_fields_unbounded(rlp, field)
+}
+
+/// Decodes the structure of an RLP encoded record like [`decode_fields`], without checking its
+/// size, for reading records of implementations that did not enforce the limit.
+// `usize::is_multiple_of` would raise the minimum supported Rust version
+#[allow(clippy::manual_is_multiple_of)]
+pub fn decode_fields_unbounded<'a>(
+    rlp: &Rlp<'a>,
+    mut field: impl FnMut(&'a [u8], &Rlp<'a>) -> Result<(), DecoderError>,
+) -> Result<(&'a [u8], u64), DecoderError> {
+    if !rlp.is_list() {
+        return Err(DecoderError::RlpExpectedToBeList);
+    }
+
+    // verify there is no extra data
+    let payload_info = rlp.payload_info()?;
+    if rlp.as_raw().len() != payload_info.header_len + payload_info.value_len {
+        return Err(DecoderError::RlpInconsistentLengthAndData);
+    }
+
+    let mut rlp_iter = rlp.iter();
+
+    if rlp_iter.len() == 0 || rlp_iter.len() % 2 != 0 {
+        return Err(DecoderError::Custom("List not a multiple of two"));
+    }
+
+    let signature = rlp_iter
+        .next()
+        .ok_or(DecoderError::Custom("List is empty"))?
+        .data()?;
+    let seq = rlp_iter
+        .next()
+        .ok_or(DecoderError::Custom("List has only one item"))?
+        .as_val()?;
+
+    let mut prev: Option<&[u8]> = None;
+    while let Some(key) = rlp_iter.next() {
+        let key = key.data()?;
+        let item = rlp_iter
+            .next()
+            .ok_or(DecoderError::Custom("List not a multiple of 2"))?;
+        field(key, &item)?;
+
+        if prev.is_some() && prev >= Some(key) {
+            return Err(DecoderError::Custom("Unsorted keys"));
+        }
+        prev = Some(key);
+    }
+
+    Ok((signature, seq))
+}
+
+/// Checks that the RLP encoded value stored under `key` is well-formed: a port for the port keys of
+/// the specification, a public key of the right length for the fields of a [`KeyScheme`] and a
+/// byte string otherwise.
+pub fn check_value(key: &[u8], item: &Rlp) -> Result<(), DecoderError> {
+    if PORT_KEYS.contains(&key) {
+        item.as_val::<u16>()?;
+    } else if let Some(scheme) = KeyScheme::from_enr_key(key) {
+        scheme.check_len(item.data()?)?;
+    } else {
+        item.data()?;
+    }
+    Ok(())
+}
+
+/// The length of the payload of the RLP list of a record, with the signature if one is given.
+pub fn fields_len<'a>(
+    signature: Option<&[u8]>,
+    seq: u64,
+    content: impl IntoIterator<Item = (&'a [u8], &'a [u8])>,
+) -> usize {
+    let content_len: usize = content
+        .into_iter()
+        .map(|(key, value)| rlp_bytes_len(key) + value.len())
+        .sum();
+    let seq_bytes = seq.to_be_bytes();
+    signature.map_or(0, rlp_bytes_len) + rlp_bytes_len(minimal_be_bytes(&seq_bytes)) + content_len
+}
+
+/// Writes the RLP list of a record: the encoded record if a signature is given, its signed content
+/// otherwise. `content` yields the keys in order, with their raw RLP encoded values.
+pub fn encode_fields<'a, I>(
+    buffer: &mut impl BufMut,
+    signature: Option<&[u8]>,
+    seq: u64,
+    content: I,
+) where
+    I: IntoIterator<Item = (&'a [u8], &'a [u8])> + Clone,
+{
+    put_rlp_header(
+        buffer,
+        RLP_LIST_OFFSET,
+        fields_len(signature, seq, content.clone()),
+    );
+    if let Some(signature) = signature {
+        put_rlp_bytes(buffer, signature);
+    }
+    put_rlp_bytes(buffer, minimal_be_bytes(&seq.to_be_bytes()));
+    for (key, value) in content {
+        put_rlp_bytes(buffer, key);
+        // values are raw RLP encoded data
+        buffer.put_slice(value);
+    }
+}
+
+/// The length of the RLP header of a string or list with a payload of `payload_len` bytes.
+#[must_use]
+pub const fn rlp_header_len(payload_len: usize) -> usize {
+    if payload_len < 56 {
+        1
+    } else {
+        1 + (usize::BITS - payload_len.leading_zeros()).div_ceil(8) as usize
+    }
+}
+
+/// The length of the RLP encoding of a byte string.
+#[must_use]
+pub const fn rlp_bytes_len(bytes: &[u8]) -> usize {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        1
+    } else {
+        rlp_header_len(bytes.len()) + bytes.len()
+    }
+}
+
+/// Writes the RLP header of a string or list, depending on `offset`, with a payload of
+/// `payload_len` bytes.
+pub fn put_rlp_header(buffer: &mut impl BufMut, offset: u8, payload_len: usize) {
+    if payload_len < 56 {
+        buffer.put_u8(offset + payload_len as u8);
+    } else {
+        let len_bytes = payload_len.to_be_bytes();
+        let len_bytes = minimal_be_bytes(&len_bytes);
+        buffer.put_u8(offset + 55 + len_bytes.len() as u8);
+        buffer.put_slice(len_bytes);
+    }
+}
+
+/// Writes the RLP encoding of a byte string.
+pub fn put_rlp_bytes(buffer: &mut impl BufMut, bytes: &[u8]) {
+    if !(bytes.len() == 1 && bytes[0] < 0x80) {
+        put_rlp_header(buffer, RLP_STRING_OFFSET, bytes.len());
+    }
+    buffer.put_slice(bytes);
+}
+
+/// Strips the leading zero bytes of a big-endian integer, as required by RLP.
+#[must_use]
+pub fn minimal_be_bytes(bytes: &[u8]) -> &[u8] {
+    let leading_zeros = bytes.iter().take_while(|b| **b == 0).count();
+    &bytes[leading_zeros..]
+}
@@ -0,0 +1,71 @@
+//! The fields holding the public key of a record.
+//!
+//! A record stores its public key under a field named after the key type, e.g. `secp256k1`. The
+//! length of these fields is checked when a record is decoded, so that a malformed key field is
+//! reported as such rather than as a failure to verify the record.
+
+use core::fmt;
+use rlp::DecoderError;
+
+/// A public key type stored in a record, identified by the name of its field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyScheme {
+    /// A compressed secp256k1 public key, stored under the `secp256k1` key.
+    Secp256k1,
+    /// An ed25519 public key, stored under the `ed25519` key.
+    Ed25519,
+    /// A compressed BLS12-381 public key, stored under the `bls381` key.
+    Bls381,
+}
+
+impl KeyScheme {
+    /// All key types known to this crate.
+    pub const ALL: [Self; 3] = [Self::Secp256k1, Self::Ed25519, Self::Bls381];
+
+    /// The ENR key under which the public key is stored.
+    #[must_use]
+    pub const fn enr_key(self) -> &'static str {
+        match self {
+            Self::Secp256k1 => "secp256k1",
+            Self::Ed25519 => "ed25519",
+            Self::Bls381 => "bls381",
+        }
+    }
+
+    /// The length in bytes of an encoded public key.
+    #[must_use]
+    pub const fn key_len(self) -> usize {
+        match self {
+            Self::Secp256k1 => 33,
+            Self::Ed25519 => 32,
+            Self::Bls381 => 48,
+        }
+    }
+
+    /// The key type stored under the ENR key `key`, if any.
+    #[must_use]
+    pub fn from_enr_key(key: &[u8]) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|scheme| scheme.enr_key().as_bytes() == key)
+    }
+
+    /// Checks the length of a public key decoded from the field of this key type.
+    pub fn check_len(self, public_key: &[u8]) -> Result<(), DecoderError> {
+        if public_key.len() == self.key_len() {
+            return Ok(());
+        }
+        Err(DecoderError::Custom(match self {
+            Self::Secp256k1 => "secp256k1 public key is not 33 bytes",
+            Self::Ed25519 => "ed25519 public key is not 32 bytes",
+            Self::Bls381 => "bls381 public key is not 48 bytes",
+        }))
+    }
+}
+
+impl fmt::Display for KeyScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.enr_key())
+    }
+}
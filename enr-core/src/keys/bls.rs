@@ -0,0 +1,77 @@
+use super::{blst::min_pk as bls, EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError};
+use crate::Key;
+use alloc::{collections::BTreeMap, vec::Vec};
+use blst::BLST_ERROR;
+use bytes::Bytes;
+use rlp::DecoderError;
+
+/// The ENR key that stores the public key in the ENR record.
+pub const ENR_KEY: &str = "bls381";
+
+/// The domain separation tag of the proof of possession ciphersuite, which the Ethereum
+/// consensus layer signs with.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+impl EnrKey for bls::SecretKey {
+    type PublicKey = bls::PublicKey;
+
+    /// Performs ENR-specific signing.
+    ///
+    /// Using `bls381` keys do not follow the `v4` identity scheme, which dictates `secp256k1`
+    /// keys should be used. The message is signed with the [`DST`] of the consensus layer, and
+    /// the compressed 96 byte signature is returned.
+    fn sign_v4(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        Ok(self.sign(msg, DST, &[]).compress().to_vec())
+    }
+
+    /// Returns the public key associated with the private key.
+    fn public(&self) -> Self::PublicKey {
+        self.sk_to_pk()
+    }
+
+    /// Decodes the raw bytes of an ENR's content into a public key if possible.
+    fn enr_to_public(content: &BTreeMap<Key, Bytes>) -> Result<Self::PublicKey, DecoderError> {
+        let pubkey_bytes = content
+            .get(ENR_KEY.as_bytes())
+            .ok_or(DecoderError::Custom("Unknown signature"))?;
+
+        // Decode the RLP
+        let pubkey_bytes = rlp::Rlp::new(pubkey_bytes).data()?;
+
+        Self::decode_public(pubkey_bytes)
+    }
+}
+
+impl EnrKeyUnambiguous for bls::SecretKey {
+    fn decode_public(bytes: &[u8]) -> Result<Self::PublicKey, DecoderError> {
+        bls::PublicKey::key_validate(bytes)
+            .map_err(|_| DecoderError::Custom("Invalid bls381 public key"))
+    }
+}
+
+impl EnrPublicKey for bls::PublicKey {
+    type Raw = [u8; 48];
+    type RawUncompressed = [u8; 96];
+
+    /// Verify a raw message, given a public key for the v4 identity scheme.
+    fn verify_v4(&self, msg: &[u8], sig: &[u8]) -> bool {
+        bls::Signature::from_bytes(sig)
+            .map(|sig| sig.verify(true, msg, DST, &[], self, true) == BLST_ERROR::BLST_SUCCESS)
+            .unwrap_or(false)
+    }
+
+    /// Encodes the public key into compressed form.
+    fn encode(&self) -> Self::Raw {
+        self.compress()
+    }
+
+    /// Encodes the public key in uncompressed form.
+    fn encode_uncompressed(&self) -> Self::RawUncompressed {
+        self.serialize()
+    }
+
+    /// Generates the ENR public key string associated with the bls381 key type.
+    fn enr_key(&self) -> Key {
+        ENR_KEY.into()
+    }
+}
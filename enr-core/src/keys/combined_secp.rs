@@ -0,0 +1,159 @@
+//! Interoperability between the `k256` and `rust-secp256k1` backends of `secp256k1` keys.
+//!
+//! With both backends enabled, their key types are disjoint although they hold the same keys.
+//! [`CombinedSecpKey`] signs records with a key of either backend, and provides the conversions of
+//! secret and public keys between the backends.
+
+use super::{EnrKey, SigningError};
+use crate::Key;
+use alloc::{collections::BTreeMap, vec::Vec};
+use bytes::Bytes;
+use rlp::DecoderError;
+use zeroize::Zeroizing;
+
+/// A `secp256k1` key of either the `k256` or the `rust-secp256k1` backend.
+///
+/// Records signed with either backend are identical, so records of this key type can be signed
+/// and verified regardless of the backend a dependency chose. Public keys are represented by the
+/// `k256` type.
+pub enum CombinedSecpKey {
+    /// A `k256` signing key.
+    K256(k256::ecdsa::SigningKey),
+    /// A `rust-secp256k1` secret key.
+    Secp256k1(secp256k1::SecretKey),
+}
+
+impl From<k256::ecdsa::SigningKey> for CombinedSecpKey {
+    fn from(secret_key: k256::ecdsa::SigningKey) -> Self {
+        Self::K256(secret_key)
+    }
+}
+
+impl From<secp256k1::SecretKey> for CombinedSecpKey {
+    fn from(secret_key: secp256k1::SecretKey) -> Self {
+        Self::Secp256k1(secret_key)
+    }
+}
+
+impl CombinedSecpKey {
+    /// The key as a `k256` signing key.
+    #[must_use]
+    pub fn to_k256(&self) -> k256::ecdsa::SigningKey {
+        match self {
+            Self::K256(key) => key.clone(),
+            Self::Secp256k1(key) => Self::k256_from_secp256k1(key),
+        }
+    }
+
+    /// The key as a `rust-secp256k1` secret key.
+    #[must_use]
+    pub fn to_secp256k1(&self) -> secp256k1::SecretKey {
+        match self {
+            Self::K256(key) => Self::secp256k1_from_k256(key),
+            Self::Secp256k1(key) => *key,
+        }
+    }
+
+    /// Converts a `rust-secp256k1` secret key to a `k256` signing key.
+    #[must_use]
+    pub fn k256_from_secp256k1(key: &secp256k1::SecretKey) -> k256::ecdsa::SigningKey {
+        let bytes = Zeroizing::new(key.secret_bytes());
+        k256::ecdsa::SigningKey::from_slice(bytes.as_ref())
+            .expect("secp256k1 secret keys are valid for both backends")
+    }
+
+    /// Converts a `k256` signing key to a `rust-secp256k1` secret key.
+    #[must_use]
+    pub fn secp256k1_from_k256(key: &k256::ecdsa::SigningKey) -> secp256k1::SecretKey {
+        let bytes = Zeroizing::new(<[u8; 32]>::from(key.to_bytes()));
+        secp256k1::SecretKey::from_slice(bytes.as_ref())
+            .expect("secp256k1 secret keys are valid for both backends")
+    }
+
+    /// Converts a `rust-secp256k1` public key to a `k256` verifying key.
+    #[must_use]
+    pub fn k256_public_from_secp256k1(key: &secp256k1::PublicKey) -> k256::ecdsa::VerifyingKey {
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(&key.serialize())
+            .expect("secp256k1 public keys are valid for both backends")
+    }
+
+    /// Converts a `k256` verifying key to a `rust-secp256k1` public key.
+    #[must_use]
+    pub fn secp256k1_public_from_k256(key: &k256::ecdsa::VerifyingKey) -> secp256k1::PublicKey {
+        secp256k1::PublicKey::from_slice(key.to_encoded_point(true).as_bytes())
+            .expect("secp256k1 public keys are valid for both backends")
+    }
+}
+
+impl EnrKey for CombinedSecpKey {
+    type PublicKey = k256::ecdsa::VerifyingKey;
+
+    fn sign_v4(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        match self {
+            Self::K256(key) => key.sign_v4(msg),
+            Self::Secp256k1(key) => key.sign_v4(msg),
+        }
+    }
+
+    fn public(&self) -> Self::PublicKey {
+        match self {
+            Self::K256(key) => key.public(),
+            Self::Secp256k1(key) => Self::k256_public_from_secp256k1(&key.public()),
+        }
+    }
+
+    fn enr_to_public(content: &BTreeMap<Key, Bytes>) -> Result<Self::PublicKey, DecoderError> {
+        k256::ecdsa::SigningKey::enr_to_public(content)
+    }
+
+    /// Signs with the `k256` backend, which supports recoverable signatures.
+    fn sign_v4_recoverable(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        match self {
+            Self::K256(key) => key.sign_v4_recoverable(msg),
+            Self::Secp256k1(key) => Self::k256_from_secp256k1(key).sign_v4_recoverable(msg),
+        }
+    }
+
+    fn recover_v4(msg: &[u8], sig: &[u8]) -> Option<Self::PublicKey> {
+        k256::ecdsa::SigningKey::recover_v4(msg, sig)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EnrPublicKey;
+
+    #[test]
+    fn test_backend_conversions() {
+        let k256_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let secp_key = CombinedSecpKey::secp256k1_from_k256(&k256_key);
+        assert_eq!(CombinedSecpKey::k256_from_secp256k1(&secp_key), k256_key);
+
+        let k256_public = k256_key.public();
+        let secp_public = CombinedSecpKey::secp256k1_public_from_k256(&k256_public);
+        assert_eq!(secp_public, secp_key.public());
+        assert_eq!(
+            CombinedSecpKey::k256_public_from_secp256k1(&secp_public),
+            k256_public
+        );
+
+        let k256_combined = CombinedSecpKey::from(k256_key);
+        let secp_combined = CombinedSecpKey::from(secp_key);
+        assert_eq!(k256_combined.public(), secp_combined.public());
+        assert_eq!(k256_combined.to_secp256k1(), secp_key);
+        assert_eq!(secp_combined.to_k256(), k256_combined.to_k256());
+
+        // signatures of either backend verify with the other
+        let signature = secp_combined.sign_v4(b"message").unwrap();
+        assert!(k256_public.verify_v4(b"message", &signature));
+        let signature = k256_combined.sign_v4(b"message").unwrap();
+        assert!(secp_public.verify_v4(b"message", &signature));
+
+        let signature = secp_combined.sign_v4_recoverable(b"message").unwrap();
+        assert_eq!(
+            CombinedSecpKey::recover_v4(b"message", &signature),
+            Some(k256_public)
+        );
+    }
+}
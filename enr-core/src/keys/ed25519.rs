@@ -3,9 +3,10 @@ use super::{
     EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError,
 };
 use crate::Key;
+use alloc::{collections::BTreeMap, vec::Vec};
 use bytes::Bytes;
+use core::convert::TryFrom;
 use rlp::DecoderError;
-use std::{collections::BTreeMap, convert::TryFrom};
 
 /// The ENR key that stores the public key in the ENR record.
 pub const ENR_KEY: &str = "ed25519";
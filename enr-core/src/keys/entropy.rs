@@ -0,0 +1,119 @@
+//! The randomness used by the key backends when signing and generating keys.
+//!
+//! Signatures of the `k256` and `rust-secp256k1` backends mix random data into their otherwise
+//! deterministic nonces, and [`CombinedKey`] generates keys from random data, so records built by
+//! these backends differ between runs. With the `test-determinism` feature, tests can replace this
+//! randomness on the current thread with a stream derived from a fixed seed by calling
+//! [`inject_entropy`], making signatures and generated keys reproducible across crates. `ed25519`
+//! signatures are deterministic and need no injection.
+//!
+//! Injected entropy is not random. It must never be used outside of tests.
+//!
+//! [`CombinedKey`]: crate::CombinedKey
+
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+
+#[cfg(feature = "test-determinism")]
+use core::cell::Cell;
+
+#[cfg(feature = "test-determinism")]
+std::thread_local! {
+    /// The injected seed and the number of 32 byte blocks drawn from it.
+    static INJECTED: Cell<Option<([u8; 32], u64)>> = const { Cell::new(None) };
+}
+
+/// Replaces the randomness used by signing and key generation on the current thread with a
+/// stream derived from `seed`, until [`clear_injected_entropy`] is called.
+///
+/// The stream is the keccak256 hash of the seed followed by a little-endian 8 byte block counter,
+/// restarting from the first block on every call.
+#[cfg(feature = "test-determinism")]
+pub fn inject_entropy(seed: [u8; 32]) {
+    INJECTED.with(|injected| injected.set(Some((seed, 0))));
+}
+
+/// Restores the use of the operating system's randomness on the current thread.
+#[cfg(feature = "test-determinism")]
+pub fn clear_injected_entropy() {
+    INJECTED.with(|injected| injected.set(None));
+}
+
+/// Draws from the injected entropy of the current thread, if any.
+#[cfg(feature = "test-determinism")]
+fn fill_injected(dest: &mut [u8]) -> bool {
+    INJECTED.with(|injected| {
+        let (seed, mut counter) = match injected.get() {
+            Some(state) => state,
+            None => return false,
+        };
+        for chunk in dest.chunks_mut(32) {
+            let mut block = [0_u8; 40];
+            block[..32].copy_from_slice(&seed);
+            block[32..].copy_from_slice(&counter.to_le_bytes());
+            chunk.copy_from_slice(&crate::digest(&block)[..chunk.len()]);
+            counter += 1;
+        }
+        injected.set(Some((seed, counter)));
+        true
+    })
+}
+
+/// The randomness of the key backends: the operating system's, unless entropy was injected.
+#[cfg_attr(
+    not(any(feature = "k256", feature = "rust-secp256k1")),
+    allow(dead_code)
+)]
+pub(crate) struct EntropyRng;
+
+impl RngCore for EntropyRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0_u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0_u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        #[cfg(feature = "test-determinism")]
+        if fill_injected(dest) {
+            return;
+        }
+        OsRng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for EntropyRng {}
+
+#[cfg(test)]
+#[cfg(feature = "test-determinism")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_entropy() {
+        let draw = || {
+            let mut bytes = [0_u8; 48];
+            EntropyRng.fill_bytes(&mut bytes);
+            bytes
+        };
+
+        inject_entropy([7; 32]);
+        let first = draw();
+        assert_ne!(draw(), first);
+        inject_entropy([7; 32]);
+        assert_eq!(draw(), first);
+
+        clear_injected_entropy();
+        assert_ne!(draw(), first);
+    }
+}
@@ -1,12 +1,14 @@
 //! An implementation for `EnrKey` for `k256::ecdsa::SigningKey`
 
-use super::{EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError};
+use super::{entropy::EntropyRng, EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError};
 use crate::Key;
+use alloc::{collections::BTreeMap, vec::Vec};
 use bytes::Bytes;
+use core::convert::TryFrom;
 use k256::{
     ecdsa::{
         signature::{DigestVerifier, RandomizedDigestSigner},
-        Signature, SigningKey, VerifyingKey,
+        RecoveryId, Signature, SigningKey, VerifyingKey,
     },
     elliptic_curve::{
         point::DecompressPoint,
@@ -15,10 +17,8 @@ use k256::{
     },
     AffinePoint, CompressedPoint, EncodedPoint,
 };
-use rand::rngs::OsRng;
 use rlp::DecoderError;
 use sha3::{Digest, Keccak256};
-use std::{collections::BTreeMap, convert::TryFrom};
 
 /// The ENR key that stores the public key in the ENR record.
 pub const ENR_KEY: &str = "secp256k1";
@@ -30,7 +30,7 @@ impl EnrKey for SigningKey {
         // take a keccak256 hash then sign.
         let digest = Keccak256::new().chain_update(msg);
         let signature: Signature = self
-            .try_sign_digest_with_rng(&mut OsRng, digest)
+            .try_sign_digest_with_rng(&mut EntropyRng, digest)
             .map_err(|_| SigningError::new("failed to sign"))?;
 
         Ok(signature.to_vec())
@@ -50,6 +50,34 @@ impl EnrKey for SigningKey {
 
         Self::decode_public(pubkey_bytes)
     }
+
+    fn sign_v4_recoverable(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        // the signature is followed by the recovery id, `r || s || v`
+        let (signature, recovery_id) = self
+            .sign_digest_recoverable(Keccak256::new().chain_update(msg))
+            .map_err(|_| SigningError::new("failed to sign"))?;
+
+        let mut signature = signature.to_vec();
+        signature.push(recovery_id.to_byte());
+        Ok(signature)
+    }
+
+    fn recover_v4(msg: &[u8], sig: &[u8]) -> Option<Self::PublicKey> {
+        let (signature, recovery_id) = match sig {
+            [signature @ .., v] if signature.len() == 64 => (signature, *v),
+            _ => return None,
+        };
+        let signature = Signature::from_slice(signature).ok()?;
+        // the recovery id may be offset by 27, as in Ethereum transactions
+        let recovery_id =
+            RecoveryId::from_byte(recovery_id.checked_sub(27).unwrap_or(recovery_id))?;
+        VerifyingKey::recover_from_digest(
+            Keccak256::new().chain_update(msg),
+            &signature,
+            recovery_id,
+        )
+        .ok()
+    }
 }
 
 impl EnrKeyUnambiguous for SigningKey {
@@ -65,6 +93,9 @@ impl EnrPublicKey for VerifyingKey {
     type RawUncompressed = [u8; 64];
 
     fn verify_v4(&self, msg: &[u8], sig: &[u8]) -> bool {
+        if sig.len() == 65 {
+            return SigningKey::recover_v4(msg, sig).as_ref() == Some(self);
+        }
         if let Ok(sig) = k256::ecdsa::Signature::try_from(sig) {
             return self
                 .verify_digest(Keccak256::new().chain_update(msg), &sig)
@@ -1,15 +1,15 @@
 use super::{EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError};
 use crate::{digest, Key};
+use alloc::{collections::BTreeMap, vec::Vec};
 use bytes::Bytes;
 use rand::RngCore;
 use rlp::DecoderError;
 use secp256k1::SECP256K1;
-use std::collections::BTreeMap;
 
 #[cfg(test)]
 use self::MockOsRng as OsRng;
 #[cfg(not(test))]
-use rand::rngs::OsRng;
+use super::entropy::EntropyRng as OsRng;
 
 /// The ENR key that stores the public key in the ENR record.
 pub const ENR_KEY: &str = "secp256k1";
@@ -112,3 +112,59 @@ impl RngCore for MockOsRng {
         unimplemented!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rlp::RlpStream;
+
+    #[test]
+    fn test_secp256k1_sign_ecdsa_with_mock_noncedata() {
+        // Uses the example record from the ENR spec.
+        //
+        // The feature "rust-secp256k1" creates ECDSA signatures with additional random data.
+        // Under the unit testing environment, the mock value `MOCK_ECDSA_NONCE_ADDITIONAL_DATA`
+        // is always used.
+        //
+        // The expected signature `expected_signature` is constructed by a Python script:
+        // ```
+        // key = SigningKey.from_secret_exponent(
+        //     0xb71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291, curve=SECP256k1)
+        //
+        // # Builds content RLP
+        // rlp_data = encode([1, 'id', 'v4', 'ip', 0x7f000001, 'secp256k1', bytes.fromhex(
+        //     '03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138'), 'udp', 0x765f])
+        // rlp_data_hash = keccak(rlp_data)
+        //
+        // # Signs the content RLP **with** the additional data.
+        // additional_data = bytes.fromhex(
+        //     'baaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaad')
+        // content_signature = key.sign_digest_deterministic(rlp_data_hash, hashfunc=sha256,
+        //                                                   sigencode=sigencode_string_canonize,
+        //                                                   extra_entropy=additional_data)
+        // ```
+        let expected_signature = "b258751c31772f806f3582eb02fda2807a3babaf0af26c277eab6c9ced880b352733d8d3ffd8920173670354b8d866d410cde9ce3e51544224ff5b5cd0a6a036";
+
+        let key_data =
+            hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap();
+        let key = secp256k1::SecretKey::from_slice(&key_data).unwrap();
+
+        let mut content = RlpStream::new_list(9);
+        content.append(&1_u64);
+        content.append(&"id");
+        content.append(&"v4");
+        content.append(&"ip");
+        content.append(&vec![127_u8, 0, 0, 1]);
+        content.append(&ENR_KEY);
+        content.append(&key.public().encode().to_vec());
+        content.append(&"udp");
+        content.append(&30303_u16);
+        let content = content.out();
+
+        let signature = key.sign_v4(&content).unwrap();
+        assert_eq!(hex::encode(&signature), expected_signature);
+        assert!(key.public().verify_v4(&content, &signature));
+    }
+}
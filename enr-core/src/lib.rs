@@ -0,0 +1,91 @@
+//! Core types of the [`enr`] crate, an implementation of Ethereum Node Records (ENR) as specified
+//! by [EIP-778](https://eips.ethereum.org/EIPS/eip-778).
+//!
+//! This crate provides the [`Enr`] record type with its RLP encoding and decoding, see [`codec`],
+//! the [`NodeId`] and the [`EnrKey`], [`EnrPublicKey`] and [`EnrKeyUnambiguous`] traits with
+//! minimal dependencies. It is `no_std` compatible when the default `std` feature is disabled, so
+//! embedded and wasm targets can decode, verify and create records and implement their own signing
+//! schemes without pulling in a cryptography stack.
+//!
+//! Most users should depend on [`enr`], which re-exports the key traits and types of this crate
+//! and provides a builder, typed fields and updates on top of its own `Enr`, which converts from
+//! and to the [`Enr`] of this crate.
+//!
+//! ## Features
+//!
+//! - `std`: Enabled by default. Provides [`NodeId::random`] and is required by the `k256` and
+//!   `rust-secp256k1` key backends.
+//! - `serde`: Allows for serde serialization and deserialization of [`NodeId`]s.
+//! - `k256`: Implements the key traits for `k256` secp256k1 keys.
+//! - `bls`: Implements the key traits for `blst` BLS12-381 keys, and adds them to
+//!   [`CombinedKey`].
+//! - `ed25519`: Implements the key traits for `ed25519_dalek` keys. Together with `k256`, provides
+//!   [`CombinedKey`].
+//! - `rust-secp256k1`: Implements the key traits for `rust-secp256k1` keys.
+//! - `pkcs8`: Provides PKCS#8 DER and PEM import and export of [`CombinedKey`]s.
+//! - `zeroize`: Marks [`CombinedKey`] as [`ZeroizeOnDrop`](zeroize::ZeroizeOnDrop) and provides
+//!   conversions between `CombinedKey` and `secrecy` secrets.
+//! - `primitive-types`: Provides conversions between [`NodeId`] and the `H256` and `U256` types
+//!   of `primitive-types`.
+//! - `test-determinism`: Provides [`inject_entropy`], replacing the randomness used by the key
+//!   backends with a fixed stream so that tests can reproduce signatures and generated keys.
+//!
+//! [`enr`]: https://docs.rs/enr
+//! [`CombinedKey`]: https://docs.rs/enr/latest/enr/enum.CombinedKey.html
+
+#![no_std]
+#![warn(clippy::all)]
+#![allow(
+    clippy::map_err_ignore,
+    clippy::missing_errors_doc,
+    clippy::module_name_repetitions
+)]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+pub mod codec;
+mod key_field;
+mod keys;
+mod node_id;
+mod record;
+
+use alloc::vec::Vec;
+use sha3::{Digest, Keccak256};
+
+#[cfg(feature = "bls")]
+pub use keys::blst;
+#[cfg(feature = "ed25519")]
+pub use keys::ed25519_dalek;
+#[cfg(feature = "k256")]
+pub use keys::k256;
+#[cfg(feature = "rust-secp256k1")]
+pub use keys::secp256k1;
+#[cfg(feature = "zeroize")]
+pub use keys::secrecy;
+#[cfg(all(feature = "k256", feature = "rust-secp256k1"))]
+pub use keys::CombinedSecpKey;
+#[cfg(feature = "test-determinism")]
+pub use keys::{clear_injected_entropy, inject_entropy};
+#[cfg(all(feature = "ed25519", feature = "k256"))]
+pub use keys::{CombinedKey, CombinedPublicKey};
+
+pub use codec::MAX_ENR_SIZE;
+pub use key_field::KeyScheme;
+pub use keys::{EnrKey, EnrKeyUnambiguous, EnrPublicKey, SigningError};
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use node_id::serde_hex_prfx;
+pub use node_id::{NodeId, NodeIdHasher};
+pub use record::Enr;
+
+/// The "key" in an ENR record can be arbitrary bytes.
+type Key = Vec<u8>;
+
+/// The keccak256 hash of `b`.
+fn digest(b: &[u8]) -> [u8; 32] {
+    let mut output = [0_u8; 32];
+    output.copy_from_slice(&Keccak256::digest(b));
+    output
+}
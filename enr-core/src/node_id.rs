@@ -0,0 +1,293 @@
+//! The identifier for an ENR record. This is the keccak256 hash of the public key (for secp256k1
+//! keys this is the uncompressed encoded form of the public key).
+//!
+//! Networks with other identities can derive node ids with any [`NodeIdHasher`], e.g. from an
+//! identity scheme of the `enr` crate.
+
+use crate::{digest, keys::EnrPublicKey};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sha3::{
+    digest::{consts::U32, OutputSizeUser},
+    Digest,
+};
+
+type RawNodeId = [u8; 32];
+
+/// A hash function deriving node ids from encoded public keys.
+///
+/// Implemented for every [`Digest`] with a 32 byte output, e.g. [`sha3::Keccak256`], the hash of the
+/// "v4" identity scheme, or `sha2::Sha256`.
+pub trait NodeIdHasher {
+    /// The hash of `input`.
+    fn hash(input: &[u8]) -> RawNodeId;
+}
+
+impl<D: Digest + OutputSizeUser<OutputSize = U32>> NodeIdHasher for D {
+    fn hash(input: &[u8]) -> RawNodeId {
+        D::digest(input).into()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// The `NodeId` of an ENR (a 32 byte identifier).
+pub struct NodeId {
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex_prfx"))]
+    raw: RawNodeId,
+}
+
+impl NodeId {
+    /// Creates a new node record from 32 bytes.
+    #[must_use]
+    pub const fn new(raw_input: &[u8; 32]) -> Self {
+        Self { raw: *raw_input }
+    }
+
+    /// Parses a byte slice to form a node Id. This fails if the slice isn't of length 32.
+    pub fn parse(raw_input: &[u8]) -> Result<Self, &'static str> {
+        if raw_input.len() > 32 {
+            return Err("Input too large");
+        }
+
+        let mut raw: RawNodeId = [0_u8; 32];
+        raw[..core::cmp::min(32, raw_input.len())].copy_from_slice(raw_input);
+
+        Ok(Self { raw })
+    }
+
+    /// Generates a random `NodeId`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn random() -> Self {
+        Self {
+            raw: rand::random(),
+        }
+    }
+
+    /// Derives the node id of a public key with the hash `H`, hashing the uncompressed encoding
+    /// of the key like the keccak256 based `From` conversion.
+    #[must_use]
+    pub fn from_public_key_with<H: NodeIdHasher>(public_key: &impl EnrPublicKey) -> Self {
+        Self::new(&H::hash(public_key.encode_uncompressed().as_ref()))
+    }
+
+    /// Returns a `RawNodeId` which is a 32 byte list.
+    #[must_use]
+    pub const fn raw(&self) -> RawNodeId {
+        self.raw
+    }
+
+    /// Returns a reference to the 32 bytes of the node id.
+    #[must_use]
+    pub const fn as_raw(&self) -> &RawNodeId {
+        &self.raw
+    }
+}
+
+impl<T: EnrPublicKey> From<T> for NodeId {
+    fn from(public_key: T) -> Self {
+        Self::new(&digest(public_key.encode_uncompressed().as_ref()))
+    }
+}
+
+impl AsRef<[u8]> for NodeId {
+    fn as_ref(&self) -> &[u8] {
+        &self.raw[..]
+    }
+}
+
+impl PartialEq<RawNodeId> for NodeId {
+    fn eq(&self, other: &RawNodeId) -> bool {
+        self.raw.eq(other)
+    }
+}
+
+impl From<RawNodeId> for NodeId {
+    fn from(raw: RawNodeId) -> Self {
+        Self { raw }
+    }
+}
+
+impl From<NodeId> for RawNodeId {
+    fn from(node_id: NodeId) -> Self {
+        node_id.raw
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl From<primitive_types::H256> for NodeId {
+    fn from(hash: primitive_types::H256) -> Self {
+        Self { raw: hash.0 }
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl From<NodeId> for primitive_types::H256 {
+    fn from(node_id: NodeId) -> Self {
+        Self(node_id.raw)
+    }
+}
+
+/// Interprets the node id as a big-endian integer, as done for the XOR distance of discv5.
+#[cfg(feature = "primitive-types")]
+impl From<primitive_types::U256> for NodeId {
+    fn from(value: primitive_types::U256) -> Self {
+        Self {
+            raw: value.to_big_endian(),
+        }
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl From<NodeId> for primitive_types::U256 {
+    fn from(node_id: NodeId) -> Self {
+        Self::from_big_endian(&node_id.raw)
+    }
+}
+
+/// Parses the hex encoding of a node id, with or without a `0x` prefix.
+impl core::str::FromStr for NodeId {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut raw = [0_u8; 32];
+        hex::decode_to_slice(s.strip_prefix("0x").unwrap_or(s), &mut raw)?;
+        Ok(Self { raw })
+    }
+}
+
+/// Displays the node id as `0x` prefixed hex, the encoding parsed by `FromStr`.
+impl core::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "0x{}", hex::encode(self.raw))
+    }
+}
+
+impl core::fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "0x{}", hex::encode(self.raw))
+    }
+}
+
+/// Serialize with the 0x prefix.
+#[cfg(feature = "serde")]
+pub mod serde_hex_prfx {
+    use alloc::format;
+
+    pub fn serialize<T: AsRef<[u8]> + hex::ToHex, S: serde::Serializer>(
+        data: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let dst = format!("0x{}", hex::encode(data));
+        serializer.serialize_str(&dst)
+    }
+
+    /// Deserialize with the 0x prefix.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: hex::FromHex,
+        <T as hex::FromHex>::Error: core::fmt::Display,
+    {
+        let raw: &[u8] = serde::Deserialize::deserialize(deserializer)?;
+        let src = raw.strip_prefix(b"0x").unwrap_or(raw);
+        hex::FromHex::from_hex(src).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, string::ToString};
+
+    #[test]
+    fn test_eq_node_raw_node() {
+        let node = NodeId::random();
+        let raw = node.raw;
+        assert_eq!(node, raw);
+        assert_eq!(node.as_ref(), &raw[..]);
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_from_public_key_with() {
+        use crate::EnrKey;
+        use sha3::{Keccak256, Sha3_256};
+
+        let public_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng()).public();
+        assert_eq!(
+            NodeId::from_public_key_with::<Keccak256>(&public_key),
+            NodeId::from(public_key)
+        );
+        let node_id = NodeId::from_public_key_with::<Sha3_256>(&public_key);
+        assert_eq!(
+            node_id,
+            <[u8; 32]>::from(Sha3_256::digest(public_key.encode_uncompressed()))
+        );
+        assert_ne!(node_id, NodeId::from(public_key));
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let node = NodeId::random();
+        let hex = node.to_string();
+        assert_eq!(hex.len(), 66);
+        assert_eq!(hex.parse::<NodeId>(), Ok(node));
+        assert_eq!(hex[2..].parse::<NodeId>(), Ok(node));
+        assert_eq!(
+            hex[..64].parse::<NodeId>(),
+            Err(hex::FromHexError::InvalidStringLength)
+        );
+        assert_eq!(<[u8; 32]>::from(node), node.raw());
+        assert_eq!(node.as_raw(), &node.raw());
+    }
+
+    #[cfg(feature = "primitive-types")]
+    #[test]
+    fn test_primitive_types() {
+        use primitive_types::{H256, U256};
+
+        let node = NodeId::random();
+        assert_eq!(NodeId::from(H256::from(node)), node);
+        assert_eq!(NodeId::from(U256::from(node)), node);
+
+        let mut raw = [0; 32];
+        raw[31] = 5;
+        assert_eq!(U256::from(NodeId::new(&raw)), U256::from(5));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let node = NodeId::random();
+        let json_string = serde_json::to_string(&node).unwrap();
+        assert_eq!(node, serde_json::from_str::<NodeId>(&json_string).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_0x() {
+        let raw = [
+            154, 95, 80, 100, 224, 32, 222, 137, 157, 219, 197, 24, 45, 143, 90, 106, 99, 12, 9,
+            93, 44, 66, 196, 203, 35, 233, 26, 59, 50, 128, 168, 180,
+        ];
+        let node = NodeId::parse(&raw).unwrap();
+        let json_string = serde_json::to_string(&node).unwrap();
+        assert_eq!(
+            json_string,
+            "\"0x9a5f5064e020de899ddbc5182d8f5a6a630c095d2c42c4cb23e91a3b3280a8b4\""
+        );
+        let snode = serde_json::from_str::<NodeId>(&json_string).unwrap();
+        assert_eq!(node, snode);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_as_hashmap_key() {
+        let mut responses: HashMap<NodeId, u8> = HashMap::default();
+        responses.insert(NodeId::random(), 1);
+        let _ = serde_json::json!(responses);
+    }
+}
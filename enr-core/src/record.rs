@@ -0,0 +1,331 @@
+//! The [`Enr`] type, a signed record of the `v4` identity scheme.
+//!
+//! This is the record with its RLP encoding and decoding and nothing else, for targets that can't
+//! afford the [`enr`] crate. Records are verified when they are decoded or created, so an [`Enr`]
+//! always holds a valid signature of the public key it stores:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr_core::{k256::ecdsa::SigningKey, Enr, EnrKey};
+//! use std::collections::BTreeMap;
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut content = BTreeMap::new();
+//! content.insert(b"udp".to_vec(), rlp::encode(&30303_u16).freeze());
+//! let enr = Enr::new(&key, 1, content).unwrap();
+//!
+//! let decoded: Enr<SigningKey> = rlp::decode(&rlp::encode(&enr)).unwrap();
+//! assert_eq!(decoded, enr);
+//! assert_eq!(decoded.public_key(), key.public());
+//! # }
+//! ```
+//!
+//! The [`enr`] crate converts its records from and to this type.
+//!
+//! [`enr`]: https://docs.rs/enr
+
+use crate::{
+    codec::{self, MAX_ENR_SIZE},
+    EnrKey, EnrPublicKey, Key, NodeId, SigningError,
+};
+use alloc::{collections::BTreeMap, vec::Vec};
+use bytes::{BufMut, Bytes};
+use core::{fmt, marker::PhantomData};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+/// The identity scheme of records.
+const ID: &[u8] = b"v4";
+
+/// A signed Ethereum Node Record of the `v4` identity scheme.
+pub struct Enr<K: EnrKey> {
+    /// The sequence number of the record.
+    seq: u64,
+    /// The node id, derived from the public key.
+    node_id: NodeId,
+    /// The keys and their raw RLP encoded values, in the order they are signed in.
+    content: BTreeMap<Key, Bytes>,
+    /// The signature of the content.
+    signature: Vec<u8>,
+    phantom: PhantomData<K>,
+}
+
+impl<K: EnrKey> Enr<K> {
+    /// Creates a record with sequence number `seq` holding `content`, a map of keys to their raw
+    /// RLP encoded values, signed by `key`.
+    ///
+    /// The `id` field and the public key field of `key` are added to the content. Fails if a
+    /// value is malformed or the record exceeds [`MAX_ENR_SIZE`].
+    pub fn new(key: &K, seq: u64, mut content: BTreeMap<Key, Bytes>) -> Result<Self, SigningError> {
+        let public_key = key.public();
+        content.insert(b"id".to_vec(), rlp::encode(&ID).freeze());
+        content.insert(
+            public_key.enr_key(),
+            rlp::encode(&public_key.encode().as_ref()).freeze(),
+        );
+        for (key, value) in &content {
+            let item = Rlp::new(value);
+            if item.as_raw().len() != item.payload_info().map_or(0, |info| info.total())
+                || codec::check_value(key, &item).is_err()
+            {
+                return Err(SigningError::new("malformed record value"));
+            }
+        }
+
+        let mut enr = Self {
+            seq,
+            node_id: NodeId::from(public_key),
+            content,
+            signature: Vec::new(),
+            phantom: PhantomData,
+        };
+        enr.signature = key.sign_v4(&enr.signed_content())?;
+        if enr.encoded_len() > MAX_ENR_SIZE {
+            return Err(SigningError::new("record exceeds the maximum size"));
+        }
+        Ok(enr)
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The node id of the record.
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// The signature of the record.
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The value stored under `key`, if it is a byte string.
+    #[must_use]
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        Rlp::new(self.get_raw_rlp(key)?).data().ok()
+    }
+
+    /// The raw RLP encoded value stored under `key`.
+    #[must_use]
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.content.get(key.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// The keys of the record and their raw RLP encoded values, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &[u8])> {
+        self.content
+            .iter()
+            .map(|(key, value)| (key, value.as_ref()))
+    }
+
+    /// The public key of the record.
+    #[must_use]
+    pub fn public_key(&self) -> K::PublicKey {
+        K::enr_to_public(&self.content).expect("records are verified when created")
+    }
+
+    /// Verifies the signature of the record.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.get("id") == Some(ID)
+            && K::enr_to_public(&self.content).is_ok_and(|public_key| {
+                public_key.verify_v4(&self.signed_content(), &self.signature)
+            })
+    }
+
+    /// The RLP encoding of the content signed by the identity scheme.
+    #[must_use]
+    pub fn signed_content(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(MAX_ENR_SIZE);
+        codec::encode_fields(&mut buffer, None, self.seq, self.fields());
+        buffer
+    }
+
+    /// The length of the RLP encoding of the record.
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        let payload_len = codec::fields_len(Some(&self.signature), self.seq, self.fields());
+        codec::rlp_header_len(payload_len) + payload_len
+    }
+
+    /// Writes the RLP encoding of the record into `buffer`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `buffer` has less than [`Enr::encoded_len`] bytes of remaining capacity.
+    pub fn encode_to_buf(&self, buffer: &mut impl BufMut) {
+        codec::encode_fields(buffer, Some(&self.signature), self.seq, self.fields());
+    }
+
+    /// The keys and values of the record, as encoded.
+    fn fields(&self) -> impl Iterator<Item = (&[u8], &[u8])> + Clone {
+        self.content
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_ref()))
+    }
+}
+
+impl<K: EnrKey> Clone for Enr<K> {
+    fn clone(&self) -> Self {
+        Self {
+            seq: self.seq,
+            node_id: self.node_id,
+            content: self.content.clone(),
+            signature: self.signature.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<K: EnrKey> PartialEq for Enr<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq == other.seq
+            && self.node_id == other.node_id
+            && self.content == other.content
+            && self.signature == other.signature
+    }
+}
+
+impl<K: EnrKey> Eq for Enr<K> {}
+
+impl<K: EnrKey> fmt::Debug for Enr<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Enr")
+            .field("seq", &self.seq)
+            .field("node_id", &self.node_id)
+            .field("content", &self.content)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: EnrKey> Encodable for Enr<K> {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        stream.begin_list(2 + self.content.len() * 2);
+        stream.append(&self.signature);
+        stream.append(&self.seq);
+        for (key, value) in &self.content {
+            stream.append(key);
+            // values are raw RLP encoded data
+            stream.append_raw(value, 1);
+        }
+    }
+}
+
+impl<K: EnrKey> Decodable for Enr<K> {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let mut content = BTreeMap::new();
+        let (signature, seq) = codec::decode_fields(rlp, |key, item| {
+            codec::check_value(key, item)?;
+            content.insert(key.to_vec(), Bytes::copy_from_slice(item.as_raw()));
+            Ok(())
+        })?;
+
+        let public_key = K::enr_to_public(&content)?;
+        let enr = Self {
+            seq,
+            node_id: NodeId::from(public_key),
+            content,
+            signature: signature.to_vec(),
+            phantom: PhantomData,
+        };
+        if !enr.verify() {
+            return Err(DecoderError::Custom("Invalid Signature"));
+        }
+        Ok(enr)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_vector() {
+        let valid_record = hex::decode("f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f").unwrap();
+
+        let enr: Enr<SigningKey> = rlp::decode(&valid_record).unwrap();
+        assert_eq!(enr.seq(), 1);
+        assert_eq!(enr.get("ip"), Some([127, 0, 0, 1].as_ref()));
+        assert_eq!(
+            enr.get_raw_rlp("udp"),
+            Some(rlp::encode(&30303_u16).as_ref())
+        );
+        assert_eq!(
+            hex::encode(enr.public_key().encode()),
+            "03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138"
+        );
+        assert_eq!(enr.encoded_len(), valid_record.len());
+        assert_eq!(rlp::encode(&enr), valid_record);
+
+        // a changed value no longer matches the signature
+        let mut tampered = valid_record;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert_eq!(
+            rlp::decode::<Enr<SigningKey>>(&tampered),
+            Err(DecoderError::Custom("Invalid Signature"))
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let mut content = BTreeMap::new();
+        content.insert(b"tcp".to_vec(), rlp::encode(&30303_u16).freeze());
+        let enr = Enr::new(&key, 2, content).unwrap();
+        assert!(enr.verify());
+        assert_eq!(enr.node_id(), NodeId::from(key.public()));
+        assert_eq!(
+            enr.iter()
+                .map(|(key, _)| key.as_slice())
+                .collect::<Vec<_>>(),
+            [b"id".as_ref(), b"secp256k1", b"tcp"]
+        );
+        assert_eq!(rlp::decode::<Enr<SigningKey>>(&rlp::encode(&enr)), Ok(enr));
+
+        // malformed and oversized content
+        let mut content = BTreeMap::new();
+        content.insert(
+            b"udp".to_vec(),
+            rlp::encode(&[1_u8, 2, 3].as_ref()).freeze(),
+        );
+        assert!(Enr::new(&key, 1, content).is_err());
+        let mut content = BTreeMap::new();
+        content.insert(
+            b"large".to_vec(),
+            rlp::encode(&[0_u8; MAX_ENR_SIZE].as_ref()).freeze(),
+        );
+        assert!(Enr::new(&key, 1, content).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_records() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let enr = Enr::new(&key, 1, BTreeMap::new()).unwrap();
+        let encoded = rlp::encode(&enr);
+
+        // trailing data
+        let mut trailing = encoded.to_vec();
+        trailing.push(0);
+        assert!(rlp::decode::<Enr<SigningKey>>(&trailing).is_err());
+
+        // unsorted keys
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&enr.signature());
+        stream.append(&1_u64);
+        stream.append(&"secp256k1");
+        stream.append_raw(enr.get_raw_rlp("secp256k1").unwrap(), 1);
+        stream.append(&"id");
+        stream.append(&"v4");
+        assert_eq!(
+            rlp::decode::<Enr<SigningKey>>(&stream.out()),
+            Err(DecoderError::Custom("Unsorted keys"))
+        );
+    }
+}
@@ -0,0 +1,8 @@
+//! Decodes arbitrary bytes as an RLP encoded record.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    enr::fuzz_utils::roundtrip(data);
+});
@@ -0,0 +1,11 @@
+//! Parses arbitrary strings as the text form of a record.
+#![no_main]
+
+use enr::{CombinedKey, Enr};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(enr) = text.parse::<Enr<CombinedKey>>() {
+        enr::fuzz_utils::roundtrip(&rlp::encode(&enr));
+    }
+});
@@ -0,0 +1,22 @@
+//! Writes the seed corpus of the fuzz targets.
+//!
+//! ```text
+//! cargo run --bin seed_corpus
+//! ```
+
+use std::{fs, path::Path};
+
+fn main() -> std::io::Result<()> {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    let decode = corpus.join("decode");
+    let decode_text = corpus.join("decode_text");
+    fs::create_dir_all(&decode)?;
+    fs::create_dir_all(&decode_text)?;
+    for (i, bytes) in enr::fuzz_utils::seed_corpus().iter().enumerate() {
+        let name = format!("seed-{i}");
+        fs::write(decode.join(&name), bytes)?;
+        let enr: enr::Enr<enr::CombinedKey> = rlp::decode(bytes).expect("valid seed");
+        fs::write(decode_text.join(&name), enr.to_base64())?;
+    }
+    Ok(())
+}
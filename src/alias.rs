@@ -0,0 +1,58 @@
+//! Key aliases for migrating fields between names.
+//!
+//! When a network renames a field, readers and writers are upgraded at different times. An
+//! [`AliasTable`] attached to a record lets getters fall back to the other name of a field when
+//! the requested one is absent, and validates values inserted under an alias of a reserved key as
+//! if they were inserted under the reserved key itself. Aliases are local configuration and are
+//! not part of the encoded record.
+
+use crate::Key;
+use std::collections::BTreeMap;
+
+/// A table of keys that can be read in place of each other.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AliasTable {
+    /// The aliases of each key, in order of registration.
+    aliases: BTreeMap<Key, Vec<Key>>,
+}
+
+impl AliasTable {
+    /// Creates an empty alias table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `key` and `alias` as aliases of each other, so that reading either falls back to
+    /// the other.
+    pub fn alias(&mut self, key: impl AsRef<[u8]>, alias: impl AsRef<[u8]>) -> &mut Self {
+        let (key, alias) = (key.as_ref(), alias.as_ref());
+        if key != alias {
+            self.insert(key, alias);
+            self.insert(alias, key);
+        }
+        self
+    }
+
+    /// The aliases of `key`, in order of registration.
+    pub fn aliases_of(&self, key: impl AsRef<[u8]>) -> impl Iterator<Item = &[u8]> {
+        self.aliases
+            .get(key.as_ref())
+            .into_iter()
+            .flatten()
+            .map(Key::as_slice)
+    }
+
+    /// Returns whether the table contains no aliases.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    fn insert(&mut self, key: &[u8], alias: &[u8]) {
+        let aliases = self.aliases.entry(Key::from(key)).or_default();
+        if !aliases.iter().any(|existing| existing.as_slice() == alias) {
+            aliases.push(Key::from(alias));
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Allowlisting of record keys for permissioned networks.
+//!
+//! Records of closed networks should only carry the fields the operator expects. A
+//! [`KeyAllowlist`] lists the permitted keys and whether records carrying other keys are rejected
+//! or have those keys stripped. The identity scheme and the public key of a record are always
+//! permitted.
+
+use crate::{Enr, EnrError, EnrKey, EnrPublicKey, NodeId};
+use bytes::Bytes;
+use rlp::{Decodable, DecoderError};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What happens to records carrying keys outside a [`KeyAllowlist`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DisallowedKeyAction {
+    /// The record is rejected with [`EnrError::DisallowedKeys`].
+    #[default]
+    Reject,
+    /// The keys are removed and the remaining fields are returned as a [`StrippedRecord`].
+    Strip,
+}
+
+/// A verified record checked against a [`KeyAllowlist`].
+#[derive(Debug)]
+pub enum Filtered<K: EnrKey> {
+    /// The record carries only permitted keys.
+    Allowed(Enr<K>),
+    /// The record carried keys outside the allowlist, which were stripped.
+    Stripped(StrippedRecord),
+}
+
+/// The permitted fields of a verified record whose other keys were stripped.
+///
+/// This is not an [`Enr`]: without the stripped keys, the content no longer matches the signature
+/// of the record, so it cannot be verified or relayed to other nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StrippedRecord {
+    node_id: NodeId,
+    seq: u64,
+    content: BTreeMap<Vec<u8>, Bytes>,
+    stripped: Vec<Vec<u8>>,
+}
+
+impl StrippedRecord {
+    /// The node id of the record.
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Reads a permitted raw RLP encoded value.
+    #[must_use]
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.content.get(key.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// Reads a permitted value, decoded as `T`.
+    pub fn get_decodable<T: Decodable>(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Option<Result<T, DecoderError>> {
+        self.get_raw_rlp(key).map(rlp::decode)
+    }
+
+    /// Returns an iterator over the permitted key/value pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.content
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_ref()))
+    }
+
+    /// The keys removed from the record, in order.
+    #[must_use]
+    pub fn stripped(&self) -> &[Vec<u8>] {
+        &self.stripped
+    }
+}
+
+/// The set of keys records are permitted to carry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyAllowlist {
+    keys: BTreeSet<Vec<u8>>,
+    action: DisallowedKeyAction,
+}
+
+impl KeyAllowlist {
+    /// Creates an allowlist permitting only the identity scheme and public key of a record.
+    #[must_use]
+    pub fn new(action: DisallowedKeyAction) -> Self {
+        Self {
+            keys: BTreeSet::new(),
+            action,
+        }
+    }
+
+    /// Permits a key.
+    pub fn allow(&mut self, key: impl AsRef<[u8]>) -> &mut Self {
+        self.keys.insert(key.as_ref().to_vec());
+        self
+    }
+
+    /// The action taken on records carrying keys outside the allowlist.
+    #[must_use]
+    pub const fn action(&self) -> DisallowedKeyAction {
+        self.action
+    }
+
+    /// Returns whether the key is permitted for a record signed with the given public key.
+    #[must_use]
+    pub fn is_allowed(&self, key: impl AsRef<[u8]>, public_key: &impl EnrPublicKey) -> bool {
+        let key = key.as_ref();
+        key == b"id" || key == public_key.enr_key().as_slice() || self.keys.contains(key)
+    }
+
+    /// The keys of the record outside the allowlist, in order.
+    #[must_use]
+    pub fn disallowed_keys<K: EnrKey>(&self, enr: &Enr<K>) -> Vec<Vec<u8>> {
+        let public_key = enr.public_key();
+        enr.keys()
+            .filter(|key| !self.is_allowed(key, &public_key))
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    /// Checks a record against the allowlist, rejecting it or stripping the disallowed keys
+    /// depending on the action.
+    pub fn apply<K: EnrKey>(&self, enr: Enr<K>) -> Result<Filtered<K>, EnrError> {
+        let disallowed = self.disallowed_keys(&enr);
+        if disallowed.is_empty() {
+            return Ok(Filtered::Allowed(enr));
+        }
+        match self.action {
+            DisallowedKeyAction::Reject => Err(EnrError::DisallowedKeys(
+                disallowed
+                    .iter()
+                    .map(|key| String::from_utf8_lossy(key).into_owned())
+                    .collect(),
+            )),
+            DisallowedKeyAction::Strip => Ok(Filtered::Stripped(StrippedRecord {
+                node_id: enr.node_id(),
+                seq: enr.seq(),
+                content: enr
+                    .into_iter()
+                    .filter(|(key, _)| !disallowed.contains(key))
+                    .collect(),
+                stripped: disallowed,
+            })),
+        }
+    }
+
+    /// Decodes and verifies an RLP encoded record, then checks it against the allowlist.
+    pub fn decode<K: EnrKey>(&self, bytes: &[u8]) -> Result<Filtered<K>, EnrError> {
+        let enr = rlp::decode(bytes).map_err(|e| EnrError::InvalidRlpData(e.to_string()))?;
+        self.apply(enr)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    fn record() -> DefaultEnr {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        DefaultEnr::builder()
+            .udp4(30303)
+            .add_value("payload", &b"arbitrary".as_ref())
+            .add_value("eth2", &[0_u8; 16].as_ref())
+            .build(&key)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reject() {
+        let enr = record();
+        let mut allowlist = KeyAllowlist::new(DisallowedKeyAction::Reject);
+        allowlist.allow("udp");
+        assert_eq!(
+            allowlist
+                .decode::<k256::ecdsa::SigningKey>(&rlp::encode(&enr))
+                .unwrap_err(),
+            EnrError::DisallowedKeys(vec!["eth2".into(), "payload".into()])
+        );
+
+        allowlist.allow("eth2").allow("payload");
+        match allowlist.apply(enr.clone()).unwrap() {
+            Filtered::Allowed(allowed) => assert_eq!(allowed, enr),
+            Filtered::Stripped(stripped) => panic!("unexpected strip: {:?}", stripped),
+        }
+    }
+
+    #[test]
+    fn test_strip() {
+        let enr = record();
+        let mut allowlist = KeyAllowlist::new(DisallowedKeyAction::Strip);
+        allowlist.allow("udp").allow("eth2");
+
+        let record = match allowlist
+            .decode::<k256::ecdsa::SigningKey>(&rlp::encode(&enr))
+            .unwrap()
+        {
+            Filtered::Stripped(record) => record,
+            Filtered::Allowed(enr) => panic!("unexpected record: {}", enr),
+        };
+        assert_eq!(record.stripped(), [b"payload".to_vec()]);
+        assert_eq!(record.node_id(), enr.node_id());
+        assert_eq!(record.seq(), enr.seq());
+        assert_eq!(record.get_raw_rlp("payload"), None);
+        assert_eq!(record.get_decodable::<u16>("udp"), Some(Ok(30303)));
+        assert_eq!(record.iter().count(), enr.iter().count() - 1);
+    }
+}
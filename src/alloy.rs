@@ -0,0 +1,98 @@
+//! `alloy-rlp` encoding of records.
+//!
+//! Records implement the `Encodable` and `Decodable` traits of `alloy-rlp`, so they can be fields
+//! of types deriving them, e.g. in messages of the reth ecosystem, without going through bytes.
+//! Decoding verifies the record like [`rlp::Decodable`].
+
+use crate::{Enr, EnrKey, IdentityScheme};
+use alloy_rlp::{BufMut, Decodable, Encodable, Error, Header};
+use rlp::DecoderError;
+
+impl<K: EnrKey, S: IdentityScheme> Encodable for Enr<K, S> {
+    fn encode(&self, out: &mut dyn BufMut) {
+        self.encode_to_buf(&mut &mut *out);
+    }
+
+    fn length(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Decodable for Enr<K, S> {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = *buf;
+        let header = Header::decode(&mut payload)?;
+        if !header.list {
+            return Err(Error::UnexpectedString);
+        }
+        let len = buf.len() - payload.len() + header.payload_length;
+        let record = buf.get(..len).ok_or(Error::InputTooShort)?;
+        let enr = rlp::decode(record).map_err(decoder_error)?;
+        *buf = &buf[len..];
+        Ok(enr)
+    }
+}
+
+/// The `alloy-rlp` error closest to an error of the `rlp` crate.
+fn decoder_error(error: DecoderError) -> Error {
+    match error {
+        DecoderError::RlpIsTooShort | DecoderError::RlpIsTooBig => Error::InputTooShort,
+        DecoderError::RlpExpectedToBeList => Error::UnexpectedString,
+        DecoderError::RlpExpectedToBeData => Error::UnexpectedList,
+        DecoderError::RlpDataLenWithZeroPrefix | DecoderError::RlpListLenWithZeroPrefix => {
+            Error::LeadingZero
+        }
+        DecoderError::RlpInvalidIndirection => Error::NonCanonicalSingleByte,
+        DecoderError::Custom(reason) => Error::Custom(reason),
+        _ => Error::Custom("invalid ENR encoding"),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_alloy_rlp_round_trip() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let other = DefaultEnr::empty(&key).unwrap();
+
+        let encoded = alloy_rlp::encode(&enr);
+        assert_eq!(encoded, rlp::encode(&enr).to_vec());
+        assert_eq!(enr.length(), encoded.len());
+
+        // records within a list, followed by other data
+        let records = vec![enr.clone(), other.clone()];
+        let mut encoded = alloy_rlp::encode(&records);
+        7_u64.encode(&mut encoded);
+        let buf = &mut encoded.as_slice();
+        assert_eq!(Vec::<DefaultEnr>::decode(buf).unwrap(), records);
+        assert_eq!(u64::decode(buf).unwrap(), 7);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_alloy_rlp_invalid() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut encoded = alloy_rlp::encode(DefaultEnr::empty(&key).unwrap());
+
+        assert_eq!(
+            DefaultEnr::decode(&mut &encoded[..encoded.len() - 1]),
+            Err(Error::InputTooShort)
+        );
+        assert_eq!(
+            DefaultEnr::decode(&mut alloy_rlp::encode("enr").as_slice()),
+            Err(Error::UnexpectedString)
+        );
+        // a byte of the signature, after the list and string headers
+        encoded[5] ^= 0x01;
+        assert_eq!(
+            DefaultEnr::decode(&mut encoded.as_slice()),
+            Err(Error::Custom("Invalid Signature"))
+        );
+    }
+}
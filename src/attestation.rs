@@ -0,0 +1,58 @@
+//! Attestations of a record by a secondary key.
+//!
+//! Gateways and relays republishing the records of the nodes they serve can countersign them.
+//! [`Enr::attest`] signs the content of a record with a secondary key, through
+//! [`EnrKey::sign_message`], and stores the signature under the [`ATTESTATION_KEY`] of the record.
+//! The field is part of the signed content, so the record is re-signed with its own key in the
+//! process and stays valid under its identity scheme.
+//!
+//! The attestation covers the node id and every field of the record except the attestation
+//! itself, but not the sequence number. It verifies with [`Enr::verify_attestation`] until any
+//! other field changes. Attesting a record again replaces its attestation.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, EnrKey};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let relay_key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//!
+//! enr.attest(&relay_key, &key).unwrap();
+//! assert!(enr.verify());
+//! assert!(enr.verify_attestation(&relay_key.public()));
+//!
+//! enr.set_udp4(30304, &key).unwrap();
+//! assert!(!enr.verify_attestation(&relay_key.public()));
+//! # }
+//! ```
+//!
+//! [`Enr::attest`]: crate::Enr::attest
+//! [`Enr::verify_attestation`]: crate::Enr::verify_attestation
+//! [`EnrKey::sign_message`]: crate::EnrKey::sign_message
+
+use crate::{Key, NodeId, Value};
+use bytes::BytesMut;
+use rlp::RlpStream;
+use std::collections::BTreeMap;
+
+/// The key the attestation of a record is stored under.
+pub const ATTESTATION_KEY: &str = "attsig";
+
+/// The message signed by an attestation: the RLP list `["enr-attestation", node_id, [k, v, ...]]`
+/// of the keys and values of the record, without the attestation.
+pub(crate) fn message(node_id: &NodeId, content: &BTreeMap<Key, Value>) -> BytesMut {
+    let attested = content.len() - usize::from(content.contains_key(ATTESTATION_KEY.as_bytes()));
+    let mut stream = RlpStream::new_list(3);
+    stream.append(&"enr-attestation");
+    stream.append(&node_id.raw().as_ref());
+    stream.begin_list(attested * 2);
+    for (key, value) in content {
+        if key.as_slice() != ATTESTATION_KEY.as_bytes() {
+            stream.append(&key.as_slice());
+            // values are raw RLP encoded data
+            stream.append_raw(value, 1);
+        }
+    }
+    stream.out()
+}
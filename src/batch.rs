@@ -0,0 +1,45 @@
+//! Parallel construction of many ENRs.
+//!
+//! Simulation tooling often needs to create a large number of node identities. Building and
+//! signing each record is independent, so this module spreads the work over a thread pool using
+//! [`rayon`].
+
+use crate::{builder::Builder, Enr, EnrError, EnrKey};
+use rayon::prelude::*;
+
+/// Builds one record per key from a common `template`, signing the records in parallel.
+///
+/// The results are returned in the order of the keys.
+pub fn build_many<'a, K: EnrKey>(
+    keys: impl IntoParallelIterator<Item = &'a K>,
+    template: &Builder<K>,
+) -> Vec<Result<Enr<K>, EnrError>> {
+    keys.into_par_iter()
+        .map(|key| template.clone().build(key))
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_build_many() {
+        let keys: Vec<_> = (0..16)
+            .map(|_| k256::ecdsa::SigningKey::random(&mut rand::thread_rng()))
+            .collect();
+        let mut template = Enr::builder();
+        template.ip4(Ipv4Addr::LOCALHOST).udp4(30303);
+
+        let records = build_many(&keys, &template);
+        assert_eq!(records.len(), keys.len());
+        for (key, enr) in keys.iter().zip(records) {
+            let enr = enr.unwrap();
+            assert!(enr.verify());
+            assert_eq!(enr.public_key(), *key.verifying_key());
+            assert_eq!(enr.udp4_socket(), Some("127.0.0.1:30303".parse().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Decodes, verifies and updates records from the command line. See [`enr::cli`].
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match enr::cli::run(std::env::args().skip(1), &mut std::io::stdout()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
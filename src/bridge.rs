@@ -0,0 +1,126 @@
+//! Conversion between records of different versions of this crate.
+//!
+//! Applications may depend on two versions of this crate at once, for example directly and
+//! through `discv5`. Their record types are distinct, so records have to be converted between
+//! them. Rather than going through the base64 text form, records can be converted by their RLP
+//! encoding with [`convert`], which works for any record type implementing the `rlp` traits. The
+//! decoding side verifies the signature as usual, so conversions never produce unverified records.
+//!
+//! [`RecordView`] provides read access to a record using only `std` types, so that code can
+//! inspect records without depending on the concrete version of the record type. The trait is
+//! defined in the `enr-compat` crate, so that all versions of this crate implement the same trait.
+//! Records are turned back into an [`Enr`] with its `TryFrom<&dyn RecordView>` implementation.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{bridge::{self, RecordView}, k256::ecdsa::SigningKey, Enr};
+//! use std::convert::TryFrom;
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//!
+//! // e.g. from the record type of another version of this crate
+//! let converted: Enr<SigningKey> = bridge::convert(&enr).unwrap();
+//! assert_eq!(converted, enr);
+//!
+//! let view: &dyn RecordView = &enr;
+//! assert_eq!(view.seq(), 1);
+//! assert_eq!(Enr::<SigningKey>::try_from(view).unwrap(), enr);
+//! # }
+//! ```
+
+use crate::{Enr, EnrKey, IdentityScheme, Key};
+pub use enr_compat::RecordView;
+use rlp::{Decodable, DecoderError, Encodable};
+use std::convert::TryFrom;
+
+impl<K: EnrKey, S: IdentityScheme> RecordView for Enr<K, S> {
+    fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    fn node_id(&self) -> [u8; 32] {
+        self.node_id.raw()
+    }
+
+    fn get_raw_rlp(&self, key: &[u8]) -> Option<&[u8]> {
+        self.content.get(key).map(AsRef::as_ref)
+    }
+
+    fn keys(&self) -> Vec<&[u8]> {
+        self.content.keys().map(Key::as_slice).collect()
+    }
+
+    fn to_rlp(&self) -> Vec<u8> {
+        rlp::encode(self).to_vec()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> TryFrom<&dyn RecordView> for Enr<K, S> {
+    type Error = DecoderError;
+
+    /// Decodes the record from its RLP encoding, verifying its signature.
+    fn try_from(view: &dyn RecordView) -> Result<Self, DecoderError> {
+        rlp::decode(&view.to_rlp())
+    }
+}
+
+/// Converts a record into another record type by its RLP encoding.
+///
+/// The target type decodes the encoding, which for records of any version of this crate verifies
+/// the signature.
+pub fn convert<T: Decodable>(record: &impl Encodable) -> Result<T, DecoderError> {
+    rlp::decode(&rlp::encode(record))
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    /// Stands in for the record type of another version of this crate.
+    struct Foreign(Vec<u8>);
+
+    impl Encodable for Foreign {
+        fn rlp_append(&self, s: &mut rlp::RlpStream) {
+            s.append_raw(&self.0, 1);
+        }
+    }
+
+    impl Decodable for Foreign {
+        fn decode(rlp: &rlp::Rlp) -> Result<Self, DecoderError> {
+            Ok(Self(rlp.as_raw().to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().tcp4(9000).build(&key).unwrap();
+
+        let foreign: Foreign = convert(&enr).unwrap();
+        let back: DefaultEnr = convert(&foreign).unwrap();
+        assert_eq!(back, enr);
+
+        // conversions verify the signature
+        let mut tampered = foreign.0;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(convert::<DefaultEnr>(&Foreign(tampered)).is_err());
+    }
+
+    #[test]
+    fn test_record_view() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().tcp4(9000).build(&key).unwrap();
+        let view: &dyn RecordView = &enr;
+
+        assert_eq!(view.seq(), enr.seq());
+        assert_eq!(view.node_id(), enr.node_id().raw());
+        assert_eq!(view.get_raw_rlp(b"tcp"), enr.get_raw_rlp("tcp"));
+        assert_eq!(view.keys(), [&b"id"[..], b"secp256k1", b"tcp"]);
+        assert_eq!(DefaultEnr::try_from(view).unwrap(), enr);
+    }
+}
@@ -1,14 +1,26 @@
-use crate::{Enr, EnrError, EnrKey, EnrPublicKey, Key, NodeId, MAX_ENR_SIZE};
+#[cfg(feature = "async-signing")]
+use crate::AsyncEnrSigner;
+use crate::{
+    check_spec_reserved_keys, multiaddr, signer::check_signature, storage, AliasTable,
+    ConsistencyPolicy, Endpoint, Enr, EnrError, EnrKey, EnrPublicKey, EnrSigner, Genesis,
+    IdentityScheme, Key, RecordFlags, SeqPolicy, SignatureMode, SigningError, Transport, V4Scheme,
+    Value, FLAGS_KEY, GENESIS_KEY, MAX_ENR_SIZE,
+};
+#[cfg(feature = "eth2")]
+use crate::{
+    AttestationSubnets, EnrForkId, SyncCommitteeSubnets, ATTNETS_KEY, ETH2_KEY, SYNCNETS_KEY,
+};
 use bytes::{Bytes, BytesMut};
 use rlp::{Encodable, RlpStream};
 use std::{
     collections::BTreeMap,
     marker::PhantomData,
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, OnceLock},
 };
 
 /// The base builder for generating ENR records with arbitrary signing algorithms.
-pub struct Builder<K: EnrKey> {
+pub struct Builder<K: EnrKey, S: IdentityScheme = V4Scheme> {
     /// The identity scheme used to build the ENR record.
     id: String,
 
@@ -17,42 +29,169 @@ pub struct Builder<K: EnrKey> {
 
     /// The key-value pairs for the ENR record.
     /// Values are stored as RLP encoded bytes.
-    content: BTreeMap<Key, Bytes>,
+    content: BTreeMap<Key, Value>,
+
+    /// The policy enforced on the ip and port fields of the built record.
+    consistency_policy: ConsistencyPolicy,
+
+    /// The policy choosing the sequence number of the built record and its modifications.
+    seq_policy: SeqPolicy,
+
+    /// The aliases of the built record.
+    alias_table: Option<Arc<AliasTable>>,
+
+    /// The keys designated immutable by the genesis of the built record, if any.
+    immutable_keys: Option<Vec<Key>>,
+
+    /// How the built record is signed.
+    signature_mode: SignatureMode,
 
-    /// Pins the generic key types.
-    phantom: PhantomData<K>,
+    /// The IP address of each family set by [`Builder::socket`], to detect conflicting sockets.
+    endpoint_ips: BTreeMap<&'static str, IpAddr>,
+
+    /// The first error of the sockets and values added to the builder, reported when building.
+    error: Option<EnrError>,
+
+    /// Pins the generic key and identity scheme types.
+    phantom: PhantomData<(K, fn() -> S)>,
+}
+
+impl<K: EnrKey, S: IdentityScheme> Clone for Builder<K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            seq: self.seq,
+            content: self.content.clone(),
+            consistency_policy: self.consistency_policy,
+            seq_policy: self.seq_policy,
+            alias_table: self.alias_table.clone(),
+            immutable_keys: self.immutable_keys.clone(),
+            signature_mode: self.signature_mode,
+            endpoint_ips: self.endpoint_ips.clone(),
+            error: self.error.clone(),
+            phantom: PhantomData,
+        }
+    }
 }
 
-impl<K: EnrKey> Default for Builder<K> {
-    /// Constructs a minimal [`Builder`] for the v4 identity scheme.
+impl<K: EnrKey, S: IdentityScheme> Default for Builder<K, S> {
+    /// Constructs a minimal [`Builder`] for the identity scheme `S`.
     fn default() -> Self {
         Self {
-            id: String::from("v4"),
+            id: String::from(S::ID),
             seq: 1,
             content: BTreeMap::new(),
+            consistency_policy: ConsistencyPolicy::default(),
+            seq_policy: SeqPolicy::default(),
+            alias_table: None,
+            immutable_keys: None,
+            signature_mode: SignatureMode::default(),
+            endpoint_ips: BTreeMap::new(),
+            error: None,
             phantom: PhantomData,
         }
     }
 }
 
-impl<K: EnrKey> Builder<K> {
+impl<K: EnrKey, S: IdentityScheme> Builder<K, S> {
     /// Modifies the sequence number of the builder.
     pub fn seq(&mut self, seq: u64) -> &mut Self {
         self.seq = seq;
         self
     }
 
+    /// Sets the [`ConsistencyPolicy`] checked when building the record and enforced on later
+    /// modifications of it.
+    pub fn consistency_policy(&mut self, policy: ConsistencyPolicy) -> &mut Self {
+        self.consistency_policy = policy;
+        self
+    }
+
+    /// Sets the [`SeqPolicy`] of the built record and its later modifications. The built record
+    /// starts at the sequence number of the builder under [`SeqPolicy::Increment`], at the current
+    /// unix time if larger under [`SeqPolicy::UnixTimestamp`] and at the given sequence number
+    /// under [`SeqPolicy::Explicit`].
+    pub fn seq_policy(&mut self, policy: SeqPolicy) -> &mut Self {
+        self.seq_policy = policy;
+        self
+    }
+
+    /// Sets the [`AliasTable`] of the built record.
+    pub fn alias_table(&mut self, alias_table: Arc<AliasTable>) -> &mut Self {
+        self.alias_table = Some(alias_table);
+        self
+    }
+
+    /// Sets the [`SignatureMode`] of the built record. Recoverable modes are only supported by
+    /// the "v4" identity scheme and by keys implementing [`EnrKey::sign_v4_recoverable`].
+    pub fn signature_mode(&mut self, mode: SignatureMode) -> &mut Self {
+        self.signature_mode = mode;
+        self
+    }
+
+    /// Designates fields whose values must never change in later versions of the built record.
+    ///
+    /// A [`Genesis`] recording the keys and a digest of their values is stored under
+    /// [`GENESIS_KEY`] when the record is built. Designated keys that are not set are recorded as
+    /// absent and can never be added.
+    pub fn immutable_fields(
+        &mut self,
+        keys: impl IntoIterator<Item = impl AsRef<[u8]>>,
+    ) -> &mut Self {
+        self.immutable_keys
+            .get_or_insert_with(Vec::new)
+            .extend(keys.into_iter().map(|key| Key::from(key.as_ref())));
+        self
+    }
+
     /// Adds an arbitrary key-value to the `ENRBuilder`.
+    ///
+    /// Building fails before signing if the key was already set to a different value, with
+    /// [`EnrError::ConflictingValue`], or with [`EnrError::UnsupportedIdentityScheme`] for an `id`
+    /// other than the builder's identity scheme. It also fails with [`EnrError::ValueTooLarge`]
+    /// if the key and value alone exceed [`MAX_ENR_SIZE`].
     pub fn add_value<T: Encodable>(&mut self, key: impl AsRef<[u8]>, value: &T) -> &mut Self {
         self.add_value_rlp(key, rlp::encode(value).freeze())
     }
 
-    /// Adds an arbitrary key-value where the value is raw RLP encoded bytes.
+    /// Adds an arbitrary key-value where the value is raw RLP encoded bytes, checked like
+    /// [`Builder::add_value`].
     pub fn add_value_rlp(&mut self, key: impl AsRef<[u8]>, rlp: Bytes) -> &mut Self {
-        self.content.insert(key.as_ref().to_vec(), rlp);
+        let key = key.as_ref();
+        if let Err(error) = self.check_value(key, &rlp) {
+            self.error.get_or_insert(error);
+        }
+        self.content.insert(Key::from(key), storage::value(rlp));
         self
     }
 
+    /// Checks a value added under `key` against the identity scheme, the previous value of the
+    /// key and the maximum record size.
+    fn check_value(&self, key: &[u8], rlp: &Bytes) -> Result<(), EnrError> {
+        let name = || String::from_utf8_lossy(key).into_owned();
+        if key == b"id" && rlp::Rlp::new(rlp).data().ok() != Some(self.id.as_bytes()) {
+            return Err(EnrError::UnsupportedIdentityScheme);
+        }
+        if self
+            .content
+            .get(key)
+            .is_some_and(|previous| previous[..] != rlp[..])
+        {
+            return Err(EnrError::ConflictingValue(name()));
+        }
+        if key.len() + rlp.len() > MAX_ENR_SIZE {
+            return Err(EnrError::ValueTooLarge(name()));
+        }
+        Ok(())
+    }
+
+    /// Adds an arbitrary key-value where the value is a pre-encoded RLP item, such as a list.
+    ///
+    /// The value is checked when the record is built with [`Builder::try_build`].
+    pub fn add_raw_rlp(&mut self, key: impl AsRef<[u8]>, rlp: Bytes) -> &mut Self {
+        self.add_value_rlp(key, rlp)
+    }
+
     /// Adds an `ip`/`ip6` field to the `ENRBuilder`.
     pub fn ip(&mut self, ip: IpAddr) -> &mut Self {
         match ip {
@@ -96,12 +235,104 @@ impl<K: EnrKey> Builder<K> {
         self
     }
 
+    /// Adds the IP address and the port of `transport` of `socket` to the `ENRBuilder`, e.g.
+    /// under the `ip6` and `tcp6` fields for an IPv6 TCP socket.
+    ///
+    /// Sockets of the same IP address family must share the same IP address, otherwise building
+    /// fails with [`EnrError::ConflictingEndpoints`].
+    pub fn socket(&mut self, transport: Transport, socket: SocketAddr) -> &mut Self {
+        let endpoint = Endpoint::new(transport, socket);
+        if let Some(ip) = self.endpoint_ips.insert(endpoint.ip_key(), endpoint.ip) {
+            if ip != endpoint.ip {
+                self.error.get_or_insert(EnrError::ConflictingEndpoints);
+            }
+        }
+        self.ip(endpoint.ip)
+            .add_value(endpoint.port_key(), &endpoint.port)
+    }
+
+    /// Adds each socket like [`Builder::socket`], so that a dual-stack node can advertise all of
+    /// its IPv4 and IPv6 sockets at once.
+    pub fn sockets(&mut self, sockets: &[(Transport, SocketAddr)]) -> &mut Self {
+        for (transport, socket) in sockets {
+            self.socket(*transport, *socket);
+        }
+        self
+    }
+
+    /// Adds the sockets of the text form of a libp2p multiaddr like [`Builder::socket`], mapping
+    /// its `/ip4`, `/ip6`, `/tcp`, `/udp` and `/quic-v1` components to the matching fields.
+    ///
+    /// A `/p2p` component is skipped. If the multiaddr has other protocols or no socket, building
+    /// fails with [`EnrError::InvalidMultiaddr`].
+    pub fn multiaddr(&mut self, multiaddr: &str) -> &mut Self {
+        match multiaddr::sockets(multiaddr) {
+            Ok(sockets) => self.sockets(&sockets),
+            Err(error) => {
+                self.error.get_or_insert(error);
+                self
+            }
+        }
+    }
+
+    /// Adds a `quic` field to the `ENRBuilder`.
+    pub fn quic4(&mut self, quic: u16) -> &mut Self {
+        self.add_value("quic", &quic);
+        self
+    }
+
+    /// Adds a `quic6` field to the `ENRBuilder`.
+    pub fn quic6(&mut self, quic: u16) -> &mut Self {
+        self.add_value("quic6", &quic);
+        self
+    }
+
+    /// Adds the IP address and QUIC port of `socket` to the `ENRBuilder`, under the `ip` and
+    /// `quic` or the `ip6` and `quic6` fields.
+    pub fn quic_socket(&mut self, socket: SocketAddr) -> &mut Self {
+        self.socket(Transport::Quic, socket)
+    }
+
     /// Adds a `udp` field to the `ENRBuilder`.
     pub fn udp4(&mut self, udp: u16) -> &mut Self {
         self.add_value("udp", &udp);
         self
     }
 
+    /// Adds an `eth2` field with the SSZ encoded `fork_id` to the `ENRBuilder`.
+    #[cfg(feature = "eth2")]
+    pub fn eth2_fork_id(&mut self, fork_id: EnrForkId) -> &mut Self {
+        self.add_value(ETH2_KEY, &fork_id);
+        self
+    }
+
+    /// Adds the fields of a consensus layer node to the `ENRBuilder`: the `eth2` field with the
+    /// SSZ encoded `fork_id` and the `attnets` and `syncnets` fields with the SSZ encoded subnet
+    /// bitfields.
+    ///
+    /// The types fix the size of each field, together 49 bytes of the record. A record signed
+    /// with a secp256k1 key holding these fields and an IPv4 and an IPv6 address with `tcp`,
+    /// `udp` and `quic` ports each is 244 bytes, leaving 56 bytes for custom fields before
+    /// [`Builder::build`] fails with [`EnrError::ExceedsMaxSize`].
+    #[cfg(feature = "eth2")]
+    pub fn consensus_defaults(
+        &mut self,
+        fork_id: EnrForkId,
+        attnets: AttestationSubnets,
+        syncnets: SyncCommitteeSubnets,
+    ) -> &mut Self {
+        self.add_value(ETH2_KEY, &fork_id);
+        self.add_value(ATTNETS_KEY, &attnets);
+        self.add_value(SYNCNETS_KEY, &syncnets);
+        self
+    }
+
+    /// Adds a `flags` field to the `ENRBuilder`.
+    pub fn flags(&mut self, flags: RecordFlags) -> &mut Self {
+        self.add_value(FLAGS_KEY, &flags.bits());
+        self
+    }
+
     /// Adds a `udp6` field to the `ENRBuilder`.
     pub fn udp6(&mut self, udp: u16) -> &mut Self {
         self.add_value("udp6", &udp);
@@ -114,36 +345,53 @@ impl<K: EnrKey> Builder<K> {
         stream.begin_list(self.content.len() * 2 + 1);
         stream.append(&self.seq);
         for (k, v) in &self.content {
-            stream.append(k);
+            stream.append(&k.as_slice());
             // The values are stored as raw RLP encoded bytes
             stream.append_raw(v, 1);
         }
         stream.out()
     }
 
-    /// Signs record based on the identity scheme. Currently only "v4" is supported.
+    /// Signs record based on the identity scheme `S`.
     fn signature(&self, key: &K) -> Result<Vec<u8>, EnrError> {
-        match self.id.as_str() {
-            "v4" => key
-                .sign_v4(&self.rlp_content())
-                .map_err(|_| EnrError::SigningError),
+        if self.id != S::ID {
             // unsupported identity schemes
-            _ => Err(EnrError::SigningError),
+            return Err(EnrError::SigningError);
         }
+        self.signature_mode.sign::<K, S>(key, &self.rlp_content())
     }
 
-    /// Adds a public key to the ENR builder.
+    /// Adds a public key to the ENR builder, replacing the key of a previous build.
     fn add_public_key(&mut self, key: &K::PublicKey) {
-        self.add_value(key.enr_key(), &key.encode().as_ref());
+        self.content.insert(
+            Key::from(key.enr_key()),
+            storage::value(rlp::encode(&key.encode().as_ref()).freeze()),
+        );
     }
 
     /// Constructs an ENR from the [`Builder`].
     ///
     /// # Errors
-    /// Fails if the identity scheme is not supported, or the record size exceeds `MAX_ENR_SIZE`.
-    pub fn build(&mut self, key: &K) -> Result<Enr<K>, EnrError> {
+    /// Fails if the identity scheme is not supported, the record size exceeds `MAX_ENR_SIZE`, a
+    /// value added to the builder is rejected as described in [`Builder::add_value`] or the
+    /// content violates the builder's [`ConsistencyPolicy`]. Apart from the size of the signature,
+    /// all of this is checked before signing.
+    pub fn build(&mut self, key: &K) -> Result<Enr<K, S>, EnrError> {
+        let public_key = key.public();
+        let rlp_content = self.prepare(&public_key)?;
+        let signature = self.signature(key)?;
+        self.finish(&public_key, rlp_content.len(), signature)
+    }
+
+    /// Validates the content, resolves the sequence number and adds the identity scheme, the public
+    /// key and the genesis, returning the RLP encoded content to sign.
+    fn prepare(&mut self, public_key: &K::PublicKey) -> Result<BytesMut, EnrError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
         // add the identity scheme to the content
-        if self.id != "v4" {
+        if self.id != S::ID {
             return Err(EnrError::UnsupportedIdentityScheme);
         }
 
@@ -156,24 +404,129 @@ impl<K: EnrKey> Builder<K> {
             }
         }
 
-        self.add_value_rlp("id", rlp::encode(&self.id.as_bytes()).freeze());
+        self.consistency_policy.check(&self.content)?;
+        self.seq = self.seq_policy.initial(self.seq);
 
-        self.add_public_key(&key.public());
-        let rlp_content = self.rlp_content();
+        self.content.insert(
+            Key::from(&b"id"[..]),
+            storage::value(rlp::encode(&self.id.as_bytes()).freeze()),
+        );
 
-        let signature = self.signature(key)?;
+        if self.signature_mode.stores_public_key() {
+            self.add_public_key(public_key);
+        }
+        if let Some(immutable_keys) = &self.immutable_keys {
+            let genesis = Genesis::new(immutable_keys.iter().cloned(), &self.content);
+            self.content.insert(
+                Key::from(GENESIS_KEY.as_bytes()),
+                storage::value(genesis.encode()),
+            );
+        }
 
+        // the record can't fit even before adding the signature
+        let rlp_content = self.rlp_content();
+        if rlp_content.len() + 8 > MAX_ENR_SIZE {
+            return Err(EnrError::ExceedsMaxSize);
+        }
+        Ok(rlp_content)
+    }
+
+    /// Constructs the ENR from the prepared content and its signature.
+    fn finish(
+        &self,
+        public_key: &K::PublicKey,
+        rlp_content_len: usize,
+        signature: Vec<u8>,
+    ) -> Result<Enr<K, S>, EnrError> {
         // check the size of the record
-        if rlp_content.len() + signature.len() + 8 > MAX_ENR_SIZE {
+        if rlp_content_len + signature.len() + 8 > MAX_ENR_SIZE {
             return Err(EnrError::ExceedsMaxSize);
         }
 
         Ok(Enr {
             seq: self.seq,
-            node_id: NodeId::from(key.public()),
+            node_id: S::node_id::<K>(public_key),
             content: self.content.clone(),
             signature,
+            consistency_policy: self.consistency_policy,
+            seq_policy: self.seq_policy,
+            alias_table: self.alias_table.clone(),
+            encoded: OnceLock::new(),
+            content_hash: OnceLock::new(),
             phantom: PhantomData,
         })
     }
+
+    /// Constructs an ENR from the [`Builder`], signed by `signer`.
+    ///
+    /// # Errors
+    /// Fails like [`Builder::build`], or with [`EnrError::SigningError`] if the signer fails or
+    /// returns an invalid signature.
+    pub fn build_with_signer(
+        &mut self,
+        signer: &impl EnrSigner<PublicKey = K::PublicKey>,
+    ) -> Result<Enr<K, S>, EnrError> {
+        let public_key = signer.public_key();
+        let content = self.prepare(&public_key)?;
+        let signature = self.external_signature(&public_key, &content, signer.sign_v4(&content))?;
+        self.finish(&public_key, content.len(), signature)
+    }
+
+    /// Constructs an ENR from the [`Builder`], signed by an asynchronous `signer`.
+    ///
+    /// # Errors
+    /// Fails like [`Builder::build_with_signer`].
+    #[cfg(feature = "async-signing")]
+    pub async fn build_async(
+        &mut self,
+        signer: &impl AsyncEnrSigner<PublicKey = K::PublicKey>,
+    ) -> Result<Enr<K, S>, EnrError> {
+        let public_key = signer.public_key();
+        let content = self.prepare(&public_key)?;
+        let signature = signer.sign_v4(&content).await;
+        let signature = self.external_signature(&public_key, &content, signature)?;
+        self.finish(&public_key, content.len(), signature)
+    }
+
+    /// Accepts the signature of an external signer, which only supports the "v4" identity scheme.
+    fn external_signature(
+        &self,
+        public_key: &K::PublicKey,
+        content: &[u8],
+        signature: Result<Vec<u8>, SigningError>,
+    ) -> Result<Vec<u8>, EnrError> {
+        if S::ID != V4Scheme::ID {
+            return Err(EnrError::UnsupportedIdentityScheme);
+        }
+        let signature = check_signature(public_key, content, signature)?;
+        self.signature_mode.check(&signature)?;
+        Ok(signature)
+    }
+
+    /// Constructs an ENR from the [`Builder`], first validating every value like
+    /// [`Enr::insert_raw_rlp`] does.
+    ///
+    /// # Errors
+    /// Fails like [`Builder::build`], or if a value is not a single well-formed RLP item or
+    /// violates the format of a key reserved by the specification.
+    pub fn try_build(&mut self, key: &K) -> Result<Enr<K, S>, EnrError> {
+        for (field, value) in &self.content {
+            let rlp = rlp::Rlp::new(value);
+            let well_formed = rlp
+                .payload_info()
+                .is_ok_and(|info| info.header_len + info.value_len == value.len());
+            if !well_formed {
+                return Err(EnrError::InvalidRlpData(
+                    String::from_utf8_lossy(field).into(),
+                ));
+            }
+            check_spec_reserved_keys::<S>(field, value)?;
+            if let Some(alias_table) = &self.alias_table {
+                for alias in alias_table.aliases_of(field) {
+                    check_spec_reserved_keys::<S>(alias, value)?;
+                }
+            }
+        }
+        self.build(key)
+    }
 }
@@ -0,0 +1,211 @@
+//! Records bundled with signed advertisement payloads.
+//!
+//! Topic discovery experiments need nodes to advertise more data than fits into the 300 byte limit
+//! of a record. A [`SignedBundle`] carries a base record together with several small payloads, such
+//! as topic advertisements. The payloads are covered by a single signature of the record's key, so
+//! adding a payload costs only its own size rather than a signature per payload.
+//!
+//! A bundle is encoded as the RLP list `[signature, record, [payload, ...]]`. The signature is
+//! created with [`EnrKey::sign_message`] over the RLP list `["enr-bundle", seq, [payload, ...]]`,
+//! which binds the payloads to the sequence number of the record they were published with.
+
+use crate::{Enr, EnrError, EnrKey, EnrPublicKey};
+use bytes::{Bytes, BytesMut};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use subtle::ConstantTimeEq;
+
+/// A record with payloads signed by the record's key.
+#[derive(Debug)]
+pub struct SignedBundle<K: EnrKey> {
+    enr: Enr<K>,
+    payloads: Vec<Bytes>,
+    signature: Vec<u8>,
+}
+
+impl<K: EnrKey> Clone for SignedBundle<K> {
+    fn clone(&self) -> Self {
+        Self {
+            enr: self.enr.clone(),
+            payloads: self.payloads.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+impl<K: EnrKey> PartialEq for SignedBundle<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.enr == other.enr
+            && self.payloads == other.payloads
+            && bool::from(self.signature.ct_eq(&other.signature))
+    }
+}
+
+impl<K: EnrKey> Eq for SignedBundle<K> {}
+
+impl<K: EnrKey> SignedBundle<K> {
+    /// Bundles the payloads with the record, signing them with `key`.
+    ///
+    /// Fails with [`EnrError::PublicKeyMismatch`] if `key` is not the key of the record.
+    pub fn new(enr: Enr<K>, payloads: Vec<Bytes>, key: &K) -> Result<Self, EnrError> {
+        if key.public().encode().as_ref() != enr.public_key().encode().as_ref() {
+            return Err(EnrError::PublicKeyMismatch);
+        }
+        let signature = key
+            .sign_message(&signed_content(enr.seq(), &payloads))
+            .map_err(|_| EnrError::SigningError)?;
+        Ok(Self {
+            enr,
+            payloads,
+            signature,
+        })
+    }
+
+    /// The base record of the bundle.
+    #[must_use]
+    pub const fn enr(&self) -> &Enr<K> {
+        &self.enr
+    }
+
+    /// The signed payloads, in order.
+    #[must_use]
+    pub fn payloads(&self) -> &[Bytes] {
+        &self.payloads
+    }
+
+    /// The signature over the payloads.
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Verifies the signatures of the record and of the payloads.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.enr.verify()
+            && self.enr.public_key().verify_message(
+                &signed_content(self.enr.seq(), &self.payloads),
+                &self.signature,
+            )
+    }
+
+    /// The RLP encoding of the bundle.
+    #[must_use]
+    pub fn encode(&self) -> BytesMut {
+        rlp::encode(self)
+    }
+
+    /// Decodes an RLP encoded bundle, verifying the record and the payload signature.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecoderError> {
+        rlp::decode(bytes)
+    }
+}
+
+impl<K: EnrKey> Encodable for SignedBundle<K> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        s.append(&self.signature);
+        s.append(&self.enr);
+        s.begin_list(self.payloads.len());
+        for payload in &self.payloads {
+            s.append(&payload.as_ref());
+        }
+    }
+}
+
+impl<K: EnrKey> Decodable for SignedBundle<K> {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 3 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let signature = rlp.val_at(0)?;
+        let enr: Enr<K> = rlp.val_at(1)?;
+        let payloads = rlp
+            .at(2)?
+            .iter()
+            .map(|payload| payload.data().map(Bytes::copy_from_slice))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bundle = Self {
+            enr,
+            payloads,
+            signature,
+        };
+        // the record is verified when decoded
+        if !bundle.enr.public_key().verify_message(
+            &signed_content(bundle.enr.seq(), &bundle.payloads),
+            &bundle.signature,
+        ) {
+            return Err(DecoderError::Custom("Invalid Signature"));
+        }
+        Ok(bundle)
+    }
+}
+
+/// The content signed by the payload signature.
+fn signed_content(seq: u64, payloads: &[Bytes]) -> BytesMut {
+    let mut s = RlpStream::new_list(3);
+    s.append(&"enr-bundle");
+    s.append(&seq);
+    s.begin_list(payloads.len());
+    for payload in payloads {
+        s.append(&payload.as_ref());
+    }
+    s.out()
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    fn payloads() -> Vec<Bytes> {
+        vec![
+            Bytes::from_static(b"topic-a"),
+            Bytes::from_static(b"topic-b"),
+            Bytes::from_static(&[0; 64]),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        let bundle = SignedBundle::new(enr, payloads(), &key).unwrap();
+        assert!(bundle.verify());
+
+        let decoded = SignedBundle::<DefaultKey>::decode(&bundle.encode()).unwrap();
+        assert_eq!(decoded, bundle);
+        assert_eq!(decoded.payloads(), payloads().as_slice());
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let other = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Enr::empty(&key).unwrap();
+        assert_eq!(
+            SignedBundle::new(enr, payloads(), &other).unwrap_err(),
+            EnrError::PublicKeyMismatch
+        );
+    }
+
+    #[test]
+    fn test_tampered() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::empty(&key).unwrap();
+        let mut bundle = SignedBundle::new(enr.clone(), payloads(), &key).unwrap();
+
+        let mut tampered = bundle.clone();
+        tampered.payloads[0] = Bytes::from_static(b"topic-c");
+        assert!(!tampered.verify());
+        assert!(SignedBundle::<DefaultKey>::decode(&tampered.encode()).is_err());
+
+        // payloads are bound to the sequence number of the record
+        enr.set_seq(5, &key).unwrap();
+        bundle.enr = enr;
+        assert!(!bundle.verify());
+        assert!(SignedBundle::<DefaultKey>::decode(&bundle.encode()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Batches of field changes applied to a record as a single update.
+//!
+//! [`FieldChanges`] collects removals, by key or by predicate, and insertions of any
+//! [`Encodable`] value ahead of an update. [`Enr::apply_changes`] applies them through the same
+//! [`Transaction`] as [`Enr::update`], with a single increment of the sequence number, and returns
+//! the [`PreviousValues`] of every field the update removed or overwrote.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, FieldChanges};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder()
+//!     .udp4(9000)
+//!     .add_value("tmp_a", &1_u8)
+//!     .add_value("tmp_b", &2_u8)
+//!     .build(&key)
+//!     .unwrap();
+//!
+//! let mut changes = FieldChanges::new();
+//! changes
+//!     .remove_if(|key| key.starts_with(b"tmp_"))
+//!     .insert("udp", &9001_u16);
+//! let previous = enr.apply_changes(&changes, &key).unwrap();
+//!
+//! assert_eq!(previous.get_decodable::<u16>("udp"), Some(Ok(9000)));
+//! assert_eq!(previous.get_decodable::<u8>("tmp_a"), Some(Ok(1)));
+//! assert_eq!(enr.udp4(), Some(9001));
+//! assert_eq!(enr.get("tmp_b"), None);
+//! assert_eq!(enr.seq(), 2);
+//! # }
+//! ```
+//!
+//! [`Enr::apply_changes`]: crate::Enr::apply_changes
+//! [`Enr::update`]: crate::Enr::update
+
+use crate::{FieldValue, IdentityScheme, Key, Transaction};
+use bytes::Bytes;
+use rlp::{Decodable, DecoderError, Encodable};
+use std::{collections::BTreeMap, fmt};
+
+/// A predicate selecting keys to remove.
+type KeyPredicate = Box<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// Removals and insertions to apply to a record as one update.
+#[derive(Default)]
+pub struct FieldChanges {
+    /// Keys to remove.
+    pub(crate) removals: Vec<Key>,
+    /// Predicates selecting further keys to remove.
+    pub(crate) predicates: Vec<KeyPredicate>,
+    /// Keys to add or overwrite, with their RLP encoded values.
+    pub(crate) insertions: Vec<(Key, Bytes)>,
+}
+
+impl FieldChanges {
+    /// Creates an empty batch of changes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes `key`, like [`Transaction::remove`].
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> &mut Self {
+        self.removals.push(Key::from(key.as_ref()));
+        self
+    }
+
+    /// Removes every key matching `predicate`, like [`Transaction::remove_if`].
+    pub fn remove_if(
+        &mut self,
+        predicate: impl Fn(&[u8]) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Adds or overwrites `key` with the RLP encoding of `value`, like [`Transaction::insert`].
+    pub fn insert<T: Encodable>(&mut self, key: impl AsRef<[u8]>, value: &T) -> &mut Self {
+        self.insert_raw_rlp(key, rlp::encode(value).freeze())
+    }
+
+    /// Adds or overwrites `key` with a raw RLP encoded value.
+    pub fn insert_raw_rlp(&mut self, key: impl AsRef<[u8]>, value: Bytes) -> &mut Self {
+        self.insertions.push((Key::from(key.as_ref()), value));
+        self
+    }
+
+    /// Whether the batch holds no changes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.removals.is_empty() && self.predicates.is_empty() && self.insertions.is_empty()
+    }
+
+    /// Records the changes in a transaction, removals before insertions.
+    pub(crate) fn apply<S: IdentityScheme>(&self, tx: &mut Transaction<'_, S>) {
+        for key in &self.removals {
+            tx.remove(key);
+        }
+        for predicate in &self.predicates {
+            tx.remove_if(predicate);
+        }
+        for (key, value) in &self.insertions {
+            tx.insert_raw_rlp(key, value.clone());
+        }
+    }
+}
+
+impl fmt::Debug for FieldChanges {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldChanges")
+            .field("removals", &self.removals)
+            .field("predicates", &self.predicates.len())
+            .field("insertions", &self.insertions)
+            .finish()
+    }
+}
+
+/// The values of the fields removed or overwritten by [`Enr::apply_changes`], as they were
+/// before the update.
+///
+/// [`Enr::apply_changes`]: crate::Enr::apply_changes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreviousValues {
+    values: BTreeMap<Key, Bytes>,
+}
+
+impl PreviousValues {
+    /// Records the value a field had before the update, unless already recorded.
+    pub(crate) fn record(&mut self, key: &[u8], value: Option<Bytes>) {
+        if let Some(value) = value {
+            self.values.entry(Key::from(key)).or_insert(value);
+        }
+    }
+
+    /// The previous raw RLP encoded value of `key`.
+    #[must_use]
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.values.get(key.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// The previous value of `key`, decoded as `T`.
+    pub fn get_decodable<T: Decodable>(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Option<Result<T, DecoderError>> {
+        self.get_raw_rlp(key).map(rlp::decode)
+    }
+
+    /// The previous values, decoded into their typed representation, in ascending key order.
+    pub fn iter_decoded(&self) -> impl Iterator<Item = FieldValue<'_>> {
+        self.values
+            .iter()
+            .map(|(key, value)| FieldValue::decode(key, value))
+    }
+
+    /// The number of fields removed or overwritten.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether no field was removed or overwritten.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
@@ -0,0 +1,176 @@
+//! A command line tool for inspecting and updating `secp256k1` records.
+//!
+//! With the `cli` feature, the `enr-cli` binary exposes [`run`]:
+//!
+//! ```text
+//! enr-cli decode <enr>
+//! enr-cli verify <enr>
+//! enr-cli update <enr> --key <hex> [--set <key>=<value>]... [--remove <key>]...
+//! ```
+//!
+//! `decode` prints the fields of a record, `verify` checks its signature and `update` applies all
+//! changes as a single update re-signed with the given private key, printing the new record.
+//! Values passed to `--set` are parsed like the fixed values of a
+//! [`RecordTemplate`](crate::RecordTemplate).
+
+use crate::{
+    check_spec_reserved_keys, k256::ecdsa::SigningKey, storage, template::parse_value, Enr,
+    FieldValue, Key, UpdateGuard, V4Scheme,
+};
+use std::io::Write;
+
+/// The usage text printed by `help`.
+pub const USAGE: &str = "\
+usage:
+    enr-cli decode <enr>
+    enr-cli verify <enr>
+    enr-cli update <enr> --key <hex> [--set <key>=<value>]... [--remove <key>]...";
+
+/// Runs the command given by `args`, which exclude the program name, writing its output to `out`.
+///
+/// Returns a human readable error if the command fails.
+pub fn run(args: impl IntoIterator<Item = String>, out: &mut impl Write) -> Result<(), String> {
+    let mut args = args.into_iter();
+    let command = args.next().unwrap_or_default();
+    let result = match command.as_str() {
+        "decode" => decode(&parse_enr(args.next())?, out),
+        "verify" => {
+            let enr = parse_enr(args.next())?;
+            writeln!(out, "valid signature, node id {}", node_id_hex(&enr))
+        }
+        "update" => {
+            let mut enr = parse_enr(args.next())?;
+            update(&mut enr, args)?;
+            writeln!(out, "{enr}")
+        }
+        "help" | "--help" | "-h" => writeln!(out, "{USAGE}"),
+        _ => return Err(format!("unknown command `{command}`\n{USAGE}")),
+    };
+    result.map_err(|e| format!("failed to write output: {e}"))
+}
+
+fn parse_enr(arg: Option<String>) -> Result<Enr<SigningKey>, String> {
+    Ok(arg
+        .ok_or_else(|| format!("missing record\n{USAGE}"))?
+        .parse()?)
+}
+
+/// The full hex encoding of the node id, which `NodeId` displays abbreviated.
+fn node_id_hex(enr: &Enr<SigningKey>) -> String {
+    format!("0x{}", hex::encode(enr.node_id().raw()))
+}
+
+/// Prints the sequence number, node id and fields of a record.
+fn decode(enr: &Enr<SigningKey>, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "{:<12}{}", "seq", enr.seq())?;
+    writeln!(out, "{:<12}{}", "node id", node_id_hex(enr))?;
+    for (key, value) in enr.iter() {
+        let field = FieldValue::decode(key, value);
+        writeln!(out, "{:<12}{}", String::from_utf8_lossy(key), field)?;
+    }
+    Ok(())
+}
+
+/// Applies the `--key`, `--set` and `--remove` options as one update of the record.
+fn update(enr: &mut Enr<SigningKey>, mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut key = None;
+    let mut update = UpdateGuard::new(enr);
+    while let Some(option) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for `{option}`"))?;
+        match option.as_str() {
+            "--key" => {
+                let secret = hex::decode(value.trim_start_matches("0x"))
+                    .map_err(|e| format!("invalid private key: {e}"))?;
+                key = Some(
+                    SigningKey::from_slice(&secret)
+                        .map_err(|e| format!("invalid private key: {e}"))?,
+                );
+            }
+            "--set" => {
+                let (field, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected `<key>=<value>`, got `{value}`"))?;
+                let value = parse_value(value).map_err(|e| format!("invalid hex: {e}"))?;
+                check_spec_reserved_keys::<V4Scheme>(field.as_bytes(), &value)
+                    .map_err(|e| format!("invalid value for `{field}`: {e}"))?;
+                update
+                    .content
+                    .insert(Key::from(field.as_bytes()), storage::value(value));
+            }
+            "--remove" => {
+                update.content.remove(value.as_bytes());
+            }
+            _ => return Err(format!("unknown option `{option}`\n{USAGE}")),
+        }
+    }
+    let key = key.ok_or_else(|| format!("missing `--key`\n{USAGE}"))?;
+    update.prune();
+    update
+        .sign(&key)
+        .map_err(|e| format!("failed to update the record: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291";
+
+    fn run_to_string(args: &[&str]) -> Result<String, String> {
+        let mut out = Vec::new();
+        run(args.iter().map(|arg| arg.to_string()), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn record() -> Enr<SigningKey> {
+        let key = SigningKey::from_slice(&hex::decode(SECRET).unwrap()).unwrap();
+        Enr::builder().udp4(30303).build(&key).unwrap()
+    }
+
+    #[test]
+    fn test_decode() {
+        let enr = record();
+        let output = run_to_string(&["decode", &enr.to_base64()]).unwrap();
+        assert!(output.starts_with("seq         1\n"));
+        assert!(output.contains(&format!("node id     {}\n", node_id_hex(&enr))));
+        assert!(output.contains("id          v4\n"));
+        assert!(output.contains("udp         30303\n"));
+
+        assert!(run_to_string(&["verify", &enr.to_base64()])
+            .unwrap()
+            .starts_with("valid signature"));
+        assert!(run_to_string(&["verify", "enr:-IS4"]).is_err());
+    }
+
+    #[test]
+    fn test_update() {
+        let enr = record();
+        let output = run_to_string(&[
+            "update",
+            &enr.to_base64(),
+            "--key",
+            SECRET,
+            "--set",
+            "ip=10.0.0.1",
+            "--set",
+            "note=hello",
+            "--remove",
+            "udp",
+        ])
+        .unwrap();
+        let updated: Enr<SigningKey> = output.trim().parse().unwrap();
+        assert_eq!(updated.seq(), 2);
+        assert_eq!(updated.ip4(), Some([10, 0, 0, 1].into()));
+        assert_eq!(updated.get_utf8("note"), Some(Ok("hello")));
+        assert_eq!(updated.udp4(), None);
+        assert_eq!(updated.node_id(), enr.node_id());
+
+        assert!(run_to_string(&["update", &enr.to_base64(), "--set", "udp=1"]).is_err());
+        assert!(
+            run_to_string(&["update", &enr.to_base64(), "--key", SECRET, "--set", "ip=1"]).is_err()
+        );
+        assert!(run_to_string(&["unknown"]).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Lossless reductions of the encoded size of a record.
+//!
+//! Values are always stored with their minimal RLP encoding, so a record can only shrink by
+//! storing the same information under fewer or shorter fields. [`Enr::compaction_report`] lists
+//! the reductions that leave every getter of the record returning the same information, and
+//! [`Enr::compact`] applies them in a single update:
+//!
+//! - An IPv4-mapped IPv6 address under `ip6` is stored as an IPv4 address under `ip`, with its
+//!   `udp6`, `tcp6` and `quic6` ports moved to their IPv4 counterparts.
+//! - A field holding the same value as one of its aliases in the record's [`AliasTable`] is
+//!   removed, as reading it falls back to the alias. Of two aliased fields, the one with the
+//!   shorter key is kept.
+//!
+//! [`Enr::compaction_report`]: crate::Enr::compaction_report
+//! [`Enr::compact`]: crate::Enr::compact
+
+use crate::{rlp_bytes_len, storage, AliasTable, IdentityScheme, Key, Transaction, Value};
+use bytes::Bytes;
+use rlp::Rlp;
+use std::{collections::BTreeMap, convert::TryFrom, net::Ipv6Addr};
+
+/// The IPv6 port keys and their IPv4 counterparts.
+pub(crate) const PORT_KEYS: [(&str, &str); 3] =
+    [("udp6", "udp"), ("tcp6", "tcp"), ("quic6", "quic")];
+
+/// A lossless reduction of the size of a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Compaction {
+    /// The IPv4-mapped address under `ip6` and its ports can be stored under the IPv4 keys.
+    Ipv4MappedIpv6 {
+        /// The IPv4-mapped address.
+        address: Ipv6Addr,
+        /// The bytes saved.
+        saving: usize,
+    },
+    /// The field `alias` holds the same value as the field `key` it is aliased to and can be
+    /// removed.
+    DuplicateAlias {
+        /// The field that is kept.
+        key: String,
+        /// The field that is removed.
+        alias: String,
+        /// The bytes saved.
+        saving: usize,
+    },
+}
+
+impl Compaction {
+    /// The number of bytes the compaction saves.
+    #[must_use]
+    pub const fn saving(&self) -> usize {
+        match self {
+            Self::Ipv4MappedIpv6 { saving, .. } | Self::DuplicateAlias { saving, .. } => *saving,
+        }
+    }
+
+    /// Adds the modifications of the compaction to `tx`.
+    pub(crate) fn apply<S: IdentityScheme>(
+        &self,
+        content: &BTreeMap<Key, Value>,
+        tx: &mut Transaction<'_, S>,
+    ) {
+        match self {
+            Self::Ipv4MappedIpv6 { address, .. } => {
+                if let Some(ip) = address.to_ipv4_mapped() {
+                    tx.remove("ip6").set_ip(ip.into());
+                }
+                for (v6, v4) in PORT_KEYS {
+                    if let Some(port) = content.get(v6.as_bytes()) {
+                        tx.remove(v6)
+                            .insert_raw_rlp(v4, Bytes::copy_from_slice(port));
+                    }
+                }
+            }
+            Self::DuplicateAlias { alias, .. } => {
+                tx.remove(alias);
+            }
+        }
+    }
+}
+
+/// The compactions applicable to a record, as returned by
+/// [`Enr::compaction_report`](crate::Enr::compaction_report).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// The encoded size of the record before compaction.
+    pub size: usize,
+    /// The applicable compactions.
+    pub compactions: Vec<Compaction>,
+}
+
+impl CompactionReport {
+    /// Finds the compactions applicable to the content of a record of `size` bytes.
+    pub(crate) fn new(
+        size: usize,
+        content: &BTreeMap<Key, Value>,
+        alias_table: Option<&AliasTable>,
+    ) -> Self {
+        let mut compactions = Vec::new();
+        if let Some(compaction) = mapped_ipv6(content) {
+            compactions.push(compaction);
+        }
+        if let Some(alias_table) = alias_table {
+            for (key, value) in content {
+                for alias in alias_table.aliases_of(key) {
+                    // each pair is reported once, from the key that is kept
+                    let keep = (key.len(), key.as_slice()) < (alias.len(), alias);
+                    if keep && content.get(alias) == Some(value) {
+                        compactions.push(Compaction::DuplicateAlias {
+                            key: String::from_utf8_lossy(key).into(),
+                            alias: String::from_utf8_lossy(alias).into(),
+                            saving: field_len(alias, value),
+                        });
+                    }
+                }
+            }
+        }
+        Self { size, compactions }
+    }
+
+    /// The total number of bytes the compactions save. The actual saving may be up to two bytes
+    /// larger, as the RLP header of the record may shrink.
+    #[must_use]
+    pub fn savings(&self) -> usize {
+        self.compactions.iter().map(Compaction::saving).sum()
+    }
+
+    /// Whether no compaction is applicable.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.compactions.is_empty()
+    }
+}
+
+/// The encoded length of a field.
+fn field_len(key: &[u8], value: &[u8]) -> usize {
+    rlp_bytes_len(key) + value.len()
+}
+
+/// The compaction of an IPv4-mapped `ip6` address, if the IPv4 fields it moves to are absent or
+/// already hold the same values.
+fn mapped_ipv6(content: &BTreeMap<Key, Value>) -> Option<Compaction> {
+    let ip6 = content.get(b"ip6".as_ref())?;
+    let address = Ipv6Addr::from(<[u8; 16]>::try_from(Rlp::new(ip6).data().ok()?).ok()?);
+    let ip4 = storage::value(rlp::encode(&address.to_ipv4_mapped()?.octets().as_ref()).freeze());
+
+    let mut moves = vec![(("ip6", ip6), ("ip", ip4))];
+    for (v6, v4) in PORT_KEYS {
+        if let Some(port) = content.get(v6.as_bytes()) {
+            moves.push(((v6, port), (v4, port.clone())));
+        }
+    }
+
+    let mut saving = 0;
+    for ((from, from_value), (to, to_value)) in moves {
+        match content.get(to.as_bytes()) {
+            None => {
+                saving += field_len(from.as_bytes(), from_value);
+                saving -= field_len(to.as_bytes(), &to_value);
+            }
+            Some(existing) if *existing == to_value => {
+                saving += field_len(from.as_bytes(), from_value);
+            }
+            Some(_) => return None,
+        }
+    }
+    Some(Compaction::Ipv4MappedIpv6 { address, saving })
+}
@@ -0,0 +1,274 @@
+//! Migration of records serialized by older versions of this crate.
+//!
+//! Older versions of this crate were more lenient when encoding and decoding records. Databases
+//! created with them may contain records that current versions reject:
+//!
+//! - Text forms with base64 padding or the standard (non URL-safe) alphabet.
+//! - Ports encoded as fixed two byte big-endian strings rather than RLP integers, e.g. a port of
+//!   30 encoded as `0x82001e` instead of `0x1e`.
+//! - Records exceeding the maximum record size of 300 bytes.
+//!
+//! [`from_legacy_bytes`] decodes such records and verifies their signature. Records that are
+//! already canonical are returned as an [`Enr`] directly. Otherwise a [`LegacyRecord`] is
+//! returned, which lists the quirks found and can be [migrated](LegacyRecord::migrate) into a
+//! canonical record by re-signing it.
+
+use crate::{
+    decode_record_fields, is_keyof_u16, storage, Enr, EnrError, EnrKey, EnrPublicKey, Key, NodeId,
+    MAX_ENR_SIZE,
+};
+use base64::{
+    engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig},
+    engine::DecodePaddingMode,
+    Engine as _,
+};
+use rlp::{DecoderError, Rlp};
+
+/// Decodes URL-safe and standard base64, with or without padding.
+const LENIENT_URL_SAFE: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+const LENIENT_STANDARD: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// The serialized form of a legacy record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegacyFormat {
+    /// The RLP encoding of the record.
+    Rlp,
+    /// The base64 text form of the record, with or without the `enr:` prefix.
+    Base64,
+}
+
+/// A deviation of a legacy record from the canonical encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LegacyQuirk {
+    /// The base64 text form is padded.
+    Base64Padding,
+    /// The base64 text form uses the standard rather than the URL-safe alphabet.
+    StandardBase64Alphabet,
+    /// The port stored under the given key is not encoded as a canonical RLP integer.
+    NonCanonicalPort(String),
+    /// The record exceeds the maximum record size.
+    ExceedsMaxSize,
+}
+
+/// The result of decoding a legacy record.
+#[derive(Debug)]
+pub enum Migration<K: EnrKey> {
+    /// The record is canonical and needs no migration.
+    Canonical(Enr<K>),
+    /// The record has a valid signature but deviates from the canonical encoding.
+    Legacy(LegacyRecord<K>),
+}
+
+/// A record with a valid signature that deviates from the canonical encoding.
+#[derive(Debug)]
+pub struct LegacyRecord<K: EnrKey> {
+    /// The record, holding the content exactly as it was signed.
+    enr: Enr<K>,
+    /// The deviations found when decoding.
+    quirks: Vec<LegacyQuirk>,
+}
+
+impl<K: EnrKey> LegacyRecord<K> {
+    /// The deviations from the canonical encoding found when decoding the record.
+    #[must_use]
+    pub fn quirks(&self) -> &[LegacyQuirk] {
+        &self.quirks
+    }
+
+    /// The sequence number of the legacy record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.enr.seq()
+    }
+
+    /// The `NodeId` of the legacy record.
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.enr.node_id()
+    }
+
+    /// Migrates the record into a canonical [`Enr`], normalizing its content and re-signing it
+    /// with an incremented sequence number.
+    ///
+    /// The `key` should be the record's own key, otherwise the migrated record has a different
+    /// `NodeId`. Fails if the normalized record exceeds the maximum record size.
+    pub fn migrate(self, key: &K) -> Result<Enr<K>, EnrError> {
+        let mut enr = self.enr;
+        enr.clear_encoded();
+        for quirk in &self.quirks {
+            if let LegacyQuirk::NonCanonicalPort(port_key) = quirk {
+                let port = enr
+                    .get(port_key)
+                    .and_then(legacy_port)
+                    .ok_or_else(|| EnrError::InvalidRlpData(port_key.clone()))?;
+                enr.content.insert(
+                    Key::from(port_key.as_bytes()),
+                    storage::value(rlp::encode(&port).freeze()),
+                );
+            }
+        }
+        let public_key = key.public();
+        enr.content.insert(
+            Key::from(public_key.enr_key()),
+            storage::value(rlp::encode(&public_key.encode().as_ref()).freeze()),
+        );
+        enr.seq = enr
+            .seq
+            .checked_add(1)
+            .ok_or(EnrError::SequenceNumberTooHigh)?;
+        enr.sign(key)?;
+        enr.node_id = NodeId::from(key.public());
+        if enr.size() > MAX_ENR_SIZE {
+            return Err(EnrError::ExceedsMaxSize);
+        }
+        Ok(enr)
+    }
+}
+
+/// Decodes a record serialized by an older version of this crate, verifying its signature.
+pub fn from_legacy_bytes<K: EnrKey>(
+    bytes: &[u8],
+    format: LegacyFormat,
+) -> Result<Migration<K>, DecoderError> {
+    let mut quirks = Vec::new();
+    let rlp_bytes = match format {
+        LegacyFormat::Rlp => bytes.to_vec(),
+        LegacyFormat::Base64 => {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|_| DecoderError::Custom("Invalid base64 encoding"))?
+                .trim();
+            let text = text.strip_prefix("enr:").unwrap_or(text);
+            if text.ends_with('=') {
+                quirks.push(LegacyQuirk::Base64Padding);
+            }
+            if text.contains(['+', '/']) {
+                quirks.push(LegacyQuirk::StandardBase64Alphabet);
+                LENIENT_STANDARD.decode(text)
+            } else {
+                LENIENT_URL_SAFE.decode(text)
+            }
+            .map_err(|_| DecoderError::Custom("Invalid base64 encoding"))?
+        }
+    };
+
+    let enr = decode_lenient::<K>(&rlp_bytes, &mut quirks)?;
+
+    // quirks of the text form don't affect the signed record
+    if quirks.iter().all(|quirk| {
+        matches!(
+            quirk,
+            LegacyQuirk::Base64Padding | LegacyQuirk::StandardBase64Alphabet
+        )
+    }) {
+        return Ok(Migration::Canonical(enr));
+    }
+    Ok(Migration::Legacy(LegacyRecord { enr, quirks }))
+}
+
+/// Decodes a record without enforcing the size limit or canonical port encodings, recording any
+/// deviations in `quirks`, and verifies its signature.
+fn decode_lenient<K: EnrKey>(
+    bytes: &[u8],
+    quirks: &mut Vec<LegacyQuirk>,
+) -> Result<Enr<K>, DecoderError> {
+    let (signature, seq, content) = decode_record_fields(&Rlp::new(bytes), true)?;
+    if bytes.len() > MAX_ENR_SIZE {
+        quirks.push(LegacyQuirk::ExceedsMaxSize);
+    }
+    for (key, value) in &content {
+        let item = Rlp::new(value);
+        if is_keyof_u16(key) && item.as_val::<u16>().is_err() {
+            legacy_port(item.data()?).ok_or(DecoderError::RlpIsTooBig)?;
+            quirks.push(LegacyQuirk::NonCanonicalPort(
+                String::from_utf8_lossy(key).into(),
+            ));
+        }
+    }
+    Enr::from_record_fields(signature, seq, content)
+}
+
+/// Decodes a port stored as a big-endian byte string of at most two bytes.
+fn legacy_port(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [] => Some(0),
+        [low] => Some(u16::from(low)),
+        [high, low] => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_canonical_record() {
+        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+        match from_legacy_bytes::<DefaultKey>(text.as_bytes(), LegacyFormat::Base64).unwrap() {
+            Migration::Canonical(enr) => assert_eq!(enr.to_base64(), text),
+            Migration::Legacy(_) => panic!("record is canonical"),
+        }
+
+        // padding is accepted
+        let padded = format!("{text}=");
+        assert!(matches!(
+            from_legacy_bytes::<DefaultKey>(padded.as_bytes(), LegacyFormat::Base64).unwrap(),
+            Migration::Canonical(_)
+        ));
+    }
+
+    #[test]
+    fn test_low_integer_port_migration() {
+        // A record with a `tcp` port of 30 encoded as `0x82001e`, see `test_low_integer_bad_enr`.
+        let text = "enr:-Hy4QCCgTB9tAEJL1DFwTTtwd79xxQx2hvi5RX9vWvcdKqbpS3SDzHHBivpOgxE40HGt6P0NtCE5QKzOQ5fzBwepDfMBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggAe";
+        assert!(text.parse::<Enr<DefaultKey>>().is_err());
+
+        let legacy =
+            match from_legacy_bytes::<DefaultKey>(text.as_bytes(), LegacyFormat::Base64).unwrap() {
+                Migration::Legacy(legacy) => legacy,
+                Migration::Canonical(_) => panic!("record is not canonical"),
+            };
+        assert_eq!(
+            legacy.quirks(),
+            &[LegacyQuirk::NonCanonicalPort("tcp".into())]
+        );
+        assert_eq!(legacy.seq(), 1);
+
+        // the same port in a record of a known key, which the migration re-signs it with
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr: Enr<DefaultKey> = Enr::empty(&key).unwrap();
+        enr.content.insert(
+            Key::from(b"tcp".as_ref()),
+            storage::copy_value(&[0x82, 0x00, 0x1e]),
+        );
+        enr.clear_encoded();
+        enr.sign(&key).unwrap();
+        let node_id = enr.node_id();
+
+        let legacy =
+            match from_legacy_bytes::<DefaultKey>(&rlp::encode(&enr), LegacyFormat::Rlp).unwrap() {
+                Migration::Legacy(legacy) => legacy,
+                Migration::Canonical(_) => panic!("record is not canonical"),
+            };
+        assert_eq!(legacy.node_id(), node_id);
+
+        let migrated = legacy.migrate(&key).unwrap();
+        assert_eq!(migrated.node_id(), node_id);
+        assert_eq!(migrated.tcp4(), Some(30));
+        assert_eq!(migrated.seq(), 2);
+        assert!(migrated.verify());
+        assert_eq!(
+            migrated.to_base64().parse::<Enr<DefaultKey>>().unwrap(),
+            migrated
+        );
+    }
+}
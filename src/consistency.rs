@@ -0,0 +1,68 @@
+//! Policies for keeping the IP and port fields of an ENR consistent.
+//!
+//! A port without an IP address of the same family (e.g. `tcp6` without `ip6`) cannot be used
+//! by peers to reach a node. By default this crate allows such records to be built and modified.
+//! A [`ConsistencyPolicy`] can be set when building an [`Enr`], or on an existing [`Enr`], to
+//! opt-in to stricter handling.
+//!
+//! [`Enr`]: crate::Enr
+
+use crate::{EnrError, Key, Value};
+use std::collections::BTreeMap;
+
+/// The IP key and port keys of each IP address family.
+const IP_FAMILIES: [(&str, &[&str]); 2] = [
+    ("ip", &["tcp", "udp", "quic"]),
+    ("ip6", &["tcp6", "udp6", "quic6"]),
+];
+
+/// Defines how record mutations handle ports and IP addresses that lack their counterpart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConsistencyPolicy {
+    /// No consistency checks are performed.
+    #[default]
+    Permissive,
+    /// Mutations that leave a port without an IP address of the same family, or an IP address
+    /// without any port of the same family, are rejected.
+    Reject,
+    /// When the IP address of a family is removed from the record, the ports of that family are
+    /// removed along with it. No other checks are performed.
+    Prune,
+}
+
+impl ConsistencyPolicy {
+    /// Checks the content of a record against the policy.
+    pub(crate) fn check(self, content: &BTreeMap<Key, Value>) -> Result<(), EnrError> {
+        if self != Self::Reject {
+            return Ok(());
+        }
+        for (ip_key, port_keys) in IP_FAMILIES {
+            let has_ip = content.contains_key(ip_key.as_bytes());
+            let port = port_keys
+                .iter()
+                .find(|port_key| content.contains_key(port_key.as_bytes()));
+            match port {
+                Some(port_key) if !has_ip => return Err(EnrError::OrphanPort((*port_key).into())),
+                None if has_ip => return Err(EnrError::OrphanIp(ip_key.into())),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the ports of any IP address family whose IP address was present in `previous`
+    /// but is absent from `content`, if the policy requires it.
+    pub(crate) fn prune(self, previous: &BTreeMap<Key, Value>, content: &mut BTreeMap<Key, Value>) {
+        if self != Self::Prune {
+            return;
+        }
+        for (ip_key, port_keys) in IP_FAMILIES {
+            if previous.contains_key(ip_key.as_bytes()) && !content.contains_key(ip_key.as_bytes())
+            {
+                for port_key in port_keys {
+                    content.remove(port_key.as_bytes());
+                }
+            }
+        }
+    }
+}
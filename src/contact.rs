@@ -0,0 +1,357 @@
+//! The identity and dialing information of a node, as used by connection managers.
+//!
+//! A [`NodeContact`] answers "who to dial": the node id and public key of a node, the
+//! [`Endpoint`] to dial it at and, when the node was learned from a record, the record itself.
+//! Contacts can be created from an [`Enr`], from an `enode://` URL or from a libp2p multiaddr
+//! carrying a `/p2p` peer id that embeds the public key.
+//!
+//! An observed socket, e.g. the source address of a packet received from the node, can override
+//! the advertised endpoint with [`NodeContact::set_observed_socket`].
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, NodeContact};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder()
+//!     .ip4("203.0.113.7".parse().unwrap())
+//!     .udp4(9000)
+//!     .build(&key)
+//!     .unwrap();
+//!
+//! let mut contact = NodeContact::from_enr(enr.clone()).unwrap();
+//! assert_eq!(contact.node_id(), enr.node_id());
+//! assert_eq!(contact.dial_socket(), "203.0.113.7:9000".parse().unwrap());
+//!
+//! contact.set_observed_socket(Some("198.51.100.1:9001".parse().unwrap()));
+//! assert_eq!(contact.dial_socket(), "198.51.100.1:9001".parse().unwrap());
+//! # }
+//! ```
+
+use crate::{
+    enode::Enode, multiaddr, Endpoint, EnodeError, Enr, EnrKey, EnrKeyUnambiguous, EnrPublicKey,
+    NodeId, Transport,
+};
+use rlp::DecoderError;
+use std::{error::Error, fmt, net::SocketAddr};
+
+/// The libp2p key type of `secp256k1` public keys.
+const LIBP2P_SECP256K1: u8 = 2;
+/// The libp2p key type of `ed25519` public keys.
+const LIBP2P_ED25519: u8 = 1;
+/// The multihash code of the identity hash, under which peer ids embed their public key.
+const MULTIHASH_IDENTITY: u8 = 0;
+
+/// A node to dial: its identity, the endpoint to dial it at and its record, if known.
+pub struct NodeContact<K: EnrKey> {
+    /// The node id of the node.
+    node_id: NodeId,
+    /// The public key of the node.
+    public_key: K::PublicKey,
+    /// The record the contact was created from, if any.
+    enr: Option<Enr<K>>,
+    /// The advertised endpoint of the node.
+    endpoint: Endpoint,
+    /// A socket the node was observed at, overriding the advertised endpoint.
+    observed_socket: Option<SocketAddr>,
+}
+
+impl<K: EnrKey> NodeContact<K> {
+    /// Creates a contact from a record, dialing its primary endpoint.
+    ///
+    /// Fails if the record advertises no complete endpoint.
+    pub fn from_enr(enr: Enr<K>) -> Result<Self, ContactError> {
+        let endpoint = enr
+            .endpoints()
+            .into_iter()
+            .next()
+            .ok_or(ContactError::NoEndpoint)?;
+        Ok(Self {
+            node_id: enr.node_id(),
+            public_key: enr.public_key(),
+            enr: Some(enr),
+            endpoint,
+            observed_socket: None,
+        })
+    }
+
+    /// Creates a contact for a node without a record.
+    fn new(public_key: K::PublicKey, endpoint: Endpoint) -> Self {
+        Self {
+            node_id: NodeId::from(public_key.clone()),
+            public_key,
+            enr: None,
+            endpoint,
+            observed_socket: None,
+        }
+    }
+
+    /// The node id of the node.
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// The public key of the node.
+    #[must_use]
+    pub const fn public_key(&self) -> &K::PublicKey {
+        &self.public_key
+    }
+
+    /// The key scheme of the node, e.g. `secp256k1`.
+    #[must_use]
+    pub fn scheme(&self) -> String {
+        String::from_utf8_lossy(&self.public_key.enr_key()).into()
+    }
+
+    /// The record the contact was created from, if any.
+    #[must_use]
+    pub const fn enr(&self) -> Option<&Enr<K>> {
+        self.enr.as_ref()
+    }
+
+    /// The advertised endpoint of the node.
+    #[must_use]
+    pub const fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// The socket the node was observed at, if any.
+    #[must_use]
+    pub const fn observed_socket(&self) -> Option<SocketAddr> {
+        self.observed_socket
+    }
+
+    /// Sets or clears the socket the node was observed at.
+    pub fn set_observed_socket(&mut self, socket: Option<SocketAddr>) {
+        self.observed_socket = socket;
+    }
+
+    /// The socket to dial: the observed socket if set, otherwise the advertised endpoint's.
+    #[must_use]
+    pub fn dial_socket(&self) -> SocketAddr {
+        self.observed_socket
+            .unwrap_or_else(|| self.endpoint.socket())
+    }
+}
+
+impl<K: EnrKeyUnambiguous> NodeContact<K> {
+    /// Creates a contact from an `enode://<public key>@<ip>:<port>` URL, dialing its TCP port.
+    ///
+    /// The public key is the hex encoded, uncompressed `secp256k1` key without its `04` prefix.
+    /// The optional `?discport=` query names the discovery port, which is not needed for dialing
+    /// and is ignored.
+    pub fn from_enode(url: &str) -> Result<Self, ContactError> {
+        let enode: Enode = url.parse().map_err(ContactError::Enode)?;
+        let mut encoded = vec![0x04];
+        encoded.extend(enode.public_key);
+        let public_key = K::decode_public(&encoded).map_err(ContactError::PublicKey)?;
+        Ok(Self::new(
+            public_key,
+            Endpoint::new(Transport::Tcp, enode.socket),
+        ))
+    }
+
+    /// Creates a contact from a libp2p multiaddr with a `/p2p` peer id, such as
+    /// `/ip4/<ip>/udp/<port>/p2p/<peer id>`, dialing its first socket.
+    ///
+    /// The multiaddr is read like [`Enr::set_from_multiaddr`]. The peer id must embed the public
+    /// key of the node, as libp2p does for `secp256k1` and `ed25519` keys.
+    pub fn from_multiaddr(multiaddr: &str) -> Result<Self, ContactError> {
+        let (sockets, peer_id) = multiaddr::parse(multiaddr).map_err(ContactError::Multiaddr)?;
+        let peer_id = peer_id.ok_or(ContactError::MissingPeerId)?;
+        let public_key =
+            K::decode_public(&peer_id_public_key(peer_id)?).map_err(ContactError::PublicKey)?;
+        let (transport, socket) = sockets[0];
+        Ok(Self::new(public_key, Endpoint::new(transport, socket)))
+    }
+}
+
+/// Extracts the public key embedded in a base58 encoded libp2p peer id.
+fn peer_id_public_key(peer_id: &str) -> Result<Vec<u8>, ContactError> {
+    let bytes = bs58::decode(peer_id)
+        .into_vec()
+        .map_err(ContactError::PeerIdEncoding)?;
+    // a multihash of the protobuf encoded public key: `08 <key type> 12 <length> <key>`
+    match bytes.as_slice() {
+        [MULTIHASH_IDENTITY, len, 0x08, key_type, 0x12, key_len, key @ ..]
+            if usize::from(*len) == key.len() + 4 && usize::from(*key_len) == key.len() =>
+        {
+            match *key_type {
+                LIBP2P_SECP256K1 | LIBP2P_ED25519 => Ok(key.to_vec()),
+                key_type => Err(ContactError::UnsupportedKeyType(key_type)),
+            }
+        }
+        _ => Err(ContactError::PeerIdWithoutKey),
+    }
+}
+
+/// An error creating a [`NodeContact`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContactError {
+    /// The record advertises no complete endpoint.
+    NoEndpoint,
+    /// The `enode://` URL is invalid.
+    Enode(EnodeError),
+    /// The multiaddr is invalid, for the given reason.
+    Multiaddr(String),
+    /// The multiaddr has no `/p2p` peer id.
+    MissingPeerId,
+    /// The peer id is not valid base58.
+    PeerIdEncoding(bs58::decode::Error),
+    /// The peer id does not embed a public key.
+    PeerIdWithoutKey,
+    /// The peer id embeds a public key of an unsupported libp2p key type.
+    UnsupportedKeyType(u8),
+    /// The public key is invalid.
+    PublicKey(DecoderError),
+}
+
+impl fmt::Display for ContactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoEndpoint => write!(f, "Record advertises no endpoint"),
+            Self::Enode(e) => write!(f, "{e}"),
+            Self::Multiaddr(reason) => write!(f, "Invalid multiaddr: {reason}"),
+            Self::MissingPeerId => write!(f, "Multiaddr has no peer id"),
+            Self::PeerIdEncoding(e) => write!(f, "Invalid peer id encoding: {e}"),
+            Self::PeerIdWithoutKey => write!(f, "Peer id does not embed a public key"),
+            Self::UnsupportedKeyType(key_type) => {
+                write!(f, "Unsupported peer id key type {key_type}")
+            }
+            Self::PublicKey(e) => write!(f, "Invalid public key: {e}"),
+        }
+    }
+}
+
+impl Error for ContactError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Enode(e) => Some(e),
+            Self::PeerIdEncoding(e) => Some(e),
+            Self::PublicKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<K: EnrKey> Clone for NodeContact<K> {
+    fn clone(&self) -> Self {
+        Self {
+            node_id: self.node_id,
+            public_key: self.public_key.clone(),
+            enr: self.enr.clone(),
+            endpoint: self.endpoint.clone(),
+            observed_socket: self.observed_socket,
+        }
+    }
+}
+
+impl<K: EnrKey> fmt::Debug for NodeContact<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodeContact")
+            .field("node_id", &self.node_id)
+            .field("endpoint", &self.endpoint)
+            .field("observed_socket", &self.observed_socket)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: EnrKey> fmt::Display for NodeContact<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.node_id, self.dial_socket())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    fn key() -> DefaultKey {
+        DefaultKey::from_slice(
+            &hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_enr() {
+        let key = key();
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .ip6("::1".parse().unwrap())
+            .tcp6(30303)
+            .build(&key)
+            .unwrap();
+        let contact = NodeContact::from_enr(enr.clone()).unwrap();
+        assert_eq!(contact.node_id(), enr.node_id());
+        assert_eq!(contact.scheme(), "secp256k1");
+        assert_eq!(contact.enr(), Some(&enr));
+        assert_eq!(contact.endpoint().transport, Transport::Tcp);
+        assert_eq!(contact.dial_socket(), "[::1]:30303".parse().unwrap());
+
+        let empty: Enr<DefaultKey> = Enr::empty(&key).unwrap();
+        assert_eq!(
+            NodeContact::from_enr(empty).unwrap_err(),
+            ContactError::NoEndpoint
+        );
+    }
+
+    #[test]
+    fn test_from_enode() {
+        let key = key();
+        let enode = format!(
+            "enode://{}@127.0.0.1:30303?discport=30301",
+            hex::encode(key.public().encode_uncompressed())
+        );
+        let contact = NodeContact::<DefaultKey>::from_enode(&enode).unwrap();
+        assert_eq!(contact.node_id(), NodeId::from(key.public()));
+        assert_eq!(contact.enr(), None);
+        assert_eq!(
+            *contact.endpoint(),
+            Endpoint::new(Transport::Tcp, "127.0.0.1:30303".parse().unwrap())
+        );
+
+        assert!(matches!(
+            NodeContact::<DefaultKey>::from_enode("enode://00@127.0.0.1:30303"),
+            Err(ContactError::Enode(EnodeError::InvalidUrl(_)))
+        ));
+        assert!(matches!(
+            NodeContact::<DefaultKey>::from_enode("127.0.0.1:30303"),
+            Err(ContactError::Enode(EnodeError::InvalidUrl(_)))
+        ));
+    }
+
+    #[test]
+    fn test_from_multiaddr() {
+        // the peer id of the `secp256k1` key above, as generated by libp2p
+        let peer_id = "16Uiu2HAmSH2XVgZqYHWucap5kuPzLnt2TsNQkoppVxB5eJGvaXwm";
+        let contact = NodeContact::<DefaultKey>::from_multiaddr(&format!(
+            "/ip4/10.0.0.1/udp/9000/p2p/{peer_id}"
+        ))
+        .unwrap();
+        assert_eq!(contact.node_id(), NodeId::from(key().public()));
+        assert_eq!(
+            *contact.endpoint(),
+            Endpoint::new(Transport::Udp, "10.0.0.1:9000".parse().unwrap())
+        );
+
+        assert_eq!(
+            NodeContact::<DefaultKey>::from_multiaddr("/ip4/10.0.0.1/udp/9000").unwrap_err(),
+            ContactError::MissingPeerId
+        );
+        assert!(matches!(
+            NodeContact::<DefaultKey>::from_multiaddr(&format!(
+                "/ip6/10.0.0.1/udp/9000/p2p/{peer_id}"
+            )),
+            Err(ContactError::Multiaddr(_))
+        ));
+        assert!(matches!(
+            NodeContact::<DefaultKey>::from_multiaddr("/ip4/10.0.0.1/udp/9000/p2p/0OIl"),
+            Err(ContactError::PeerIdEncoding(_))
+        ));
+    }
+}
@@ -0,0 +1,448 @@
+//! A minimal append-only store of verified records.
+//!
+//! [`RecordDb`] persists records in a single file and keeps an in-memory index from [`NodeId`] to
+//! the newest record of each node. The file is memory-mapped for reads, so looking up a record
+//! returns an [`EnrRef`] borrowing the stored bytes without copying or decoding them.
+//!
+//! The file starts with an 8 byte magic header, followed by entries of the form
+//!
+//! ```text
+//! node id (32 bytes) | seq (8 bytes, big-endian) | length (2 bytes, big-endian) | RLP encoded record
+//! ```
+//!
+//! Newer records of a node are appended rather than overwriting older ones; only the entry with
+//! the highest sequence number is indexed. A partially written entry at the end of the file, e.g.
+//! after a crash, is discarded when the database is opened.
+//!
+//! Appended entries are kept in memory until they amount to as many bytes as are mapped, up to
+//! 1 MiB, and only then is the file synced and mapped again. [`RecordDb::sync`] syncs appended
+//! entries earlier.
+
+use crate::{supersedes, ConflictingRecord, Enr, EnrKey, EnrRef, NodeId, Supersedes, MAX_ENR_SIZE};
+use memmap2::Mmap;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+/// Identifies a file as a record database, including the format version.
+const MAGIC: &[u8; 8] = b"ENRDB\x00\x00\x01";
+
+/// The length of an entry's header: node id, sequence number and record length.
+const ENTRY_HEADER_LEN: usize = 32 + 8 + 2;
+
+/// The most bytes of appended entries held in memory before the file is mapped again.
+const MAX_UNMAPPED_LEN: usize = 1 << 20;
+
+/// The location of a record in the file.
+#[derive(Clone, Copy, Debug)]
+struct IndexEntry {
+    /// The offset of the record's RLP encoding.
+    offset: usize,
+    /// The length of the record's RLP encoding.
+    len: usize,
+    /// The sequence number of the record.
+    seq: u64,
+}
+
+/// An append-only, memory-mapped file of verified records, indexed by [`NodeId`].
+pub struct RecordDb<K: EnrKey> {
+    /// The database file, opened for appending.
+    file: File,
+    /// A read-only mapping of the file.
+    mmap: Mmap,
+    /// The entries appended to the file since it was mapped.
+    unmapped: Vec<u8>,
+    /// The newest record of each node.
+    index: HashMap<NodeId, IndexEntry>,
+    /// Pins the key type of the stored records.
+    phantom: PhantomData<K>,
+}
+
+impl<K: EnrKey> RecordDb<K> {
+    /// Opens the database at `path`, creating it if it doesn't exist, and builds the index.
+    ///
+    /// Every entry must hold a well-formed record of the node id and sequence number in its
+    /// header, or the file is rejected as corrupted. Signatures are not re-verified, see
+    /// [`EnrRef::verify`].
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+            file.sync_data()?;
+        }
+
+        let mmap = map(&file)?;
+        if mmap.get(..MAGIC.len()) != Some(MAGIC.as_ref()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an ENR database",
+            ));
+        }
+
+        let mut index = HashMap::new();
+        let mut offset = MAGIC.len();
+        while let Some(header) = mmap.get(offset..offset + ENTRY_HEADER_LEN) {
+            let node_id = NodeId::new(header[..32].try_into().expect("32 bytes"));
+            let seq = u64::from_be_bytes(header[32..40].try_into().expect("8 bytes"));
+            let len = usize::from(u16::from_be_bytes(
+                header[40..].try_into().expect("2 bytes"),
+            ));
+            let record_offset = offset + ENTRY_HEADER_LEN;
+            let bytes = match mmap.get(record_offset..record_offset + len) {
+                Some(bytes) => bytes,
+                None => break,
+            };
+            let record = EnrRef::<K>::decode(bytes).map_err(|_| corrupted())?;
+            if record.seq() != seq || record.node_id().ok() != Some(node_id) {
+                return Err(corrupted());
+            }
+            let relation = index.get(&node_id).map(|existing: &IndexEntry| {
+                supersedes(seq, existing.seq, || {
+                    let bytes = &mmap[existing.offset..existing.offset + existing.len];
+                    EnrRef::<K>::decode(bytes)
+                        .expect("records are decoded before they are indexed")
+                        .iter()
+                        .eq(record.iter())
+                })
+            });
+            match relation {
+                None | Some(Supersedes::Newer) => {
+                    let entry = IndexEntry {
+                        offset: record_offset,
+                        len,
+                        seq,
+                    };
+                    index.insert(node_id, entry);
+                }
+                Some(Supersedes::Conflict) => return Err(conflicting(node_id, seq)),
+                Some(_) => {}
+            }
+            offset = record_offset + len;
+        }
+
+        // discard a partially written entry
+        let mmap = if offset < mmap.len() {
+            drop(mmap);
+            file.set_len(offset as u64)?;
+            map(&file)?
+        } else {
+            mmap
+        };
+
+        Ok(Self {
+            file,
+            mmap,
+            unmapped: Vec::new(),
+            index,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Stores a record if it is newer than the stored record of the same node.
+    ///
+    /// Returns whether the record was stored. A record with the sequence number of the stored
+    /// record but different content is rejected with a [`ConflictingRecord`] error of kind
+    /// [`io::ErrorKind::InvalidData`].
+    pub fn insert(&mut self, enr: &Enr<K>) -> io::Result<bool> {
+        if let Some(existing) = self.get(&enr.node_id()) {
+            let same_content = || existing.iter().eq(enr.iter());
+            match supersedes(enr.seq(), existing.seq(), same_content) {
+                Supersedes::Newer => {}
+                Supersedes::Conflict => return Err(conflicting(enr.node_id(), enr.seq())),
+                Supersedes::Same | Supersedes::Older => return Ok(false),
+            }
+        }
+
+        let encoded = enr.encoded();
+        // records are verified to be at most `MAX_ENR_SIZE` bytes, which fits a u16
+        debug_assert!(encoded.len() <= MAX_ENR_SIZE);
+        let start = self.unmapped.len();
+        let offset = self.mmap.len() + start;
+        self.unmapped.extend_from_slice(&enr.node_id().raw());
+        self.unmapped.extend_from_slice(&enr.seq().to_be_bytes());
+        self.unmapped
+            .extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+        self.unmapped.extend_from_slice(encoded);
+        if let Err(e) = self.file.write_all(&self.unmapped[start..]) {
+            // drop a partially written entry
+            self.unmapped.truncate(start);
+            let _ = self.file.set_len(offset as u64);
+            return Err(e);
+        }
+
+        let entry = IndexEntry {
+            offset: offset + ENTRY_HEADER_LEN,
+            len: encoded.len(),
+            seq: enr.seq(),
+        };
+        self.index.insert(enr.node_id(), entry);
+        if self.unmapped.len() >= self.mmap.len().min(MAX_UNMAPPED_LEN) {
+            self.sync()?;
+        }
+        Ok(true)
+    }
+
+    /// Syncs the appended entries to disk and maps them.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if !self.unmapped.is_empty() {
+            self.file.sync_data()?;
+            self.mmap = map(&self.file)?;
+            self.unmapped.clear();
+        }
+        Ok(())
+    }
+
+    /// The newest stored record of a node.
+    #[must_use]
+    pub fn get(&self, node_id: &NodeId) -> Option<EnrRef<'_, K>> {
+        self.index.get(node_id).map(|entry| {
+            let bytes = match entry.offset.checked_sub(self.mmap.len()) {
+                Some(offset) => &self.unmapped[offset..offset + entry.len],
+                None => &self.mmap[entry.offset..entry.offset + entry.len],
+            };
+            EnrRef::decode(bytes).expect("records are decoded before they are indexed")
+        })
+    }
+
+    /// Returns whether a record of the node is stored.
+    #[must_use]
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.index.contains_key(node_id)
+    }
+
+    /// The node ids of all stored records, in arbitrary order.
+    pub fn node_ids(&self) -> impl Iterator<Item = &NodeId> {
+        self.index.keys()
+    }
+
+    /// The newest stored record of every node, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = EnrRef<'_, K>> {
+        self.index
+            .keys()
+            .filter_map(move |node_id| self.get(node_id))
+    }
+
+    /// The number of nodes with a stored record.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns whether no records are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+impl<K: EnrKey> Drop for RecordDb<K> {
+    fn drop(&mut self) {
+        // errors can't be reported from a drop, `sync` reports them
+        let _ = self.file.sync_data();
+    }
+}
+
+/// The error of a file holding an entry that doesn't match its record.
+fn corrupted() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupted ENR database entry")
+}
+
+/// The error of a record conflicting with the stored record of its node.
+fn conflicting(node_id: NodeId, seq: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        ConflictingRecord { node_id, seq },
+    )
+}
+
+fn map(file: &File) -> io::Result<Mmap> {
+    // SAFETY: the file is only ever appended to by this process, so the mapped range is not
+    // modified while it is mapped. Concurrent modification by other processes is not supported.
+    unsafe { Mmap::map(file) }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    /// A path in the temporary directory that is removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            let name = format!("enr-db-{}-{}", std::process::id(), rand::random::<u64>());
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_insert_and_reopen() {
+        let path = TempPath::new();
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        let other = Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap();
+
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        assert!(db.is_empty());
+        assert!(db.insert(&enr).unwrap());
+        assert!(db.insert(&other).unwrap());
+        // records that are not newer are ignored
+        assert!(!db.insert(&enr).unwrap());
+
+        enr.set_udp4(30304, &key).unwrap();
+        assert!(db.insert(&enr).unwrap());
+        assert_eq!(db.len(), 2);
+
+        let stored = db.get(&enr.node_id()).unwrap();
+        assert_eq!(stored.seq(), 2);
+        assert_eq!(stored.as_bytes(), rlp::encode(&enr).as_ref());
+        assert_eq!(stored.to_enr().unwrap(), enr);
+        drop(db);
+
+        let db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.get(&enr.node_id()).unwrap().to_enr().unwrap(), enr);
+        assert_eq!(db.get(&other.node_id()).unwrap().to_enr().unwrap(), other);
+        assert_eq!(db.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_conflicting_record() {
+        let path = TempPath::new();
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        let conflicting = Enr::builder().udp4(30304).build(&key).unwrap();
+
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        db.insert(&enr).unwrap();
+        // the same content signed again is not a conflict
+        let resigned = Enr::builder().udp4(30303).build(&key).unwrap();
+        assert!(!db.insert(&resigned).unwrap());
+
+        let err = db.insert(&conflicting).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref(),
+            Some(&ConflictingRecord {
+                node_id: enr.node_id(),
+                seq: 1,
+            })
+        );
+        assert_eq!(db.get(&enr.node_id()).unwrap().to_enr().unwrap(), enr);
+        drop(db);
+
+        // a file holding conflicting records is rejected when opened
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        let encoded = conflicting.encoded();
+        file.write_all(&conflicting.node_id().raw()).unwrap();
+        file.write_all(&conflicting.seq().to_be_bytes()).unwrap();
+        file.write_all(&(encoded.len() as u16).to_be_bytes())
+            .unwrap();
+        file.write_all(encoded).unwrap();
+        drop(file);
+        assert_eq!(
+            RecordDb::<DefaultKey>::open(&path.0).err().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_truncated_entry() {
+        let path = TempPath::new();
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Enr::empty(&key).unwrap();
+
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        db.insert(&enr).unwrap();
+        drop(db);
+        let len = std::fs::metadata(&path.0).unwrap().len();
+
+        // simulate a crash while appending a second entry
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        file.write_all(&[1; ENTRY_HEADER_LEN + 10]).unwrap();
+        drop(file);
+
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(std::fs::metadata(&path.0).unwrap().len(), len);
+
+        let other = Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap();
+        db.insert(&other).unwrap();
+        assert_eq!(db.get(&other.node_id()).unwrap().to_enr().unwrap(), other);
+    }
+
+    #[test]
+    fn test_many_inserts() {
+        let path = TempPath::new();
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        let records: Vec<Enr<DefaultKey>> = (0..200)
+            .map(|_| Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap())
+            .collect();
+        for enr in &records {
+            assert!(db.insert(enr).unwrap());
+            assert_eq!(db.get(&enr.node_id()).unwrap().to_enr().unwrap(), *enr);
+        }
+        // some entries are mapped and some are not yet
+        assert!(!db.unmapped.is_empty());
+        assert!(db.mmap.len() > MAGIC.len());
+        for enr in &records {
+            assert_eq!(
+                db.get(&enr.node_id()).unwrap().as_bytes(),
+                &enr.encoded()[..]
+            );
+        }
+        db.sync().unwrap();
+        assert!(db.unmapped.is_empty());
+        drop(db);
+
+        let db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        assert_eq!(db.len(), records.len());
+    }
+
+    #[test]
+    fn test_corrupted_entry() {
+        let path = TempPath::new();
+        let enr = Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap();
+        let mut db = RecordDb::<DefaultKey>::open(&path.0).unwrap();
+        db.insert(&enr).unwrap();
+        drop(db);
+        let valid = std::fs::read(&path.0).unwrap();
+
+        // the header claims a different node id or seq than the record holds
+        for position in [MAGIC.len(), MAGIC.len() + 39] {
+            let mut bytes = valid.clone();
+            bytes[position] ^= 0x01;
+            std::fs::write(&path.0, &bytes).unwrap();
+            assert_eq!(
+                RecordDb::<DefaultKey>::open(&path.0).err().unwrap().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let path = TempPath::new();
+        std::fs::write(&path.0, b"not a database").unwrap();
+        assert!(RecordDb::<DefaultKey>::open(&path.0).is_err());
+    }
+}
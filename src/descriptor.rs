@@ -0,0 +1,223 @@
+//! Compact, key-type-agnostic textual identities of ENRs.
+//!
+//! A [`NodeDescriptor`] is a single-line string identifying a node without including its full
+//! (mutable) record, for use in configuration such as allowlists. It has the form
+//!
+//! ```text
+//! <node-id prefix>@<key scheme>[/<transport>=<socket>][/fork=<fork digest>]
+//! ```
+//!
+//! for example `a448f24c6d18e575@secp256k1/udp=127.0.0.1:30303`. Descriptors generated by
+//! [`Enr::descriptor`] contain an 8 byte node id prefix, the key scheme, the record's primary
+//! endpoint and the fork digest of the `eth2` field, if present. When parsing, every component
+//! other than the node id prefix is optional and the prefix may be of any length, so that
+//! descriptors can be written by hand to match a range of records.
+//!
+//! [`Enr::descriptor`]: crate::Enr::descriptor
+
+use crate::{Endpoint, Enr, EnrKey, EnrPublicKey, IdentityScheme, Transport, UnknownTransport};
+use std::{
+    error::Error,
+    fmt,
+    net::{AddrParseError, SocketAddr},
+    str::FromStr,
+};
+
+/// The number of bytes of the node id included in generated descriptors.
+const NODE_ID_PREFIX_LEN: usize = 8;
+
+/// A compact textual identity of a node that can be matched against records.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeDescriptor {
+    /// A prefix of the node id.
+    node_id_prefix: Vec<u8>,
+    /// The ENR key of the public key, e.g. `secp256k1`.
+    scheme: Option<String>,
+    /// An endpoint the record must advertise.
+    endpoint: Option<Endpoint>,
+    /// The fork digest of the record's `eth2` field.
+    fork_digest: Option<[u8; 4]>,
+}
+
+impl NodeDescriptor {
+    /// Generates the descriptor of a record.
+    pub(crate) fn new<K: EnrKey, S: IdentityScheme>(enr: &Enr<K, S>) -> Self {
+        Self {
+            node_id_prefix: enr.node_id().raw()[..NODE_ID_PREFIX_LEN].to_vec(),
+            scheme: Some(String::from_utf8_lossy(&enr.public_key().enr_key()).into()),
+            endpoint: enr.endpoints().into_iter().next(),
+            fork_digest: fork_digest(enr),
+        }
+    }
+
+    /// The node id prefix of the descriptor.
+    #[must_use]
+    pub fn node_id_prefix(&self) -> &[u8] {
+        &self.node_id_prefix
+    }
+
+    /// The key scheme of the descriptor, if specified.
+    #[must_use]
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    /// The endpoint of the descriptor, if specified.
+    #[must_use]
+    pub const fn endpoint(&self) -> Option<&Endpoint> {
+        self.endpoint.as_ref()
+    }
+
+    /// The fork digest of the descriptor, if specified.
+    #[must_use]
+    pub const fn fork_digest(&self) -> Option<[u8; 4]> {
+        self.fork_digest
+    }
+
+    /// Returns whether a record matches every component specified by the descriptor.
+    #[must_use]
+    pub fn matches<K: EnrKey, S: IdentityScheme>(&self, enr: &Enr<K, S>) -> bool {
+        if !enr.node_id().raw().starts_with(&self.node_id_prefix) {
+            return false;
+        }
+        if let Some(scheme) = &self.scheme {
+            if enr.public_key().enr_key() != scheme.as_bytes() {
+                return false;
+            }
+        }
+        if let Some(endpoint) = &self.endpoint {
+            if !enr.endpoints().contains(endpoint) {
+                return false;
+            }
+        }
+        if self.fork_digest.is_some() && self.fork_digest != fork_digest(enr) {
+            return false;
+        }
+        true
+    }
+}
+
+impl fmt::Display for NodeDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.node_id_prefix))?;
+        if let Some(scheme) = &self.scheme {
+            write!(f, "@{scheme}")?;
+        }
+        if let Some(endpoint) = &self.endpoint {
+            write!(f, "/{}={}", endpoint.transport, endpoint.socket())?;
+        }
+        if let Some(fork_digest) = &self.fork_digest {
+            write!(f, "/fork={}", hex::encode(fork_digest))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NodeDescriptor {
+    type Err = DescriptorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(DescriptorParseError::Empty);
+        }
+        let mut components = s.split('/');
+        let identity = components.next().unwrap_or_default();
+        let (node_id_prefix, scheme) = match identity.split_once('@') {
+            Some((prefix, scheme)) => (prefix, Some(scheme.to_string())),
+            None => (identity, None),
+        };
+        let node_id_prefix =
+            hex::decode(node_id_prefix).map_err(DescriptorParseError::NodeIdPrefix)?;
+        if node_id_prefix.len() > 32 {
+            return Err(DescriptorParseError::NodeIdPrefixTooLong);
+        }
+
+        let mut descriptor = Self {
+            node_id_prefix,
+            scheme,
+            endpoint: None,
+            fork_digest: None,
+        };
+        for component in components {
+            let (name, value) = component
+                .split_once('=')
+                .ok_or_else(|| DescriptorParseError::InvalidComponent(component.into()))?;
+            match name {
+                "fork" => {
+                    let mut fork_digest = [0_u8; 4];
+                    hex::decode_to_slice(value, &mut fork_digest)
+                        .map_err(DescriptorParseError::ForkDigest)?;
+                    descriptor.fork_digest = Some(fork_digest);
+                }
+                transport => {
+                    let transport = transport.parse::<Transport>()?;
+                    let socket = value
+                        .parse::<SocketAddr>()
+                        .map_err(DescriptorParseError::Socket)?;
+                    descriptor.endpoint = Some(Endpoint::new(transport, socket));
+                }
+            }
+        }
+        Ok(descriptor)
+    }
+}
+
+/// An error parsing the text form of a [`NodeDescriptor`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DescriptorParseError {
+    /// The descriptor is empty.
+    Empty,
+    /// The node id prefix is not valid hex.
+    NodeIdPrefix(hex::FromHexError),
+    /// The node id prefix is longer than a node id.
+    NodeIdPrefixTooLong,
+    /// A component is not of the form `<name>=<value>`.
+    InvalidComponent(String),
+    /// The fork digest is not four bytes of hex.
+    ForkDigest(hex::FromHexError),
+    /// The name of an endpoint component is not a transport.
+    Transport(UnknownTransport),
+    /// The socket address of an endpoint component is invalid.
+    Socket(AddrParseError),
+}
+
+impl From<UnknownTransport> for DescriptorParseError {
+    fn from(error: UnknownTransport) -> Self {
+        Self::Transport(error)
+    }
+}
+
+impl fmt::Display for DescriptorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty node descriptor"),
+            Self::NodeIdPrefix(e) => write!(f, "Invalid node id prefix: {e}"),
+            Self::NodeIdPrefixTooLong => write!(f, "Node id prefix too long"),
+            Self::InvalidComponent(component) => {
+                write!(f, "Invalid descriptor component: {component}")
+            }
+            Self::ForkDigest(e) => write!(f, "Invalid fork digest: {e}"),
+            Self::Transport(e) => write!(f, "{e}"),
+            Self::Socket(e) => write!(f, "Invalid socket address: {e}"),
+        }
+    }
+}
+
+impl Error for DescriptorParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::NodeIdPrefix(e) | Self::ForkDigest(e) => Some(e),
+            Self::Transport(e) => Some(e),
+            Self::Socket(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The fork digest (the first four bytes) of the record's `eth2` field.
+pub(crate) fn fork_digest<K: EnrKey, S: IdentityScheme>(enr: &Enr<K, S>) -> Option<[u8; 4]> {
+    let eth2 = enr.get("eth2")?;
+    let mut fork_digest = [0_u8; 4];
+    fork_digest.copy_from_slice(eth2.get(..4)?);
+    Some(fork_digest)
+}
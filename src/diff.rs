@@ -0,0 +1,80 @@
+//! Field-level comparison of two records.
+
+use crate::{FieldValue, Key, Value};
+use std::{collections::BTreeMap, fmt};
+
+/// The differences between the content of two records, as returned by [`Enr::diff`].
+///
+/// Values are decoded into their typed representation where the key has a meaning known to this
+/// crate, see [`FieldValue`]. All changes are listed in ascending key order.
+///
+/// [`Enr::diff`]: crate::Enr::diff
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnrDiff<'a> {
+    /// The sequence numbers of the compared and the other record.
+    pub seq: (u64, u64),
+    /// The fields only present in the other record.
+    pub added: Vec<(&'a [u8], FieldValue<'a>)>,
+    /// The fields only present in the compared record.
+    pub removed: Vec<(&'a [u8], FieldValue<'a>)>,
+    /// The fields present in both records with different values, with the value of the compared
+    /// record first.
+    pub modified: Vec<(&'a [u8], FieldValue<'a>, FieldValue<'a>)>,
+}
+
+impl<'a> EnrDiff<'a> {
+    /// Compares the content of two records.
+    pub(crate) fn new(
+        seq: (u64, u64),
+        from: &'a BTreeMap<Key, Value>,
+        to: &'a BTreeMap<Key, Value>,
+    ) -> Self {
+        let mut diff = Self {
+            seq,
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+        };
+        for (key, value) in from {
+            match to.get(key) {
+                None => diff.removed.push((key, FieldValue::decode(key, value))),
+                Some(other) if other != value => diff.modified.push((
+                    key,
+                    FieldValue::decode(key, value),
+                    FieldValue::decode(key, other),
+                )),
+                Some(_) => {}
+            }
+        }
+        for (key, value) in to {
+            if !from.contains_key(key) {
+                diff.added.push((key, FieldValue::decode(key, value)));
+            }
+        }
+        diff
+    }
+
+    /// Whether the content of the records is identical. The sequence numbers may still differ.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl fmt::Display for EnrDiff<'_> {
+    /// Renders one line per change: `+ key value` for added, `- key value` for removed and
+    /// `~ key old -> new` for modified fields.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seq {} -> {}", self.seq.0, self.seq.1)?;
+        for (key, value) in &self.added {
+            writeln!(f, "+ {} {value}", String::from_utf8_lossy(key))?;
+        }
+        for (key, value) in &self.removed {
+            writeln!(f, "- {} {value}", String::from_utf8_lossy(key))?;
+        }
+        for (key, from, to) in &self.modified {
+            writeln!(f, "~ {} {from} -> {to}", String::from_utf8_lossy(key))?;
+        }
+        Ok(())
+    }
+}
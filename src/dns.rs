@@ -0,0 +1,730 @@
+//! Node discovery through DNS, as specified by [EIP-1459].
+//!
+//! A tree of records is published under a domain as TXT records. Its root is signed by the
+//! tree's secp256k1 key and points to two subtrees: one holding the records of the tree and one
+//! holding links to other trees. A tree is referenced by an `enrtree://<key>@<domain>` URL.
+//!
+//! [`DnsResolver::resolve`] walks a tree and every tree it links to, verifying the signature of
+//! each root against the key of its link and the hash of every entry, and returns the records
+//! found. The two subtrees of a root are walked separately: a link in the subtree of records, or
+//! a record in the subtree of links, fails the resolution. Trees linked more than once, including
+//! links back to an already visited tree, are walked once. [`DnsResolver::records`] yields the
+//! records one at a time instead, as soon as their entry is verified. Lookups are made through a [`TxtLookup`], so that any DNS client can be used to
+//! crawl trees. With the `dns-resolve` feature, it is implemented by the hickory resolver:
+//!
+//! ```no_run
+//! # #[cfg(feature = "dns-resolve")]
+//! # async fn example() -> Result<(), enr::dns::DnsError> {
+//! use enr::{dns::DnsResolver, k256::ecdsa::SigningKey, Enr};
+//! use std::time::Duration;
+//!
+//! let resolver = DnsResolver::from_system_conf()?.rate_limit(Duration::from_millis(50));
+//! let records: Vec<Enr<SigningKey>> = resolver
+//!     .resolve("enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@all.mainnet.ethdisco.net")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [EIP-1459]: https://eips.ethereum.org/EIPS/eip-1459
+
+use crate::{Enr, EnrKey, EnrPublicKey};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use data_encoding::BASE32_NOPAD;
+#[cfg(feature = "dns-resolve")]
+use hickory_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+use k256::ecdsa::VerifyingKey;
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::HashSet, error::Error, fmt, future::Future, marker::PhantomData, str::FromStr,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// The prefix of a tree root entry.
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+/// The prefix of a branch entry.
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+/// The prefix of a link entry.
+const LINK_PREFIX: &str = "enrtree://";
+/// The default maximum number of entries looked up by a single resolution.
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// An error resolving a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DnsError {
+    /// The DNS lookup of the given name failed.
+    Lookup(String),
+    /// No TXT record exists under the given name.
+    MissingEntry(String),
+    /// The given entry is malformed.
+    InvalidEntry(String),
+    /// The root of the tree at the given domain is not signed by the key of its link.
+    InvalidSignature(String),
+    /// The entry under the given name does not match the hash it is published under.
+    HashMismatch(String),
+    /// The entry under the given name is a record in the subtree of links, or a link in the
+    /// subtree of records.
+    MisplacedEntry(String),
+    /// The resolution exceeded the maximum number of entries.
+    TooManyEntries,
+}
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lookup(error) => write!(f, "dns lookup failed: {error}"),
+            Self::MissingEntry(name) => write!(f, "no entry at `{name}`"),
+            Self::InvalidEntry(entry) => write!(f, "invalid tree entry `{entry}`"),
+            Self::InvalidSignature(domain) => write!(f, "invalid root signature at `{domain}`"),
+            Self::HashMismatch(name) => write!(f, "entry at `{name}` does not match its hash"),
+            Self::MisplacedEntry(name) => write!(f, "entry at `{name}` is in the wrong subtree"),
+            Self::TooManyEntries => write!(f, "too many tree entries"),
+        }
+    }
+}
+
+impl Error for DnsError {}
+
+/// The signed root of a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeRoot {
+    /// The hash of the root of the subtree holding records.
+    pub enr_root: String,
+    /// The hash of the root of the subtree holding links.
+    pub link_root: String,
+    /// The sequence number of the tree.
+    pub seq: u64,
+    /// The 65 byte recoverable signature of the root.
+    pub signature: Vec<u8>,
+}
+
+impl TreeRoot {
+    /// The signed part of the root entry.
+    fn signed_text(&self) -> String {
+        format!(
+            "{ROOT_PREFIX} e={} l={} seq={}",
+            self.enr_root, self.link_root, self.seq
+        )
+    }
+
+    /// Whether the root is signed by `public_key`.
+    #[must_use]
+    pub fn verify(&self, public_key: &VerifyingKey) -> bool {
+        self.signature.len() == 65
+            && public_key.verify_v4(self.signed_text().as_bytes(), &self.signature[..64])
+    }
+}
+
+impl FromStr for TreeRoot {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DnsError::InvalidEntry(s.into());
+        let mut fields = s.strip_prefix(ROOT_PREFIX).ok_or_else(invalid)?.split(' ');
+        if fields.next() != Some("") {
+            return Err(invalid());
+        }
+        let mut field = |name: &str| {
+            fields
+                .next()
+                .and_then(|field| field.strip_prefix(name))
+                .ok_or_else(invalid)
+        };
+        let enr_root = field("e=")?.to_string();
+        let link_root = field("l=")?.to_string();
+        let seq = field("seq=")?.parse().map_err(|_| invalid())?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(field("sig=")?)
+            .map_err(|_| invalid())?;
+        Ok(Self {
+            enr_root,
+            link_root,
+            seq,
+            signature,
+        })
+    }
+}
+
+impl fmt::Display for TreeRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sig={}",
+            self.signed_text(),
+            URL_SAFE_NO_PAD.encode(&self.signature)
+        )
+    }
+}
+
+/// A reference to a tree, in its `enrtree://<key>@<domain>` form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeLink {
+    /// The key the root of the tree is signed with.
+    pub public_key: VerifyingKey,
+    /// The domain the tree is published under.
+    pub domain: String,
+}
+
+impl FromStr for TreeLink {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DnsError::InvalidEntry(s.into());
+        let (key, domain) = s
+            .strip_prefix(LINK_PREFIX)
+            .and_then(|link| link.split_once('@'))
+            .ok_or_else(invalid)?;
+        let key = BASE32_NOPAD.decode(key.as_bytes()).map_err(|_| invalid())?;
+        let public_key = VerifyingKey::from_sec1_bytes(&key).map_err(|_| invalid())?;
+        if domain.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            public_key,
+            domain: domain.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for TreeLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{LINK_PREFIX}{}@{}",
+            BASE32_NOPAD.encode(&self.public_key.encode()),
+            self.domain
+        )
+    }
+}
+
+/// An entry of a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeEntry<K: EnrKey> {
+    /// The signed root of the tree.
+    Root(TreeRoot),
+    /// The hashes of child entries.
+    Branch(Vec<String>),
+    /// A link to another tree.
+    Link(TreeLink),
+    /// A record.
+    Enr(Enr<K>),
+}
+
+impl<K: EnrKey> FromStr for TreeEntry<K> {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with(ROOT_PREFIX) {
+            s.parse().map(Self::Root)
+        } else if let Some(children) = s.strip_prefix(BRANCH_PREFIX) {
+            Ok(Self::Branch(
+                children
+                    .split(',')
+                    .filter(|child| !child.is_empty())
+                    .map(String::from)
+                    .collect(),
+            ))
+        } else if s.starts_with(LINK_PREFIX) {
+            s.parse().map(Self::Link)
+        } else if s.starts_with("enr:") {
+            s.parse()
+                .map(Self::Enr)
+                .map_err(|_| DnsError::InvalidEntry(s.into()))
+        } else {
+            Err(DnsError::InvalidEntry(s.into()))
+        }
+    }
+}
+
+/// The subdomain an entry is published under: the base32 encoding of the first 16 bytes of the
+/// keccak256 hash of its text.
+#[must_use]
+pub fn entry_hash(entry: &str) -> String {
+    BASE32_NOPAD.encode(&Keccak256::digest(entry.as_bytes())[..16])
+}
+
+/// Looks up the TXT records of a name.
+pub trait TxtLookup: Sync {
+    /// The text of the TXT record of `name`, if any. The character strings of a record are
+    /// concatenated.
+    fn lookup_txt(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<String>, DnsError>> + Send;
+}
+
+#[cfg(feature = "dns-resolve")]
+impl TxtLookup for TokioAsyncResolver {
+    async fn lookup_txt(&self, name: &str) -> Result<Option<String>, DnsError> {
+        let lookup = match self.txt_lookup(name).await {
+            Ok(lookup) => lookup,
+            Err(error) if matches!(error.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                return Ok(None)
+            }
+            Err(error) => return Err(DnsError::Lookup(error.to_string())),
+        };
+        Ok(lookup.iter().next().map(|txt| {
+            txt.txt_data()
+                .iter()
+                .map(|data| String::from_utf8_lossy(data))
+                .collect()
+        }))
+    }
+}
+
+/// Resolves trees into the records they hold.
+#[derive(Clone, Debug)]
+pub struct DnsResolver<L> {
+    /// The source of TXT records.
+    lookup: L,
+    /// The minimum time between two lookups of one resolution.
+    min_interval: Duration,
+    /// The maximum number of entries looked up by one resolution.
+    max_entries: usize,
+}
+
+#[cfg(feature = "dns-resolve")]
+impl DnsResolver<TokioAsyncResolver> {
+    /// Creates a resolver using the system's DNS configuration.
+    pub fn from_system_conf() -> Result<Self, DnsError> {
+        TokioAsyncResolver::tokio_from_system_conf()
+            .map(Self::new)
+            .map_err(|error| DnsError::Lookup(error.to_string()))
+    }
+}
+
+impl<L: TxtLookup> DnsResolver<L> {
+    /// Creates a resolver looking up TXT records through `lookup`, without rate limit.
+    pub const fn new(lookup: L) -> Self {
+        Self {
+            lookup,
+            min_interval: Duration::ZERO,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+
+    /// Waits at least `min_interval` between two lookups of one resolution.
+    #[must_use]
+    pub const fn rate_limit(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Fails a resolution with [`DnsError::TooManyEntries`] once it has looked up
+    /// `max_entries` entries, 10000 by default.
+    #[must_use]
+    pub const fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Resolves the tree referenced by `url` and the trees it links to, returning every record
+    /// found. Records are returned in the order they are found.
+    pub async fn resolve<K: EnrKey>(&self, url: &str) -> Result<Vec<Enr<K>>, DnsError> {
+        let mut records = Vec::new();
+        let mut stream = self.records(url)?;
+        while let Some(record) = stream.next().await {
+            records.push(record?);
+        }
+        Ok(records)
+    }
+
+    /// Resolves the tree referenced by `url` and the trees it links to, yielding each record as
+    /// soon as its entry is verified. Records are yielded in the same order as by
+    /// [`Self::resolve`].
+    pub fn records<K: EnrKey>(&self, url: &str) -> Result<TreeRecords<'_, L, K>, DnsError> {
+        Ok(TreeRecords {
+            walk: Walk {
+                resolver: self,
+                last_lookup: None,
+                entries: 0,
+            },
+            trees: vec![url.parse()?],
+            visited_trees: HashSet::new(),
+            domain: String::new(),
+            pending: Vec::new(),
+            visited_entries: HashSet::new(),
+            done: false,
+            _key: PhantomData,
+        })
+    }
+}
+
+/// The subtrees of a tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Subtree {
+    /// The subtree holding records.
+    Enr,
+    /// The subtree holding links.
+    Link,
+}
+
+/// The records of a tree and of the trees it links to, yielded as they are verified.
+///
+/// Records are pulled with [`TreeRecords::next`]. This is not a `futures::Stream` so that the
+/// crate does not depend on `futures`; wrapping it in one only takes a `futures::stream::unfold`.
+/// Once an error is yielded, the walk is over.
+pub struct TreeRecords<'a, L, K> {
+    /// The lookups of the walk.
+    walk: Walk<'a, L>,
+    /// The trees left to walk.
+    trees: Vec<TreeLink>,
+    /// The domains of the trees walked so far.
+    visited_trees: HashSet<String>,
+    /// The domain of the tree being walked.
+    domain: String,
+    /// The hashes of the entries left to look up in the tree being walked, with their subtree.
+    pending: Vec<(String, Subtree)>,
+    /// The entries of the tree being walked looked up so far.
+    visited_entries: HashSet<(String, Subtree)>,
+    /// Whether the walk is over.
+    done: bool,
+    _key: PhantomData<K>,
+}
+
+impl<L: TxtLookup, K: EnrKey> TreeRecords<'_, L, K> {
+    /// The next record, or `None` once every tree has been walked or an error was yielded.
+    pub async fn next(&mut self) -> Option<Result<Enr<K>, DnsError>> {
+        if self.done {
+            return None;
+        }
+        let next = self.advance().await.transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+
+    /// Looks up entries until a record is found or every tree has been walked.
+    async fn advance(&mut self) -> Result<Option<Enr<K>>, DnsError> {
+        loop {
+            if let Some((hash, subtree)) = self.pending.pop() {
+                if !self.visited_entries.insert((hash.clone(), subtree)) {
+                    continue;
+                }
+                let name = format!("{hash}.{}", self.domain);
+                let entry = self.walk.lookup(&name).await?;
+                if !entry_hash(&entry).eq_ignore_ascii_case(&hash) {
+                    return Err(DnsError::HashMismatch(name));
+                }
+                match (entry.parse::<TreeEntry<K>>()?, subtree) {
+                    (TreeEntry::Branch(children), _) => self
+                        .pending
+                        .extend(children.into_iter().map(|child| (child, subtree))),
+                    (TreeEntry::Enr(enr), Subtree::Enr) => return Ok(Some(enr)),
+                    (TreeEntry::Link(link), Subtree::Link) => self.trees.push(link),
+                    (TreeEntry::Root(_), _) => return Err(DnsError::InvalidEntry(entry)),
+                    _ => return Err(DnsError::MisplacedEntry(name)),
+                }
+            } else if let Some(tree) = self.trees.pop() {
+                if !self.visited_trees.insert(tree.domain.clone()) {
+                    continue;
+                }
+                let root = self.walk.lookup(&tree.domain).await?;
+                let root: TreeRoot = root.parse()?;
+                if !root.verify(&tree.public_key) {
+                    return Err(DnsError::InvalidSignature(tree.domain));
+                }
+                // the subtree of records is walked first, then the subtree of links
+                self.domain = tree.domain;
+                self.visited_entries.clear();
+                self.pending.push((root.link_root, Subtree::Link));
+                self.pending.push((root.enr_root, Subtree::Enr));
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// The lookups of one resolution.
+struct Walk<'a, L> {
+    /// The resolver of the resolution.
+    resolver: &'a DnsResolver<L>,
+    /// When the previous lookup was made.
+    last_lookup: Option<Instant>,
+    /// The number of entries looked up so far.
+    entries: usize,
+}
+
+impl<L: TxtLookup> Walk<'_, L> {
+    /// Looks up the entry at `name`, respecting the rate and entry limits.
+    async fn lookup(&mut self, name: &str) -> Result<String, DnsError> {
+        if self.entries >= self.resolver.max_entries {
+            return Err(DnsError::TooManyEntries);
+        }
+        self.entries += 1;
+        if let Some(last_lookup) = self.last_lookup {
+            tokio::time::sleep_until(last_lookup + self.resolver.min_interval).await;
+        }
+        self.last_lookup = Some(Instant::now());
+        self.resolver
+            .lookup
+            .lookup_txt(name)
+            .await?
+            .ok_or_else(|| DnsError::MissingEntry(name.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use std::collections::HashMap;
+
+    type DefaultEnr = Enr<SigningKey>;
+
+    /// TXT records held in memory.
+    #[derive(Default)]
+    struct Zone(HashMap<String, String>);
+
+    impl TxtLookup for Zone {
+        async fn lookup_txt(&self, name: &str) -> Result<Option<String>, DnsError> {
+            Ok(self.0.get(name).cloned())
+        }
+    }
+
+    impl Zone {
+        /// Publishes an entry under its hash, returning the hash.
+        fn publish(&mut self, domain: &str, entry: String) -> String {
+            let hash = entry_hash(&entry);
+            self.0.insert(format!("{hash}.{domain}"), entry);
+            hash
+        }
+
+        /// Publishes a tree signed by `key` holding `records` and `links`.
+        fn publish_tree(
+            &mut self,
+            link: &TreeLink,
+            key: &SigningKey,
+            records: &[DefaultEnr],
+            links: &[TreeLink],
+        ) {
+            let domain = link.domain.as_str();
+            let records: Vec<_> = records
+                .iter()
+                .map(|enr| self.publish(domain, enr.to_base64()))
+                .collect();
+            let links: Vec<_> = links
+                .iter()
+                .map(|link| self.publish(domain, link.to_string()))
+                .collect();
+            self.publish_root(domain, key, &records, &links);
+        }
+
+        /// Publishes a root signed by `key` over branches of the given entry hashes.
+        fn publish_root(
+            &mut self,
+            domain: &str,
+            key: &SigningKey,
+            records: &[String],
+            links: &[String],
+        ) {
+            let enr_root = self.publish(domain, format!("{BRANCH_PREFIX}{}", records.join(",")));
+            let link_root = self.publish(domain, format!("{BRANCH_PREFIX}{}", links.join(",")));
+
+            let mut root = TreeRoot {
+                enr_root,
+                link_root,
+                seq: 1,
+                signature: Vec::new(),
+            };
+            root.signature = key.sign_v4(root.signed_text().as_bytes()).unwrap();
+            root.signature.push(0);
+            self.0.insert(domain.into(), root.to_string());
+        }
+    }
+
+    /// A new tree key and the link to its tree at `domain`.
+    fn tree(domain: &str) -> (SigningKey, TreeLink) {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let link = TreeLink {
+            public_key: key.public(),
+            domain: domain.into(),
+        };
+        (key, link)
+    }
+
+    fn records(count: u16) -> Vec<DefaultEnr> {
+        (0..count)
+            .map(|port| {
+                let key = SigningKey::random(&mut rand::thread_rng());
+                Enr::builder().udp4(9000 + port).build(&key).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let link: TreeLink =
+            "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@morenodes.example.org"
+                .parse()
+                .unwrap();
+        assert_eq!(link.domain, "morenodes.example.org");
+        assert_eq!(
+            link.to_string(),
+            "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@morenodes.example.org"
+        );
+
+        // the example root of EIP-1459
+        let root = "enrtree-root:v1 e=JWXYDBPXYWG6FX3GMDIBFA6CJ4 l=C7HRFPF3BLGF3YR4DY5KX3SMBE seq=1 sig=o908WmNp7LibOfPsr4btQwatZJ5URBr2ZAuxvK4UWHlsB9sUOTJQaGAlLPVAhM__XJesCHxLISo94z5Z2a463gA";
+        let parsed: TreeRoot = root.parse().unwrap();
+        assert_eq!(parsed.seq, 1);
+        assert_eq!(parsed.to_string(), root);
+
+        assert!(matches!(
+            "enrtree-branch:".parse::<TreeEntry<SigningKey>>(),
+            Ok(TreeEntry::Branch(children)) if children.is_empty()
+        ));
+        assert!("enrtree-leaf:x".parse::<TreeEntry<SigningKey>>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_linked_trees() {
+        let mut zone = Zone::default();
+        let (first, second) = (records(3), records(2));
+
+        // the trees link to each other
+        let (first_key, first_link) = tree("a.example.org");
+        let (second_key, second_link) = tree("b.example.org");
+        zone.publish_tree(
+            &first_link,
+            &first_key,
+            &first,
+            std::slice::from_ref(&second_link),
+        );
+        zone.publish_tree(
+            &second_link,
+            &second_key,
+            &second,
+            std::slice::from_ref(&first_link),
+        );
+
+        let resolver = DnsResolver::new(zone);
+        let mut found: Vec<DefaultEnr> = resolver.resolve(&first_link.to_string()).await.unwrap();
+        let mut expected: Vec<_> = first.into_iter().chain(second).collect();
+        found.sort_by_key(|enr| enr.node_id().raw());
+        expected.sort_by_key(|enr| enr.node_id().raw());
+        assert_eq!(found, expected);
+
+        let limited = resolver.max_entries(4);
+        assert_eq!(
+            limited
+                .resolve::<SigningKey>(&first_link.to_string())
+                .await
+                .unwrap_err(),
+            DnsError::TooManyEntries
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_trees() {
+        let mut zone = Zone::default();
+        let (key, link) = tree("a.example.org");
+        zone.publish_tree(&link, &key, &records(1), &[]);
+        let url = link.to_string();
+
+        // signed by another key
+        let other = TreeLink {
+            public_key: SigningKey::random(&mut rand::thread_rng()).public(),
+            domain: link.domain.clone(),
+        };
+        let resolver = DnsResolver::new(zone);
+        assert_eq!(
+            resolver
+                .resolve::<SigningKey>(&other.to_string())
+                .await
+                .unwrap_err(),
+            DnsError::InvalidSignature(link.domain.clone())
+        );
+
+        // an entry not matching its hash
+        let mut zone = resolver.lookup;
+        let root: TreeRoot = zone.0[&link.domain].parse().unwrap();
+        let name = format!("{}.{}", root.enr_root, link.domain);
+        zone.0.insert(name.clone(), "enrtree-branch:".into());
+        assert_eq!(
+            DnsResolver::new(zone)
+                .resolve::<SigningKey>(&url)
+                .await
+                .unwrap_err(),
+            DnsError::HashMismatch(name)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_misplaced_entries() {
+        let (key, link) = tree("a.example.org");
+        let (_, other) = tree("b.example.org");
+        let domain = link.domain.as_str();
+
+        // a link in the subtree of records
+        let mut zone = Zone::default();
+        let leaf = zone.publish(domain, other.to_string());
+        zone.publish_root(domain, &key, std::slice::from_ref(&leaf), &[]);
+        assert_eq!(
+            DnsResolver::new(zone)
+                .resolve::<SigningKey>(&link.to_string())
+                .await
+                .unwrap_err(),
+            DnsError::MisplacedEntry(format!("{leaf}.{domain}"))
+        );
+
+        // a record in the subtree of links
+        let mut zone = Zone::default();
+        let leaf = zone.publish(domain, records(1)[0].to_base64());
+        zone.publish_root(domain, &key, &[], std::slice::from_ref(&leaf));
+        assert_eq!(
+            DnsResolver::new(zone)
+                .resolve::<SigningKey>(&link.to_string())
+                .await
+                .unwrap_err(),
+            DnsError::MisplacedEntry(format!("{leaf}.{domain}"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_records_stream() {
+        let mut zone = Zone::default();
+        let (key, link) = tree("a.example.org");
+        let expected = records(2);
+        zone.publish_tree(&link, &key, &expected, &[]);
+
+        let resolver = DnsResolver::new(zone);
+        let mut stream = resolver.records::<SigningKey>(&link.to_string()).unwrap();
+        let mut found = Vec::new();
+        while let Some(record) = stream.next().await {
+            found.push(record.unwrap());
+        }
+        assert_eq!(found, resolver.resolve(&link.to_string()).await.unwrap());
+        found.sort_by_key(|enr| enr.node_id().raw());
+        let mut expected = expected;
+        expected.sort_by_key(|enr| enr.node_id().raw());
+        assert_eq!(found, expected);
+
+        // the walk is over after an error
+        let resolver = DnsResolver::new(Zone::default());
+        let mut stream = resolver.records::<SigningKey>(&link.to_string()).unwrap();
+        assert_eq!(
+            stream.next().await,
+            Some(Err(DnsError::MissingEntry(link.domain.clone())))
+        );
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit() {
+        let mut zone = Zone::default();
+        let (key, link) = tree("a.example.org");
+        zone.publish_tree(&link, &key, &records(2), &[]);
+        let resolver = DnsResolver::new(zone).rate_limit(Duration::from_secs(1));
+
+        let start = Instant::now();
+        let found = resolver
+            .resolve::<SigningKey>(&link.to_string())
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        // the root, two branches and two records
+        assert!(start.elapsed() >= Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,283 @@
+//! Unsigned records prepared for out-of-band signing.
+//!
+//! A [`DraftEnr`] holds the content of a "v4" record for a declared public key, without a
+//! signature. It can be modified without access to the private key, and
+//! [`DraftEnr::signing_bytes`] yields the exact RLP encoded content a signer signs with
+//! [`EnrKey::sign_v4`]. [`DraftEnr::finalize`] turns the draft into an [`Enr`] once the signature
+//! is supplied, verifying it against the declared public key.
+//!
+//! The signing bytes are also the transport form of a draft: a pipeline can prepare a draft, hand
+//! its signing bytes to an air-gapped signer and restore the draft with
+//! [`DraftEnr::from_signing_bytes`] to complete it.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, DraftEnr, EnrKey};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//!
+//! // prepared without the private key
+//! let mut draft = DraftEnr::<SigningKey>::new(key.public());
+//! draft.insert("udp", &30303_u16).unwrap();
+//! let bytes = draft.signing_bytes();
+//!
+//! // signed elsewhere
+//! let signature = key.sign_v4(&bytes).unwrap();
+//!
+//! let enr = DraftEnr::<SigningKey>::from_signing_bytes(&bytes)
+//!     .unwrap()
+//!     .finalize(signature)
+//!     .unwrap();
+//! assert_eq!(enr.udp4(), Some(30303));
+//! # }
+//! ```
+
+use crate::{
+    decode_record_fields, storage, Enr, EnrError, EnrKey, EnrPublicKey, FieldChanges, Key,
+    PreviousValues, Transaction, V4Scheme, Value, MAX_ENR_SIZE,
+};
+use bytes::Bytes;
+use rlp::{DecoderError, Encodable, Rlp, RlpStream};
+use std::{collections::BTreeMap, fmt};
+
+/// The unsigned content of a record for a declared public key.
+pub struct DraftEnr<K: EnrKey> {
+    /// The sequence number of the record.
+    seq: u64,
+    /// The declared public key the record will be signed with.
+    public_key: K::PublicKey,
+    /// The key-value pairs of the record, including the identity scheme and public key.
+    content: BTreeMap<Key, Value>,
+}
+
+impl<K: EnrKey> DraftEnr<K> {
+    /// Creates a draft of a new record with sequence number 1 for `public_key`.
+    #[must_use]
+    pub fn new(public_key: K::PublicKey) -> Self {
+        let mut content = BTreeMap::new();
+        content.insert(
+            Key::from(&b"id"[..]),
+            storage::value(rlp::encode(&"v4").freeze()),
+        );
+        content.insert(
+            Key::from(public_key.enr_key()),
+            storage::value(rlp::encode(&public_key.encode().as_ref()).freeze()),
+        );
+        Self {
+            seq: 1,
+            public_key,
+            content,
+        }
+    }
+
+    /// Creates a draft of the next version of `enr`, with its content and the sequence number
+    /// following its own under its [`SeqPolicy`](crate::SeqPolicy).
+    pub fn from_enr(enr: &Enr<K>) -> Result<Self, EnrError> {
+        Ok(Self {
+            seq: enr.seq_policy().next(enr.seq())?,
+            public_key: enr.public_key(),
+            content: enr.content.clone(),
+        })
+    }
+
+    /// Restores a draft from its [`DraftEnr::signing_bytes`].
+    pub fn from_signing_bytes(bytes: &[u8]) -> Result<Self, DecoderError> {
+        let rlp = Rlp::new(bytes);
+        let header_len = rlp.payload_info()?.header_len;
+        // decoded as a record with an empty signature
+        let mut record = RlpStream::new_list(rlp.item_count()? + 1);
+        record.append_empty_data();
+        record.append_raw(&bytes[header_len..], rlp.item_count()?);
+        let (_, seq, content) = decode_record_fields(&Rlp::new(&record.out()), false)?;
+
+        if content.get(b"id".as_ref()).map(AsRef::as_ref) != Some(rlp::encode(&"v4").as_ref()) {
+            return Err(DecoderError::Custom("Unsupported identity scheme"));
+        }
+        Ok(Self {
+            seq,
+            public_key: storage::enr_to_public::<K>(&content)?,
+            content,
+        })
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Sets the sequence number of the record.
+    pub fn set_seq(&mut self, seq: u64) {
+        self.seq = seq;
+    }
+
+    /// The declared public key.
+    #[must_use]
+    pub const fn public_key(&self) -> &K::PublicKey {
+        &self.public_key
+    }
+
+    /// The raw RLP encoded value of `key`.
+    #[must_use]
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.content.get(key.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// Adds or overwrites `key` with the RLP encoding of `value`, returning the previous raw RLP
+    /// encoded value.
+    pub fn insert<T: Encodable>(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: &T,
+    ) -> Result<Option<Bytes>, EnrError> {
+        let key = key.as_ref();
+        let mut changes = FieldChanges::new();
+        changes.insert(key, value);
+        self.apply_changes(&changes)
+            .map(|previous| previous.get_raw_rlp(key).map(Bytes::copy_from_slice))
+    }
+
+    /// Removes `key`, returning its raw RLP encoded value.
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Result<Option<Bytes>, EnrError> {
+        let key = key.as_ref();
+        let mut changes = FieldChanges::new();
+        changes.remove(key);
+        self.apply_changes(&changes)
+            .map(|previous| previous.get_raw_rlp(key).map(Bytes::copy_from_slice))
+    }
+
+    /// Applies a batch of changes like [`Enr::apply_changes`], without incrementing the sequence
+    /// number. The identity scheme and the declared public key cannot be changed.
+    pub fn apply_changes(&mut self, changes: &FieldChanges) -> Result<PreviousValues, EnrError> {
+        let public_key = self.public_key.enr_key();
+        let mut content = self.content.clone();
+        let mut tx =
+            Transaction::<V4Scheme>::new(&mut content, None, Key::from(public_key.as_slice()));
+        tx.apply_changes(changes);
+        let previous = tx.finish()?;
+        if content.get(public_key.as_slice()) != self.content.get(public_key.as_slice()) {
+            return Err(EnrError::PublicKeyMismatch);
+        }
+        self.content = content;
+        Ok(previous)
+    }
+
+    /// The RLP encoded content to sign with [`EnrKey::sign_v4`].
+    #[must_use]
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(self.content.len() * 2 + 1);
+        stream.append(&self.seq);
+        for (key, value) in &self.content {
+            stream.append(&key.as_slice());
+            stream.append_raw(value, 1);
+        }
+        stream.out().to_vec()
+    }
+
+    /// Completes the draft with the signature of its [`DraftEnr::signing_bytes`].
+    ///
+    /// # Errors
+    /// Fails with [`EnrError::SigningError`] if the signature is invalid for the declared public
+    /// key, or if the record exceeds the maximum size or violates the default
+    /// [`ConsistencyPolicy`](crate::ConsistencyPolicy).
+    pub fn finalize(self, signature: Vec<u8>) -> Result<Enr<K>, EnrError> {
+        let enr = Enr::from_record_fields(signature, self.seq, self.content)
+            .map_err(|_| EnrError::SigningError)?;
+        if enr.size() > MAX_ENR_SIZE {
+            return Err(EnrError::ExceedsMaxSize);
+        }
+        enr.consistency_policy.check(&enr.content)?;
+        Ok(enr)
+    }
+}
+
+impl<K: EnrKey> Clone for DraftEnr<K> {
+    fn clone(&self) -> Self {
+        Self {
+            seq: self.seq,
+            public_key: self.public_key.clone(),
+            content: self.content.clone(),
+        }
+    }
+}
+
+impl<K: EnrKey> fmt::Debug for DraftEnr<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DraftEnr")
+            .field("seq", &self.seq)
+            .field("public_key", &self.public_key)
+            .field("content", &self.content)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_finalize() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut draft = DraftEnr::<DefaultKey>::new(key.public());
+        draft.insert("ip", &[10_u8, 0, 0, 1].as_ref()).unwrap();
+        draft.insert("udp", &30303_u16).unwrap();
+        assert!(draft.insert("udp", &u32::MAX).is_err());
+
+        // the draft of a built record has the content the builder signs
+        let built: Enr<DefaultKey> = Enr::builder()
+            .ip4([10, 0, 0, 1].into())
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
+        assert_eq!(draft.signing_bytes(), built.rlp_content().to_vec());
+
+        let signature = key.sign_v4(&draft.signing_bytes()).unwrap();
+        let enr = draft.clone().finalize(signature).unwrap();
+        assert!(enr.compare_content(&built));
+
+        // a signature by another key is rejected
+        let other = DefaultKey::random(&mut rand::thread_rng());
+        let signature = other.sign_v4(&draft.signing_bytes()).unwrap();
+        assert_eq!(
+            draft.finalize(signature).unwrap_err(),
+            EnrError::SigningError
+        );
+    }
+
+    #[test]
+    fn test_next_version() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+
+        let mut draft = DraftEnr::from_enr(&enr).unwrap();
+        assert_eq!(
+            draft.remove("udp").unwrap(),
+            enr.get_raw_rlp("udp").map(Bytes::copy_from_slice)
+        );
+        draft.insert("tcp", &9000_u16).unwrap();
+        assert_eq!(
+            draft.remove("secp256k1").unwrap_err(),
+            EnrError::MandatoryKey("secp256k1".into())
+        );
+        assert_eq!(
+            draft.remove("id").unwrap_err(),
+            EnrError::MandatoryKey("id".into())
+        );
+
+        let restored = DraftEnr::<DefaultKey>::from_signing_bytes(&draft.signing_bytes()).unwrap();
+        assert_eq!(restored.seq(), 2);
+        assert_eq!(restored.signing_bytes(), draft.signing_bytes());
+
+        let signature = key.sign_v4(&restored.signing_bytes()).unwrap();
+        let next = restored.finalize(signature).unwrap();
+        assert_eq!(next.seq(), 2);
+        assert_eq!(next.tcp4(), Some(9000));
+        assert_eq!(next.udp4(), None);
+        assert_eq!(next.node_id(), enr.node_id());
+
+        assert!(DraftEnr::<DefaultKey>::from_signing_bytes(&[0xc0]).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Transport endpoints advertised in an ENR.
+//!
+//! An [`Endpoint`] combines a [`Transport`] with an IP address and port. The ENR stores the IP
+//! address under the `ip`/`ip6` key and the port under a transport and family specific key (e.g.
+//! `udp` or `tcp6`). Further fields describing the endpoint can be carried along as its `extra`
+//! fields.
+
+use bytes::Bytes;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+/// A transport protocol a node can be reached over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Transport {
+    /// The UDP transport, stored under the `udp`/`udp6` keys.
+    Udp,
+    /// The TCP transport, stored under the `tcp`/`tcp6` keys.
+    Tcp,
+    /// The QUIC transport, stored under the `quic`/`quic6` keys.
+    Quic,
+}
+
+impl Transport {
+    /// All supported transports.
+    pub const ALL: [Self; 3] = [Self::Udp, Self::Tcp, Self::Quic];
+
+    /// The ENR key of the transport's IPv4 port.
+    #[must_use]
+    pub const fn port_key_v4(self) -> &'static str {
+        match self {
+            Self::Udp => "udp",
+            Self::Tcp => "tcp",
+            Self::Quic => "quic",
+        }
+    }
+
+    /// The ENR key of the transport's IPv6 port.
+    #[must_use]
+    pub const fn port_key_v6(self) -> &'static str {
+        match self {
+            Self::Udp => "udp6",
+            Self::Tcp => "tcp6",
+            Self::Quic => "quic6",
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Udp => write!(f, "udp"),
+            Self::Tcp => write!(f, "tcp"),
+            Self::Quic => write!(f, "quic"),
+        }
+    }
+}
+
+impl FromStr for Transport {
+    type Err = UnknownTransport;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "udp" => Ok(Self::Udp),
+            "tcp" => Ok(Self::Tcp),
+            "quic" => Ok(Self::Quic),
+            _ => Err(UnknownTransport(s.to_string())),
+        }
+    }
+}
+
+/// The error of parsing the name of a transport this crate does not know.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownTransport(pub String);
+
+impl fmt::Display for UnknownTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown transport: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTransport {}
+
+/// An address a node can be reached at over a given [`Transport`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    /// The transport protocol of the endpoint.
+    pub transport: Transport,
+    /// The IP address of the endpoint.
+    pub ip: IpAddr,
+    /// The port of the endpoint.
+    pub port: u16,
+    /// Further fields describing the endpoint, e.g. transport parameters, as ENR keys and their
+    /// raw RLP encoded values. They are written along with the endpoint when it is set. The record
+    /// does not tie fields to an endpoint, so the endpoints read from a record have none.
+    pub extra: BTreeMap<Vec<u8>, Bytes>,
+}
+
+impl Endpoint {
+    /// Creates a new [`Endpoint`] from a transport and socket address.
+    #[must_use]
+    pub const fn new(transport: Transport, socket: SocketAddr) -> Self {
+        Self {
+            transport,
+            ip: socket.ip(),
+            port: socket.port(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the field `key` with the raw RLP encoded `value` to the extra fields of the endpoint.
+    #[must_use]
+    pub fn with_extra(mut self, key: impl AsRef<[u8]>, value: Bytes) -> Self {
+        self.extra.insert(key.as_ref().to_vec(), value);
+        self
+    }
+
+    /// The socket address of the endpoint.
+    #[must_use]
+    pub const fn socket(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port)
+    }
+
+    /// The ENR key under which the endpoint's IP address is stored.
+    #[must_use]
+    pub const fn ip_key(&self) -> &'static str {
+        match self.ip {
+            IpAddr::V4(_) => "ip",
+            IpAddr::V6(_) => "ip6",
+        }
+    }
+
+    /// The ENR key under which the endpoint's port is stored.
+    #[must_use]
+    pub const fn port_key(&self) -> &'static str {
+        match self.ip {
+            IpAddr::V4(_) => self.transport.port_key_v4(),
+            IpAddr::V6(_) => self.transport.port_key_v6(),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://{}", self.transport, self.socket())
+    }
+}
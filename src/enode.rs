@@ -0,0 +1,120 @@
+//! Conversion between records and legacy `enode://` URLs.
+//!
+//! Before ENRs, Ethereum nodes were identified by URLs of the form
+//! `enode://<public key>@<ip>:<tcp port>?discport=<udp port>`, which execution layer tooling
+//! still uses. The public key is the hex encoded, uncompressed `secp256k1` key without its `04`
+//! prefix, and the `discport` query is present only if the UDP port differs from the TCP port.
+//!
+//! [`Enr::to_enode_url`] and [`Enr::try_from_enode_url`] convert between the two formats.
+//!
+//! [`Enr::to_enode_url`]: crate::Enr::to_enode_url
+//! [`Enr::try_from_enode_url`]: crate::Enr::try_from_enode_url
+
+use crate::EnrError;
+use std::{convert::TryInto, error::Error, fmt, net::SocketAddr, str::FromStr};
+
+/// An error converting between a record and an `enode://` URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnodeError {
+    /// The public key of the record is not a `secp256k1` key.
+    UnsupportedKey,
+    /// The record has no IP address.
+    MissingIp,
+    /// The record has no TCP port for its IP address.
+    MissingTcpPort,
+    /// The URL is malformed, for the given reason.
+    InvalidUrl(String),
+    /// The public key of the URL is not the public key of the signing key.
+    PublicKeyMismatch,
+    /// The record could not be built.
+    Build(EnrError),
+}
+
+impl fmt::Display for EnodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedKey => write!(f, "enode URLs require a secp256k1 public key"),
+            Self::MissingIp => write!(f, "the record has no IP address"),
+            Self::MissingTcpPort => write!(f, "the record has no TCP port"),
+            Self::InvalidUrl(reason) => write!(f, "invalid enode URL: {reason}"),
+            Self::PublicKeyMismatch => {
+                write!(
+                    f,
+                    "the enode public key is not the public key of the signing key"
+                )
+            }
+            Self::Build(error) => write!(f, "failed to build the record: {error}"),
+        }
+    }
+}
+
+impl Error for EnodeError {}
+
+/// The content of an `enode://` URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Enode {
+    /// The uncompressed `secp256k1` public key without its `04` prefix.
+    pub(crate) public_key: [u8; 64],
+    /// The IP address and TCP port.
+    pub(crate) socket: SocketAddr,
+    /// The UDP port, if it differs from the TCP port.
+    pub(crate) discport: Option<u16>,
+}
+
+impl Enode {
+    /// The UDP port, which defaults to the TCP port.
+    pub(crate) fn udp_port(&self) -> u16 {
+        self.discport.unwrap_or_else(|| self.socket.port())
+    }
+}
+
+impl FromStr for Enode {
+    type Err = EnodeError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| EnodeError::InvalidUrl(reason);
+        let rest = url
+            .strip_prefix("enode://")
+            .ok_or_else(|| invalid("expected an `enode://` URL".into()))?;
+        let (public_key, address) = rest
+            .split_once('@')
+            .ok_or_else(|| invalid("expected `<public key>@<address>`".into()))?;
+        let (address, query) = address.split_once('?').unwrap_or((address, ""));
+
+        let public_key = hex::decode(public_key)
+            .map_err(|e| invalid(format!("invalid public key: {e}")))?
+            .try_into()
+            .map_err(|_| invalid("invalid public key: expected 64 bytes".into()))?;
+        let socket = address
+            .parse()
+            .map_err(|e| invalid(format!("invalid address `{address}`: {e}")))?;
+        let discport = query
+            .split('&')
+            .find_map(|param| param.strip_prefix("discport="))
+            .map(|port| {
+                port.parse()
+                    .map_err(|e| invalid(format!("invalid discport `{port}`: {e}")))
+            })
+            .transpose()?;
+        Ok(Self {
+            public_key,
+            socket,
+            discport,
+        })
+    }
+}
+
+impl fmt::Display for Enode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "enode://{}@{}",
+            hex::encode(self.public_key),
+            self.socket
+        )?;
+        match self.discport {
+            Some(discport) if discport != self.socket.port() => write!(f, "?discport={discport}"),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Decoding of records in place, borrowing their values from the encoded bytes.
+//!
+//! Decoding an [`Enr`] copies every key and value into an owned map. Servers parsing many records
+//! only to inspect a few fields, e.g. to filter discovered nodes by their endpoints, can decode an
+//! [`EnrRef`] instead: it checks the structure of the record like [`Enr`] decoding does, but keeps
+//! a reference to the encoded bytes and looks up fields by scanning them, without allocating.
+
+use crate::{
+    codec, put_rlp_header, sanitize_value, Enr, EnrKey, IdentityScheme, KeyScheme, NodeId,
+    RawRecord, V4Scheme, MAX_ENR_SIZE, RLP_LIST_OFFSET,
+};
+use bytes::Bytes;
+use rlp::{Decodable, DecoderError, Rlp};
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    fmt,
+    marker::PhantomData,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// A record decoded in place, borrowing its values from the encoded bytes.
+///
+/// The structure of the record is checked when it is decoded, but the signature only by
+/// [`EnrRef::verify`].
+///
+/// ```rust
+/// # #[cfg(feature = "k256")] {
+/// use enr::{k256::ecdsa::SigningKey, Enr, EnrRef};
+/// use std::net::Ipv4Addr;
+///
+/// let key = SigningKey::random(&mut rand::thread_rng());
+/// let enr: Enr<SigningKey> = Enr::builder()
+///     .ip4(Ipv4Addr::LOCALHOST)
+///     .tcp4(30303)
+///     .build(&key)
+///     .unwrap();
+/// let encoded = enr.to_rlp_bytes();
+///
+/// let record = EnrRef::<SigningKey>::decode(&encoded).unwrap();
+/// assert_eq!(record.ip4(), Some(Ipv4Addr::LOCALHOST));
+/// assert_eq!(record.tcp4(), Some(30303));
+/// assert!(record.verify());
+/// assert_eq!(record.to_enr().unwrap(), enr);
+/// # }
+/// ```
+pub struct EnrRef<'a, K: EnrKey, S: IdentityScheme = V4Scheme> {
+    /// The RLP encoding of the record.
+    bytes: &'a [u8],
+    /// The signature of the record.
+    signature: &'a [u8],
+    /// The sequence number of the record.
+    seq: u64,
+    /// The RLP encoded sequence number and key/value pairs, the payload of the signed content.
+    content: &'a [u8],
+    /// The RLP encoded key/value pairs.
+    pairs: &'a [u8],
+    /// Pins the generic key and identity scheme types.
+    phantom: PhantomData<(K, fn() -> S)>,
+}
+
+impl<K: EnrKey, S: IdentityScheme> Clone for EnrRef<'_, K, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Copy for EnrRef<'_, K, S> {}
+
+impl<K: EnrKey, S: IdentityScheme> PartialEq for EnrRef<'_, K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Eq for EnrRef<'_, K, S> {}
+
+impl<K: EnrKey, S: IdentityScheme> fmt::Debug for EnrRef<'_, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EnrRef")
+            .field("seq", &self.seq)
+            .field("signature", &hex::encode(self.signature))
+            .field(
+                "pairs",
+                &self
+                    .iter()
+                    .map(|(key, value)| (String::from_utf8_lossy(key), hex::encode(value)))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a, K: EnrKey, S: IdentityScheme> EnrRef<'a, K, S> {
+    /// Decodes the RLP encoding of a record in place, checking its structure like [`Enr`]
+    /// decoding: the size, the sorted keys and the format of the values. The signature is not
+    /// verified.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, DecoderError> {
+        let rlp = Rlp::new(bytes);
+        let (signature, seq) = codec::decode_fields(&rlp, |key, item| sanitize_value(key, item))?;
+
+        // the structure is checked, so the list splits into the signature, seq and pairs
+        let payload = &bytes[rlp.payload_info()?.header_len..];
+        let (_, content) = split_item(payload)?;
+        let (_, pairs) = split_item(content)?;
+        Ok(Self {
+            bytes,
+            signature,
+            seq,
+            content,
+            pairs,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The RLP encoding of the record.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The signature of the record.
+    #[must_use]
+    pub const fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    /// Returns an iterator over the keys and raw RLP values of the record, in the order of their
+    /// keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a [u8], &'a [u8])> {
+        Pairs { rest: self.pairs }
+    }
+
+    /// The raw RLP value stored under `key`, if any.
+    #[must_use]
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&'a [u8]> {
+        let key = key.as_ref();
+        self.iter()
+            .find(|(field, _)| *field == key)
+            .map(|(_, value)| value)
+    }
+
+    /// The value stored under `key`, if any, with its RLP header stripped.
+    #[must_use]
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&'a [u8]> {
+        self.get_raw_rlp(key)
+            .map(|value| Rlp::new(value).data().expect("All data is sanitized"))
+    }
+
+    /// Decodes the value stored under `key`, if any.
+    pub fn get_decodable<T: Decodable>(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Option<Result<T, DecoderError>> {
+        self.get_raw_rlp(key).map(rlp::decode)
+    }
+
+    /// The identity scheme of the record, if valid UTF-8.
+    #[must_use]
+    pub fn id(&self) -> Option<&'a str> {
+        self.get("id").and_then(|id| std::str::from_utf8(id).ok())
+    }
+
+    /// The IPv4 address of the record, if defined.
+    #[must_use]
+    pub fn ip4(&self) -> Option<Ipv4Addr> {
+        let ip: [u8; 4] = self.get("ip")?.try_into().ok()?;
+        Some(ip.into())
+    }
+
+    /// The IPv6 address of the record, if defined.
+    #[must_use]
+    pub fn ip6(&self) -> Option<Ipv6Addr> {
+        let ip: [u8; 16] = self.get("ip6")?.try_into().ok()?;
+        Some(ip.into())
+    }
+
+    /// The TCP port of the record, if defined.
+    #[must_use]
+    pub fn tcp4(&self) -> Option<u16> {
+        self.get_decodable("tcp").and_then(Result::ok)
+    }
+
+    /// The IPv6-specific TCP port of the record, if defined.
+    #[must_use]
+    pub fn tcp6(&self) -> Option<u16> {
+        self.get_decodable("tcp6").and_then(Result::ok)
+    }
+
+    /// The UDP port of the record, if defined.
+    #[must_use]
+    pub fn udp4(&self) -> Option<u16> {
+        self.get_decodable("udp").and_then(Result::ok)
+    }
+
+    /// The IPv6-specific UDP port of the record, if defined.
+    #[must_use]
+    pub fn udp6(&self) -> Option<u16> {
+        self.get_decodable("udp6").and_then(Result::ok)
+    }
+
+    /// The QUIC port of the record, if defined.
+    #[must_use]
+    pub fn quic4(&self) -> Option<u16> {
+        self.get_decodable("quic").and_then(Result::ok)
+    }
+
+    /// The IPv6-specific QUIC port of the record, if defined.
+    #[must_use]
+    pub fn quic6(&self) -> Option<u16> {
+        self.get_decodable("quic6").and_then(Result::ok)
+    }
+
+    /// The public key of the record, recovered from its signature if the record omits it.
+    ///
+    /// Only the public key fields known to this crate, see [`KeyScheme`], are passed to
+    /// [`EnrKey::enr_to_public`]. They are the only values copied out of the record.
+    pub fn public_key(&self) -> Result<K::PublicKey, DecoderError> {
+        let key_fields: BTreeMap<Vec<u8>, Bytes> = self
+            .iter()
+            .filter(|(key, _)| KeyScheme::from_enr_key(key).is_some())
+            .map(|(key, value)| (key.to_vec(), Bytes::copy_from_slice(value)))
+            .collect();
+        K::enr_to_public(&key_fields).or_else(|err| {
+            if !key_fields.is_empty() {
+                return Err(err);
+            }
+            self.with_signed_content(|content| K::recover_v4(content, self.signature))
+                .ok_or(err)
+        })
+    }
+
+    /// The `NodeId` of the record, derived from its public key.
+    pub fn node_id(&self) -> Result<NodeId, DecoderError> {
+        self.public_key()
+            .map(|public_key| S::node_id::<K>(&public_key))
+    }
+
+    /// Verifies the signature of the record under the identity scheme `S`.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        if self.id() != Some(S::ID) {
+            return false;
+        }
+        self.public_key().is_ok_and(|public_key| {
+            self.with_signed_content(|content| S::verify::<K>(&public_key, content, self.signature))
+        })
+    }
+
+    /// Decodes the record into an owned [`RawRecord`] without verifying its signature.
+    pub fn to_raw_record(&self) -> Result<RawRecord, DecoderError> {
+        RawRecord::decode(self.bytes)
+    }
+
+    /// Decodes and verifies the record into an owned [`Enr`].
+    pub fn to_enr(&self) -> Result<Enr<K, S>, DecoderError> {
+        rlp::decode(self.bytes)
+    }
+
+    /// Calls `f` with the signed content of the record, the RLP list of the sequence number and
+    /// the key/value pairs, assembled on the stack.
+    fn with_signed_content<T>(&self, f: impl FnOnce(&[u8]) -> T) -> T {
+        let mut buffer = [0_u8; MAX_ENR_SIZE];
+        let mut unfilled = &mut buffer[..];
+        put_rlp_header(&mut unfilled, RLP_LIST_OFFSET, self.content.len());
+        let header_len = MAX_ENR_SIZE - unfilled.len();
+        let len = header_len + self.content.len();
+        // the content is shorter than the record by at least the signature's RLP header
+        buffer[header_len..len].copy_from_slice(self.content);
+        f(&buffer[..len])
+    }
+}
+
+/// Iterates over the keys and raw values of RLP encoded key/value pairs that have been checked.
+struct Pairs<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Pairs<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, rest) = split_item(self.rest).ok()?;
+        let (value, rest) = split_item(rest).ok()?;
+        self.rest = rest;
+        Some((Rlp::new(key).data().ok()?, value))
+    }
+}
+
+/// Splits the first RLP item off `bytes`, returning the raw item and the remaining bytes.
+fn split_item(bytes: &[u8]) -> Result<(&[u8], &[u8]), DecoderError> {
+    let info = Rlp::new(bytes).payload_info()?;
+    let len = info.header_len + info.value_len;
+    if len > bytes.len() {
+        return Err(DecoderError::RlpIsTooShort);
+    }
+    Ok(bytes.split_at(len))
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_enr_ref() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .ip6(Ipv6Addr::LOCALHOST)
+            .tcp4(30303)
+            .udp6(30304)
+            .add_value("custom", &"value")
+            .build(&key)
+            .unwrap();
+        let encoded = enr.to_rlp_bytes();
+
+        let record = EnrRef::<DefaultKey>::decode(&encoded).unwrap();
+        assert_eq!(record.as_bytes(), encoded.as_ref());
+        assert_eq!(record.seq(), enr.seq());
+        assert_eq!(record.signature(), enr.signature());
+        assert_eq!(record.id(), Some("v4"));
+        assert_eq!(record.ip4(), enr.ip4());
+        assert_eq!(record.ip6(), enr.ip6());
+        assert_eq!(record.tcp4(), Some(30303));
+        assert_eq!(record.udp6(), Some(30304));
+        assert_eq!(record.udp4(), None);
+        assert_eq!(record.get("custom"), enr.get("custom"));
+        assert_eq!(record.get("missing"), None);
+        assert!(record.iter().eq(enr.iter()));
+        assert_eq!(record.public_key().unwrap(), enr.public_key());
+        assert_eq!(record.node_id().unwrap(), enr.node_id());
+        assert!(record.verify());
+        assert_eq!(record.to_raw_record().unwrap().seq(), enr.seq());
+        assert_eq!(record.to_enr().unwrap(), enr);
+
+        // a tampered signature decodes but does not verify
+        let mut tampered = encoded.to_vec();
+        tampered[5] ^= 0x01;
+        let record = EnrRef::<DefaultKey>::decode(&tampered).unwrap();
+        assert!(!record.verify());
+        assert!(record.to_enr().is_err());
+    }
+
+    #[test]
+    fn test_enr_ref_recoverable_signature() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .signature_mode(crate::SignatureMode::RecoverableWithoutPublicKey)
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
+        let encoded = enr.to_rlp_bytes();
+        let record = EnrRef::<DefaultKey>::decode(&encoded).unwrap();
+        assert_eq!(record.node_id().unwrap(), enr.node_id());
+        assert!(record.verify());
+    }
+
+    #[test]
+    fn test_enr_ref_invalid() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder().build(&key).unwrap();
+        let encoded = enr.to_rlp_bytes();
+
+        // trailing data
+        let mut trailing = encoded.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            EnrRef::<DefaultKey>::decode(&trailing),
+            Err(DecoderError::RlpInconsistentLengthAndData)
+        );
+        assert_eq!(
+            EnrRef::<DefaultKey>::decode(&[0x80]),
+            Err(DecoderError::RlpExpectedToBeList)
+        );
+        assert!(EnrRef::<DefaultKey>::decode(&[0xc0]).is_err());
+
+        // every structure rejected by `Enr` decoding is rejected in place
+        for (seq, pairs) in [
+            (1_u8, vec![b"b".as_ref(), b"1", b"a", b"2"]),
+            (1, vec![b"a".as_ref(), b"1", b"a", b"2"]),
+            (1, vec![b"a".as_ref()]),
+            (1, vec![b"tcp".as_ref(), b"\x01\x02\x03"]),
+        ] {
+            let mut stream = rlp::RlpStream::new_list(2 + pairs.len());
+            stream.append(&vec![0_u8; 64]);
+            stream.append(&seq);
+            for item in pairs {
+                stream.append(&item);
+            }
+            let bytes = stream.out();
+            assert!(EnrRef::<DefaultKey>::decode(&bytes).is_err());
+            assert!(rlp::decode::<Enr<DefaultKey>>(&bytes).is_err());
+        }
+    }
+}
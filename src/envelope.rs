@@ -0,0 +1,237 @@
+//! Record version negotiation of the discv5 handshake.
+//!
+//! discv5 peers advertise the sequence number of their record in PING and PONG messages and
+//! request a newer record when they hold an older one. During the handshake, the WHOAREYOU
+//! challenge carries the sequence number of the record the challenger knows (0 if none), and the
+//! handshake answer attaches the responder's record only if that one is outdated.
+//!
+//! An [`EnrEnvelope`] is this `(enr_seq, optional record)` pair. It is encoded as the RLP list
+//! `[enr_seq]`, or `[enr_seq, record]` if it carries the record:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{
+//!     envelope::{should_request_enr, EnrEnvelope},
+//!     k256::ecdsa::SigningKey,
+//!     Enr,
+//! };
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//!
+//! // the challenger knows no record of the responder, so the record is attached
+//! let envelope = EnrEnvelope::for_known_seq(&enr, 0);
+//! let decoded = EnrEnvelope::<SigningKey>::decode(&envelope.encode()).unwrap();
+//! assert_eq!(decoded.record(), Some(&enr));
+//!
+//! // peers holding the current record receive only the sequence number
+//! assert_eq!(EnrEnvelope::for_known_seq(&enr, enr.seq()).record(), None);
+//! assert!(!should_request_enr(enr.seq(), decoded.enr_seq()));
+//! # }
+//! ```
+
+use crate::{Enr, EnrKey, IdentityScheme, V4Scheme};
+use bytes::BytesMut;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::fmt;
+
+/// Returns whether a peer advertising the record sequence number `advertised_seq` has a newer
+/// record than the one known locally with sequence number `local_known_seq`, 0 if none is known,
+/// so that its record should be requested.
+#[must_use]
+pub const fn should_request_enr(local_known_seq: u64, advertised_seq: u64) -> bool {
+    advertised_seq > local_known_seq
+}
+
+/// The sequence number of a node's record, optionally along with the record itself.
+pub struct EnrEnvelope<K: EnrKey, S: IdentityScheme = V4Scheme> {
+    enr_seq: u64,
+    record: Option<Enr<K, S>>,
+}
+
+impl<K: EnrKey, S: IdentityScheme> Clone for EnrEnvelope<K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            enr_seq: self.enr_seq,
+            record: self.record.clone(),
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> PartialEq for EnrEnvelope<K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.enr_seq == other.enr_seq && self.record == other.record
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Eq for EnrEnvelope<K, S> {}
+
+impl<K: EnrKey, S: IdentityScheme> fmt::Debug for EnrEnvelope<K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnrEnvelope")
+            .field("enr_seq", &self.enr_seq)
+            .field("record", &self.record)
+            .finish()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> EnrEnvelope<K, S> {
+    /// An envelope advertising the sequence number `enr_seq` without a record, as in PING and
+    /// PONG messages.
+    #[must_use]
+    pub const fn seq_only(enr_seq: u64) -> Self {
+        Self {
+            enr_seq,
+            record: None,
+        }
+    }
+
+    /// An envelope carrying `enr` along with its sequence number.
+    #[must_use]
+    pub fn with_record(enr: Enr<K, S>) -> Self {
+        Self {
+            enr_seq: enr.seq(),
+            record: Some(enr),
+        }
+    }
+
+    /// The envelope answering a peer that knows the version `known_seq` of `enr`, 0 if it knows
+    /// none: it carries the record only if the peer's version is outdated.
+    #[must_use]
+    pub fn for_known_seq(enr: &Enr<K, S>, known_seq: u64) -> Self {
+        if known_seq < enr.seq() {
+            Self::with_record(enr.clone())
+        } else {
+            Self::seq_only(enr.seq())
+        }
+    }
+
+    /// The advertised sequence number of the record.
+    #[must_use]
+    pub const fn enr_seq(&self) -> u64 {
+        self.enr_seq
+    }
+
+    /// The record, if the envelope carries it.
+    #[must_use]
+    pub const fn record(&self) -> Option<&Enr<K, S>> {
+        self.record.as_ref()
+    }
+
+    /// Takes the record out of the envelope, if it carries it.
+    #[must_use]
+    pub fn into_record(self) -> Option<Enr<K, S>> {
+        self.record
+    }
+
+    /// The RLP encoding of the envelope.
+    #[must_use]
+    pub fn encode(&self) -> BytesMut {
+        rlp::encode(self)
+    }
+
+    /// Decodes an RLP encoded envelope, verifying the record it carries.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecoderError> {
+        rlp::decode(bytes)
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Encodable for EnrEnvelope<K, S> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match &self.record {
+            Some(enr) => {
+                s.begin_list(2);
+                s.append(&self.enr_seq);
+                s.append(enr);
+            }
+            None => {
+                s.begin_list(1);
+                s.append(&self.enr_seq);
+            }
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Decodable for EnrEnvelope<K, S> {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let enr_seq = rlp.val_at(0)?;
+        let record = match rlp.item_count()? {
+            1 => None,
+            2 => {
+                let enr: Enr<K, S> = rlp.val_at(1)?;
+                if enr.seq() != enr_seq {
+                    return Err(DecoderError::Custom(
+                        "Record sequence number differs from the envelope",
+                    ));
+                }
+                Some(enr)
+            }
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+        Ok(Self { enr_seq, record })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_should_request_enr() {
+        assert!(should_request_enr(0, 1));
+        assert!(should_request_enr(4, 5));
+        assert!(!should_request_enr(5, 5));
+        assert!(!should_request_enr(6, 5));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder().seq(7).udp4(30303).build(&key).unwrap();
+
+        let seq_only = EnrEnvelope::<DefaultKey>::seq_only(7);
+        assert_eq!(seq_only.encode().as_ref(), [0xc1, 0x07]);
+        assert_eq!(EnrEnvelope::decode(&seq_only.encode()), Ok(seq_only));
+
+        let with_record = EnrEnvelope::with_record(enr.clone());
+        let decoded = EnrEnvelope::decode(&with_record.encode()).unwrap();
+        assert_eq!(decoded, with_record);
+        assert_eq!(decoded.into_record(), Some(enr.clone()));
+
+        assert_eq!(EnrEnvelope::for_known_seq(&enr, 0), with_record);
+        assert_eq!(EnrEnvelope::for_known_seq(&enr, 6), with_record);
+        assert_eq!(
+            EnrEnvelope::for_known_seq(&enr, 7),
+            EnrEnvelope::seq_only(7)
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder().seq(7).build(&key).unwrap();
+
+        let mut s = RlpStream::new_list(2);
+        s.append(&8_u64);
+        s.append(&enr);
+        assert!(EnrEnvelope::<DefaultKey>::decode(&s.out()).is_err());
+
+        let mut s = RlpStream::new_list(3);
+        s.append(&7_u64);
+        s.append(&enr);
+        s.append(&enr);
+        assert_eq!(
+            EnrEnvelope::<DefaultKey>::decode(&s.out()),
+            Err(DecoderError::RlpIncorrectListLen)
+        );
+        assert!(EnrEnvelope::<DefaultKey>::decode(&[0xc0]).is_err());
+
+        let mut tampered = EnrEnvelope::with_record(enr).encode();
+        // a byte of the signature of the record
+        tampered[8] ^= 0x01;
+        assert!(EnrEnvelope::<DefaultKey>::decode(&tampered).is_err());
+    }
+}
@@ -1,21 +1,53 @@
 //! The error type emitted for various ENR operations.
 
+use crate::{Key, KeyScheme, NodeId, Value};
+use rlp::DecoderError;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 /// An error type for handling various ENR operations.
 pub enum EnrError {
     /// The ENR is too large.
     ExceedsMaxSize,
     /// The sequence number is too large.
     SequenceNumberTooHigh,
+    /// The given explicit sequence number does not exceed the current sequence number.
+    SequenceNumberNotIncreasing(u64),
     /// There was an error with signing an ENR record.
     SigningError,
     /// The identity scheme is not supported.
     UnsupportedIdentityScheme,
     /// The entered RLP data is invalid.
     InvalidRlpData(String),
+    /// The given port key is set without an IP address of the same family.
+    OrphanPort(String),
+    /// The given IP key is set without any port of the same family.
+    OrphanIp(String),
+    /// Endpoints of the same IP address family have different IP addresses.
+    ConflictingEndpoints,
+    /// No public key could be recovered from the signature.
+    UnrecoverableSignature,
+    /// The public key stored in the record differs from the key recovered from its signature.
+    PublicKeyMismatch,
+    /// The record contains keys outside the allowlist.
+    DisallowedKeys(Vec<String>),
+    /// An update would change a field designated immutable by the record's genesis.
+    ImmutableFieldChanged,
+    /// No value was provided for the given placeholder of a record template.
+    UnresolvedPlaceholder(String),
+    /// The given key is mandatory for the record and cannot be removed.
+    MandatoryKey(String),
+    /// A multiaddr could not be mapped to record fields, for the given reason.
+    InvalidMultiaddr(String),
+    /// The given key was set twice with different values.
+    ConflictingValue(String),
+    /// The value of the given key alone exceeds the maximum record size.
+    ValueTooLarge(String),
+    /// The given string is not a valid IP address.
+    InvalidIpAddress(String),
 }
 
 impl fmt::Display for EnrError {
@@ -23,11 +55,210 @@ impl fmt::Display for EnrError {
         match self {
             Self::ExceedsMaxSize => write!(f, "enr exceeds max size"),
             Self::SequenceNumberTooHigh => write!(f, "sequence number too large"),
+            Self::SequenceNumberNotIncreasing(seq) => {
+                write!(f, "sequence number {seq} does not increase the current one")
+            }
             Self::SigningError => write!(f, "signing error"),
             Self::UnsupportedIdentityScheme => write!(f, "unsupported identity scheme"),
             Self::InvalidRlpData(_rlp) => write!(f, "invalid rlp data"),
+            Self::OrphanPort(key) => write!(f, "port `{key}` has no matching ip address"),
+            Self::OrphanIp(key) => write!(f, "ip address `{key}` has no matching port"),
+            Self::ConflictingEndpoints => write!(f, "endpoints have conflicting ip addresses"),
+            Self::UnrecoverableSignature => write!(f, "public key cannot be recovered"),
+            Self::PublicKeyMismatch => {
+                write!(f, "public key does not match the recovered public key")
+            }
+            Self::DisallowedKeys(keys) => {
+                write!(f, "keys not in the allowlist: {}", keys.join(", "))
+            }
+            Self::ImmutableFieldChanged => write!(f, "an immutable genesis field was changed"),
+            Self::UnresolvedPlaceholder(name) => write!(f, "no value for placeholder `{name}`"),
+            Self::MandatoryKey(key) => write!(f, "mandatory key `{key}` cannot be removed"),
+            Self::InvalidMultiaddr(reason) => write!(f, "invalid multiaddr: {reason}"),
+            Self::ConflictingValue(key) => write!(f, "key `{key}` is set to conflicting values"),
+            Self::ValueTooLarge(key) => write!(f, "value of `{key}` exceeds the max enr size"),
+            Self::InvalidIpAddress(addr) => write!(f, "invalid ip address `{addr}`"),
         }
     }
 }
 
 impl Error for EnrError {}
+
+/// Describes the identity fields of a record whose public key could not be decoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeContext {
+    /// The identity scheme given by the `id` field, if present.
+    scheme: Option<String>,
+    /// The known public key fields present in the record.
+    key_fields: Vec<String>,
+    /// The remaining fields of the record, which may hold keys of unknown types.
+    other_fields: Vec<String>,
+}
+
+impl DecodeContext {
+    /// Records the identity fields of the decoded content.
+    pub(crate) fn new(content: &BTreeMap<Key, Value>) -> Self {
+        let mut context = Self::default();
+        for (key, value) in content {
+            let name = String::from_utf8_lossy(key).into_owned();
+            if key.as_slice() == b"id" {
+                context.scheme = rlp::decode::<Vec<u8>>(value)
+                    .ok()
+                    .map(|scheme| String::from_utf8_lossy(&scheme).into_owned());
+            } else if KeyScheme::from_enr_key(key).is_some() {
+                context.key_fields.push(name);
+            } else {
+                context.other_fields.push(name);
+            }
+        }
+        context
+    }
+
+    /// The identity scheme given by the `id` field, if present.
+    #[must_use]
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    /// The known public key fields (`secp256k1`, `ed25519`) present in the record.
+    #[must_use]
+    pub fn key_fields(&self) -> &[String] {
+        &self.key_fields
+    }
+
+    /// The remaining fields of the record.
+    #[must_use]
+    pub fn other_fields(&self) -> &[String] {
+        &self.other_fields
+    }
+}
+
+impl fmt::Display for DecodeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "id: {}, key fields: [{}], other fields: [{}]",
+            self.scheme.as_deref().unwrap_or("none"),
+            self.key_fields.join(", "),
+            self.other_fields.join(", ")
+        )
+    }
+}
+
+/// Two records compared as versions of the same record belong to different nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrongNode {
+    /// The node id of the record compared against.
+    pub expected: NodeId,
+    /// The node id of the other record.
+    pub found: NodeId,
+}
+
+impl fmt::Display for WrongNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the record of node {} is not a record of node {}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl Error for WrongNode {}
+
+/// A node signed two records with the same sequence number but different content, see
+/// [`Supersedes::Conflict`](crate::Supersedes::Conflict).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConflictingRecord {
+    /// The node that signed the records.
+    pub node_id: NodeId,
+    /// The sequence number of both records.
+    pub seq: u64,
+}
+
+impl fmt::Display for ConflictingRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node {} signed conflicting records with sequence number {}",
+            self.node_id, self.seq
+        )
+    }
+}
+
+impl Error for ConflictingRecord {}
+
+/// An error parsing the text form of a record.
+///
+/// The [`fmt::Display`] form of the base64 and RLP errors is the error message returned before
+/// parsing returned a typed error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnrParseError {
+    /// The string is too short to hold a record.
+    InvalidString,
+    /// The string is not valid unpadded, URL-safe base64.
+    Base64(base64::DecodeError),
+    /// The record exceeds the maximum size of 300 bytes.
+    TooLarge,
+    /// The record is not validly RLP encoded, or its public key could not be decoded.
+    Rlp {
+        /// The underlying RLP decoding error.
+        error: DecoderError,
+        /// The identity fields of the record, present if the record was well-formed but its
+        /// public key or signature could not be verified.
+        context: Option<DecodeContext>,
+    },
+    /// The signature of the record does not verify.
+    InvalidSignature,
+    /// The record is of an unsupported identity scheme, with the given `id`, if any.
+    UnsupportedScheme(Option<String>),
+}
+
+impl fmt::Display for EnrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidString => write!(f, "Invalid ENR string"),
+            Self::Base64(e) => write!(f, "Invalid base64 encoding: {e:?}"),
+            Self::TooLarge => write!(f, "Invalid ENR: the record exceeds the maximum size"),
+            Self::Rlp { error, context } => {
+                write!(f, "Invalid ENR: {error:?}")?;
+                if let Some(context) = context {
+                    write!(f, " ({context})")?;
+                }
+                Ok(())
+            }
+            Self::InvalidSignature => write!(f, "Invalid ENR: invalid signature"),
+            Self::UnsupportedScheme(Some(id)) => {
+                write!(f, "Invalid ENR: unsupported identity scheme `{id}`")
+            }
+            Self::UnsupportedScheme(None) => write!(f, "Invalid ENR: missing identity scheme"),
+        }
+    }
+}
+
+impl Error for EnrParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Base64(e) => Some(e),
+            Self::Rlp { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecoderError> for EnrParseError {
+    fn from(error: DecoderError) -> Self {
+        Self::Rlp {
+            error,
+            context: None,
+        }
+    }
+}
+
+/// The error message of parsing before it returned an [`EnrParseError`], to ease migrating code
+/// that handles errors as strings.
+impl From<EnrParseError> for String {
+    fn from(error: EnrParseError) -> Self {
+        error.to_string()
+    }
+}
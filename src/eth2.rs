@@ -0,0 +1,193 @@
+//! SSZ encoding of records for the Ethereum consensus layer.
+//!
+//! [`Enr`] implements [`ssz::Encode`] and [`ssz::Decode`] as an opaque byte list holding its RLP
+//! encoding, the form of an `ENR` field of type `ByteList[300]` inside a larger SSZ type.
+//! [`EnrContainer`] is a record standing alone as an SSZ container with that single field:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{eth2::EnrContainer, k256::ecdsa::SigningKey, Enr};
+//! use ssz::{Decode, Encode};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(9000).build(&key).unwrap();
+//!
+//! assert_eq!(enr.as_ssz_bytes(), rlp::encode(&enr).to_vec());
+//! let bytes = EnrContainer(enr.clone()).as_ssz_bytes();
+//! assert_eq!(EnrContainer::from_ssz_bytes(&bytes).unwrap().0, enr);
+//! # }
+//! ```
+//!
+//! Decoding verifies the record like [`rlp::Decodable`].
+
+use crate::{Enr, EnrKey, IdentityScheme};
+use ssz::{Decode, DecodeError, Encode, SszDecoderBuilder, SszEncoder, BYTES_PER_LENGTH_OFFSET};
+use std::fmt;
+
+impl<K: EnrKey, S: IdentityScheme> Encode for Enr<K, S> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        self.encode_to_buf(buf);
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Decode for Enr<K, S> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        rlp::decode(bytes).map_err(|e| DecodeError::BytesInvalid(format!("invalid ENR: {e}")))
+    }
+}
+
+/// A record as the SSZ container `EnrContainer(Container)` with the single field
+/// `enr: ByteList[300]`.
+pub struct EnrContainer<K: EnrKey, S: IdentityScheme = crate::V4Scheme>(pub Enr<K, S>);
+
+impl<K: EnrKey, S: IdentityScheme> Clone for EnrContainer<K, S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> PartialEq for EnrContainer<K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Eq for EnrContainer<K, S> {}
+
+impl<K: EnrKey, S: IdentityScheme> fmt::Debug for EnrContainer<K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EnrContainer").field(&self.0).finish()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Encode for EnrContainer<K, S> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        let mut encoder = SszEncoder::container(buf, BYTES_PER_LENGTH_OFFSET);
+        encoder.append(&self.0);
+        encoder.finalize();
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        BYTES_PER_LENGTH_OFFSET + self.0.ssz_bytes_len()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Decode for EnrContainer<K, S> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut builder = SszDecoderBuilder::new(bytes);
+        builder.register_type::<Enr<K, S>>()?;
+        let mut decoder = builder.build()?;
+        decoder.decode_next().map(Self)
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> From<Enr<K, S>> for EnrContainer<K, S> {
+    fn from(enr: Enr<K, S>) -> Self {
+        Self(enr)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_ssz_in_container() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(9000).build(&key).unwrap();
+        let rlp = rlp::encode(&enr).to_vec();
+
+        let container = EnrContainer::from(enr.clone());
+        let bytes = container.as_ssz_bytes();
+        // the offset of the variable-size field, followed by the field
+        assert_eq!(bytes[..4], 4_u32.to_le_bytes());
+        assert_eq!(bytes[4..], rlp[..]);
+        assert_eq!(container.ssz_bytes_len(), bytes.len());
+        assert_eq!(EnrContainer::from_ssz_bytes(&bytes), Ok(container));
+
+        // records as variable-size items of a list
+        let records = vec![enr.clone(), DefaultEnr::empty(&key).unwrap()];
+        let bytes = records.as_ssz_bytes();
+        assert_eq!(Vec::<DefaultEnr>::from_ssz_bytes(&bytes), Ok(records));
+    }
+
+    #[test]
+    fn test_consensus_defaults() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let fork_id = crate::EnrForkId {
+            fork_digest: [0x6a, 0x95, 0xa1, 0xa9],
+            next_fork_version: [0x04, 0x00, 0x00, 0x00],
+            next_fork_epoch: u64::MAX,
+        };
+        let mut attnets = crate::AttestationSubnets::new();
+        attnets.set(63, true);
+        let mut syncnets = crate::SyncCommitteeSubnets::new();
+        syncnets.set(2, true);
+
+        let bare = DefaultEnr::builder().build(&key).unwrap();
+        let enr = DefaultEnr::builder()
+            .consensus_defaults(fork_id, attnets, syncnets)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.eth2_fork_id(), Some(Ok(fork_id)));
+        assert_eq!(enr.attestation_subnets(), Some(Ok(attnets)));
+        assert_eq!(enr.sync_committee_subnets(), Some(Ok(syncnets)));
+        assert_eq!(enr.get_raw_rlp("attnets").unwrap().len(), 9);
+        assert_eq!(enr.get_raw_rlp("syncnets").unwrap().len(), 1);
+        assert_eq!(enr.size() - bare.size(), 49);
+
+        // with endpoints of both address families
+        let enr = DefaultEnr::builder()
+            .consensus_defaults(fork_id, attnets, syncnets)
+            .ip4(std::net::Ipv4Addr::LOCALHOST)
+            .tcp4(9000)
+            .udp4(9000)
+            .quic4(9001)
+            .ip6(std::net::Ipv6Addr::LOCALHOST)
+            .tcp6(9000)
+            .udp6(9000)
+            .quic6(9001)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.size(), 244);
+    }
+
+    #[test]
+    fn test_ssz_invalid() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut bytes = EnrContainer(DefaultEnr::empty(&key).unwrap()).as_ssz_bytes();
+
+        assert!(EnrContainer::<k256::ecdsa::SigningKey>::from_ssz_bytes(&bytes[..2]).is_err());
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(matches!(
+            EnrContainer::<k256::ecdsa::SigningKey>::from_ssz_bytes(&bytes),
+            Err(DecodeError::BytesInvalid(_))
+        ));
+        bytes[0] = 8;
+        assert!(EnrContainer::<k256::ecdsa::SigningKey>::from_ssz_bytes(&bytes).is_err());
+    }
+}
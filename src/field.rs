@@ -0,0 +1,122 @@
+//! Typed views of the fields of an ENR.
+
+use crate::RecordFlags;
+use std::{
+    convert::TryFrom,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// The decoded value of a record field, as yielded by [`Enr::iter_decoded`].
+///
+/// Fields with a meaning known to this crate are decoded into their typed representation. Known
+/// fields whose value can't be decoded are yielded as [`FieldValue::Invalid`], all other fields
+/// as [`FieldValue::Unknown`].
+///
+/// [`Enr::iter_decoded`]: crate::Enr::iter_decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldValue<'a> {
+    /// The identity scheme, stored under `id`.
+    Id(String),
+    /// The public key of the record, stored under the key named by `scheme`.
+    PublicKey {
+        /// The ENR key of the public key, e.g. `secp256k1`.
+        scheme: &'static str,
+        /// The encoded public key.
+        key: &'a [u8],
+    },
+    /// The IPv4 address, stored under `ip`.
+    Ip4(Ipv4Addr),
+    /// The IPv6 address, stored under `ip6`.
+    Ip6(Ipv6Addr),
+    /// The IPv4 TCP port, stored under `tcp`.
+    Tcp(u16),
+    /// The IPv6 TCP port, stored under `tcp6`.
+    Tcp6(u16),
+    /// The IPv4 UDP port, stored under `udp`.
+    Udp(u16),
+    /// The IPv6 UDP port, stored under `udp6`.
+    Udp6(u16),
+    /// The IPv4 QUIC port, stored under `quic`.
+    Quic(u16),
+    /// The IPv6 QUIC port, stored under `quic6`.
+    Quic6(u16),
+    /// The capabilities of the node, stored under `flags`.
+    Flags(RecordFlags),
+    /// The SSZ encoded fork id of the Ethereum consensus layer, stored under `eth2`.
+    Eth2(&'a [u8]),
+    /// A known field whose value can't be decoded, with its raw RLP value.
+    Invalid(&'a [u8], &'a [u8]),
+    /// A field unknown to this crate, with its raw RLP value.
+    Unknown(&'a [u8], &'a [u8]),
+}
+
+impl<'a> FieldValue<'a> {
+    /// Decodes the raw RLP value of the field stored under `key`.
+    pub(crate) fn decode(key: &'a [u8], value: &'a [u8]) -> Self {
+        let rlp = rlp::Rlp::new(value);
+        let port = |variant: fn(u16) -> Self| rlp.as_val::<u16>().ok().map(variant);
+        let decoded = match key {
+            b"id" => rlp
+                .data()
+                .ok()
+                .map(|id| Self::Id(String::from_utf8_lossy(id).into_owned())),
+            b"secp256k1" => rlp.data().ok().map(|key| Self::PublicKey {
+                scheme: "secp256k1",
+                key,
+            }),
+            b"ed25519" => rlp.data().ok().map(|key| Self::PublicKey {
+                scheme: "ed25519",
+                key,
+            }),
+            b"ip" => rlp
+                .data()
+                .ok()
+                .and_then(|ip| <[u8; 4]>::try_from(ip).ok())
+                .map(|ip| Self::Ip4(ip.into())),
+            b"ip6" => rlp
+                .data()
+                .ok()
+                .and_then(|ip| <[u8; 16]>::try_from(ip).ok())
+                .map(|ip| Self::Ip6(ip.into())),
+            b"tcp" => port(Self::Tcp),
+            b"tcp6" => port(Self::Tcp6),
+            b"udp" => port(Self::Udp),
+            b"udp6" => port(Self::Udp6),
+            b"quic" => port(Self::Quic),
+            b"quic6" => port(Self::Quic6),
+            b"flags" => rlp
+                .as_val()
+                .ok()
+                .map(RecordFlags::from_bits_retain)
+                .map(Self::Flags),
+            b"eth2" => rlp.data().ok().map(Self::Eth2),
+            _ => return Self::Unknown(key, value),
+        };
+        decoded.unwrap_or(Self::Invalid(key, value))
+    }
+}
+
+impl fmt::Display for FieldValue<'_> {
+    /// Renders the value in a human readable form. Binary values are rendered as hex, with
+    /// invalid and unknown fields rendered as the hex encoding of their raw RLP value.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{id}"),
+            Self::PublicKey { key, .. } => write!(f, "0x{}", hex::encode(key)),
+            Self::Ip4(ip) => write!(f, "{ip}"),
+            Self::Ip6(ip) => write!(f, "{ip}"),
+            Self::Tcp(port)
+            | Self::Tcp6(port)
+            | Self::Udp(port)
+            | Self::Udp6(port)
+            | Self::Quic(port)
+            | Self::Quic6(port) => write!(f, "{port}"),
+            Self::Flags(flags) => write!(f, "{flags:?}"),
+            Self::Eth2(fork_id) => write!(f, "0x{}", hex::encode(fork_id)),
+            Self::Invalid(_, value) | Self::Unknown(_, value) => {
+                write!(f, "0x{}", hex::encode(value))
+            }
+        }
+    }
+}
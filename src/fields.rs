@@ -0,0 +1,213 @@
+//! Typed access to record fields.
+//!
+//! A [`TypedEnrField`] maps the key of a field to the Rust type of its value, so the field can be
+//! read with [`Enr::get_field`] instead of [`Enr::get_decodable`] plus manual validation. This
+//! module implements the trait for the keys reserved by the specification and for common Ethereum
+//! consensus layer fields. Custom fields can be declared with [`typed_enr_field!`].
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{fields, k256::ecdsa::SigningKey, typed_enr_field, Enr};
+//!
+//! typed_enr_field! {
+//!     /// The height of the node's chain.
+//!     pub struct Height: "height" => u64;
+//! }
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr = Enr::builder()
+//!     .udp4(30303)
+//!     .add_value("height", &42_u64)
+//!     .build(&key)
+//!     .unwrap();
+//!
+//! assert_eq!(enr.get_field::<fields::Udp>(), Some(Ok(30303)));
+//! assert_eq!(enr.get_field::<Height>(), Some(Ok(42)));
+//! assert_eq!(enr.get_field::<fields::Tcp>(), None);
+//! # }
+//! ```
+//!
+//! [`Enr::get_field`]: crate::Enr::get_field
+//! [`Enr::get_decodable`]: crate::Enr::get_decodable
+//! [`typed_enr_field!`]: crate::typed_enr_field
+
+use rlp::{DecoderError, Rlp};
+use std::{
+    convert::TryFrom,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// A record field with a known key and value type.
+pub trait TypedEnrField {
+    /// The key the field is stored under.
+    const KEY: &'static [u8];
+
+    /// The decoded value of the field.
+    type Value;
+
+    /// Decodes the raw RLP value of the field.
+    fn decode(rlp: &[u8]) -> Result<Self::Value, DecoderError>;
+}
+
+/// Declares unit structs implementing [`TypedEnrField`] for fields whose value implements
+/// [`rlp::Decodable`].
+///
+/// ```rust
+/// enr::typed_enr_field! {
+///     /// A counter stored under `count`.
+///     pub struct Count: "count" => u32;
+///     /// An opaque payload stored under `payload`.
+///     pub struct Payload: "payload" => Vec<u8>;
+/// }
+/// ```
+#[macro_export]
+macro_rules! typed_enr_field {
+    ($($(#[$attr:meta])* $vis:vis struct $name:ident: $key:literal => $value:ty;)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            $vis struct $name;
+
+            impl $crate::TypedEnrField for $name {
+                const KEY: &'static [u8] = $key.as_bytes();
+
+                type Value = $value;
+
+                fn decode(
+                    rlp: &[u8],
+                ) -> Result<Self::Value, $crate::fields::__private::DecoderError> {
+                    $crate::fields::__private::decode(rlp)
+                }
+            }
+        )*
+    };
+}
+
+/// Items used by [`typed_enr_field!`](crate::typed_enr_field) expansions.
+#[doc(hidden)]
+pub mod __private {
+    pub use rlp::{decode, DecoderError};
+}
+
+/// Decodes RLP data of exactly `N` bytes.
+fn decode_array<const N: usize>(rlp: &[u8]) -> Result<[u8; N], DecoderError> {
+    <[u8; N]>::try_from(Rlp::new(rlp).data()?).map_err(|_| DecoderError::RlpInvalidLength)
+}
+
+/// The identity scheme, stored under `id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Id;
+
+impl TypedEnrField for Id {
+    const KEY: &'static [u8] = b"id";
+
+    type Value = String;
+
+    fn decode(rlp: &[u8]) -> Result<String, DecoderError> {
+        String::from_utf8(Rlp::new(rlp).data()?.to_vec())
+            .map_err(|_| DecoderError::Custom("Invalid identity scheme"))
+    }
+}
+
+/// The compressed secp256k1 public key, stored under `secp256k1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl TypedEnrField for Secp256k1 {
+    const KEY: &'static [u8] = b"secp256k1";
+
+    type Value = [u8; 33];
+
+    fn decode(rlp: &[u8]) -> Result<[u8; 33], DecoderError> {
+        decode_array(rlp)
+    }
+}
+
+/// The ed25519 public key, stored under `ed25519`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ed25519;
+
+impl TypedEnrField for Ed25519 {
+    const KEY: &'static [u8] = b"ed25519";
+
+    type Value = [u8; 32];
+
+    fn decode(rlp: &[u8]) -> Result<[u8; 32], DecoderError> {
+        decode_array(rlp)
+    }
+}
+
+/// The IPv4 address, stored under `ip`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ip;
+
+impl TypedEnrField for Ip {
+    const KEY: &'static [u8] = b"ip";
+
+    type Value = Ipv4Addr;
+
+    fn decode(rlp: &[u8]) -> Result<Ipv4Addr, DecoderError> {
+        decode_array::<4>(rlp).map(Ipv4Addr::from)
+    }
+}
+
+/// The IPv6 address, stored under `ip6`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ip6;
+
+impl TypedEnrField for Ip6 {
+    const KEY: &'static [u8] = b"ip6";
+
+    type Value = Ipv6Addr;
+
+    fn decode(rlp: &[u8]) -> Result<Ipv6Addr, DecoderError> {
+        decode_array::<16>(rlp).map(Ipv6Addr::from)
+    }
+}
+
+typed_enr_field! {
+    /// The IPv4 TCP port, stored under `tcp`.
+    pub struct Tcp: "tcp" => u16;
+    /// The IPv6 TCP port, stored under `tcp6`.
+    pub struct Tcp6: "tcp6" => u16;
+    /// The IPv4 UDP port, stored under `udp`.
+    pub struct Udp: "udp" => u16;
+    /// The IPv6 UDP port, stored under `udp6`.
+    pub struct Udp6: "udp6" => u16;
+    /// The IPv4 QUIC port, stored under `quic`.
+    pub struct Quic: "quic" => u16;
+    /// The IPv6 QUIC port, stored under `quic6`.
+    pub struct Quic6: "quic6" => u16;
+    /// The SSZ encoded fork id of the Ethereum consensus layer, stored under `eth2`.
+    pub struct Eth2: "eth2" => Vec<u8>;
+}
+
+/// The attestation subnet bitfield of the Ethereum consensus layer, an SSZ `Bitvector[64]`
+/// stored under `attnets`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attnets;
+
+impl TypedEnrField for Attnets {
+    const KEY: &'static [u8] = b"attnets";
+
+    type Value = [u8; 8];
+
+    fn decode(rlp: &[u8]) -> Result<[u8; 8], DecoderError> {
+        decode_array(rlp)
+    }
+}
+
+/// The sync committee subnet bitfield of the Ethereum consensus layer, an SSZ `Bitvector[4]`
+/// stored under `syncnets`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Syncnets;
+
+impl TypedEnrField for Syncnets {
+    const KEY: &'static [u8] = b"syncnets";
+
+    type Value = u8;
+
+    fn decode(rlp: &[u8]) -> Result<u8, DecoderError> {
+        decode_array::<1>(rlp).map(|[bits]| bits)
+    }
+}
@@ -0,0 +1,58 @@
+//! Boolean capabilities of a node, stored as a single integer.
+//!
+//! [`RecordFlags`] packs capabilities into a `u64` stored under the `flags` key, costing a few
+//! bytes for any number of capabilities. The low 32 bits are defined by this crate, the high 32
+//! bits are left to networks through [`RecordFlags::user`]. Bits unknown to the reader are
+//! preserved, so records with newer capabilities round-trip unchanged.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, RecordFlags};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder()
+//!     .flags(RecordFlags::ARCHIVE_NODE)
+//!     .build(&key)
+//!     .unwrap();
+//!
+//! enr.update_flags(RecordFlags::RELAY_CAPABLE | RecordFlags::user(0), RecordFlags::ARCHIVE_NODE, &key)
+//!     .unwrap();
+//! assert_eq!(enr.flags(), Some(RecordFlags::RELAY_CAPABLE | RecordFlags::user(0)));
+//! # }
+//! ```
+
+/// The ENR key of the flags field.
+pub const FLAGS_KEY: &str = "flags";
+
+bitflags::bitflags! {
+    /// Boolean capabilities of a node, stored under the `flags` key.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct RecordFlags: u64 {
+        /// The node serves the full history of its network.
+        const ARCHIVE_NODE = 1;
+        /// The node relays connections to nodes that are not directly reachable.
+        const RELAY_CAPABLE = 1 << 1;
+
+        // bits set by other implementations are kept
+        const _ = !0;
+    }
+}
+
+impl RecordFlags {
+    /// The first bit left to networks.
+    const USER_OFFSET: u32 = 32;
+
+    /// The user defined flag `bit`, for `bit` in `0..32`.
+    ///
+    /// # Panics
+    /// Panics if `bit` is not below 32.
+    #[must_use]
+    pub const fn user(bit: u32) -> Self {
+        assert!(
+            bit < 64 - Self::USER_OFFSET,
+            "user flags are numbered 0 to 31"
+        );
+        Self::from_bits_retain(1 << (Self::USER_OFFSET + bit))
+    }
+}
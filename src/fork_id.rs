@@ -0,0 +1,180 @@
+//! The fork id of the Ethereum consensus layer.
+//!
+//! Consensus clients store an SSZ encoded `ENRForkID` under the `eth2` key, identifying the fork
+//! they are on and the next fork they are scheduled to follow. [`EnrForkId`] is the typed form of
+//! that field:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, EnrForkId};
+//!
+//! let fork_id = EnrForkId {
+//!     fork_digest: [0x6a, 0x95, 0xa1, 0xa9],
+//!     next_fork_version: [0x04, 0x00, 0x00, 0x00],
+//!     next_fork_epoch: 269_568,
+//! };
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().eth2_fork_id(fork_id).build(&key).unwrap();
+//! assert_eq!(enr.eth2_fork_id(), Some(Ok(fork_id)));
+//! # }
+//! ```
+//!
+//! [`Enr::is_compatible_fork`] compares the fork ids of two records, to filter discovered peers
+//! by the [`ForkCompatibility`] they have with the local node.
+//!
+//! [`Enr::is_compatible_fork`]: crate::Enr::is_compatible_fork
+
+use rlp::{DecoderError, Encodable, Rlp, RlpStream};
+use std::{convert::TryFrom, fmt};
+
+/// The ENR key of the fork id.
+pub const ETH2_KEY: &str = "eth2";
+
+/// The `ENRForkID` of the Ethereum consensus layer, stored SSZ encoded under `eth2`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EnrForkId {
+    /// The digest of the current fork version and the genesis validators root.
+    pub fork_digest: [u8; 4],
+    /// The fork version of the next scheduled fork, or of the current fork if none is scheduled.
+    pub next_fork_version: [u8; 4],
+    /// The epoch of the next scheduled fork, or `u64::MAX` if none is scheduled.
+    pub next_fork_epoch: u64,
+}
+
+impl EnrForkId {
+    /// The length of the SSZ encoding in bytes.
+    pub const SSZ_LEN: usize = 16;
+
+    /// The SSZ encoding of the fork id.
+    #[must_use]
+    pub fn to_ssz_bytes(&self) -> [u8; Self::SSZ_LEN] {
+        let mut bytes = [0; Self::SSZ_LEN];
+        bytes[..4].copy_from_slice(&self.fork_digest);
+        bytes[4..8].copy_from_slice(&self.next_fork_version);
+        bytes[8..].copy_from_slice(&self.next_fork_epoch.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes the SSZ encoding of the fork id.
+    pub fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecoderError> {
+        let bytes =
+            <[u8; Self::SSZ_LEN]>::try_from(bytes).map_err(|_| DecoderError::RlpInvalidLength)?;
+        let [d0, d1, d2, d3, v0, v1, v2, v3, epoch @ ..] = bytes;
+        Ok(Self {
+            fork_digest: [d0, d1, d2, d3],
+            next_fork_version: [v0, v1, v2, v3],
+            next_fork_epoch: u64::from_le_bytes(epoch),
+        })
+    }
+
+    /// The compatibility of a peer on the fork `remote` with a node on this fork.
+    #[must_use]
+    pub fn compatibility(&self, remote: &Self) -> ForkCompatibility {
+        if self.fork_digest != remote.fork_digest {
+            ForkCompatibility::ForkDigestMismatch {
+                local: self.fork_digest,
+                remote: remote.fork_digest,
+            }
+        } else if self.next_fork_version != remote.next_fork_version
+            || self.next_fork_epoch != remote.next_fork_epoch
+        {
+            ForkCompatibility::NextForkMismatch {
+                local: *self,
+                remote: *remote,
+            }
+        } else {
+            ForkCompatibility::Compatible
+        }
+    }
+}
+
+/// The compatibility of a peer's fork with the local node's, as returned by
+/// [`Enr::is_compatible_fork`](crate::Enr::is_compatible_fork).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ForkCompatibility {
+    /// Both nodes are on the same fork and schedule the same next fork.
+    Compatible,
+    /// Both nodes are on the same fork but schedule different next forks, so they are compatible
+    /// only until the earlier of the two.
+    NextForkMismatch {
+        /// The fork id of the local node.
+        local: EnrForkId,
+        /// The fork id of the peer.
+        remote: EnrForkId,
+    },
+    /// The nodes are on different forks.
+    ForkDigestMismatch {
+        /// The fork digest of the local node.
+        local: [u8; 4],
+        /// The fork digest of the peer.
+        remote: [u8; 4],
+    },
+    /// At least one of the records has no well-formed `eth2` field.
+    MissingForkId,
+}
+
+impl ForkCompatibility {
+    /// Whether the nodes are on the same fork, even if they schedule different next forks.
+    #[must_use]
+    pub const fn is_compatible(&self) -> bool {
+        matches!(self, Self::Compatible | Self::NextForkMismatch { .. })
+    }
+}
+
+impl fmt::Display for ForkCompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Compatible => write!(f, "compatible"),
+            Self::NextForkMismatch { local, remote } => write!(
+                f,
+                "next fork mismatch: local 0x{} at epoch {}, remote 0x{} at epoch {}",
+                hex::encode(local.next_fork_version),
+                local.next_fork_epoch,
+                hex::encode(remote.next_fork_version),
+                remote.next_fork_epoch
+            ),
+            Self::ForkDigestMismatch { local, remote } => write!(
+                f,
+                "fork digest mismatch: local 0x{}, remote 0x{}",
+                hex::encode(local),
+                hex::encode(remote)
+            ),
+            Self::MissingForkId => write!(f, "missing fork id"),
+        }
+    }
+}
+
+impl Encodable for EnrForkId {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append(&self.to_ssz_bytes().as_ref());
+    }
+}
+
+impl rlp::Decodable for EnrForkId {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Self::from_ssz_bytes(rlp.data()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssz_encoding() {
+        let fork_id = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            next_fork_version: [5, 6, 7, 8],
+            next_fork_epoch: 0x0102,
+        };
+        let bytes = fork_id.to_ssz_bytes();
+        assert_eq!(bytes, [1, 2, 3, 4, 5, 6, 7, 8, 2, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(EnrForkId::from_ssz_bytes(&bytes), Ok(fork_id));
+        assert_eq!(rlp::decode(&rlp::encode(&fork_id)), Ok(fork_id));
+        assert_eq!(
+            EnrForkId::from_ssz_bytes(&bytes[..15]),
+            Err(DecoderError::RlpInvalidLength)
+        );
+    }
+}
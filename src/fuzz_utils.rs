@@ -0,0 +1,158 @@
+//! Helpers for fuzzing the record decoder.
+//!
+//! The `fuzz` directory of the repository holds `cargo-fuzz` targets built on these helpers.
+//! Services decoding records received from the network can reuse them in their own fuzz targets,
+//! e.g. with their own dictionaries or combined with their message decoding:
+//!
+//! ```rust,no_run
+//! # fn fuzz_target(data: &[u8]) {
+//! enr::fuzz_utils::roundtrip(data);
+//! # }
+//! ```
+//!
+//! [`seed_corpus`] provides valid records to start fuzzing from.
+
+use crate::{CombinedKey, Enr, EnrKey, MAX_ENR_SIZE};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// The RLP encoding of the example record of EIP-778.
+const SPEC_EXAMPLE: &str = "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f";
+
+/// The secret key of the example record of EIP-778.
+const SPEC_SECRET: &str = "b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291";
+
+/// Decodes `bytes` as an RLP encoded record and checks the invariants of every record that
+/// decodes.
+///
+/// Input that fails to decode is ignored. A decoded record must verify, fit into
+/// [`MAX_ENR_SIZE`], re-encode to exactly `bytes` and decode again to the same record, also
+/// through its text form.
+///
+/// # Panics
+///
+/// Panics if a decoded record violates one of the invariants.
+pub fn roundtrip(bytes: &[u8]) {
+    let enr = match rlp::decode::<Enr<CombinedKey>>(bytes) {
+        Ok(enr) => enr,
+        Err(_) => return,
+    };
+    assert!(enr.verify(), "a decoded record does not verify");
+    assert!(bytes.len() <= MAX_ENR_SIZE, "a decoded record is too large");
+    assert_eq!(
+        enr.size(),
+        bytes.len(),
+        "the size of a decoded record differs"
+    );
+
+    let encoded = rlp::encode(&enr);
+    assert_eq!(encoded, bytes, "re-encoding a decoded record changed it");
+    let decoded: Enr<CombinedKey> =
+        rlp::decode(&encoded).expect("a re-encoded record does not decode");
+    assert_same(&decoded, &enr);
+
+    let parsed: Enr<CombinedKey> = enr
+        .to_base64()
+        .parse()
+        .expect("the text form of a decoded record does not parse");
+    assert_same(&parsed, &enr);
+}
+
+/// Asserts that two records are identical, including their content and signature.
+fn assert_same(enr: &Enr<CombinedKey>, expected: &Enr<CombinedKey>) {
+    assert_eq!(enr, expected, "a record decoded differently");
+    assert_eq!(enr.node_id(), expected.node_id(), "the node id changed");
+    assert!(
+        enr.iter().eq(expected.iter()),
+        "the content of a record changed"
+    );
+    assert_eq!(
+        enr.public_key(),
+        expected.public_key(),
+        "the public key changed"
+    );
+}
+
+/// The RLP encodings of valid records covering both key types, all reserved fields and records
+/// of the minimal size and near the maximum size, for seeding a fuzzing corpus.
+///
+/// # Panics
+///
+/// Never panics, all records are built from fixed keys and fields.
+#[must_use]
+pub fn seed_corpus() -> Vec<Vec<u8>> {
+    let secp256k1 = CombinedKey::secp256k1_from_bytes(&mut hex::decode(SPEC_SECRET).unwrap())
+        .expect("valid secret");
+    let ed25519 = CombinedKey::ed25519_from_bytes(&mut [7; 32]).expect("valid secret");
+
+    let mut corpus = vec![hex::decode(SPEC_EXAMPLE).expect("valid hex")];
+    for key in [&secp256k1, &ed25519] {
+        let minimal: Enr<CombinedKey> = Enr::empty(key).expect("valid record");
+        let all_fields: Enr<CombinedKey> = Enr::builder()
+            .ip4(Ipv4Addr::new(203, 0, 113, 7))
+            .tcp4(30303)
+            .udp4(30304)
+            .quic4(30305)
+            .ip6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))
+            .tcp6(u16::MAX)
+            .udp6(1)
+            .quic6(9000)
+            .add_value("eth2", &[0xab; 16].as_ref())
+            .build(key)
+            .expect("valid record");
+        let max_size = padded(key);
+        corpus.extend(
+            [minimal, all_fields, max_size]
+                .iter()
+                .map(rlp::encode)
+                .map(Vec::from),
+        );
+    }
+    corpus
+}
+
+/// The largest record padded by a custom field that can be built.
+fn padded<K: EnrKey>(key: &K) -> Enr<K> {
+    let build = |padding: usize| {
+        Enr::builder()
+            .add_value("padding", &vec![0_u8; padding])
+            .build(key)
+    };
+    let mut enr = build(0).expect("valid record");
+    for padding in 1.. {
+        match build(padding) {
+            Ok(larger) => enr = larger,
+            Err(_) => break,
+        }
+    }
+    enr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_corpus_roundtrips() {
+        let corpus = seed_corpus();
+        assert_eq!(corpus.len(), 7);
+        for bytes in &corpus {
+            let enr: Enr<CombinedKey> = rlp::decode(bytes).unwrap();
+            assert!(enr.verify());
+            roundtrip(bytes);
+        }
+    }
+
+    #[test]
+    fn test_mutations_keep_invariants() {
+        for bytes in seed_corpus() {
+            for i in 0..bytes.len() {
+                roundtrip(&bytes[..i]);
+                let mut mutated = bytes.clone();
+                mutated[i] ^= 0x01;
+                roundtrip(&mutated);
+                mutated[i] = 0x80;
+                roundtrip(&mutated);
+            }
+        }
+    }
+}
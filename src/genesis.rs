@@ -0,0 +1,148 @@
+//! Fields that are fixed when a record is created.
+//!
+//! Some fields, such as a network id, must never change over the lifetime of a node. When building
+//! a record, the builder's `immutable_fields` designates such fields. The builder then stores a
+//! [`Genesis`] under the [`GENESIS_KEY`] of the record, holding the designated keys and a digest of
+//! their values, so the genesis is signed into the first version of the record.
+//!
+//! Updates of a record through this crate refuse to change a designated field or the genesis
+//! itself. Receivers can check that a record still matches its genesis with
+//! [`Enr::verify_genesis`], and that two versions of a record share the same genesis with
+//! [`Enr::same_genesis`].
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, EnrError};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr = Enr::builder()
+//!     .add_value("network", &5_u64)
+//!     .immutable_fields(["network"])
+//!     .build(&key)
+//!     .unwrap();
+//! let first = enr.clone();
+//!
+//! enr.set_udp4(30303, &key).unwrap();
+//! assert_eq!(
+//!     enr.insert("network", &6_u64, &key).unwrap_err(),
+//!     EnrError::ImmutableFieldChanged
+//! );
+//! assert!(enr.verify_genesis());
+//! assert!(enr.same_genesis(&first));
+//! # }
+//! ```
+//!
+//! [`Enr::verify_genesis`]: crate::Enr::verify_genesis
+//! [`Enr::same_genesis`]: crate::Enr::same_genesis
+
+use crate::{digest, EnrError, Key, Value};
+use bytes::Bytes;
+use rlp::{DecoderError, Rlp, RlpStream};
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// The key the [`Genesis`] of a record is stored under.
+pub const GENESIS_KEY: &str = "genesis";
+
+/// The designated immutable fields of a record and a digest of their values at creation.
+///
+/// Encoded as the RLP list `[digest, [key, ...]]`. The digest is the keccak256 hash of the RLP
+/// list holding, for each designated key in order, the list `[key, value]`, or `[key]` if the
+/// field is absent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Genesis {
+    digest: [u8; 32],
+    keys: Vec<Vec<u8>>,
+}
+
+impl Genesis {
+    /// Records the current values of the designated keys of a record's content.
+    pub(crate) fn new(keys: impl IntoIterator<Item = Key>, content: &BTreeMap<Key, Value>) -> Self {
+        let mut keys: Vec<Vec<u8>> = keys.into_iter().map(|key| key.to_vec()).collect();
+        // the genesis cannot cover itself
+        keys.retain(|key| key != GENESIS_KEY.as_bytes());
+        keys.sort();
+        keys.dedup();
+        Self {
+            digest: fields_digest(&keys, content),
+            keys,
+        }
+    }
+
+    /// The designated immutable keys, in ascending order.
+    #[must_use]
+    pub fn keys(&self) -> &[Vec<u8>] {
+        &self.keys
+    }
+
+    /// The digest of the values of the designated keys at creation.
+    #[must_use]
+    pub const fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+
+    /// Whether the designated fields of the content still have their values at creation.
+    #[must_use]
+    pub(crate) fn matches(&self, content: &BTreeMap<Key, Value>) -> bool {
+        fields_digest(&self.keys, content) == self.digest
+    }
+
+    /// The RLP encoding of the genesis, as stored in the record.
+    pub(crate) fn encode(&self) -> Bytes {
+        let mut s = RlpStream::new_list(2);
+        s.append(&self.digest.as_ref());
+        s.begin_list(self.keys.len());
+        for key in &self.keys {
+            s.append(key);
+        }
+        s.out().freeze()
+    }
+
+    /// Decodes the genesis stored in a record.
+    pub(crate) fn decode(value: &[u8]) -> Result<Self, DecoderError> {
+        let rlp = Rlp::new(value);
+        if rlp.item_count()? != 2 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let digest =
+            <[u8; 32]>::try_from(rlp.at(0)?.data()?).map_err(|_| DecoderError::RlpInvalidLength)?;
+        let keys = rlp.list_at(1)?;
+        Ok(Self { digest, keys })
+    }
+}
+
+/// Checks an update of a record's content against the genesis it had before the update.
+///
+/// Fails if the genesis was added, removed or changed, or if a designated field changed.
+pub(crate) fn check_update(
+    previous: Option<&Value>,
+    content: &BTreeMap<Key, Value>,
+) -> Result<(), EnrError> {
+    let current = content.get(GENESIS_KEY.as_bytes());
+    if current != previous {
+        return Err(EnrError::ImmutableFieldChanged);
+    }
+    match current.map(|value| Genesis::decode(value)) {
+        None => Ok(()),
+        Some(Ok(genesis)) if genesis.matches(content) => Ok(()),
+        Some(_) => Err(EnrError::ImmutableFieldChanged),
+    }
+}
+
+/// The digest of the values of `keys` in `content`.
+fn fields_digest(keys: &[Vec<u8>], content: &BTreeMap<Key, Value>) -> [u8; 32] {
+    let mut s = RlpStream::new_list(keys.len());
+    for key in keys {
+        match content.get(key.as_slice()) {
+            Some(value) => {
+                s.begin_list(2);
+                s.append(key);
+                s.append_raw(value, 1);
+            }
+            None => {
+                s.begin_list(1);
+                s.append(key);
+            }
+        }
+    }
+    digest(&s.out())
+}
@@ -0,0 +1,342 @@
+//! Tracking of the versions of a node's record over time.
+//!
+//! Peer scoring systems are interested in how a node's record evolves: nodes that update their
+//! record unusually often, publish records with a lower sequence number than before or alternate
+//! between IP addresses are likely misconfigured or misbehaving. An [`EnrHistory`] records each
+//! observed version of a node's record in a bounded ring buffer and derives these signals from it.
+
+use crate::{Enr, EnrKey, NodeId, Supersedes};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::{Duration, SystemTime},
+};
+
+/// How an observed record relates to the previously observed versions.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Observation {
+    /// The first observed record of the node.
+    First,
+    /// A record with a higher sequence number than the newest observed one.
+    Update {
+        /// The number of sequence numbers skipped since the newest observed record.
+        missed: u64,
+    },
+    /// A record with the same sequence number and content as the newest observed one. It is not
+    /// recorded.
+    Unchanged,
+    /// A record with the same sequence number as the newest observed one but different content.
+    /// The node signed conflicting records. It is not recorded.
+    Conflict,
+    /// A record with a lower sequence number than the newest observed one.
+    Regression {
+        /// The sequence number of the newest observed record.
+        newest: u64,
+    },
+    /// A record of a different node. It is not recorded.
+    OtherNode,
+}
+
+/// An observed version of a node's record.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(bound = ""))]
+#[derive(Debug)]
+pub struct HistoryEntry<K: EnrKey> {
+    enr: Enr<K>,
+    observed_at: SystemTime,
+    observation: Observation,
+}
+
+impl<K: EnrKey> Clone for HistoryEntry<K> {
+    fn clone(&self) -> Self {
+        Self {
+            enr: self.enr.clone(),
+            observed_at: self.observed_at,
+            observation: self.observation,
+        }
+    }
+}
+
+impl<K: EnrKey> HistoryEntry<K> {
+    /// The observed record.
+    #[must_use]
+    pub const fn enr(&self) -> &Enr<K> {
+        &self.enr
+    }
+
+    /// When the record was observed.
+    #[must_use]
+    pub const fn observed_at(&self) -> SystemTime {
+        self.observed_at
+    }
+
+    /// How the record related to the versions observed before it.
+    #[must_use]
+    pub const fn observation(&self) -> Observation {
+        self.observation
+    }
+}
+
+/// A bounded history of the observed versions of a node's record.
+#[derive(Debug)]
+pub struct EnrHistory<K: EnrKey> {
+    node_id: NodeId,
+    capacity: usize,
+    entries: VecDeque<HistoryEntry<K>>,
+    /// The observed record with the highest sequence number, including evicted entries.
+    newest: Option<Enr<K>>,
+    /// The number of regressions observed, including evicted entries.
+    regressions: u64,
+}
+
+impl<K: EnrKey> Clone for EnrHistory<K> {
+    fn clone(&self) -> Self {
+        Self {
+            node_id: self.node_id,
+            capacity: self.capacity,
+            entries: self.entries.clone(),
+            newest: self.newest.clone(),
+            regressions: self.regressions,
+        }
+    }
+}
+
+impl<K: EnrKey> EnrHistory<K> {
+    /// Creates an empty history of the records of `node_id`, keeping at most `capacity` entries.
+    #[must_use]
+    pub fn new(node_id: NodeId, capacity: usize) -> Self {
+        Self {
+            node_id,
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            newest: None,
+            regressions: 0,
+        }
+    }
+
+    /// Records a version of the node's record observed now.
+    pub fn observe(&mut self, enr: &Enr<K>) -> Observation {
+        self.observe_at(enr, SystemTime::now())
+    }
+
+    /// Records a version of the node's record observed at `observed_at`, evicting the oldest
+    /// entry if the history is full.
+    pub fn observe_at(&mut self, enr: &Enr<K>, observed_at: SystemTime) -> Observation {
+        if enr.node_id() != self.node_id {
+            return Observation::OtherNode;
+        }
+
+        let observation = match &self.newest {
+            None => Observation::First,
+            Some(newest) => match enr.supersedes(newest) {
+                Ok(Supersedes::Newer) => Observation::Update {
+                    missed: enr.seq() - newest.seq() - 1,
+                },
+                Ok(Supersedes::Older) => {
+                    self.regressions += 1;
+                    Observation::Regression {
+                        newest: newest.seq(),
+                    }
+                }
+                Ok(Supersedes::Conflict) => return Observation::Conflict,
+                // the node id is checked above
+                Ok(Supersedes::Same) | Err(_) => return Observation::Unchanged,
+            },
+        };
+        if matches!(observation, Observation::First | Observation::Update { .. }) {
+            self.newest = Some(enr.clone());
+        }
+
+        if self.capacity == 0 {
+            return observation;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            enr: enr.clone(),
+            observed_at,
+            observation,
+        });
+        observation
+    }
+
+    /// The node whose records are tracked.
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// The recorded entries, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry<K>> {
+        self.entries.iter()
+    }
+
+    /// The most recently recorded entry.
+    #[must_use]
+    pub fn latest(&self) -> Option<&HistoryEntry<K>> {
+        self.entries.back()
+    }
+
+    /// The number of recorded entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no entries are recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of sequence number regressions observed over the lifetime of the history,
+    /// including those evicted from the buffer.
+    #[must_use]
+    pub const fn seq_regressions(&self) -> u64 {
+        self.regressions
+    }
+
+    /// The total number of sequence numbers skipped between the recorded updates.
+    #[must_use]
+    pub fn seq_gaps(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|entry| match entry.observation {
+                Observation::Update { missed } => missed,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// The rate of updates over the time spanned by the recorded entries.
+    ///
+    /// Returns `0.0` if the entries don't span any time.
+    #[must_use]
+    pub fn updates_per_hour(&self) -> f64 {
+        let span = match (self.entries.front(), self.entries.back()) {
+            (Some(first), Some(last)) => last
+                .observed_at
+                .duration_since(first.observed_at)
+                .unwrap_or(Duration::ZERO),
+            _ => return 0.0,
+        };
+        if span.is_zero() {
+            return 0.0;
+        }
+        let updates = self
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.observation, Observation::Update { .. }))
+            .count();
+        updates as f64 * 3600.0 / span.as_secs_f64()
+    }
+
+    /// The number of times a recorded entry changed its IP address back to an address advertised
+    /// by an earlier entry, other than the one immediately before it.
+    ///
+    /// IPv4 and IPv6 addresses are tracked independently.
+    #[must_use]
+    pub fn ip_flaps(&self) -> usize {
+        let ip4: Vec<Option<Ipv4Addr>> = self.entries.iter().map(|e| e.enr.ip4()).collect();
+        let ip6: Vec<Option<Ipv6Addr>> = self.entries.iter().map(|e| e.enr.ip6()).collect();
+        flaps(&ip4) + flaps(&ip6)
+    }
+
+    /// A copy of the recorded entries, oldest first, e.g. for serialization.
+    #[must_use]
+    pub fn export(&self) -> Vec<HistoryEntry<K>> {
+        self.entries.iter().cloned().collect()
+    }
+}
+
+/// Counts the changes in `addresses` to an address seen before the previous one.
+fn flaps<T: PartialEq>(addresses: &[Option<T>]) -> usize {
+    (1..addresses.len())
+        .filter(|&i| {
+            addresses[i].is_some()
+                && addresses[i] != addresses[i - 1]
+                && addresses[..i - 1].contains(&addresses[i])
+        })
+        .count()
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_observations() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        let mut history = EnrHistory::new(enr.node_id(), 8);
+
+        assert_eq!(history.observe_at(&enr, at(0)), Observation::First);
+        assert_eq!(history.observe_at(&enr, at(1)), Observation::Unchanged);
+        let conflicting = Enr::builder().udp4(30304).build(&key).unwrap();
+        assert_eq!(
+            history.observe_at(&conflicting, at(1)),
+            Observation::Conflict
+        );
+        let old = enr.clone();
+
+        enr.set_seq(5, &key).unwrap();
+        assert_eq!(
+            history.observe_at(&enr, at(1800)),
+            Observation::Update { missed: 3 }
+        );
+        assert_eq!(
+            history.observe_at(&old, at(3600)),
+            Observation::Regression { newest: 5 }
+        );
+
+        let other = Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap();
+        assert_eq!(history.observe_at(&other, at(3600)), Observation::OtherNode);
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.seq_regressions(), 1);
+        assert_eq!(history.seq_gaps(), 3);
+        assert!((history.updates_per_hour() - 1.0).abs() < f64::EPSILON);
+        assert_eq!(history.latest().unwrap().enr(), &old);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::empty(&key).unwrap();
+        let mut history = EnrHistory::new(enr.node_id(), 2);
+        for secs in 0..5 {
+            history.observe_at(&enr, at(secs));
+            enr.set_seq(enr.seq() + 1, &key).unwrap();
+        }
+        let seqs: Vec<u64> = history.entries().map(|entry| entry.enr().seq()).collect();
+        assert_eq!(seqs, vec![4, 5]);
+        assert_eq!(history.export().len(), 2);
+    }
+
+    #[test]
+    fn test_ip_flaps() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::empty(&key).unwrap();
+        let mut history = EnrHistory::new(enr.node_id(), 16);
+
+        let a = Ipv4Addr::new(10, 0, 0, 1);
+        let b = Ipv4Addr::new(10, 0, 0, 2);
+        let c = Ipv4Addr::new(10, 0, 0, 3);
+        for ip in [a, b, a, c, c, b] {
+            enr.set_ip(ip.into(), &key).unwrap();
+            history.observe(&enr);
+        }
+        // a -> b -> a and c -> b return to earlier addresses
+        assert_eq!(history.ip_flaps(), 2);
+    }
+}
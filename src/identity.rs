@@ -0,0 +1,374 @@
+//! A node identity, its key and record, persisted as a single file.
+//!
+//! An [`Identity`] file holds the text form of the node's record along with its secret key, in
+//! the clear or encrypted as a [`Keystore`]. Files with a `.toml` extension are written and read
+//! as TOML, all others as JSON:
+//!
+//! ```toml
+//! enr = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8"
+//! key_type = "secp256k1"
+//! secret_key = "b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291"
+//! ```
+//!
+//! Loading an identity whose record was not signed by its key, e.g. after the key was replaced,
+//! re-signs the record with the key:
+//!
+//! ```rust,no_run
+//! use enr::{identity::Identity, CombinedKey, Enr};
+//!
+//! let key = CombinedKey::generate_secp256k1();
+//! let enr = Enr::builder().udp4(30303).build(&key).unwrap();
+//! Identity { key, enr }.save("node.toml").unwrap();
+//!
+//! let identity = Identity::load("node.toml", None).unwrap();
+//! assert_eq!(identity.enr.udp4(), Some(30303));
+//! ```
+
+use crate::{keystore::Keystore, CombinedKey, Enr, EnrError, EnrKey, EnrParseError, EnrPublicKey};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    ffi::OsString,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+/// An error saving or loading an [`Identity`].
+#[derive(Debug)]
+pub enum IdentityError {
+    /// The file could not be read or written.
+    Io(io::Error),
+    /// The file is not a valid identity file.
+    InvalidFormat(String),
+    /// The record of the file is invalid.
+    InvalidEnr(EnrParseError),
+    /// The secret key of the file is invalid or of an unknown type.
+    InvalidKey,
+    /// The key of the file is encrypted and no password was given.
+    PasswordRequired,
+    /// The encrypted key could not be encrypted or decrypted.
+    Keystore(crate::keystore::KeystoreError),
+    /// The record could not be re-signed with the key.
+    Resign(EnrError),
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "identity file error: {e}"),
+            Self::InvalidFormat(e) => write!(f, "invalid identity file: {e}"),
+            Self::InvalidEnr(e) => write!(f, "invalid identity record: {e}"),
+            Self::InvalidKey => write!(f, "invalid identity key"),
+            Self::PasswordRequired => write!(f, "the identity key is encrypted"),
+            Self::Keystore(e) => write!(f, "{e}"),
+            Self::Resign(e) => write!(f, "the identity record cannot be re-signed: {e}"),
+        }
+    }
+}
+
+impl Error for IdentityError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::InvalidEnr(e) => Some(e),
+            Self::Keystore(e) => Some(e),
+            Self::Resign(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IdentityError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The key and record of a node.
+pub struct Identity {
+    /// The secret key of the node.
+    pub key: CombinedKey,
+    /// The record of the node, signed by `key`.
+    pub enr: Enr<CombinedKey>,
+}
+
+/// The content of an identity file.
+#[derive(Serialize, Deserialize)]
+struct IdentityFile {
+    enr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keystore: Option<Keystore>,
+}
+
+impl Drop for IdentityFile {
+    fn drop(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+impl Identity {
+    /// Writes the identity to `path` with its key in the clear. On unix, a new file is only
+    /// readable by its owner.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), IdentityError> {
+        let key_type = String::from_utf8(self.key.public().enr_key())
+            .map_err(|_| IdentityError::InvalidKey)?;
+        let secret_key = Zeroizing::new(self.key.encode());
+        Self::write(
+            path.as_ref(),
+            IdentityFile {
+                enr: self.enr.to_base64(),
+                key_type: Some(key_type),
+                secret_key: Some(hex::encode(&*secret_key)),
+                keystore: None,
+            },
+        )
+    }
+
+    /// Writes the identity to `path` with its key encrypted with `password` as a [`Keystore`].
+    /// Encrypting the key takes about a second, see [`Keystore::encrypt`].
+    pub fn save_encrypted(
+        &self,
+        path: impl AsRef<Path>,
+        password: &str,
+    ) -> Result<(), IdentityError> {
+        let keystore = Keystore::encrypt(&self.key, password).map_err(IdentityError::Keystore)?;
+        self.save_keystore(path.as_ref(), keystore)
+    }
+
+    /// Writes the identity to `path` with its key encrypted as `keystore`.
+    fn save_keystore(&self, path: &Path, keystore: Keystore) -> Result<(), IdentityError> {
+        Self::write(
+            path,
+            IdentityFile {
+                enr: self.enr.to_base64(),
+                key_type: None,
+                secret_key: None,
+                keystore: Some(keystore),
+            },
+        )
+    }
+
+    /// Reads the identity at `path`, decrypting its key with `password` if it is encrypted.
+    ///
+    /// If the record was not signed by the key, it is re-signed with the key, keeping its content
+    /// apart from the public key. The re-signed record is not written back to the file.
+    pub fn load(path: impl AsRef<Path>, password: Option<&str>) -> Result<Self, IdentityError> {
+        let path = path.as_ref();
+        let text = Zeroizing::new(fs::read_to_string(path)?);
+        let file: IdentityFile = if is_toml(path) {
+            toml::from_str(&text).map_err(|e| IdentityError::InvalidFormat(e.to_string()))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| IdentityError::InvalidFormat(e.to_string()))?
+        };
+
+        let key = match (&file.keystore, &file.key_type, &file.secret_key) {
+            (Some(keystore), _, _) => keystore
+                .decrypt(password.ok_or(IdentityError::PasswordRequired)?)
+                .map_err(IdentityError::Keystore)?,
+            (None, Some(key_type), Some(secret_key)) => decode_key(key_type, secret_key)?,
+            _ => return Err(IdentityError::InvalidFormat("the file holds no key".into())),
+        };
+        let mut enr: Enr<CombinedKey> = file.enr.parse().map_err(IdentityError::InvalidEnr)?;
+
+        let public_key = key.public();
+        if enr.public_key() != public_key {
+            let stale_key = enr.public_key().enr_key();
+            enr.update(&key, |tx| {
+                if stale_key != public_key.enr_key() {
+                    tx.remove(&stale_key);
+                }
+            })
+            .map_err(IdentityError::Resign)?;
+        }
+        Ok(Self { key, enr })
+    }
+
+    /// Writes `file` to `path` through a temporary file, so that an existing identity is only
+    /// replaced once the new one is complete.
+    fn write(path: &Path, file: IdentityFile) -> Result<(), IdentityError> {
+        let text = Zeroizing::new(if is_toml(path) {
+            toml::to_string(&file).map_err(|e| IdentityError::InvalidFormat(e.to_string()))?
+        } else {
+            serde_json::to_string_pretty(&file)
+                .map_err(|e| IdentityError::InvalidFormat(e.to_string()))?
+        });
+
+        let mut temp_path = OsString::from(path);
+        temp_path.push(".tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut temp_file = options.open(&temp_path)?;
+        temp_file.write_all(text.as_bytes())?;
+        temp_file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Whether `path` is written and read as TOML rather than JSON.
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+/// Decodes a hex encoded secret key of the type named by its record key.
+fn decode_key(key_type: &str, secret_key: &str) -> Result<CombinedKey, IdentityError> {
+    let mut bytes = Zeroizing::new(
+        hex::decode(secret_key.trim_start_matches("0x")).map_err(|_| IdentityError::InvalidKey)?,
+    );
+    match key_type {
+        "secp256k1" => CombinedKey::secp256k1_from_bytes(&mut bytes),
+        "ed25519" => CombinedKey::ed25519_from_bytes(&mut bytes),
+        #[cfg(feature = "bls")]
+        "bls" => CombinedKey::bls381_from_bytes(&mut bytes),
+        _ => return Err(IdentityError::InvalidKey),
+    }
+    .map_err(|_| IdentityError::InvalidKey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A path in the temporary directory that is removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(extension: &str) -> Self {
+            let name = format!(
+                "enr-identity-{}-{}.{extension}",
+                std::process::id(),
+                rand::random::<u64>()
+            );
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn identity(key: CombinedKey) -> Identity {
+        let enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        Identity { key, enr }
+    }
+
+    #[test]
+    fn test_identity_round_trip() {
+        for (key, extension) in [
+            (CombinedKey::generate_secp256k1(), "toml"),
+            (CombinedKey::generate_secp256k1(), "json"),
+            (CombinedKey::generate_ed25519(), "toml"),
+            (CombinedKey::generate_ed25519(), "json"),
+        ] {
+            let path = TempPath::new(extension);
+            let identity = identity(key);
+            identity.save(&path.0).unwrap();
+
+            let loaded = Identity::load(&path.0, None).unwrap();
+            assert_eq!(loaded.key.public(), identity.key.public());
+            assert_eq!(loaded.enr, identity.enr);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = fs::metadata(&path.0).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+        let path = TempPath::new("toml");
+        identity(CombinedKey::generate_secp256k1())
+            .save(&path.0)
+            .unwrap();
+        let text = fs::read_to_string(&path.0).unwrap();
+        assert!(text.contains("key_type = \"secp256k1\""));
+    }
+
+    #[test]
+    fn test_identity_encrypted() {
+        for extension in ["toml", "json"] {
+            let path = TempPath::new(extension);
+            let identity = identity(CombinedKey::generate_ed25519());
+            let keystore = Keystore::encrypt_insecure(&identity.key, "password");
+            identity.save_keystore(&path.0, keystore).unwrap();
+
+            assert!(matches!(
+                Identity::load(&path.0, None),
+                Err(IdentityError::PasswordRequired)
+            ));
+            assert!(matches!(
+                Identity::load(&path.0, Some("wrong")),
+                Err(IdentityError::Keystore(_))
+            ));
+            let loaded = Identity::load(&path.0, Some("password")).unwrap();
+            assert_eq!(loaded.key.public(), identity.key.public());
+            assert_eq!(loaded.enr, identity.enr);
+        }
+    }
+
+    #[test]
+    fn test_identity_resigns_mismatched_record() {
+        let path = TempPath::new("json");
+        let old = identity(CombinedKey::generate_secp256k1());
+        for key in [
+            CombinedKey::generate_secp256k1(),
+            CombinedKey::generate_ed25519(),
+        ] {
+            let public_key = key.public();
+            Identity {
+                key,
+                enr: old.enr.clone(),
+            }
+            .save(&path.0)
+            .unwrap();
+
+            let loaded = Identity::load(&path.0, None).unwrap();
+            assert_eq!(loaded.enr.public_key(), public_key);
+            assert!(loaded.enr.verify());
+            assert_eq!(loaded.enr.udp4(), Some(30303));
+            assert_eq!(loaded.enr.seq(), old.enr.seq() + 1);
+        }
+    }
+
+    #[test]
+    fn test_identity_invalid() {
+        let path = TempPath::new("json");
+        assert!(matches!(
+            Identity::load(&path.0, None),
+            Err(IdentityError::Io(_))
+        ));
+        let enr = identity(CombinedKey::generate_secp256k1()).enr.to_base64();
+        for (content, invalid_key) in [
+            (format!("{{\"enr\": \"{enr}\"}}"), false),
+            (
+                format!("{{\"enr\": \"{enr}\", \"key_type\": \"rsa\", \"secret_key\": \"00\"}}"),
+                true,
+            ),
+            (
+                format!(
+                    "{{\"enr\": \"{enr}\", \"key_type\": \"secp256k1\", \"secret_key\": \"zz\"}}"
+                ),
+                true,
+            ),
+        ] {
+            fs::write(&path.0, content).unwrap();
+            let result = Identity::load(&path.0, None);
+            if invalid_key {
+                assert!(matches!(result, Err(IdentityError::InvalidKey)));
+            } else {
+                assert!(matches!(result, Err(IdentityError::InvalidFormat(_))));
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+//! Password-encrypted [`CombinedKey`]s in the EIP-2335 keystore format.
+//!
+//! Validator tooling stores its keys as [EIP-2335](https://eips.ethereum.org/EIPS/eip-2335) JSON
+//! keystores. A [`Keystore`] protects the secret key of a node identity the same way: the key is
+//! encrypted with AES-128-CTR under a key derived from the password with scrypt, or with PBKDF2
+//! when decrypting keystores written by other tools. The public key is stored in the clear and
+//! determines the key type on decryption.
+//!
+//! Keystores are untrusted input, so decryption rejects key derivation parameters costlier than
+//! scrypt with `n = 2^20, r = 8, p = 1`, about 1 GiB of memory, or PBKDF2 with `2^22` iterations.
+//!
+//! ```rust,no_run
+//! use enr::{keystore::Keystore, CombinedKey, EnrKey};
+//!
+//! let key = CombinedKey::generate_secp256k1();
+//! let keystore = Keystore::encrypt(&key, "correct horse").unwrap();
+//! let json = keystore.to_string();
+//!
+//! let keystore: Keystore = json.parse().unwrap();
+//! assert_eq!(keystore.decrypt("correct horse").unwrap().public(), key.public());
+//! assert!(keystore.decrypt("wrong password").is_err());
+//! ```
+
+use crate::{CombinedKey, EnrKey, EnrPublicKey};
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{convert::TryInto, error::Error, fmt, str::FromStr};
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+/// The base 2 logarithm of the scrypt cost of new keystores, as recommended by EIP-2335.
+const SCRYPT_LOG_N: u8 = 18;
+/// The most scrypt work accepted on decryption, as the product `n * r * p` of its parameters.
+/// The memory scrypt uses is `128 * n * r` bytes.
+const MAX_SCRYPT_WORK: u64 = 8 << 20;
+/// The most PBKDF2 iterations accepted on decryption.
+const MAX_PBKDF2_ITERATIONS: u32 = 1 << 22;
+/// The length of the derived key.
+const DKLEN: usize = 32;
+/// The version of the keystore format.
+const VERSION: u32 = 4;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+/// An error encrypting or decrypting a [`Keystore`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeystoreError {
+    /// The keystore is not valid JSON of the keystore format.
+    InvalidJson(String),
+    /// The parameters of the key derivation or cipher are invalid or unsupported.
+    InvalidParams(String),
+    /// The password does not decrypt the keystore.
+    InvalidPassword,
+    /// The decrypted secret is not a key matching the public key of the keystore.
+    InvalidKey,
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidJson(e) => write!(f, "invalid keystore: {e}"),
+            Self::InvalidParams(e) => write!(f, "invalid keystore parameters: {e}"),
+            Self::InvalidPassword => write!(f, "invalid keystore password"),
+            Self::InvalidKey => write!(f, "the keystore secret does not match its public key"),
+        }
+    }
+}
+
+impl Error for KeystoreError {}
+
+/// An EIP-2335 keystore holding an encrypted [`CombinedKey`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    crypto: Crypto,
+    #[serde(default)]
+    description: String,
+    pubkey: String,
+    #[serde(default)]
+    path: String,
+    uuid: String,
+    version: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Crypto {
+    kdf: KdfModule,
+    checksum: Module<EmptyParams>,
+    cipher: Module<CipherParams>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Module<P> {
+    function: String,
+    params: P,
+    message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfModule {
+    #[serde(flatten)]
+    kdf: Kdf,
+    message: String,
+}
+
+/// The password-based key derivation function and its parameters.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params", rename_all = "lowercase")]
+enum Kdf {
+    Scrypt {
+        dklen: usize,
+        n: u32,
+        p: u32,
+        r: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        dklen: usize,
+        c: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct EmptyParams {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+impl Keystore {
+    /// Encrypts `key` with `password`, deriving the encryption key with scrypt at the cost
+    /// recommended by EIP-2335. This takes about a second and 256 MiB of memory.
+    pub fn encrypt(key: &CombinedKey, password: &str) -> Result<Self, KeystoreError> {
+        let mut salt = [0_u8; 32];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let kdf = Kdf::Scrypt {
+            dklen: DKLEN,
+            n: 1 << SCRYPT_LOG_N,
+            p: 1,
+            r: 8,
+            salt: hex::encode(salt),
+        };
+        Self::encrypt_with(key, password, kdf)
+    }
+
+    /// Encrypts `key` with `password` at a negligible scrypt cost, for tests storing keystores.
+    #[cfg(test)]
+    pub(crate) fn encrypt_insecure(key: &CombinedKey, password: &str) -> Self {
+        let kdf = Kdf::Scrypt {
+            dklen: DKLEN,
+            n: 16,
+            p: 1,
+            r: 8,
+            salt: hex::encode([1; 32]),
+        };
+        Self::encrypt_with(key, password, kdf).expect("valid parameters")
+    }
+
+    /// Encrypts `key` with `password`, deriving the encryption key with `kdf`.
+    fn encrypt_with(key: &CombinedKey, password: &str, kdf: Kdf) -> Result<Self, KeystoreError> {
+        let mut iv = [0_u8; 16];
+        let mut uuid = [0_u8; 16];
+        rand::thread_rng().fill_bytes(&mut iv);
+        rand::thread_rng().fill_bytes(&mut uuid);
+
+        let derived_key = kdf.derive_key(password)?;
+        let mut message = Zeroizing::new(key.encode());
+        Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut message);
+
+        Ok(Self {
+            crypto: Crypto {
+                kdf: KdfModule {
+                    kdf,
+                    message: String::new(),
+                },
+                checksum: Module {
+                    function: "sha256".into(),
+                    params: EmptyParams {},
+                    message: hex::encode(checksum(&derived_key, &message)),
+                },
+                cipher: Module {
+                    function: "aes-128-ctr".into(),
+                    params: CipherParams {
+                        iv: hex::encode(iv),
+                    },
+                    message: hex::encode(&*message),
+                },
+            },
+            description: String::new(),
+            pubkey: hex::encode(key.public().encode()),
+            path: String::new(),
+            uuid: format_uuid(uuid),
+            version: VERSION,
+        })
+    }
+
+    /// Decrypts the key with `password`.
+    pub fn decrypt(&self, password: &str) -> Result<CombinedKey, KeystoreError> {
+        if self.crypto.checksum.function != "sha256" {
+            return Err(unsupported("checksum", &self.crypto.checksum.function));
+        }
+        if self.crypto.cipher.function != "aes-128-ctr" {
+            return Err(unsupported("cipher", &self.crypto.cipher.function));
+        }
+        let message = decode_hex("cipher message", &self.crypto.cipher.message)?;
+        let iv: [u8; 16] = decode_hex("iv", &self.crypto.cipher.params.iv)?
+            .try_into()
+            .map_err(|_| KeystoreError::InvalidParams("the iv is not 16 bytes".into()))?;
+        let expected_checksum = decode_hex("checksum", &self.crypto.checksum.message)?;
+
+        let derived_key = self.crypto.kdf.kdf.derive_key(password)?;
+        if !bool::from(checksum(&derived_key, &message)[..].ct_eq(&expected_checksum)) {
+            return Err(KeystoreError::InvalidPassword);
+        }
+        let mut secret = Zeroizing::new(message);
+        Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut secret);
+
+        let candidates = [
+            CombinedKey::secp256k1_from_bytes,
+            CombinedKey::ed25519_from_bytes,
+            #[cfg(feature = "bls")]
+            CombinedKey::bls381_from_bytes,
+        ];
+        candidates
+            .iter()
+            .filter_map(|from_bytes| from_bytes(&mut Zeroizing::new(secret.to_vec())).ok())
+            .find(|key| hex::encode(key.public().encode()) == self.pubkey.trim_start_matches("0x"))
+            .ok_or(KeystoreError::InvalidKey)
+    }
+
+    /// The hex encoded public key of the encrypted key.
+    #[must_use]
+    pub fn pubkey(&self) -> &str {
+        &self.pubkey
+    }
+
+    /// The UUID of the keystore.
+    #[must_use]
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// The description of the keystore.
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Sets the description of the keystore.
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = description.into();
+    }
+}
+
+impl Kdf {
+    /// Derives the decryption key from the password, processed as specified by EIP-2335.
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<[u8; DKLEN]>, KeystoreError> {
+        let password = Zeroizing::new(
+            password
+                .nfkd()
+                .filter(|c| !matches!(*c as u32, 0x00..=0x1f | 0x7f..=0x9f))
+                .collect::<String>(),
+        );
+        let mut derived_key = Zeroizing::new([0_u8; DKLEN]);
+        match self {
+            Self::Scrypt {
+                dklen,
+                n,
+                p,
+                r,
+                salt,
+            } => {
+                check_dklen(*dklen)?;
+                if !n.is_power_of_two() {
+                    return Err(KeystoreError::InvalidParams(format!(
+                        "the scrypt cost {n} is not a power of two"
+                    )));
+                }
+                if u64::from(*n) * u64::from(*r) * u64::from(*p) > MAX_SCRYPT_WORK {
+                    return Err(KeystoreError::InvalidParams(format!(
+                        "the scrypt parameters n = {n}, r = {r}, p = {p} exceed the supported cost"
+                    )));
+                }
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, DKLEN)
+                    .map_err(|e| KeystoreError::InvalidParams(e.to_string()))?;
+                scrypt::scrypt(
+                    password.as_bytes(),
+                    &decode_hex("salt", salt)?,
+                    &params,
+                    &mut *derived_key,
+                )
+                .map_err(|e| KeystoreError::InvalidParams(e.to_string()))?;
+            }
+            Self::Pbkdf2 {
+                dklen,
+                c,
+                prf,
+                salt,
+            } => {
+                check_dklen(*dklen)?;
+                if prf != "hmac-sha256" {
+                    return Err(unsupported("pbkdf2 prf", prf));
+                }
+                if *c > MAX_PBKDF2_ITERATIONS {
+                    return Err(KeystoreError::InvalidParams(format!(
+                        "the pbkdf2 iteration count {c} exceeds {MAX_PBKDF2_ITERATIONS}"
+                    )));
+                }
+                pbkdf2::pbkdf2_hmac::<Sha256>(
+                    password.as_bytes(),
+                    &decode_hex("salt", salt)?,
+                    *c,
+                    &mut *derived_key,
+                );
+            }
+        }
+        Ok(derived_key)
+    }
+}
+
+/// Parses the JSON form of a keystore.
+impl FromStr for Keystore {
+    type Err = KeystoreError;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        let keystore: Self =
+            serde_json::from_str(json).map_err(|e| KeystoreError::InvalidJson(e.to_string()))?;
+        if keystore.version != VERSION {
+            return Err(KeystoreError::InvalidJson(format!(
+                "unsupported version {}",
+                keystore.version
+            )));
+        }
+        Ok(keystore)
+    }
+}
+
+/// Displays the JSON form of a keystore.
+impl fmt::Display for Keystore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+/// The checksum of EIP-2335, proving knowledge of the password.
+fn checksum(derived_key: &[u8; DKLEN], cipher_message: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(&derived_key[16..])
+        .chain_update(cipher_message)
+        .finalize()
+        .into()
+}
+
+fn check_dklen(dklen: usize) -> Result<(), KeystoreError> {
+    if dklen == DKLEN {
+        Ok(())
+    } else {
+        Err(KeystoreError::InvalidParams(format!(
+            "the derived key length {dklen} is not {DKLEN}"
+        )))
+    }
+}
+
+fn decode_hex(name: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| KeystoreError::InvalidJson(format!("invalid {name}: {e}")))
+}
+
+fn unsupported(module: &str, function: &str) -> KeystoreError {
+    KeystoreError::InvalidParams(format!("unsupported {module} function `{function}`"))
+}
+
+/// Formats random bytes as a version 4 UUID.
+fn format_uuid(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrypt(log_n: u32) -> Kdf {
+        Kdf::Scrypt {
+            dklen: DKLEN,
+            n: 1 << log_n,
+            p: 1,
+            r: 8,
+            salt: hex::encode([1; 32]),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pbkdf2 = Kdf::Pbkdf2 {
+            dklen: DKLEN,
+            c: 16,
+            prf: "hmac-sha256".into(),
+            salt: hex::encode([2; 32]),
+        };
+        for (key, kdf) in [
+            (CombinedKey::generate_secp256k1(), scrypt(4)),
+            (CombinedKey::generate_ed25519(), pbkdf2),
+        ] {
+            let keystore = Keystore::encrypt_with(&key, "password", kdf).unwrap();
+            assert_eq!(keystore.pubkey(), hex::encode(key.public().encode()));
+            assert_eq!(keystore.uuid().len(), 36);
+
+            let keystore: Keystore = keystore.to_string().parse().unwrap();
+            let decrypted = keystore.decrypt("password").unwrap();
+            assert_eq!(decrypted.public(), key.public());
+            assert!(matches!(
+                keystore.decrypt("Password"),
+                Err(KeystoreError::InvalidPassword)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_password_processing() {
+        let key = CombinedKey::generate_secp256k1();
+        // control codes are stripped and the password is NFKD normalized
+        let keystore = Keystore::encrypt_with(&key, "pass\u{7f}word\u{212b}", scrypt(4)).unwrap();
+        assert!(keystore.decrypt("passwordA\u{30a}").is_ok());
+    }
+
+    /// The test vectors of EIP-2335, encrypting the BLS secret key
+    /// `0x000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f`.
+    #[cfg(feature = "bls")]
+    const EIP_2335_SCRYPT: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "scrypt",
+                "params": {
+                    "dklen": 32,
+                    "n": 262144,
+                    "p": 1,
+                    "r": 8,
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "d2217fe5f3e9a1e34581ef8a78f7c9928e436d36dacc5e846690a5581e8ea484"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "06ae90d55fe0a6e9c5c3bc5b170827b2e5cce3929ed3f116c2811e6366dfe20f"
+            }
+        },
+        "description": "This is a test keystore that uses scrypt to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/3141592653/589793238",
+        "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+        "version": 4
+    }"#;
+
+    #[cfg(feature = "bls")]
+    const EIP_2335_PBKDF2: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    #[cfg(feature = "bls")]
+    #[test]
+    fn test_eip_2335_vectors() {
+        let password = "𝔱𝔢𝔰𝔱𝔭𝔞𝔰𝔰𝔴𝔬𝔯𝔡🔑";
+        for json in [EIP_2335_SCRYPT, EIP_2335_PBKDF2] {
+            let keystore: Keystore = json.parse().unwrap();
+            let key = keystore.decrypt(password).unwrap();
+            assert!(matches!(key, CombinedKey::Bls381(_)));
+            assert_eq!(
+                hex::encode(key.encode()),
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+            );
+            assert!(matches!(
+                keystore.decrypt("testpassword"),
+                Err(KeystoreError::InvalidPassword)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_costly_parameters() {
+        let key = CombinedKey::generate_secp256k1();
+        let costly = [
+            scrypt(21),
+            Kdf::Scrypt {
+                dklen: DKLEN,
+                n: 1 << 20,
+                p: 2,
+                r: 8,
+                salt: hex::encode([1; 32]),
+            },
+            Kdf::Pbkdf2 {
+                dklen: DKLEN,
+                c: u32::MAX,
+                prf: "hmac-sha256".into(),
+                salt: hex::encode([2; 32]),
+            },
+        ];
+        let mut keystore = Keystore::encrypt_insecure(&key, "password");
+        for kdf in costly {
+            keystore.crypto.kdf.kdf = kdf;
+            assert!(matches!(
+                keystore.decrypt("password"),
+                Err(KeystoreError::InvalidParams(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_keystores() {
+        let key = CombinedKey::generate_secp256k1();
+        let keystore = Keystore::encrypt_with(&key, "password", scrypt(4)).unwrap();
+
+        let mut wrong_pubkey = keystore.clone();
+        wrong_pubkey.pubkey = hex::encode(CombinedKey::generate_secp256k1().public().encode());
+        assert!(matches!(
+            wrong_pubkey.decrypt("password"),
+            Err(KeystoreError::InvalidKey)
+        ));
+
+        let mut wrong_cipher = keystore.clone();
+        wrong_cipher.crypto.cipher.function = "aes-256-gcm".into();
+        assert!(matches!(
+            wrong_cipher.decrypt("password"),
+            Err(KeystoreError::InvalidParams(_))
+        ));
+
+        let json = keystore
+            .to_string()
+            .replace("\"version\": 4", "\"version\": 3");
+        assert!(matches!(
+            json.parse::<Keystore>(),
+            Err(KeystoreError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            "{}".parse::<Keystore>(),
+            Err(KeystoreError::InvalidJson(_))
+        ));
+    }
+}
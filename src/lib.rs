@@ -10,8 +10,8 @@
 //! ENR's are identified by their sequence number. When updating an ENR, the sequence number is
 //! increased.
 //!
-//! Different identity schemes can be used to define the node id and signatures. Currently only the
-//! "v4" identity is supported and is set by default.
+//! Different identity schemes can be used to define the node id and signatures. The "v4" identity
+//! scheme is used by default; other schemes can be plugged in through the [`IdentityScheme`] trait.
 //!
 //! ## Signing Algorithms
 //!
@@ -30,10 +30,64 @@
 //!
 //! This crate supports a number of features.
 //!
-//! - `serde`: Allows for serde serialization and deserialization for ENRs.
+//! - `serde`: Allows for serde serialization and deserialization for ENRs, with helpers for
+//!   collections of records in [`serde_records`].
 //! - `ed25519`: Provides support for `ed25519_dalek` keypair types.
+//! - `bls`: Provides support for `blst` BLS12-381 keys, stored under the `bls381` key. Together
+//!   with `ed25519` and `k256`, adds them to [`CombinedKey`].
 //! - `k256`: Uses `k256` for secp256k1 keys.
-//! - `rust-secp256k1`: Uses `rust-secp256k1` for secp256k1 keys.
+//! - `rust-secp256k1`: Uses `rust-secp256k1` for secp256k1 keys. Together with `k256`, provides
+//!   [`CombinedSecpKey`], signing records with and converting keys between either backend.
+//! - `parallel`: Provides [`batch::build_many`] to build and sign many records in parallel.
+//! - `compact`: Records store short keys and values inline rather than in allocations of their
+//!   own, reducing the memory footprint of large record caches.
+//! - `macros`: Provides the [`enr!`] macro for concise construction of records.
+//! - `tokio`: Provides [`tasks::republisher`] to periodically re-sign and publish a record.
+//! - `verification-cache`: Caches successful signature verifications in a process-wide LRU cache,
+//!   see [`verification_cache`].
+//! - `dns`: Provides [`dns`], parsing and verifying EIP-1459 node trees and resolving
+//!   `enrtree://` URLs into verified records through any [`dns::TxtLookup`].
+//! - `dns-resolve`: Enables `dns` and implements [`dns::TxtLookup`] with the hickory resolver,
+//!   see [`dns::DnsResolver::from_system_conf`].
+//! - `db`: Provides [`db::RecordDb`], an append-only, memory-mapped file of verified records.
+//! - `store`: Provides [`store::EnrStore`], keeping the newest valid record of each node in memory
+//!   or in a JSON file.
+//! - `store-sled`: Enables `store` and provides a backend persisting the records with `sled`.
+//! - `bridge`: Provides [`bridge`], for converting records between versions of this crate, and
+//!   implements the version independent `RecordView` trait of `enr-compat`.
+//! - `cli`: Provides the `enr-cli` binary for decoding, verifying and updating records, see
+//!   [`cli`].
+//! - `identity`: Provides [`identity::Identity`], persisting a [`CombinedKey`] and its record as
+//!   a single TOML or JSON file, with the key optionally encrypted. Implies `keystore`.
+//! - `keystore`: Provides [`keystore::Keystore`], storing [`CombinedKey`]s encrypted with a
+//!   password in the EIP-2335 keystore format.
+//! - `pkcs8`: Provides PKCS#8 DER and PEM import and export of [`CombinedKey`]s, e.g.
+//!   [`CombinedKey::from_pem`].
+//! - `zeroize`: Guarantees that [`CombinedKey`] erases its secret when dropped and provides
+//!   conversions between `CombinedKey` and `secrecy` secrets.
+//! - `async-signing`: Provides [`AsyncEnrSigner`], for building and updating records with
+//!   signers that sign asynchronously, such as remote key management services.
+//! - `alloy-rlp`: Implements the `Encodable` and `Decodable` traits of `alloy-rlp` for [`Enr`].
+//! - `eth2`: Provides SSZ encoding of records as byte lists and as an [`eth2::EnrContainer`], for
+//!   embedding records in consensus layer messages, the typed `eth2`, `attnets` and `syncnets`
+//!   fields of consensus layer nodes, see [`EnrForkId`] and [`SubnetBitfield`], and a
+//!   `consensus_defaults` preset of the [`Enr::builder`] for them.
+//! - `proto`: Provides [`proto::Enr`], a `prost` message of records for gRPC APIs.
+//! - `fuzz`: Provides [`fuzz_utils`], checking the invariants of decoded records in fuzz targets,
+//!   and a seed corpus of valid records.
+//! - `test-utils`: Provides [`test_utils::TestEnrGenerator`] and a proptest `Arbitrary`
+//!   implementation, generating reproducible, valid records for tests of downstream crates.
+//! - `wasm`: Provides [`wasm`], `wasm-bindgen` bindings for decoding, verifying and building
+//!   records client-side in the browser, with `wasm32-unknown-unknown` support.
+//! - `tracing`: Emits the diagnostics of decoding, verifying and updating records, such as the
+//!   cause of a rejected record, as structured `tracing` spans and events rather than `log`
+//!   messages.
+//! - `primitive-types`: Provides conversions between [`NodeId`] and the `H256` and `U256` types
+//!   of `primitive-types`.
+//! - `raw`: Provides [`Enr::from_parts_unchecked`] and [`Enr::content`], for assembling records
+//!   that are not valid, such as negative test vectors.
+//! - `test-determinism`: Provides [`inject_entropy`], making signatures and generated keys
+//!   reproducible in tests. Never enable it outside of tests.
 //!
 //! These can be enabled via adding the feature flag in your `Cargo.toml`
 //!
@@ -175,18 +229,100 @@
     clippy::option_if_let_else
 )]
 
+mod alias;
+mod allowlist;
+#[cfg(feature = "alloy-rlp")]
+mod alloy;
+mod attestation;
+#[cfg(feature = "parallel")]
+pub mod batch;
+#[cfg(feature = "bridge")]
+pub mod bridge;
 mod builder;
+pub mod bundle;
+mod changes;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod compaction;
+pub mod compat;
+mod consistency;
+mod contact;
+#[cfg(feature = "db")]
+pub mod db;
+mod descriptor;
+mod diff;
+#[cfg(feature = "dns")]
+pub mod dns;
+mod draft;
+mod endpoint;
+mod enode;
+mod enr_ref;
+pub mod envelope;
 mod error;
-mod keys;
-mod node_id;
-
-use bytes::{Bytes, BytesMut};
-use log::debug;
+#[cfg(feature = "eth2")]
+pub mod eth2;
+mod field;
+pub mod fields;
+mod flags;
+#[cfg(feature = "eth2")]
+mod fork_id;
+#[cfg(feature = "fuzz")]
+pub mod fuzz_utils;
+mod genesis;
+mod history;
+#[cfg(feature = "identity")]
+pub mod identity;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod macros;
+mod multiaddr;
+mod normalize;
+pub mod policy;
+mod pretty;
+#[cfg(feature = "proto")]
+pub mod proto;
+mod raw_record;
+pub mod records;
+mod recovery;
+mod reserved;
+mod scheme;
+mod scope;
+mod seq_policy;
+#[cfg(feature = "serde")]
+pub mod serde_records;
+mod signer;
+mod similarity;
+mod storage;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "eth2")]
+mod subnets;
+mod summary;
+#[cfg(feature = "tokio")]
+pub mod tasks;
+mod template;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+mod trace;
+mod transaction;
+mod update;
+pub mod vanity;
+#[cfg(feature = "verification-cache")]
+pub mod verification_cache;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use bytes::{BufMut, Bytes, BytesMut};
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use std::{
+    cell::RefCell,
     collections::BTreeMap,
+    convert::TryFrom,
     hash::{Hash, Hasher},
     net::{SocketAddrV4, SocketAddrV6},
+    sync::{Arc, OnceLock},
 };
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
@@ -198,29 +334,90 @@ use std::{
     str::FromStr,
 };
 
-pub use error::EnrError;
-
+pub use alias::AliasTable;
+pub use allowlist::{DisallowedKeyAction, Filtered, KeyAllowlist, StrippedRecord};
+pub use attestation::ATTESTATION_KEY;
+pub use changes::{FieldChanges, PreviousValues};
+pub use compaction::{Compaction, CompactionReport};
+pub use consistency::ConsistencyPolicy;
+pub use contact::{ContactError, NodeContact};
+pub use descriptor::{DescriptorParseError, NodeDescriptor};
+pub use diff::EnrDiff;
+pub use draft::DraftEnr;
+pub use endpoint::{Endpoint, Transport, UnknownTransport};
+pub use enode::EnodeError;
+pub use enr_ref::EnrRef;
+pub use error::{ConflictingRecord, DecodeContext, EnrError, EnrParseError, WrongNode};
+pub use field::FieldValue;
+pub use fields::TypedEnrField;
+pub use flags::{RecordFlags, FLAGS_KEY};
+#[cfg(feature = "eth2")]
+pub use fork_id::{EnrForkId, ForkCompatibility, ETH2_KEY};
+pub use genesis::{Genesis, GENESIS_KEY};
+pub use history::{EnrHistory, HistoryEntry, Observation};
+
+#[cfg(feature = "bls")]
+pub use enr_core::blst;
 #[cfg(feature = "k256")]
-pub use keys::k256;
+pub use enr_core::k256;
 #[cfg(feature = "rust-secp256k1")]
-pub use keys::secp256k1;
+pub use enr_core::secp256k1;
+#[cfg(feature = "zeroize")]
+pub use enr_core::secrecy;
+#[cfg(all(feature = "k256", feature = "rust-secp256k1"))]
+pub use enr_core::CombinedSecpKey;
+#[cfg(feature = "test-determinism")]
+pub use enr_core::{clear_injected_entropy, inject_entropy};
 #[cfg(all(feature = "ed25519", feature = "k256"))]
-pub use keys::{ed25519_dalek, CombinedKey, CombinedPublicKey};
+pub use enr_core::{ed25519_dalek, CombinedKey, CombinedPublicKey};
+#[cfg(feature = "eth2")]
+pub use ssz;
 
-pub use keys::{EnrKey, EnrKeyUnambiguous, EnrPublicKey};
-pub use node_id::NodeId;
+use enr_core::codec::{
+    self, put_rlp_header, rlp_bytes_len, rlp_header_len, MAX_ENR_SIZE, RLP_LIST_OFFSET,
+    RLP_STRING_OFFSET,
+};
+pub use enr_core::{
+    EnrKey, EnrKeyUnambiguous, EnrPublicKey, KeyScheme, NodeId, NodeIdHasher, SigningError,
+};
+pub use normalize::{Normalization, Normalized};
+pub use pretty::PrettyEnr;
+#[cfg(feature = "k256")]
+pub use raw_record::RecoveryMode;
+pub use raw_record::{RawRecord, UnverifiedEnr};
+pub use recovery::SignatureMode;
+pub use reserved::{is_reserved_key, reserved_keys, KeyValidator, ReservedKey};
+pub use scheme::{IdentityScheme, V4Scheme};
+pub use seq_policy::SeqPolicy;
+#[cfg(feature = "async-signing")]
+pub use signer::AsyncEnrSigner;
+pub use signer::EnrSigner;
+pub use similarity::{SimilarityConfig, SimilarityHash};
 use std::marker::PhantomData;
+use storage::{Key, Value};
+#[cfg(feature = "eth2")]
+pub use subnets::{
+    AttestationSubnets, SubnetBitfield, SyncCommitteeSubnets, ATTNETS_KEY, SYNCNETS_KEY,
+};
+use subtle::ConstantTimeEq;
+pub use summary::SigningSummary;
+pub use template::{Placeholder, RecordTemplate, TemplateParseError, TemplateProviders};
+pub use transaction::Transaction;
+use update::UpdateGuard;
 
-/// The "key" in an ENR record can be arbitrary bytes.
-type Key = Vec<u8>;
 type PreviousRlpEncodedValues = Vec<Option<Bytes>>;
+/// The signature, sequence number and content of a decoded record.
+type DecodedRecordFields = (Vec<u8>, u64, BTreeMap<Key, Value>);
 
-const MAX_ENR_SIZE: usize = 300;
+thread_local! {
+    /// Scratch space for encoding record content when signing and verifying.
+    static RLP_SCRATCH_BUFFER: RefCell<BytesMut> = RefCell::new(BytesMut::with_capacity(MAX_ENR_SIZE));
+}
 
 /// The ENR, allowing for arbitrary signing algorithms.
 ///
 /// This struct will always have a valid signature, known public key type, sequence number and `NodeId`. All other parameters are variable/optional.
-pub struct Enr<K: EnrKey> {
+pub struct Enr<K: EnrKey, S: IdentityScheme = V4Scheme> {
     /// ENR sequence number.
     seq: u64,
 
@@ -230,13 +427,33 @@ pub struct Enr<K: EnrKey> {
     /// Key-value contents of the ENR. A BTreeMap is used to get the keys in sorted order, which is
     /// important for verifying the signature of the ENR.
     /// Everything is stored as raw RLP bytes.
-    content: BTreeMap<Key, Bytes>,
+    content: BTreeMap<Key, Value>,
 
     /// The signature of the ENR record, stored as bytes.
     signature: Vec<u8>,
 
-    /// Marker to pin the generic.
-    phantom: PhantomData<K>,
+    /// The policy enforced on the ip and port fields when the record is modified. This is local
+    /// configuration and is not part of the encoded record.
+    consistency_policy: ConsistencyPolicy,
+
+    /// The policy choosing the sequence number of the modified record. This is local
+    /// configuration and is not part of the encoded record.
+    seq_policy: SeqPolicy,
+
+    /// Aliases that getters fall back to when a key is absent. This is local configuration and is
+    /// not part of the encoded record.
+    alias_table: Option<Arc<AliasTable>>,
+
+    /// The RLP encoding of the record, computed on first use and cleared when the record is
+    /// modified.
+    encoded: OnceLock<Bytes>,
+
+    /// The keccak256 hash of the RLP encoding, computed on first use and cleared along with the
+    /// encoding.
+    content_hash: OnceLock<[u8; 32]>,
+
+    /// Marker to pin the generics.
+    phantom: PhantomData<(K, fn() -> S)>,
 }
 
 impl<K: EnrKey> Enr<K> {
@@ -252,6 +469,123 @@ impl<K: EnrKey> Enr<K> {
         Self::builder().build(signing_key)
     }
 
+    /// Applies a batch of changes like [`Enr::apply_changes`], signing the record with `signer`.
+    ///
+    /// Fails with [`EnrError::SigningError`] if the signer fails or returns an invalid
+    /// signature. Reverts the whole ENR record on error.
+    pub fn apply_changes_with_signer(
+        &mut self,
+        changes: &FieldChanges,
+        signer: &impl EnrSigner<PublicKey = K::PublicKey>,
+    ) -> Result<PreviousValues, EnrError> {
+        let public_key = signer.public_key();
+        let mut update = UpdateGuard::new(self);
+        let previous = update.transact(&public_key, |tx| changes.apply(tx))?;
+        update.prepare(&public_key)?;
+        let content = update.rlp_content();
+        let signature = signer::check_signature(&public_key, &content, signer.sign_v4(&content))?;
+        update.complete(signature)?;
+        Ok(previous)
+    }
+
+    /// Applies a batch of changes like [`Enr::apply_changes`], signing the record with an
+    /// asynchronous `signer`.
+    ///
+    /// Fails with [`EnrError::SigningError`] if the signer fails or returns an invalid
+    /// signature. Reverts the whole ENR record on error, including when the future is dropped
+    /// before completion.
+    #[cfg(feature = "async-signing")]
+    pub async fn apply_changes_async(
+        &mut self,
+        changes: &FieldChanges,
+        signer: &impl AsyncEnrSigner<PublicKey = K::PublicKey>,
+    ) -> Result<PreviousValues, EnrError> {
+        let public_key = signer.public_key();
+        let mut update = UpdateGuard::new(self);
+        let previous = update.transact(&public_key, |tx| changes.apply(tx))?;
+        update.prepare(&public_key)?;
+        let content = update.rlp_content();
+        let signature = signer.sign_v4(&content).await;
+        let signature = signer::check_signature(&public_key, &content, signature)?;
+        update.complete(signature)?;
+        Ok(previous)
+    }
+
+    /// The legacy `enode://<public key>@<ip>:<tcp port>?discport=<udp port>` URL of the record.
+    ///
+    /// The record must have a `secp256k1` public key and a TCP socket, preferring IPv4 over IPv6.
+    /// The `discport` query is added if the UDP port of the same IP version differs from the TCP
+    /// port.
+    pub fn to_enode_url(&self) -> Result<String, EnodeError> {
+        let public_key = self.public_key();
+        if public_key.enr_key() != KeyScheme::Secp256k1.enr_key().as_bytes() {
+            return Err(EnodeError::UnsupportedKey);
+        }
+        let public_key = <[u8; 64]>::try_from(public_key.encode_uncompressed().as_ref())
+            .map_err(|_| EnodeError::UnsupportedKey)?;
+        let (socket, udp) = match (self.tcp4_socket(), self.tcp6_socket()) {
+            (Some(socket), _) => (SocketAddr::V4(socket), self.udp4()),
+            (None, Some(socket)) => (SocketAddr::V6(socket), self.udp6()),
+            (None, None) if self.ip4().is_none() && self.ip6().is_none() => {
+                return Err(EnodeError::MissingIp)
+            }
+            (None, None) => return Err(EnodeError::MissingTcpPort),
+        };
+        let enode = enode::Enode {
+            public_key,
+            socket,
+            discport: udp,
+        };
+        Ok(enode.to_string())
+    }
+
+    /// Builds a record with sequence number `seq` from a legacy `enode://` URL, signed with
+    /// `key`.
+    ///
+    /// The record gets the IP address and TCP port of the URL, and its `discport` as UDP port,
+    /// which defaults to the TCP port. As a URL carries no signature, the record can only be
+    /// created by the node itself: the public key of the URL must be the public key of `key`.
+    pub fn try_from_enode_url(url: &str, seq: u64, key: &K) -> Result<Self, EnodeError> {
+        let enode: enode::Enode = url.parse()?;
+        if key.public().encode_uncompressed().as_ref() != enode.public_key {
+            return Err(EnodeError::PublicKeyMismatch);
+        }
+        let udp = SocketAddr::new(enode.socket.ip(), enode.udp_port());
+        Self::builder()
+            .seq(seq)
+            .socket(Transport::Tcp, enode.socket)
+            .socket(Transport::Udp, udp)
+            .build(key)
+            .map_err(EnodeError::Build)
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Enr<K, S> {
+    /// Get a [`builder::Builder`] for the identity scheme `S`.
+    pub fn builder_with_scheme() -> builder::Builder<K, S> {
+        builder::Builder::default()
+    }
+
+    /// Decodes a record from its RLP encoding. Unlike [`rlp::decode`], if the public key or
+    /// signature of a well-formed record cannot be verified, the returned error lists the
+    /// identity fields found in the record.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "enr_decode", skip_all)
+    )]
+    pub fn decode_with_context(bytes: &[u8]) -> Result<Self, EnrParseError> {
+        let rlp = Rlp::new(bytes);
+        let (signature, seq, content) = decode_record_fields(&rlp, false)
+            .inspect_err(|cause| trace::decode_failed(bytes.len(), cause))?;
+        Self::from_record_fields(signature, seq, content).map_err(|error| {
+            // the fields are only described on failure, decoding the well-formed record again
+            let context = decode_record_fields(&rlp, false)
+                .ok()
+                .map(|(_, _, content)| DecodeContext::new(&content));
+            EnrParseError::Rlp { error, context }
+        })
+    }
+
     // getters //
 
     /// The `NodeId` for the record.
@@ -266,6 +600,38 @@ impl<K: EnrKey> Enr<K> {
         self.seq
     }
 
+    /// A 64-bit fingerprint of the node id and sequence number, usable as a pre-hashed key in
+    /// peer tables. This is also what the [`Hash`] implementation feeds to the hasher.
+    ///
+    /// Two versions of a record signed with different content under the same sequence number
+    /// share a fingerprint, as do unrelated records with a probability of about 2^-64 per pair,
+    /// since node ids are uniformly distributed. Equality still compares the signature, so such
+    /// collisions only cost a comparison in hash based collections.
+    #[must_use]
+    pub fn fingerprint64(&self) -> u64 {
+        let mut prefix = [0_u8; 8];
+        prefix.copy_from_slice(&self.node_id.raw()[..8]);
+        u64::from_le_bytes(prefix) ^ self.seq.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    /// The [`ConsistencyPolicy`] enforced when modifying the record.
+    #[must_use]
+    pub const fn consistency_policy(&self) -> ConsistencyPolicy {
+        self.consistency_policy
+    }
+
+    /// The [`SeqPolicy`] applied when modifying the record.
+    #[must_use]
+    pub const fn seq_policy(&self) -> SeqPolicy {
+        self.seq_policy
+    }
+
+    /// The aliases getters fall back to when a key is absent, if any.
+    #[must_use]
+    pub fn alias_table(&self) -> Option<&AliasTable> {
+        self.alias_table.as_deref()
+    }
+
     /// Reads a custom key from the record if it exists, decoded as data.
     #[allow(clippy::missing_panics_doc)]
     pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
@@ -285,14 +651,119 @@ impl<K: EnrKey> Enr<K> {
         self.get_raw_rlp(key).map(|rlp_data| rlp::decode(rlp_data))
     }
 
+    /// Reads a custom key from the record if it exists, decoded as a UTF-8 string.
+    ///
+    /// The value must be an RLP byte string holding valid UTF-8. Values written with
+    /// [`Enr::insert_utf8`] follow this encoding.
+    pub fn get_utf8(&self, key: impl AsRef<[u8]>) -> Option<Result<&str, DecoderError>> {
+        self.get_raw_rlp(key).map(|rlp_data| {
+            let rlp = Rlp::new(rlp_data);
+            if !rlp.is_data() {
+                return Err(DecoderError::RlpExpectedToBeData);
+            }
+            std::str::from_utf8(rlp.data()?).map_err(|_| DecoderError::Custom("Invalid UTF-8"))
+        })
+    }
+
+    /// Reads a custom key from the record if it exists, decoded as an unsigned integer.
+    ///
+    /// The value must be the canonical RLP encoding of an integer of at most 8 bytes: a big-endian
+    /// byte string without leading zeros, with zero encoded as the empty string. Values written
+    /// with [`Enr::insert_uint`] follow this encoding.
+    pub fn get_uint(&self, key: impl AsRef<[u8]>) -> Option<Result<u64, DecoderError>> {
+        self.get_raw_rlp(key).map(rlp::decode)
+    }
+
+    /// Reads a field from the record if it exists, decoded as the value type of `F`.
+    ///
+    /// See the [`fields`] module for the available fields.
+    pub fn get_field<F: TypedEnrField>(&self) -> Option<Result<F::Value, DecoderError>> {
+        self.get_raw_rlp(F::KEY).map(F::decode)
+    }
+
+    /// The public key stored in the field of `scheme`, without its RLP encoding.
+    #[must_use]
+    pub fn raw_public_key_field(&self, scheme: KeyScheme) -> Option<&[u8]> {
+        self.content
+            .get(scheme.enr_key().as_bytes())
+            .and_then(|value| Rlp::new(value).data().ok())
+    }
+
+    /// Whether the record holds a public key field of `scheme`.
+    #[must_use]
+    pub fn has_key_field(&self, scheme: KeyScheme) -> bool {
+        self.content.contains_key(scheme.enr_key().as_bytes())
+    }
+
     /// Reads a custom key from the record if it exists as raw RLP bytes.
+    ///
+    /// If the key is absent, its aliases in the record's [`AliasTable`] are read instead.
     pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
-        self.content.get(key.as_ref()).map(AsRef::as_ref)
+        let key = key.as_ref();
+        self.content
+            .get(key)
+            .or_else(|| {
+                self.alias_table
+                    .as_ref()?
+                    .aliases_of(key)
+                    .find_map(|alias| self.content.get(alias))
+            })
+            .map(AsRef::as_ref)
     }
 
     /// Returns an iterator over all key/value pairs in the ENR.
-    pub fn iter(&self) -> impl Iterator<Item = (&Key, &[u8])> {
-        self.content.iter().map(|(k, v)| (k, v.as_ref()))
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.content.iter().map(|(k, v)| (k.as_slice(), v.as_ref()))
+    }
+
+    /// Returns an iterator over all fields in the ENR, decoded into their typed representation.
+    pub fn iter_decoded(&self) -> impl Iterator<Item = FieldValue<'_>> {
+        self.content
+            .iter()
+            .map(|(key, value)| FieldValue::decode(key, value))
+    }
+
+    /// Returns an iterator over all keys in the ENR, in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.content.keys().map(Key::as_slice)
+    }
+
+    /// Returns an iterator over the raw RLP values in the ENR, in the order of their keys.
+    pub fn values(&self) -> impl Iterator<Item = &[u8]> {
+        self.content.values().map(AsRef::as_ref)
+    }
+
+    /// Returns whether the ENR contains the given key.
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.content.contains_key(key.as_ref())
+    }
+
+    /// The number of key/value pairs in the ENR, including the mandatory `id` and public key.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Returns whether the ENR contains no key/value pairs.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// The number of key/value pairs in the ENR, excluding the mandatory `id` and public key.
+    #[must_use]
+    pub fn optional_len(&self) -> usize {
+        let public_key = self.public_key().enr_key();
+        self.keys()
+            .filter(|key| *key != b"id" && *key != public_key.as_slice())
+            .count()
+    }
+
+    /// Returns whether the ENR contains no key/value pairs besides the mandatory `id` and public
+    /// key.
+    #[must_use]
+    pub fn has_only_mandatory_fields(&self) -> bool {
+        self.optional_len() == 0
     }
 
     /// Returns the IPv4 address of the ENR record if it is defined.
@@ -339,1376 +810,4532 @@ impl<K: EnrKey> Enr<K> {
     /// The TCP port of ENR record if it is defined.
     #[must_use]
     pub fn tcp4(&self) -> Option<u16> {
-        self.get_decodable("tcp").and_then(Result::ok)
+        self.port_v4(Transport::Tcp)
     }
 
     /// The IPv6-specific TCP port of ENR record if it is defined.
     #[must_use]
     pub fn tcp6(&self) -> Option<u16> {
-        self.get_decodable("tcp6").and_then(Result::ok)
+        self.port_v6(Transport::Tcp)
+    }
+
+    /// The QUIC port of ENR record if it is defined.
+    #[must_use]
+    pub fn quic4(&self) -> Option<u16> {
+        self.port_v4(Transport::Quic)
+    }
+
+    /// The IPv6-specific QUIC port of ENR record if it is defined.
+    #[must_use]
+    pub fn quic6(&self) -> Option<u16> {
+        self.port_v6(Transport::Quic)
     }
 
     /// The UDP port of ENR record if it is defined.
     #[must_use]
     pub fn udp4(&self) -> Option<u16> {
-        self.get_decodable("udp").and_then(Result::ok)
+        self.port_v4(Transport::Udp)
+    }
+
+    /// The [`RecordFlags`] of the ENR record if they are defined.
+    #[must_use]
+    pub fn flags(&self) -> Option<RecordFlags> {
+        self.get_decodable(FLAGS_KEY)
+            .and_then(Result::ok)
+            .map(RecordFlags::from_bits_retain)
+    }
+
+    /// The fork id of the record, decoded from its `eth2` field if it is defined.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn eth2_fork_id(&self) -> Option<Result<EnrForkId, DecoderError>> {
+        self.get_decodable(ETH2_KEY)
+    }
+
+    /// The compatibility of the fork of the peer with record `other` with the fork of this
+    /// record, comparing the fork ids of their `eth2` fields.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn is_compatible_fork<K2: EnrKey, S2: IdentityScheme>(
+        &self,
+        other: &Enr<K2, S2>,
+    ) -> ForkCompatibility {
+        match (self.eth2_fork_id(), other.eth2_fork_id()) {
+            (Some(Ok(local)), Some(Ok(remote))) => local.compatibility(&remote),
+            _ => ForkCompatibility::MissingForkId,
+        }
+    }
+
+    /// The attestation subnets of the record, decoded from its `attnets` field if it is defined.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn attestation_subnets(&self) -> Option<Result<AttestationSubnets, DecoderError>> {
+        self.get_decodable(ATTNETS_KEY)
+    }
+
+    /// The sync committee subnets of the record, decoded from its `syncnets` field if it is
+    /// defined.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn sync_committee_subnets(&self) -> Option<Result<SyncCommitteeSubnets, DecoderError>> {
+        self.get_decodable(SYNCNETS_KEY)
+    }
+
+    /// Whether the record subscribes to the attestation subnet `subnet_id`. A missing or
+    /// malformed `attnets` field subscribes to no subnet.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn is_subscribed_to_attnet(&self, subnet_id: usize) -> bool {
+        matches!(self.attestation_subnets(), Some(Ok(subnets)) if subnets.get(subnet_id))
+    }
+
+    /// Whether the record subscribes to the sync committee subnet `subnet_id`. A missing or
+    /// malformed `syncnets` field subscribes to no subnet.
+    #[cfg(feature = "eth2")]
+    #[must_use]
+    pub fn is_subscribed_to_syncnet(&self, subnet_id: usize) -> bool {
+        matches!(self.sync_committee_subnets(), Some(Ok(subnets)) if subnets.get(subnet_id))
     }
 
     /// The IPv6-specific UDP port of ENR record if it is defined.
     #[must_use]
     pub fn udp6(&self) -> Option<u16> {
-        self.get_decodable("udp6").and_then(Result::ok)
+        self.port_v6(Transport::Udp)
     }
 
     /// Provides a socket (based on the UDP port), if the IPv4 and UDP fields are specified.
     #[must_use]
     pub fn udp4_socket(&self) -> Option<SocketAddrV4> {
-        if let Some(ip) = self.ip4() {
-            if let Some(udp) = self.udp4() {
-                return Some(SocketAddrV4::new(ip, udp));
-            }
-        }
-        None
+        self.socket_v4(Transport::Udp)
     }
 
     /// Provides a socket (based on the UDP port), if the IPv6 and UDP fields are specified.
     #[must_use]
     pub fn udp6_socket(&self) -> Option<SocketAddrV6> {
-        if let Some(ip6) = self.ip6() {
-            if let Some(udp6) = self.udp6() {
-                return Some(SocketAddrV6::new(ip6, udp6, 0, 0));
-            }
-        }
-        None
+        self.socket_v6(Transport::Udp)
     }
 
     /// Provides a socket (based on the TCP port), if the IP and TCP fields are specified.
     #[must_use]
     pub fn tcp4_socket(&self) -> Option<SocketAddrV4> {
-        if let Some(ip) = self.ip4() {
-            if let Some(tcp) = self.tcp4() {
-                return Some(SocketAddrV4::new(ip, tcp));
-            }
-        }
-        None
+        self.socket_v4(Transport::Tcp)
     }
 
     /// Provides a socket (based on the TCP port), if the IPv6 and TCP6 fields are specified.
     #[must_use]
     pub fn tcp6_socket(&self) -> Option<SocketAddrV6> {
-        if let Some(ip6) = self.ip6() {
-            if let Some(tcp6) = self.tcp6() {
-                return Some(SocketAddrV6::new(ip6, tcp6, 0, 0));
-            }
-        }
-        None
+        self.socket_v6(Transport::Tcp)
     }
 
-    /// The signature of the ENR record.
+    /// Provides a socket (based on the QUIC port), if the IP and QUIC fields are specified.
     #[must_use]
-    pub fn signature(&self) -> &[u8] {
-        &self.signature
+    pub fn quic4_socket(&self) -> Option<SocketAddrV4> {
+        self.socket_v4(Transport::Quic)
     }
 
-    /// Returns the public key of the ENR record.
-    /// # Panics
-    ///
-    /// Will panic if the public key is not supported.
+    /// Provides a socket (based on the QUIC port), if the IPv6 and QUIC6 fields are specified.
     #[must_use]
-    pub fn public_key(&self) -> K::PublicKey {
-        K::enr_to_public(&self.content).expect("ENR's can only be created with supported keys")
+    pub fn quic6_socket(&self) -> Option<SocketAddrV6> {
+        self.socket_v6(Transport::Quic)
     }
 
-    /// Verify the signature of the ENR record.
+    /// The IPv4 port of the given transport, if it is defined.
     #[must_use]
-    pub fn verify(&self) -> bool {
-        let pubkey = self.public_key();
-        match self.id() {
-            Some(ref id) if id == "v4" => pubkey.verify_v4(&self.rlp_content(), &self.signature),
-            // unsupported identity schemes
-            _ => false,
+    pub fn port_v4(&self, transport: Transport) -> Option<u16> {
+        self.get_decodable(transport.port_key_v4())
+            .and_then(Result::ok)
+    }
+
+    /// The IPv6 port of the given transport, if it is defined.
+    #[must_use]
+    pub fn port_v6(&self, transport: Transport) -> Option<u16> {
+        self.get_decodable(transport.port_key_v6())
+            .and_then(Result::ok)
+    }
+
+    /// Provides the IPv4 endpoint of the given transport, if the IPv4 and port fields are
+    /// specified.
+    #[must_use]
+    pub fn endpoint_v4(&self, transport: Transport) -> Option<Endpoint> {
+        let socket = SocketAddrV4::new(self.ip4()?, self.port_v4(transport)?);
+        Some(Endpoint::new(transport, socket.into()))
+    }
+
+    /// Provides the IPv6 endpoint of the given transport, if the IPv6 and port fields are
+    /// specified.
+    #[must_use]
+    pub fn endpoint_v6(&self, transport: Transport) -> Option<Endpoint> {
+        let socket = SocketAddrV6::new(self.ip6()?, self.port_v6(transport)?, 0, 0);
+        Some(Endpoint::new(transport, socket.into()))
+    }
+
+    /// Provides the IPv4 socket of the given transport, if the IPv4 and port fields are
+    /// specified.
+    #[must_use]
+    pub fn socket_v4(&self, transport: Transport) -> Option<SocketAddrV4> {
+        match self.endpoint_v4(transport)?.socket() {
+            SocketAddr::V4(socket) => Some(socket),
+            SocketAddr::V6(_) => None,
         }
     }
 
-    /// Compare if the content of 2 Enr's match.
+    /// Provides the IPv6 socket of the given transport, if the IPv6 and port fields are
+    /// specified.
     #[must_use]
-    pub fn compare_content(&self, other: &Self) -> bool {
-        self.rlp_content() == other.rlp_content()
+    pub fn socket_v6(&self, transport: Transport) -> Option<SocketAddrV6> {
+        match self.endpoint_v6(transport)?.socket() {
+            SocketAddr::V6(socket) => Some(socket),
+            SocketAddr::V4(_) => None,
+        }
     }
 
-    /// Provides the URL-safe base64 encoded "text" version of the ENR prefixed by "enr:".
+    /// Returns all complete endpoints (transports with both an IP address and port) advertised by
+    /// the record. IPv4 endpoints are listed before IPv6 endpoints.
     #[must_use]
-    pub fn to_base64(&self) -> String {
-        let hex = URL_SAFE_NO_PAD.encode(&rlp::encode(self));
-        format!("enr:{hex}")
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        let v4 = Transport::ALL
+            .iter()
+            .filter_map(|transport| self.endpoint_v4(*transport));
+        let v6 = Transport::ALL
+            .iter()
+            .filter_map(|transport| self.endpoint_v6(*transport));
+        v4.chain(v6).collect()
     }
 
-    /// Returns the current size of the ENR.
+    /// The IP addresses of the record, its `ip` address followed by its `ip6` address.
+    fn ips(&self) -> impl Iterator<Item = IpAddr> {
+        let ip4 = self.ip4().map(IpAddr::from);
+        let ip6 = self.ip6().map(IpAddr::from);
+        ip4.into_iter().chain(ip6)
+    }
+
+    /// Returns whether the `ip` or `ip6` address of the record is globally reachable, outside
+    /// the private, local, documentation, benchmarking, multicast and reserved ranges of RFC 6890.
     #[must_use]
-    pub fn size(&self) -> usize {
-        rlp::encode(self).len()
+    pub fn has_global_ip(&self) -> bool {
+        self.ips().any(scope::is_global)
     }
 
-    // Setters //
+    /// Returns whether the record has an IP address and all its IP addresses are loopback
+    /// addresses.
+    #[must_use]
+    pub fn is_loopback(&self) -> bool {
+        self.ips().next().is_some() && self.ips().all(scope::is_loopback)
+    }
 
-    /// Allows setting the sequence number to an arbitrary value.
-    pub fn set_seq(&mut self, seq: u64, key: &K) -> Result<(), EnrError> {
-        let prev_seq = self.seq;
-        self.seq = seq;
+    /// Returns whether the record has an IP address and all its IP addresses are addresses of
+    /// private networks (RFC 1918, RFC 6598 and RFC 4193).
+    #[must_use]
+    pub fn is_private(&self) -> bool {
+        self.ips().next().is_some() && self.ips().all(scope::is_private)
+    }
 
-        // sign the record
-        let prev_signature = match self.sign(key) {
-            Ok(signature) => signature,
-            Err(e) => {
-                self.seq = prev_seq;
-                return Err(e);
-            }
-        };
+    /// Provides the IPv4 socket of the given transport like [`Enr::socket_v4`], if its IP address
+    /// is globally reachable.
+    #[must_use]
+    pub fn socket_v4_global(&self, transport: Transport) -> Option<SocketAddrV4> {
+        self.socket_v4(transport)
+            .filter(|socket| scope::is_global((*socket.ip()).into()))
+    }
 
-        // check the size of the record
-        if self.size() > MAX_ENR_SIZE {
-            self.seq = prev_seq;
-            self.signature = prev_signature;
-            return Err(EnrError::ExceedsMaxSize);
-        }
+    /// Provides the IPv6 socket of the given transport like [`Enr::socket_v6`], if its IP address
+    /// is globally reachable.
+    #[must_use]
+    pub fn socket_v6_global(&self, transport: Transport) -> Option<SocketAddrV6> {
+        self.socket_v6(transport)
+            .filter(|socket| scope::is_global((*socket.ip()).into()))
+    }
 
-        // update the node id
-        self.node_id = NodeId::from(key.public());
+    /// Provides the UDP socket of [`Enr::udp4_socket`], if its IP address is globally reachable.
+    #[must_use]
+    pub fn udp4_socket_global(&self) -> Option<SocketAddrV4> {
+        self.socket_v4_global(Transport::Udp)
+    }
 
-        Ok(())
+    /// Provides the UDP socket of [`Enr::udp6_socket`], if its IP address is globally reachable.
+    #[must_use]
+    pub fn udp6_socket_global(&self) -> Option<SocketAddrV6> {
+        self.socket_v6_global(Transport::Udp)
     }
 
-    /// Adds or modifies a key/value to the ENR record. A `EnrKey` is required to re-sign the record once
-    /// modified.
-    ///
-    /// Returns the previous value as rlp encoded bytes in the record if it exists.
-    pub fn insert<T: Encodable>(
-        &mut self,
-        key: impl AsRef<[u8]>,
-        value: &T,
-        enr_key: &K,
-    ) -> Result<Option<Bytes>, EnrError> {
-        self.insert_raw_rlp(key, rlp::encode(value).freeze(), enr_key)
+    /// Provides the TCP socket of [`Enr::tcp4_socket`], if its IP address is globally reachable.
+    #[must_use]
+    pub fn tcp4_socket_global(&self) -> Option<SocketAddrV4> {
+        self.socket_v4_global(Transport::Tcp)
     }
 
-    /// Adds or modifies a key/value to the ENR record. A `EnrKey` is required to re-sign the record once
-    /// modified. The value here is interpreted as raw RLP data.
-    ///
-    /// Returns the previous value as rlp encoded bytes in the record if it exists.
-    pub fn insert_raw_rlp(
-        &mut self,
-        key: impl AsRef<[u8]>,
-        value: Bytes,
-        enr_key: &K,
-    ) -> Result<Option<Bytes>, EnrError> {
-        check_spec_reserved_keys(key.as_ref(), &value)?;
+    /// Provides the TCP socket of [`Enr::tcp6_socket`], if its IP address is globally reachable.
+    #[must_use]
+    pub fn tcp6_socket_global(&self) -> Option<SocketAddrV6> {
+        self.socket_v6_global(Transport::Tcp)
+    }
 
-        let previous_value = self.content.insert(key.as_ref().to_vec(), value);
-        // add the new public key
-        let public_key = enr_key.public();
-        let previous_key = self.content.insert(
-            public_key.enr_key(),
-            rlp::encode(&public_key.encode().as_ref()).freeze(),
-        );
+    /// Provides the QUIC socket of [`Enr::quic4_socket`], if its IP address is globally
+    /// reachable.
+    #[must_use]
+    pub fn quic4_socket_global(&self) -> Option<SocketAddrV4> {
+        self.socket_v4_global(Transport::Quic)
+    }
 
-        // check the size of the record
-        if self.size() > MAX_ENR_SIZE {
-            // if the size of the record is too large, revert and error
-            // revert the public key
-            if let Some(key) = previous_key {
-                self.content.insert(public_key.enr_key(), key);
-            } else {
-                self.content.remove(&public_key.enr_key());
-            }
-            // revert the content
-            if let Some(prev_value) = previous_value {
-                self.content.insert(key.as_ref().to_vec(), prev_value);
-            } else {
-                self.content.remove(key.as_ref());
-            }
-            return Err(EnrError::ExceedsMaxSize);
-        }
-        // increment the sequence number
-        self.seq = self
-            .seq
-            .checked_add(1)
-            .ok_or(EnrError::SequenceNumberTooHigh)?;
+    /// Provides the QUIC socket of [`Enr::quic6_socket`], if its IP address is globally
+    /// reachable.
+    #[must_use]
+    pub fn quic6_socket_global(&self) -> Option<SocketAddrV6> {
+        self.socket_v6_global(Transport::Quic)
+    }
 
-        // sign the record
-        self.sign(enr_key)?;
+    /// Returns every socket address the record can be reached at, with its transport. IPv4
+    /// addresses are listed before IPv6 addresses, each in the order of [`Transport::ALL`].
+    ///
+    /// Unlike [`Enr::endpoints`], this includes the addresses implied by EIP-778: an IPv6-specific
+    /// port key may be omitted if the port equals the IPv4 one, so a record with `ip6` but without
+    /// e.g. `udp6` is reachable over UDP at its `udp` port on its IPv6 address.
+    #[must_use]
+    pub fn socket_addresses(&self) -> Vec<(Transport, SocketAddr)> {
+        let v4 = Transport::ALL.iter().filter_map(|transport| {
+            self.socket_v4(*transport)
+                .map(|socket| (*transport, socket.into()))
+        });
+        let v6 = Transport::ALL.iter().filter_map(|transport| {
+            self.implied_socket_v6(*transport)
+                .map(|socket| (*transport, socket.into()))
+        });
+        v4.chain(v6).collect()
+    }
 
-        // update the node id
-        self.node_id = NodeId::from(enr_key.public());
+    /// Returns the socket addresses of [`Enr::socket_addresses`] over `transport`.
+    #[must_use]
+    pub fn socket_addresses_of(&self, transport: Transport) -> Vec<SocketAddr> {
+        let v4 = self.socket_v4(transport).map(SocketAddr::from);
+        let v6 = self.implied_socket_v6(transport).map(SocketAddr::from);
+        v4.into_iter().chain(v6).collect()
+    }
 
-        if self.size() > MAX_ENR_SIZE {
-            // in case the signature size changes, inform the user the size has exceeded the maximum
-            return Err(EnrError::ExceedsMaxSize);
-        }
+    /// Provides the IPv6 socket of the given transport, falling back to the IPv4 port if the
+    /// IPv6-specific port is not specified.
+    fn implied_socket_v6(&self, transport: Transport) -> Option<SocketAddrV6> {
+        self.socket_v6(transport).or_else(|| {
+            if self.get_raw_rlp(transport.port_key_v6()).is_some() {
+                return None;
+            }
+            let port = self.get_decodable(transport.port_key_v4())?.ok()?;
+            Some(SocketAddrV6::new(self.ip6()?, port, 0, 0))
+        })
+    }
 
-        Ok(previous_value)
+    /// The signature of the ENR record.
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
     }
 
-    /// Sets the `ip` field of the ENR. Returns any pre-existing IP address in the record.
-    pub fn set_ip(&mut self, ip: IpAddr, key: &K) -> Result<Option<IpAddr>, EnrError> {
-        match ip {
-            IpAddr::V4(addr) => {
-                let prev_value = self.insert("ip", &addr.octets().as_ref(), key)?;
-                if let Some(bytes) = prev_value {
-                    if bytes.len() == 4 {
-                        let mut v = [0_u8; 4];
-                        v.copy_from_slice(&bytes);
-                        return Ok(Some(IpAddr::V4(Ipv4Addr::from(v))));
-                    }
-                }
-            }
-            IpAddr::V6(addr) => {
-                let prev_value = self.insert("ip6", &addr.octets().as_ref(), key)?;
-                if let Some(bytes) = prev_value {
-                    if bytes.len() == 16 {
-                        let mut v = [0_u8; 16];
-                        v.copy_from_slice(&bytes);
-                        return Ok(Some(IpAddr::V6(Ipv6Addr::from(v))));
-                    }
-                }
-            }
-        }
-
-        Ok(None)
+    /// A copy of the key-value content of the record, with every value stored as raw RLP bytes.
+    #[cfg(feature = "raw")]
+    #[must_use]
+    pub fn content(&self) -> BTreeMap<Vec<u8>, Bytes> {
+        storage::export(&self.content)
     }
 
-    /// Sets the `udp` field of the ENR. Returns any pre-existing UDP port in the record.
-    pub fn set_udp4(&mut self, udp: u16, key: &K) -> Result<Option<u16>, EnrError> {
-        if let Some(udp_bytes) = self.insert("udp", &udp, key)? {
-            return Ok(rlp::decode(&udp_bytes).ok());
+    /// Assembles a record from its parts without any validation.
+    ///
+    /// Nothing is checked: the signature need not match the content, the content need not
+    /// hold an `id` or a public key, its values need not be valid RLP and `node_id` need not be
+    /// derived from anything. This is intended for implementing other identity schemes and for
+    /// crafting invalid records as test vectors.
+    ///
+    /// The invariants of [`Enr`] are not upheld by such records. [`Enr::verify`] returns `false`
+    /// if the record holds no supported public key, but methods that rely on the invariants, such
+    /// as [`Enr::public_key`], panic, and updating the record may fail. Decoding the encoded record
+    /// checks whether it is actually valid.
+    #[cfg(feature = "raw")]
+    #[must_use]
+    pub fn from_parts_unchecked(
+        seq: u64,
+        content: BTreeMap<Vec<u8>, Bytes>,
+        signature: Vec<u8>,
+        node_id: NodeId,
+    ) -> Self {
+        Self {
+            seq,
+            node_id,
+            content: storage::import(content),
+            signature,
+            consistency_policy: ConsistencyPolicy::default(),
+            seq_policy: SeqPolicy::default(),
+            alias_table: None,
+            encoded: OnceLock::new(),
+            content_hash: OnceLock::new(),
+            phantom: PhantomData,
         }
-        Ok(None)
     }
 
-    /// Sets the `udp6` field of the ENR. Returns any pre-existing UDP port in the record.
-    pub fn set_udp6(&mut self, udp: u16, key: &K) -> Result<Option<u16>, EnrError> {
-        if let Some(udp_bytes) = self.insert("udp6", &udp, key)? {
-            return Ok(rlp::decode(&udp_bytes).ok());
-        }
-        Ok(None)
+    /// Returns the public key of the ENR record.
+    /// # Panics
+    ///
+    /// Will panic if the public key is not supported.
+    #[must_use]
+    pub fn public_key(&self) -> K::PublicKey {
+        self.try_public_key()
+            .expect("ENR's can only be created with supported keys")
     }
 
-    /// Sets the `tcp` field of the ENR. Returns any pre-existing tcp port in the record.
-    pub fn set_tcp4(&mut self, tcp: u16, key: &K) -> Result<Option<u16>, EnrError> {
-        if let Some(tcp_bytes) = self.insert("tcp", &tcp, key)? {
-            return Ok(rlp::decode(&tcp_bytes).ok());
-        }
-        Ok(None)
+    /// Returns the public key of the ENR record, or an error if the key is not supported by `K`.
+    ///
+    /// Unlike [`Enr::public_key`] this never panics, for code bridging untrusted records into
+    /// other identity systems.
+    pub fn try_public_key(&self) -> Result<K::PublicKey, DecoderError> {
+        storage::enr_to_public::<K>(&self.content).or_else(|err| {
+            if self.signature_mode().stores_public_key() {
+                return Err(err);
+            }
+            self.recover_public_key().map_err(|_| err)
+        })
     }
 
-    /// Sets the `tcp6` field of the ENR. Returns any pre-existing tcp6 port in the record.
-    pub fn set_tcp6(&mut self, tcp: u16, key: &K) -> Result<Option<u16>, EnrError> {
-        if let Some(tcp_bytes) = self.insert("tcp6", &tcp, key)? {
-            return Ok(rlp::decode(&tcp_bytes).ok());
+    /// Recovers the public key from the recoverable signature of the record.
+    ///
+    /// Unlike [`Enr::public_key`], this ignores the public key field, and fails with
+    /// [`EnrError::UnrecoverableSignature`] unless the record is signed in a recoverable
+    /// [`SignatureMode`].
+    pub fn recover_public_key(&self) -> Result<K::PublicKey, EnrError> {
+        K::recover_v4(&self.rlp_content(), &self.signature).ok_or(EnrError::UnrecoverableSignature)
+    }
+
+    /// Converts the record to a record of the key type `T` without going through its encoding,
+    /// e.g. between the `k256` and `rust-secp256k1` backends, or to `CombinedSecpKey` when both
+    /// are enabled.
+    ///
+    /// The public key is decoded and the signature verified as with `T`, so this fails if `T`
+    /// does not support the key type of the record. The cached encoding and the local
+    /// configuration of the record are kept.
+    pub fn into_key_type<T: EnrKey>(self) -> Result<Enr<T, S>, DecoderError> {
+        let enr = Enr {
+            seq: self.seq,
+            node_id: self.node_id,
+            content: self.content,
+            signature: self.signature,
+            consistency_policy: self.consistency_policy,
+            seq_policy: self.seq_policy,
+            alias_table: self.alias_table,
+            encoded: self.encoded,
+            content_hash: self.content_hash,
+            phantom: PhantomData,
+        };
+        let public_key = enr.try_public_key()?;
+        if S::node_id::<T>(&public_key) != enr.node_id || !enr.verify() {
+            return Err(INVALID_SIGNATURE);
         }
-        Ok(None)
+        Ok(enr)
     }
 
-    /// Sets the IP and UDP port in a single update with a single increment in sequence number.
-    pub fn set_udp_socket(&mut self, socket: SocketAddr, key: &K) -> Result<(), EnrError> {
-        self.set_socket(socket, key, false)
+    /// The [`SignatureMode`] of the record, which is kept when the record is updated.
+    #[must_use]
+    pub fn signature_mode(&self) -> SignatureMode {
+        SignatureMode::of::<S>(&self.signature, &self.content)
     }
 
-    /// Sets the IP and TCP port in a single update with a single increment in sequence number.
-    pub fn set_tcp_socket(&mut self, socket: SocketAddr, key: &K) -> Result<(), EnrError> {
-        self.set_socket(socket, key, true)
+    /// Verify the signature of the ENR record.
+    #[must_use]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "enr_verify", skip_all, fields(seq = self.seq))
+    )]
+    pub fn verify(&self) -> bool {
+        RLP_SCRATCH_BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+            Ok(mut buffer) => self.verify_with_buffer(&mut buffer),
+            // the buffer is in use further up the stack, fall back to a fresh allocation
+            Err(_) => self.verify_with_buffer(&mut BytesMut::with_capacity(MAX_ENR_SIZE)),
+        })
     }
 
-    /// Helper function for `set_tcp_socket()` and `set_udp_socket`.
-    fn set_socket(&mut self, socket: SocketAddr, key: &K, is_tcp: bool) -> Result<(), EnrError> {
-        let (port_string, port_v6_string): (Key, Key) = if is_tcp {
-            ("tcp".into(), "tcp6".into())
-        } else {
-            ("udp".into(), "udp6".into())
-        };
+    /// Verify the signature of the ENR record like [`Enr::verify`], additionally requiring every
+    /// value to be canonically RLP encoded.
+    ///
+    /// Decoding only checks the outer encoding of values, so a signed record can hold values
+    /// with non-minimal encodings nested inside lists, which other implementations may reject or
+    /// re-encode differently. Records assembled from unchecked parts may hold any bytes.
+    #[must_use]
+    pub fn verify_strict(&self) -> bool {
+        self.content.values().all(|value| is_canonical_rlp(value)) && self.verify()
+    }
 
-        let (prev_ip, prev_port) = match socket.ip() {
-            IpAddr::V4(addr) => (
-                self.content.insert(
-                    "ip".into(),
-                    rlp::encode(&(&addr.octets() as &[u8])).freeze(),
-                ),
-                self.content
-                    .insert(port_string.clone(), rlp::encode(&socket.port()).freeze()),
-            ),
-            IpAddr::V6(addr) => (
-                self.content.insert(
-                    "ip6".into(),
-                    rlp::encode(&(&addr.octets() as &[u8])).freeze(),
-                ),
-                self.content
-                    .insert(port_v6_string.clone(), rlp::encode(&socket.port()).freeze()),
-            ),
+    /// Verify the signature of the ENR record, using `buffer` as scratch space for encoding the
+    /// record's content.
+    ///
+    /// Re-using the same buffer avoids an allocation per verification when verifying many records
+    /// in a loop. Any previous contents of the buffer are discarded.
+    #[must_use]
+    pub fn verify_with_buffer(&self, buffer: &mut BytesMut) -> bool {
+        let Ok(pubkey) = self.try_public_key() else {
+            return false;
         };
-
-        let public_key = key.public();
-        let previous_key = self.content.insert(
-            public_key.enr_key(),
-            rlp::encode(&public_key.encode().as_ref()).freeze(),
-        );
-
-        // check the size and revert on failure
-        if self.size() > MAX_ENR_SIZE {
-            // if the size of the record is too large, revert and error
-            // revert the public key
-            if let Some(key) = previous_key {
-                self.content.insert(public_key.enr_key(), key);
-            } else {
-                self.content.remove(&public_key.enr_key());
-            }
-            // revert the content
-            match socket.ip() {
-                IpAddr::V4(_) => {
-                    if let Some(ip) = prev_ip {
-                        self.content.insert("ip".into(), ip);
-                    } else {
-                        self.content.remove(b"ip".as_ref());
-                    }
-                    if let Some(udp) = prev_port {
-                        self.content.insert(port_string, udp);
-                    } else {
-                        self.content.remove(&port_string);
-                    }
-                }
-                IpAddr::V6(_) => {
-                    if let Some(ip) = prev_ip {
-                        self.content.insert("ip6".into(), ip);
-                    } else {
-                        self.content.remove(b"ip6".as_ref());
-                    }
-                    if let Some(udp) = prev_port {
-                        self.content.insert(port_v6_string, udp);
-                    } else {
-                        self.content.remove(&port_v6_string);
-                    }
-                }
+        match self.id() {
+            Some(ref id) if id == S::ID => {
+                self.write_rlp_content(buffer);
+                #[cfg(feature = "verification-cache")]
+                return verification_cache::verify_cached(
+                    pubkey.encode().as_ref(),
+                    &self.signature,
+                    buffer,
+                    || S::verify::<K>(&pubkey, buffer, &self.signature),
+                );
+                #[cfg(not(feature = "verification-cache"))]
+                S::verify::<K>(&pubkey, buffer, &self.signature)
             }
-            return Err(EnrError::ExceedsMaxSize);
+            // unsupported identity schemes
+            _ => false,
         }
+    }
 
-        // increment the sequence number
-        self.seq = self
-            .seq
-            .checked_add(1)
-            .ok_or(EnrError::SequenceNumberTooHigh)?;
+    /// The keccak256 digest of the record's content, as signed under the `v4` identity scheme.
+    ///
+    /// This is the message an external `secp256k1` signer is asked to sign for the current
+    /// content of the record. Only `v4` records with a `secp256k1` public key are supported:
+    /// `ed25519` keys sign the content itself rather than its digest, so records with other keys
+    /// or of other identity schemes fail with [`EnrError::UnsupportedIdentityScheme`].
+    pub fn signing_digest(&self) -> Result<[u8; 32], EnrError> {
+        let secp256k1 = self
+            .try_public_key()
+            .is_ok_and(|public_key| public_key.enr_key() == b"secp256k1");
+        match self.id() {
+            Some(ref id) if S::ID == V4Scheme::ID && id == V4Scheme::ID && secp256k1 => {
+                Ok(digest(&self.rlp_content()))
+            }
+            // other identity schemes and keys are unsupported
+            _ => Err(EnrError::UnsupportedIdentityScheme),
+        }
+    }
 
-        // sign the record
-        self.sign(key)?;
+    /// The [`Genesis`] of the record, if it designates immutable fields.
+    #[must_use]
+    pub fn genesis(&self) -> Option<Result<Genesis, DecoderError>> {
+        self.content
+            .get(GENESIS_KEY.as_bytes())
+            .map(|value| Genesis::decode(value))
+    }
 
-        // update the node id
-        self.node_id = NodeId::from(key.public());
+    /// Whether the designated immutable fields of the record still have their values at creation.
+    /// Records without a [`Genesis`] always pass.
+    #[must_use]
+    pub fn verify_genesis(&self) -> bool {
+        match self.genesis() {
+            None => true,
+            Some(Ok(genesis)) => genesis.matches(&self.content),
+            Some(Err(_)) => false,
+        }
+    }
 
-        Ok(())
+    /// Whether `other` is a version of the same record with the same [`Genesis`], so that none of
+    /// the designated immutable fields differ between the two.
+    #[must_use]
+    pub fn same_genesis(&self, other: &Self) -> bool {
+        self.node_id == other.node_id
+            && self.get_raw_rlp(GENESIS_KEY) == other.get_raw_rlp(GENESIS_KEY)
+            && self.verify_genesis()
+            && other.verify_genesis()
     }
 
-    /// Removes key/value mappings and adds or overwrites key/value mappings to the ENR record as
-    /// one sequence number update. An `EnrKey` is required to re-sign the record once modified.
-    /// Reverts whole ENR record on error.
+    /// Whether this record should replace `other`, a record of the same node, under the rules of
+    /// EIP-778.
     ///
-    /// Returns the previous values as rlp encoded bytes if they exist for the removed and added/
-    /// overwritten keys.
-    pub fn remove_insert<'a>(
-        &mut self,
-        remove_keys: impl Iterator<Item = impl AsRef<[u8]>>,
-        insert_key_values: impl Iterator<Item = (impl AsRef<[u8]>, &'a [u8])>,
-        enr_key: &K,
-    ) -> Result<(PreviousRlpEncodedValues, PreviousRlpEncodedValues), EnrError> {
-        let enr_backup = self.clone();
-
-        let mut removed = Vec::new();
-        for key in remove_keys {
-            removed.push(self.content.remove(key.as_ref()));
+    /// A record with a higher sequence number supersedes one with a lower sequence number. Records
+    /// with the same sequence number must have identical content; if their content differs, the
+    /// node signed conflicting records and [`Supersedes::Conflict`] is returned. Fails if `other`
+    /// is a record of a different node.
+    pub fn supersedes(&self, other: &Self) -> Result<Supersedes, WrongNode> {
+        if self.node_id != other.node_id {
+            return Err(WrongNode {
+                expected: self.node_id,
+                found: other.node_id,
+            });
         }
+        Ok(supersedes(self.seq, other.seq, || {
+            self.content == other.content
+        }))
+    }
 
-        // add the new public key
-        let public_key = enr_key.public();
-        self.content.insert(
-            public_key.enr_key(),
-            rlp::encode(&public_key.encode().as_ref()).freeze(),
-        );
+    /// A human-auditable [`SigningSummary`] of the record's content along with its
+    /// [`signing_digest`](Self::signing_digest).
+    pub fn signing_summary(&self) -> Result<SigningSummary, EnrError> {
+        let digest = self.signing_digest()?;
+        Ok(SigningSummary::new(digest, self.seq, &self.content))
+    }
 
-        let mut inserted = Vec::new();
-        for (key, value) in insert_key_values {
-            // currently only support "v4" identity schemes
-            if key.as_ref() == b"id" && value != b"v4" {
-                *self = enr_backup;
-                return Err(EnrError::UnsupportedIdentityScheme);
-            }
+    /// A locality-sensitive hash of the record's content, excluding the public key and IP address
+    /// fields. Similar records have hashes with a low hamming distance.
+    #[must_use]
+    pub fn similarity_hash(&self) -> SimilarityHash {
+        self.similarity_hash_with(&SimilarityConfig::default())
+    }
 
-            let value = rlp::encode(&(value)).freeze();
-            // Prevent inserting invalid RLP integers
-            if is_keyof_u16(key.as_ref()) {
-                rlp::decode::<u16>(&value)
-                    .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-            }
+    /// A locality-sensitive hash of the record's content, excluding the fields specified by
+    /// `config`.
+    #[must_use]
+    pub fn similarity_hash_with(&self, config: &SimilarityConfig) -> SimilarityHash {
+        SimilarityHash::new(&self.content, config)
+    }
+
+    /// A compact [`NodeDescriptor`] of the record, containing a node id prefix, the key scheme,
+    /// the primary endpoint and the `eth2` fork digest if present.
+    #[must_use]
+    pub fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new(self)
+    }
 
-            inserted.push(self.content.insert(key.as_ref().to_vec(), value));
+    /// Proves control of the record's key by signing an external `challenge` together with the
+    /// record's node id and sequence number.
+    ///
+    /// The proof can be checked with [`Enr::verify_ownership_proof`] by anyone holding the record,
+    /// without a handshake. Fails with [`EnrError::PublicKeyMismatch`] if `key` is not the
+    /// record's key.
+    pub fn ownership_proof(&self, challenge: &[u8], key: &K) -> Result<Vec<u8>, EnrError> {
+        if key.public().encode().as_ref() != self.public_key().encode().as_ref() {
+            return Err(EnrError::PublicKeyMismatch);
         }
+        key.sign_message(&self.ownership_message(challenge))
+            .map_err(|_| EnrError::SigningError)
+    }
 
-        // increment the sequence number
-        self.seq = self
-            .seq
-            .checked_add(1)
-            .ok_or(EnrError::SequenceNumberTooHigh)?;
+    /// Verifies a proof created by [`Enr::ownership_proof`] for this record and `challenge`.
+    ///
+    /// Proofs are bound to the sequence number, so a proof for an older version of the record
+    /// does not verify.
+    #[must_use]
+    pub fn verify_ownership_proof(&self, challenge: &[u8], proof: &[u8]) -> bool {
+        self.public_key()
+            .verify_message(&self.ownership_message(challenge), proof)
+    }
 
-        // sign the record
-        self.sign(enr_key)?;
+    /// Countersigns the record with `secondary_key`, such as the key of a relay republishing it,
+    /// and stores the attestation under [`ATTESTATION_KEY`].
+    ///
+    /// As the attestation is part of the signed content, the record is re-signed with `key` and
+    /// its sequence number advanced. Fails with [`EnrError::PublicKeyMismatch`] if `key` is not
+    /// the record's key.
+    ///
+    /// The attestation covers the node id and every other field of the record, but not the
+    /// sequence number, so it verifies until another field changes. Attesting again replaces it.
+    pub fn attest<A: EnrKey>(&mut self, secondary_key: &A, key: &K) -> Result<(), EnrError> {
+        if key.public().encode().as_ref() != self.public_key().encode().as_ref() {
+            return Err(EnrError::PublicKeyMismatch);
+        }
+        let signature = secondary_key
+            .sign_message(&attestation::message(&self.node_id, &self.content))
+            .map_err(|_| EnrError::SigningError)?;
+        self.insert(ATTESTATION_KEY, &signature, key).map(|_| ())
+    }
 
-        // update the node id
-        self.node_id = NodeId::from(enr_key.public());
+    /// Verifies the attestation of the record created by [`Enr::attest`] with the secondary key
+    /// `public_key`. Returns false if the record carries no attestation.
+    #[must_use]
+    pub fn verify_attestation<P: EnrPublicKey>(&self, public_key: &P) -> bool {
+        self.get(ATTESTATION_KEY).is_some_and(|signature| {
+            public_key.verify_message(
+                &attestation::message(&self.node_id, &self.content),
+                signature,
+            )
+        })
+    }
 
-        if self.size() > MAX_ENR_SIZE {
-            // in case the signature size changes, inform the user the size has exceeded the
-            // maximum
-            *self = enr_backup;
-            return Err(EnrError::ExceedsMaxSize);
+    /// Asserts that the keys of the record's encoding are in the canonical order required by the
+    /// signature scheme: strictly ascending when compared byte-wise.
+    ///
+    /// Keys are compared as raw byte strings, not as text. The empty key sorts before every other
+    /// key, a key sorts directly before any longer key it is a prefix of, and bytes are compared
+    /// as unsigned values:
+    ///
+    /// ```
+    /// # #[cfg(feature = "k256")] {
+    /// use enr::{k256::ecdsa::SigningKey, Enr};
+    ///
+    /// let key = SigningKey::random(&mut rand::thread_rng());
+    /// let enr = Enr::builder()
+    ///     .add_value("tcp6", &1_u16)
+    ///     .add_value("tcp", &1_u16)
+    ///     .add_value([0xff_u8], &1_u16)
+    ///     .add_value("", &1_u16)
+    ///     .add_value("Z", &1_u16)
+    ///     .build(&key)
+    ///     .unwrap();
+    /// enr.assert_sorted();
+    ///
+    /// let keys: Vec<&[u8]> = enr.keys().collect();
+    /// assert_eq!(
+    ///     keys,
+    ///     [&b""[..], b"Z", b"id", b"secp256k1", b"tcp", b"tcp6", &[0xff]]
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If two consecutive keys of the encoding are not in canonical order, which indicates a bug in
+    /// the encoder.
+    #[track_caller]
+    pub fn assert_sorted(&self) {
+        let encoded = rlp::encode(self);
+        let keys: Vec<&[u8]> = Rlp::new(&encoded)
+            .iter()
+            .skip(2)
+            .step_by(2)
+            .map(|key| key.data().expect("All data is sanitized"))
+            .collect();
+        for pair in keys.windows(2) {
+            assert!(
+                pair[0] < pair[1],
+                "keys {} and {} are not in canonical order",
+                hex::encode(pair[0]),
+                hex::encode(pair[1])
+            );
         }
+    }
 
-        Ok((removed, inserted))
+    /// Compare if the content of 2 Enr's match.
+    #[must_use]
+    pub fn compare_content(&self, other: &Self) -> bool {
+        self.rlp_content() == other.rlp_content()
     }
 
-    /// Sets a new public key for the record.
-    pub fn set_public_key(&mut self, public_key: &K::PublicKey, key: &K) -> Result<(), EnrError> {
-        self.insert(&public_key.enr_key(), &public_key.encode().as_ref(), key)
-            .map(|_| {})
+    /// Compares the content of this record with `other`, listing the added, removed and modified
+    /// fields with their decoded values.
+    #[must_use]
+    pub fn diff<'a>(&'a self, other: &'a Self) -> EnrDiff<'a> {
+        EnrDiff::new((self.seq, other.seq), &self.content, &other.content)
     }
 
-    /// Returns wether the node can be reached over UDP or not.
+    /// Renders the record as a multi-line table of its decoded fields, for CLIs and logs. The
+    /// alternate form of the record's `Display` implementation, `{:#}`, renders the same table.
     #[must_use]
-    pub fn is_udp_reachable(&self) -> bool {
-        self.udp4_socket().is_some() || self.udp6_socket().is_some()
+    pub const fn display_pretty(&self) -> PrettyEnr<'_, K, S> {
+        PrettyEnr::new(self)
     }
 
-    /// Returns wether the node can be reached over TCP or not.
+    /// Provides the URL-safe base64 encoded "text" version of the ENR prefixed by "enr:".
     #[must_use]
-    pub fn is_tcp_reachable(&self) -> bool {
-        self.tcp4_socket().is_some() || self.tcp6_socket().is_some()
+    pub fn to_base64(&self) -> String {
+        let hex = URL_SAFE_NO_PAD.encode(self.encoded());
+        format!("enr:{hex}")
     }
 
-    // Private Functions //
+    /// The RLP encoding of the ENR. It is computed once and cached until the record is modified,
+    /// so encoding an unchanged record again does not allocate.
+    #[must_use]
+    pub fn encoded(&self) -> &Bytes {
+        self.encoded.get_or_init(|| {
+            let mut buffer = BytesMut::with_capacity(self.encoded_len());
+            self.encode_to_buf(&mut buffer);
+            buffer.freeze()
+        })
+    }
 
-    /// Encodes the ENR's content (signature(optional) + sequence number + ordered (key, value) pairs) into the stream.
-    fn append_rlp_content(&self, stream: &mut RlpStream, include_signature: bool) {
-        let item_count = usize::from(include_signature) + 1 + self.content.len() * 2;
-        stream.begin_list(item_count);
-        if include_signature {
-            stream.append(&self.signature);
-        }
-        stream.append(&self.seq);
-        for (k, v) in &self.content {
-            // Keys are bytes
-            stream.append(k);
-            // Values are raw RLP encoded data
-            stream.append_raw(v, 1);
-        }
+    /// The RLP encoding of the ENR, as sent on the wire. This is a cheap, reference counted copy
+    /// of the cached [`Enr::encoded`] bytes.
+    #[must_use]
+    pub fn to_rlp_bytes(&self) -> Bytes {
+        self.encoded().clone()
     }
 
-    /// Encodes the ENR's content (sequence number + ordered (key, value) pairs).
-    fn rlp_content(&self) -> BytesMut {
-        let mut stream = RlpStream::new_with_buffer(BytesMut::with_capacity(MAX_ENR_SIZE));
-        let include_signature = false;
-        self.append_rlp_content(&mut stream, include_signature);
-        stream.out()
+    /// The keccak256 hash of the RLP encoding of the record, including its signature.
+    ///
+    /// Unlike the node id, the hash identifies a single version of a record, so it can key dedup
+    /// caches and gossip seen-sets without storing the record. It is computed once and cached
+    /// along with [`Enr::encoded`].
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        *self.content_hash.get_or_init(|| digest(self.encoded()))
     }
 
-    /// Compute the enr's signature with the given key.
-    fn compute_signature(&self, signing_key: &K) -> Result<Vec<u8>, EnrError> {
-        match self.id() {
-            Some(ref id) if id == "v4" => signing_key
-                .sign_v4(&self.rlp_content())
-                .map_err(|_| EnrError::SigningError),
-            // other identity schemes are unsupported
-            _ => Err(EnrError::UnsupportedIdentityScheme),
-        }
+    /// The hash of the RLP encoding of the record, including its signature, with any [`Digest`],
+    /// e.g. `sha2::Sha256`. This is [`Enr::content_hash`] for [`Keccak256`], which is cached.
+    ///
+    /// [`Keccak256`]: sha3::Keccak256
+    #[must_use]
+    pub fn record_id<D: Digest>(&self) -> sha3::digest::Output<D> {
+        D::digest(self.encoded())
     }
 
-    /// Signs the ENR record based on the identity scheme. Currently only "v4" is supported.
-    /// The previous signature is returned.
-    fn sign(&mut self, key: &K) -> Result<Vec<u8>, EnrError> {
-        let new_signature = self.compute_signature(key)?;
-        Ok(std::mem::replace(&mut self.signature, new_signature))
+    /// Clears the cached encoding, to be called before modifying the record.
+    fn clear_encoded(&mut self) {
+        self.encoded.take();
+        self.content_hash.take();
     }
-}
 
-// traits //
+    /// Appends the RLP encoding of the ENR to `buffer`, copying the encoding cached by the
+    /// record, which is computed on first use.
+    pub fn encode_to(&self, buffer: &mut BytesMut) {
+        buffer.extend_from_slice(self.encoded());
+    }
 
-impl<K: EnrKey> Clone for Enr<K> {
-    fn clone(&self) -> Self {
-        Self {
-            seq: self.seq,
-            node_id: self.node_id,
-            content: self.content.clone(),
-            signature: self.signature.clone(),
-            phantom: self.phantom,
-        }
+    /// Writes the RLP encoding of the ENR into any [`BufMut`], such as a `Vec<u8>` or the
+    /// unfilled part of a packet buffer, without intermediate allocations.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `buffer` has less than [`Enr::encoded_len`] bytes of remaining capacity.
+    pub fn encode_to_buf(&self, buffer: &mut impl BufMut) {
+        codec::encode_fields(buffer, Some(&self.signature), self.seq, self.fields());
     }
-}
 
-impl<K: EnrKey> std::cmp::Eq for Enr<K> {}
+    /// Returns the length of the RLP encoding of the ENR, without encoding it.
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        let payload_len = self.rlp_payload_len();
+        rlp_header_len(payload_len) + payload_len
+    }
 
-impl<K: EnrKey> PartialEq for Enr<K> {
-    fn eq(&self, other: &Self) -> bool {
-        self.seq == other.seq && self.node_id == other.node_id && self.signature == other.signature
+    /// Returns an approximation of the heap and inline memory used by the record, in bytes.
+    ///
+    /// This accounts for the record itself, its signature and the keys and values of its
+    /// content, whether stored inline or on the heap. Allocator overhead and the nodes of the map
+    /// holding the content are not counted, so the actual usage is somewhat higher.
+    #[must_use]
+    pub fn approx_memory_usage(&self) -> usize {
+        let entry_size = std::mem::size_of::<Key>() + std::mem::size_of::<Value>();
+        let content_size: usize = self
+            .content
+            .iter()
+            .map(|(key, value)| {
+                entry_size + storage::key_heap_size(key) + storage::value_heap_size(value)
+            })
+            .sum();
+        std::mem::size_of::<Self>() + self.signature.capacity() + content_size
     }
-}
 
-impl<K: EnrKey> Hash for Enr<K> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.seq.hash(state);
-        self.node_id.hash(state);
-        // since the struct should always have a valid signature, we can hash the signature
-        // directly, rather than hashing the content.
-        self.signature.hash(state);
+    /// Returns the current size of the ENR.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.encoded_len()
     }
-}
 
-impl<K: EnrKey> std::fmt::Display for Enr<K> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.to_base64())
+    // Setters //
+
+    /// Sets the [`ConsistencyPolicy`] enforced on subsequent modifications of the record. The
+    /// current content of the record is not checked.
+    pub fn set_consistency_policy(&mut self, policy: ConsistencyPolicy) {
+        self.consistency_policy = policy;
     }
-}
 
-#[allow(clippy::missing_fields_in_debug)]
-impl<K: EnrKey> std::fmt::Debug for Enr<K> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        struct OtherPairs<'a>(&'a BTreeMap<Key, Bytes>);
+    /// Sets the [`SeqPolicy`] applied by subsequent modifications of the record.
+    pub fn set_seq_policy(&mut self, policy: SeqPolicy) {
+        self.seq_policy = policy;
+    }
 
-        impl<'a> std::fmt::Debug for OtherPairs<'a> {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                f.debug_list()
-                    .entries(
-                        self.0
-                            .iter()
-                            .filter(|(key, _)| {
-                                // skip all pairs already covered as fields
-                                !["id", "ip", "ip6", "udp", "udp6", "tcp", "tcp6"]
-                                    .iter()
-                                    .any(|k| k.as_bytes() == key.as_slice())
-                            })
-                            .map(|(key, val)| (String::from_utf8_lossy(key), hex::encode(val))),
-                    )
-                    .finish()
+    /// Sets the aliases getters fall back to when a key is absent. Values inserted under an alias
+    /// of a reserved key are validated as values of the reserved key.
+    pub fn set_alias_table(&mut self, alias_table: Option<Arc<AliasTable>>) {
+        self.alias_table = alias_table;
+    }
+
+    /// Allows setting the sequence number to an arbitrary value.
+    pub fn set_seq(&mut self, seq: u64, key: &K) -> Result<(), EnrError> {
+        self.clear_encoded();
+        let prev_seq = self.seq;
+        self.seq = seq;
+
+        // sign the record
+        let prev_signature = match self.sign(key) {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.seq = prev_seq;
+                return Err(e);
             }
+        };
+
+        // check the size of the record
+        if self.size() > MAX_ENR_SIZE {
+            self.seq = prev_seq;
+            self.signature = prev_signature;
+            return Err(EnrError::ExceedsMaxSize);
         }
 
-        f.debug_struct("Enr")
-            .field("id", &self.id())
-            .field("seq", &self.seq())
-            .field("NodeId", &self.node_id())
-            .field("signature", &hex::encode(&self.signature))
-            .field("IpV4 UDP Socket", &self.udp4_socket())
-            .field("IpV6 UDP Socket", &self.udp6_socket())
-            .field("IpV4 TCP Socket", &self.tcp4_socket())
-            .field("IpV6 TCP Socket", &self.tcp6_socket())
-            .field("Other Pairs", &OtherPairs(&self.content))
-            .finish()
+        // update the node id
+        self.node_id = S::node_id::<K>(&key.public());
+
+        Ok(())
     }
-}
 
-/// Convert a URL-SAFE base64 encoded ENR into an ENR.
-impl<K: EnrKey> FromStr for Enr<K> {
-    type Err = String;
+    /// Adds or modifies a key/value to the ENR record. A `EnrKey` is required to re-sign the record once
+    /// modified.
+    ///
+    /// Returns the previous value as rlp encoded bytes in the record if it exists.
+    pub fn insert<T: Encodable>(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: &T,
+        enr_key: &K,
+    ) -> Result<Option<Bytes>, EnrError> {
+        self.insert_raw_rlp(key, rlp::encode(value).freeze(), enr_key)
+    }
 
-    fn from_str(base64_string: &str) -> Result<Self, Self::Err> {
-        if base64_string.len() < 4 {
-            return Err("Invalid ENR string".to_string());
-        }
-        // support both enr prefix and not
-        let mut decode_string = base64_string;
-        if base64_string.starts_with("enr:") {
-            decode_string = decode_string
-                .get(4..)
-                .ok_or_else(|| "Invalid ENR string".to_string())?;
-        }
-        let bytes = URL_SAFE_NO_PAD
-            .decode(decode_string)
-            .map_err(|e| format!("Invalid base64 encoding: {e:?}"))?;
-        rlp::decode(&bytes).map_err(|e| format!("Invalid ENR: {e:?}"))
+    /// Adds or modifies a key/value to the ENR record. A `EnrKey` is required to re-sign the record once
+    /// modified. The value here is interpreted as raw RLP data.
+    ///
+    /// Returns the previous value as rlp encoded bytes in the record if it exists.
+    pub fn insert_raw_rlp(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: Bytes,
+        enr_key: &K,
+    ) -> Result<Option<Bytes>, EnrError> {
+        let key = key.as_ref();
+        self.update(enr_key, |tx| {
+            tx.insert_raw_rlp(key, value);
+        })
+        .map(|previous| previous.get_raw_rlp(key).map(Bytes::copy_from_slice))
     }
-}
 
-#[cfg(feature = "serde")]
-impl<K: EnrKey> Serialize for Enr<K> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&self.to_base64())
+    /// Modifies the record through a [`Transaction`] as one sequence number update. An `EnrKey`
+    /// is required to re-sign the record once modified. Reverts the whole ENR record if any
+    /// modification is invalid or the update fails.
+    ///
+    /// Returns the values of the removed and overwritten fields before the update. Ports removed
+    /// by a [`ConsistencyPolicy::Prune`] policy are not included.
+    pub fn update(
+        &mut self,
+        enr_key: &K,
+        modify: impl FnOnce(&mut Transaction<'_, S>),
+    ) -> Result<PreviousValues, EnrError> {
+        let mut update = UpdateGuard::new(self);
+        let previous = update.transact(&enr_key.public(), modify)?;
+        update.sign(enr_key)?;
+        Ok(previous)
     }
-}
 
-#[cfg(feature = "serde")]
-impl<'de, K: EnrKey> Deserialize<'de> for Enr<K> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s: String = Deserialize::deserialize(deserializer)?;
-        Self::from_str(&s).map_err(D::Error::custom)
+    /// Adds or modifies a key/value like [`Enr::insert_raw_rlp`], first adjusting non-canonical
+    /// values of reserved keys to their canonical form.
+    ///
+    /// The returned [`Normalized`] reports the key the value was stored under and any adjustment
+    /// made to it. See [`Normalization`] for the adjustments applied.
+    pub fn insert_normalized(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: Bytes,
+        enr_key: &K,
+    ) -> Result<Normalized, EnrError> {
+        let normalizing = normalize::normalize(key.as_ref(), value, &self.content)?;
+        let previous = self.update(enr_key, |tx| normalizing.apply(tx))?;
+        Ok(Normalized {
+            previous: previous
+                .get_raw_rlp(&normalizing.key)
+                .map(Bytes::copy_from_slice),
+            key: normalizing.key,
+            normalization: normalizing.normalization,
+        })
     }
-}
 
-impl<K: EnrKey> rlp::Encodable for Enr<K> {
-    fn rlp_append(&self, stream: &mut RlpStream) {
-        let include_signature = true;
-        self.append_rlp_content(stream, include_signature);
+    /// Adds or modifies a key with a UTF-8 string value, encoded as an RLP byte string. A `EnrKey`
+    /// is required to re-sign the record once modified.
+    ///
+    /// Returns the previous value as rlp encoded bytes in the record if it exists.
+    pub fn insert_utf8(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: &str,
+        enr_key: &K,
+    ) -> Result<Option<Bytes>, EnrError> {
+        self.insert(key, &value, enr_key)
     }
-}
 
-impl<K: EnrKey> rlp::Decodable for Enr<K> {
-    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
-        if rlp.as_raw().len() > MAX_ENR_SIZE {
-            return Err(DecoderError::Custom("enr exceeds max size"));
+    /// Adds or modifies a key with an unsigned integer value, encoded canonically as a big-endian
+    /// RLP byte string without leading zeros. A `EnrKey` is required to re-sign the record once
+    /// modified.
+    ///
+    /// Returns the previous value as rlp encoded bytes in the record if it exists.
+    pub fn insert_uint(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: u64,
+        enr_key: &K,
+    ) -> Result<Option<Bytes>, EnrError> {
+        self.insert(key, &value, enr_key)
+    }
+
+    /// Sets the `ip` field of the ENR. Returns any pre-existing IP address in the record.
+    pub fn set_ip(&mut self, ip: IpAddr, key: &K) -> Result<Option<IpAddr>, EnrError> {
+        match ip {
+            IpAddr::V4(addr) => {
+                let prev_value = self.insert("ip", &addr.octets().as_ref(), key)?;
+                if let Some(bytes) = prev_value {
+                    if bytes.len() == 4 {
+                        let mut v = [0_u8; 4];
+                        v.copy_from_slice(&bytes);
+                        return Ok(Some(IpAddr::V4(Ipv4Addr::from(v))));
+                    }
+                }
+            }
+            IpAddr::V6(addr) => {
+                let prev_value = self.insert("ip6", &addr.octets().as_ref(), key)?;
+                if let Some(bytes) = prev_value {
+                    if bytes.len() == 16 {
+                        let mut v = [0_u8; 16];
+                        v.copy_from_slice(&bytes);
+                        return Ok(Some(IpAddr::V6(Ipv6Addr::from(v))));
+                    }
+                }
+            }
         }
 
-        if !rlp.is_list() {
-            debug!("Failed to decode ENR. Not an RLP list: {}", rlp);
-            return Err(DecoderError::RlpExpectedToBeList);
+        Ok(None)
+    }
+
+    /// Sets the `udp` field of the ENR. Returns any pre-existing UDP port in the record.
+    pub fn set_udp4(&mut self, udp: u16, key: &K) -> Result<Option<u16>, EnrError> {
+        if let Some(udp_bytes) = self.insert("udp", &udp, key)? {
+            return Ok(rlp::decode(&udp_bytes).ok());
         }
+        Ok(None)
+    }
 
-        // verify there is no extra data
-        let payload_info = rlp.payload_info()?;
-        if rlp.as_raw().len() != payload_info.header_len + payload_info.value_len {
-            return Err(DecoderError::RlpInconsistentLengthAndData);
+    /// Sets the `flags` field of the ENR. Returns any pre-existing flags in the record.
+    pub fn set_flags(
+        &mut self,
+        flags: RecordFlags,
+        key: &K,
+    ) -> Result<Option<RecordFlags>, EnrError> {
+        if let Some(flags_bytes) = self.insert(FLAGS_KEY, &flags.bits(), key)? {
+            return Ok(rlp::decode(&flags_bytes)
+                .ok()
+                .map(RecordFlags::from_bits_retain));
         }
+        Ok(None)
+    }
 
-        let mut rlp_iter = rlp.iter();
+    /// Sets the flags in `insert` and clears the flags in `remove` with a single increment in
+    /// sequence number. Returns the resulting flags.
+    pub fn update_flags(
+        &mut self,
+        insert: RecordFlags,
+        remove: RecordFlags,
+        key: &K,
+    ) -> Result<RecordFlags, EnrError> {
+        let flags = (self.flags().unwrap_or_default() - remove) | insert;
+        self.set_flags(flags, key).map(|_| flags)
+    }
 
-        if rlp_iter.len() == 0 || rlp_iter.len() % 2 != 0 {
-            debug!("Failed to decode ENR. List size is not a multiple of 2.");
-            return Err(DecoderError::Custom("List not a multiple of two"));
-        }
+    /// Sets the `eth2` field of the ENR. Returns any pre-existing fork id in the record.
+    #[cfg(feature = "eth2")]
+    pub fn set_eth2_fork_id(
+        &mut self,
+        fork_id: EnrForkId,
+        key: &K,
+    ) -> Result<Option<EnrForkId>, EnrError> {
+        let previous = self.insert(ETH2_KEY, &fork_id, key)?;
+        Ok(previous.and_then(|bytes| rlp::decode(&bytes).ok()))
+    }
 
-        let signature = rlp_iter
-            .next()
-            .ok_or(DecoderError::Custom("List is empty"))?
-            .data()?;
-        let seq = rlp_iter
-            .next()
-            .ok_or(DecoderError::Custom("List has only one item"))?
-            .as_val()?;
+    /// Sets the `attnets` field of the ENR. Returns any pre-existing attestation subnets in the
+    /// record.
+    #[cfg(feature = "eth2")]
+    pub fn set_attestation_subnets(
+        &mut self,
+        subnets: AttestationSubnets,
+        key: &K,
+    ) -> Result<Option<AttestationSubnets>, EnrError> {
+        let previous = self.insert(ATTNETS_KEY, &subnets, key)?;
+        Ok(previous.and_then(|bytes| rlp::decode(&bytes).ok()))
+    }
 
-        let mut content = BTreeMap::new();
-        let mut prev: Option<&[u8]> = None;
-        while let Some(key) = rlp_iter.next() {
-            let key = key.data()?;
-            let item = rlp_iter
-                .next()
-                .ok_or(DecoderError::Custom("List not a multiple of 2"))?;
-
-            // Sanitize the data
-            if is_keyof_u16(key) {
-                item.as_val::<u16>()?;
-            } else {
-                item.data()?;
-            }
-            let value = item.as_raw();
+    /// Sets the `syncnets` field of the ENR. Returns any pre-existing sync committee subnets in
+    /// the record.
+    #[cfg(feature = "eth2")]
+    pub fn set_sync_committee_subnets(
+        &mut self,
+        subnets: SyncCommitteeSubnets,
+        key: &K,
+    ) -> Result<Option<SyncCommitteeSubnets>, EnrError> {
+        let previous = self.insert(SYNCNETS_KEY, &subnets, key)?;
+        Ok(previous.and_then(|bytes| rlp::decode(&bytes).ok()))
+    }
 
-            if prev.is_some() && prev >= Some(key) {
-                return Err(DecoderError::Custom("Unsorted keys"));
-            }
-            prev = Some(key);
-            content.insert(key.to_vec(), Bytes::copy_from_slice(value));
+    /// Sets the `udp6` field of the ENR. Returns any pre-existing UDP port in the record.
+    pub fn set_udp6(&mut self, udp: u16, key: &K) -> Result<Option<u16>, EnrError> {
+        if let Some(udp_bytes) = self.insert("udp6", &udp, key)? {
+            return Ok(rlp::decode(&udp_bytes).ok());
         }
+        Ok(None)
+    }
 
-        // verify we know the signature type
-        let public_key = K::enr_to_public(&content)?;
+    /// Sets the `tcp` field of the ENR. Returns any pre-existing tcp port in the record.
+    pub fn set_tcp4(&mut self, tcp: u16, key: &K) -> Result<Option<u16>, EnrError> {
+        if let Some(tcp_bytes) = self.insert("tcp", &tcp, key)? {
+            return Ok(rlp::decode(&tcp_bytes).ok());
+        }
+        Ok(None)
+    }
 
-        // calculate the node id
-        let node_id = NodeId::from(public_key);
+    /// Sets the `tcp6` field of the ENR. Returns any pre-existing tcp6 port in the record.
+    pub fn set_tcp6(&mut self, tcp: u16, key: &K) -> Result<Option<u16>, EnrError> {
+        if let Some(tcp_bytes) = self.insert("tcp6", &tcp, key)? {
+            return Ok(rlp::decode(&tcp_bytes).ok());
+        }
+        Ok(None)
+    }
 
-        let enr = Self {
-            seq,
-            node_id,
-            signature: signature.into(),
-            content,
-            phantom: PhantomData,
+    /// Sets the IP and UDP port in a single update with a single increment in sequence number.
+    pub fn set_udp_socket(&mut self, socket: SocketAddr, key: &K) -> Result<(), EnrError> {
+        self.set_socket(socket, key, false)
+    }
+
+    /// Sets the IP and TCP port in a single update with a single increment in sequence number.
+    pub fn set_tcp_socket(&mut self, socket: SocketAddr, key: &K) -> Result<(), EnrError> {
+        self.set_socket(socket, key, true)
+    }
+
+    /// Helper function for `set_tcp_socket()` and `set_udp_socket`.
+    fn set_socket(&mut self, socket: SocketAddr, key: &K, is_tcp: bool) -> Result<(), EnrError> {
+        let transport = if is_tcp {
+            Transport::Tcp
+        } else {
+            Transport::Udp
         };
+        self.set_endpoints(std::iter::once(Endpoint::new(transport, socket)), key)
+    }
 
-        // verify the signature before returning
-        // if the public key is of an unknown type, this will fail.
-        // An ENR record will always have a valid public-key and therefore node-id
-        if !enr.verify() {
-            return Err(DecoderError::Custom("Invalid Signature"));
-        }
-        Ok(enr)
+    /// Sets the IP address and port of each endpoint in a single update with a single increment
+    /// in sequence number. Reverts the whole ENR record on error.
+    ///
+    /// Endpoints of the same IP address family must share the same IP address.
+    pub fn set_endpoints(
+        &mut self,
+        endpoints: impl IntoIterator<Item = Endpoint>,
+        key: &K,
+    ) -> Result<(), EnrError> {
+        self.set_endpoints_and_insert(endpoints, std::iter::empty::<(&[u8], Bytes)>(), key)
     }
-}
 
-/// Owning iterator over all key/value pairs in the ENR.
-pub struct EnrIntoIter {
-    inner: <BTreeMap<Key, Bytes> as IntoIterator>::IntoIter,
-}
+    /// Sets the IP address and the port of the transport of each socket in a single update with a
+    /// single increment in sequence number, e.g. the `ip`, `udp`, `ip6` and `udp6` fields of a
+    /// dual-stack node. Reverts the whole ENR record on error.
+    ///
+    /// Sockets of the same IP address family must share the same IP address.
+    pub fn set_sockets(
+        &mut self,
+        sockets: &[(Transport, SocketAddr)],
+        key: &K,
+    ) -> Result<(), EnrError> {
+        self.set_endpoints(
+            sockets
+                .iter()
+                .map(|(transport, socket)| Endpoint::new(*transport, *socket)),
+            key,
+        )
+    }
 
-impl Iterator for EnrIntoIter {
-    type Item = (Key, Bytes);
+    /// Sets the sockets of the text form of a libp2p multiaddr like [`Enr::set_sockets`], mapping
+    /// its `/ip4`, `/ip6`, `/tcp`, `/udp` and `/quic-v1` components to the matching fields.
+    ///
+    /// A `/p2p` component is skipped. Fails with [`EnrError::InvalidMultiaddr`] if the multiaddr
+    /// has other protocols or no socket. Reverts the whole ENR record on error.
+    pub fn set_from_multiaddr(&mut self, multiaddr: &str, key: &K) -> Result<(), EnrError> {
+        self.set_sockets(&multiaddr::sockets(multiaddr)?, key)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    /// Sets the IP address and port of each endpoint and adds or overwrites the given fields in a
+    /// single update with a single increment in sequence number. The field values are
+    /// interpreted as raw RLP data. Reverts the whole ENR record on error.
+    ///
+    /// Endpoints of the same IP address family must share the same IP address.
+    pub fn set_endpoints_and_insert(
+        &mut self,
+        endpoints: impl IntoIterator<Item = Endpoint>,
+        fields: impl IntoIterator<Item = (impl AsRef<[u8]>, Bytes)>,
+        key: &K,
+    ) -> Result<(), EnrError> {
+        self.update(key, |tx| {
+            for endpoint in endpoints {
+                tx.set_endpoint(endpoint);
+            }
+            for (field, value) in fields {
+                tx.insert_raw_rlp(field, value);
+            }
+        })
+        .map(|_| ())
+    }
+
+    /// The lossless reductions of the record's encoded size, see [`CompactionReport`].
+    #[must_use]
+    pub fn compaction_report(&self) -> CompactionReport {
+        CompactionReport::new(self.size(), &self.content, self.alias_table.as_deref())
+    }
+
+    /// Applies every compaction of [`Enr::compaction_report`] as one sequence number update. An
+    /// `EnrKey` is required to re-sign the record once modified. The record is left unchanged if
+    /// no compaction applies.
+    ///
+    /// Returns the applied compactions.
+    pub fn compact(&mut self, enr_key: &K) -> Result<CompactionReport, EnrError> {
+        let report = self.compaction_report();
+        if report.is_empty() {
+            return Ok(report);
+        }
+        let content = self.content.clone();
+        self.update(enr_key, |tx| {
+            for compaction in &report.compactions {
+                compaction.apply(&content, tx);
+            }
+        })?;
+        Ok(report)
+    }
+
+    /// Removes one or several keys from the ENR record as one sequence number update. An `EnrKey`
+    /// is required to re-sign the record once modified. Reverts the whole ENR record on error.
+    ///
+    /// Returns the previous values as rlp encoded bytes of the removed keys, in the order given.
+    /// The `id` key and the key of the public key cannot be removed and fail with
+    /// [`EnrError::MandatoryKey`].
+    pub fn remove(
+        &mut self,
+        keys: impl IntoIterator<Item = impl AsRef<[u8]>>,
+        enr_key: &K,
+    ) -> Result<PreviousRlpEncodedValues, EnrError> {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|key| key.as_ref().to_vec()).collect();
+        let previous = self.update(enr_key, |tx| {
+            for key in &keys {
+                tx.remove(key);
+            }
+        })?;
+        Ok(keys
+            .iter()
+            .map(|key| previous.get_raw_rlp(key).map(Bytes::copy_from_slice))
+            .collect())
+    }
+
+    /// Removes key/value mappings and adds or overwrites key/value mappings to the ENR record as
+    /// one sequence number update, like [`Enr::apply_changes`] with the values encoded as RLP byte
+    /// strings. An `EnrKey` is required to re-sign the record once modified. Reverts whole ENR
+    /// record on error.
+    ///
+    /// Returns the previous values as rlp encoded bytes if they exist for the removed and added/
+    /// overwritten keys. Ports removed by a [`ConsistencyPolicy::Prune`] policy are not included.
+    #[deprecated(
+        note = "use `Enr::apply_changes` with `FieldChanges`, which accepts any `Encodable` value"
+    )]
+    pub fn remove_insert<'a>(
+        &mut self,
+        remove_keys: impl Iterator<Item = impl AsRef<[u8]>>,
+        insert_key_values: impl Iterator<Item = (impl AsRef<[u8]>, &'a [u8])>,
+        enr_key: &K,
+    ) -> Result<(PreviousRlpEncodedValues, PreviousRlpEncodedValues), EnrError> {
+        let remove_keys: Vec<Vec<u8>> = remove_keys.map(|key| key.as_ref().to_vec()).collect();
+        let mut insert_keys = Vec::new();
+        let mut changes = FieldChanges::new();
+        for key in &remove_keys {
+            changes.remove(key);
+        }
+        for (key, value) in insert_key_values {
+            changes.insert(&key, &value);
+            insert_keys.push(key.as_ref().to_vec());
+        }
+
+        let previous = self.apply_changes(&changes, enr_key)?;
+        let previous_value = |key: &Vec<u8>| previous.get_raw_rlp(key).map(Bytes::copy_from_slice);
+        let removed = remove_keys.iter().map(previous_value).collect();
+        // values of keys removed by the same update were already reported as removed
+        let inserted = insert_keys
+            .iter()
+            .map(|key| {
+                if remove_keys.contains(key) {
+                    None
+                } else {
+                    previous_value(key)
+                }
+            })
+            .collect();
+        Ok((removed, inserted))
+    }
+
+    /// Applies a batch of removals and insertions as one sequence number update. Removals are
+    /// applied before insertions. Reverts the whole ENR record on error.
+    ///
+    /// Returns the values of the removed and overwritten fields before the update. Ports removed
+    /// by a [`ConsistencyPolicy::Prune`] policy are not included.
+    pub fn apply_changes(
+        &mut self,
+        changes: &FieldChanges,
+        enr_key: &K,
+    ) -> Result<PreviousValues, EnrError> {
+        self.update(enr_key, |tx| {
+            tx.apply_changes(changes);
+        })
+    }
+
+    /// Sets a new public key for the record.
+    pub fn set_public_key(&mut self, public_key: &K::PublicKey, key: &K) -> Result<(), EnrError> {
+        self.insert(public_key.enr_key(), &public_key.encode().as_ref(), key)
+            .map(|_| {})
+    }
+
+    /// Returns wether the node can be reached over UDP or not.
+    #[must_use]
+    pub fn is_udp_reachable(&self) -> bool {
+        self.udp4_socket().is_some() || self.udp6_socket().is_some()
+    }
+
+    /// Returns wether the node can be reached over TCP or not.
+    #[must_use]
+    pub fn is_tcp_reachable(&self) -> bool {
+        self.tcp4_socket().is_some() || self.tcp6_socket().is_some()
+    }
+
+    // Private Functions //
+
+    /// The length of the payload of the ENR's RLP list, including the signature.
+    fn rlp_payload_len(&self) -> usize {
+        codec::fields_len(Some(&self.signature), self.seq, self.fields())
+    }
+
+    /// The ordered (key, value) pairs of the ENR's content, as encoded.
+    fn fields(&self) -> impl Iterator<Item = (&[u8], &[u8])> + Clone {
+        self.content
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_ref()))
+    }
+
+    /// Encodes the ENR's content (sequence number + ordered (key, value) pairs) into `buffer`,
+    /// replacing its previous contents.
+    fn write_rlp_content(&self, buffer: &mut BytesMut) {
+        buffer.clear();
+        codec::encode_fields(buffer, None, self.seq, self.fields());
+    }
+
+    /// Encodes the ENR's content (sequence number + ordered (key, value) pairs).
+    fn rlp_content(&self) -> BytesMut {
+        let mut buffer = BytesMut::with_capacity(MAX_ENR_SIZE);
+        self.write_rlp_content(&mut buffer);
+        buffer
+    }
+
+    /// Compute the enr's signature with the given key.
+    fn compute_signature(&self, signing_key: &K) -> Result<Vec<u8>, EnrError> {
+        let mode = self.signature_mode();
+        match self.id() {
+            Some(ref id) if id == S::ID => {
+                RLP_SCRATCH_BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+                    Ok(mut buffer) => {
+                        self.write_rlp_content(&mut buffer);
+                        mode.sign::<K, S>(signing_key, &buffer)
+                    }
+                    Err(_) => mode.sign::<K, S>(signing_key, &self.rlp_content()),
+                })
+            }
+            // other identity schemes are unsupported
+            _ => Err(EnrError::UnsupportedIdentityScheme),
+        }
+    }
+
+    /// The message signed by an ownership proof with [`EnrKey::sign_message`]: the RLP list
+    /// `["enr-ownership-proof", node_id, seq, challenge]`.
+    fn ownership_message(&self, challenge: &[u8]) -> BytesMut {
+        let mut s = RlpStream::new_list(4);
+        s.append(&"enr-ownership-proof");
+        s.append(&self.node_id.raw().as_ref());
+        s.append(&self.seq);
+        s.append(&challenge);
+        s.out()
+    }
+
+    /// Builds a record from its decoded fields, verifying its public key and signature.
+    fn from_record_fields(
+        signature: Vec<u8>,
+        seq: u64,
+        content: BTreeMap<Key, Value>,
+    ) -> Result<Self, DecoderError> {
+        let mut enr = Self {
+            seq,
+            node_id: NodeId::new(&[0; 32]),
+            signature,
+            content,
+            consistency_policy: ConsistencyPolicy::default(),
+            seq_policy: SeqPolicy::default(),
+            alias_table: None,
+            encoded: OnceLock::new(),
+            content_hash: OnceLock::new(),
+            phantom: PhantomData,
+        };
+
+        // verify we know the signature type, recovering the public key if it is omitted
+        let public_key = enr
+            .try_public_key()
+            .inspect_err(|cause| trace::verify_failed(seq, &enr.content, cause))?;
+
+        // calculate the node id
+        enr.node_id = S::node_id::<K>(&public_key);
+
+        // verify the signature before returning
+        // if the public key is of an unknown type, this will fail.
+        // An ENR record will always have a valid public-key and therefore node-id
+        if !enr.verify() {
+            trace::verify_failed(seq, &enr.content, &INVALID_SIGNATURE);
+            return Err(INVALID_SIGNATURE);
+        }
+        Ok(enr)
+    }
+
+    /// Signs the ENR record based on its identity scheme.
+    /// The previous signature is returned.
+    fn sign(&mut self, key: &K) -> Result<Vec<u8>, EnrError> {
+        let new_signature = self.compute_signature(key)?;
+        Ok(std::mem::replace(&mut self.signature, new_signature))
+    }
+}
+
+// traits //
+
+impl<K: EnrKey, S: IdentityScheme> Clone for Enr<K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            seq: self.seq,
+            node_id: self.node_id,
+            content: self.content.clone(),
+            signature: self.signature.clone(),
+            consistency_policy: self.consistency_policy,
+            seq_policy: self.seq_policy,
+            alias_table: self.alias_table.clone(),
+            encoded: self.encoded.clone(),
+            content_hash: self.content_hash.clone(),
+            phantom: self.phantom,
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> std::cmp::Eq for Enr<K, S> {}
+
+impl<K: EnrKey, S: IdentityScheme> PartialEq for Enr<K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        // the signatures are compared in constant time, so that comparing a received record
+        // against a known one does not leak how much of the signature matches
+        self.seq == other.seq
+            && self.node_id == other.node_id
+            && bool::from(self.signature.ct_eq(&other.signature))
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Hash for Enr<K, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // a single word, so that fast hashers like FxHash or ahash need one round per record.
+        // Equal records have equal node ids and sequence numbers, so this agrees with `Eq`.
+        state.write_u64(self.fingerprint64());
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> std::fmt::Display for Enr<K, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.display_pretty());
+        }
+        write!(f, "{}", self.to_base64())
+    }
+}
+
+#[allow(clippy::missing_fields_in_debug)]
+impl<K: EnrKey, S: IdentityScheme> std::fmt::Debug for Enr<K, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        struct OtherPairs<'a>(&'a BTreeMap<Key, Value>);
+
+        impl<'a> std::fmt::Debug for OtherPairs<'a> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.debug_list()
+                    .entries(
+                        self.0
+                            .iter()
+                            .filter(|(key, _)| {
+                                // skip all pairs already covered as fields
+                                !["id", "ip", "ip6", "udp", "udp6", "tcp", "tcp6"]
+                                    .iter()
+                                    .any(|k| k.as_bytes() == key.as_slice())
+                            })
+                            .map(|(key, val)| (String::from_utf8_lossy(key), hex::encode(val))),
+                    )
+                    .finish()
+            }
+        }
+
+        f.debug_struct("Enr")
+            .field("id", &self.id())
+            .field("seq", &self.seq())
+            .field("NodeId", &self.node_id())
+            .field("signature", &hex::encode(&self.signature))
+            .field("IpV4 UDP Socket", &self.udp4_socket())
+            .field("IpV6 UDP Socket", &self.udp6_socket())
+            .field("IpV4 TCP Socket", &self.tcp4_socket())
+            .field("IpV6 TCP Socket", &self.tcp6_socket())
+            .field("Other Pairs", &OtherPairs(&self.content))
+            .finish()
+    }
+}
+
+/// Convert a URL-SAFE base64 encoded ENR into an ENR.
+impl<K: EnrKey, S: IdentityScheme> FromStr for Enr<K, S> {
+    type Err = EnrParseError;
+
+    fn from_str(base64_string: &str) -> Result<Self, Self::Err> {
+        if base64_string.len() < 4 {
+            return Err(EnrParseError::InvalidString);
+        }
+        // support both enr prefix and not
+        let mut decode_string = base64_string;
+        if base64_string.starts_with("enr:") {
+            decode_string = decode_string.get(4..).ok_or(EnrParseError::InvalidString)?;
+        }
+        let bytes = URL_SAFE_NO_PAD
+            .decode(decode_string)
+            .map_err(EnrParseError::Base64)?;
+        if bytes.len() > MAX_ENR_SIZE {
+            return Err(EnrParseError::TooLarge);
+        }
+        Self::decode_with_context(&bytes).map_err(|e| match &e {
+            EnrParseError::Rlp {
+                context: Some(context),
+                ..
+            } if context.scheme() != Some(S::ID) => {
+                EnrParseError::UnsupportedScheme(context.scheme().map(String::from))
+            }
+            EnrParseError::Rlp {
+                error,
+                context: Some(_),
+            } if *error == INVALID_SIGNATURE => EnrParseError::InvalidSignature,
+            _ => e,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K: EnrKey, S: IdentityScheme> Serialize for Enr<K, S> {
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        serializer.serialize_str(&self.to_base64())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K: EnrKey, S: IdentityScheme> Deserialize<'de> for Enr<K, S> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> rlp::Encodable for Enr<K, S> {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        // the cached encoding, so the signature, sequence number and fields of an unchanged
+        // record are only encoded once. The item is counted by `RlpStream::append`.
+        stream.append_raw(self.encoded(), 0);
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> rlp::Encodable for &Enr<K, S> {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        (*self).rlp_append(stream);
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> rlp::Decodable for Enr<K, S> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "enr_decode", skip_all)
+    )]
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let (signature, seq, content) = decode_record_fields(rlp, false)
+            .inspect_err(|cause| trace::decode_failed(rlp.as_raw().len(), cause))?;
+        Self::from_record_fields(signature, seq, content)
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> TryFrom<&[u8]> for Enr<K, S> {
+    type Error = DecoderError;
+
+    /// Decodes and verifies an RLP encoded record, e.g. taken from a discv5 packet.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        rlp::decode(bytes)
+    }
+}
+
+impl<K: EnrKey> TryFrom<enr_core::Enr<K>> for Enr<K> {
+    type Error = DecoderError;
+
+    /// Converts a record of [`enr_core`], checking its values against the reserved keys of this
+    /// crate.
+    fn try_from(enr: enr_core::Enr<K>) -> Result<Self, Self::Error> {
+        rlp::decode(&rlp::encode(&enr))
+    }
+}
+
+impl<K: EnrKey> TryFrom<&Enr<K>> for enr_core::Enr<K> {
+    type Error = DecoderError;
+
+    /// Converts a record to the record type of [`enr_core`]. Fails if the record doesn't store
+    /// its public key, see [`SignatureMode`].
+    fn try_from(enr: &Enr<K>) -> Result<Self, Self::Error> {
+        rlp::decode(enr.encoded())
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> TryFrom<Vec<u8>> for Enr<K, S> {
+    type Error = DecoderError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> TryFrom<Bytes> for Enr<K, S> {
+    type Error = DecoderError;
+
+    fn try_from(bytes: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_ref())
+    }
+}
+
+/// Owning iterator over all key/value pairs in the ENR.
+pub struct EnrIntoIter {
+    inner: <BTreeMap<Key, Value> as IntoIterator>::IntoIter,
+}
+
+impl Iterator for EnrIntoIter {
+    type Item = (Vec<u8>, Bytes);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (key.to_vec(), storage::into_bytes(value)))
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> IntoIterator for Enr<K, S> {
+    type Item = (Vec<u8>, Bytes);
+
+    type IntoIter = EnrIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        EnrIntoIter {
+            inner: self.content.into_iter(),
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> From<Enr<K, S>> for NodeId {
+    fn from(enr: Enr<K, S>) -> Self {
+        enr.node_id()
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> From<&Enr<K, S>> for NodeId {
+    fn from(enr: &Enr<K, S>) -> Self {
+        enr.node_id()
+    }
+}
+
+/// How a record relates to a known record of the same node, see [`Enr::supersedes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Supersedes {
+    /// The record has a higher sequence number and replaces the known record.
+    Newer,
+    /// The record has the same sequence number and content as the known record.
+    Same,
+    /// The record has a lower sequence number than the known record.
+    Older,
+    /// The record has the same sequence number as the known record but different content. The
+    /// node signed conflicting records, which callers should reject rather than ignore.
+    Conflict,
+}
+
+/// How a record with sequence number `seq` relates to a known record of the same node with
+/// sequence number `known`, the rule of [`Enr::supersedes`] for collections that don't hold the
+/// known record itself. `same_content` compares the content of both records and is only called
+/// if their sequence numbers are equal.
+pub(crate) fn supersedes(seq: u64, known: u64, same_content: impl FnOnce() -> bool) -> Supersedes {
+    match seq.cmp(&known) {
+        std::cmp::Ordering::Greater => Supersedes::Newer,
+        std::cmp::Ordering::Less => Supersedes::Older,
+        std::cmp::Ordering::Equal if same_content() => Supersedes::Same,
+        std::cmp::Ordering::Equal => Supersedes::Conflict,
+    }
+}
+
+pub(crate) fn digest(b: &[u8]) -> [u8; 32] {
+    let mut output = [0_u8; 32];
+    output.copy_from_slice(&Keccak256::digest(b));
+    output
+}
+
+/// Decodes the signature, sequence number and content of an RLP encoded record, with the codec
+/// of [`enr_core`].
+///
+/// This sanitizes the structure of the record (size, sorted keys, well-formed values) but does
+/// not verify the signature or the identity scheme. A `lenient` decoding, for the records of
+/// legacy implementations, checks neither the size nor the encoding of the ports.
+fn decode_record_fields(rlp: &Rlp, lenient: bool) -> Result<DecodedRecordFields, DecoderError> {
+    let mut content = BTreeMap::new();
+    let field = |key: &[u8], item: &Rlp| {
+        // the ports of lenient records are checked by the caller
+        if !(lenient && is_keyof_u16(key)) {
+            sanitize_value(key, item)?;
+        }
+        content.insert(Key::from(key), storage::copy_value(item.as_raw()));
+        Ok(())
+    };
+    let (signature, seq) = if lenient {
+        codec::decode_fields_unbounded(rlp, field)?
+    } else {
+        codec::decode_fields(rlp, field)?
+    };
+
+    Ok((signature.into(), seq, content))
+}
+
+/// Checks that the RLP encoded value stored under `key` is well-formed, with the format of the key
+/// for ports and public keys. Ports are those of the reserved keys, see [`codec::check_value`]
+/// for the other rules.
+fn sanitize_value(key: &[u8], item: &Rlp) -> Result<(), DecoderError> {
+    if is_keyof_u16(key) {
+        item.as_val::<u16>()?;
+        return Ok(());
+    }
+    codec::check_value(key, item)
+}
+
+/// The error of decoding a record whose signature does not verify.
+const INVALID_SIGNATURE: DecoderError = DecoderError::Custom("Invalid Signature");
+
+/// Whether `bytes` is a single, canonically encoded RLP item.
+fn is_canonical_rlp(bytes: &[u8]) -> bool {
+    canonical_rlp_item_len(bytes) == Some(bytes.len())
+}
+
+/// The length of the RLP item at the start of `bytes`, or `None` if the item is truncated or it or
+/// any nested item is not minimally encoded.
+fn canonical_rlp_item_len(bytes: &[u8]) -> Option<usize> {
+    let (&first, rest) = bytes.split_first()?;
+    if first < RLP_STRING_OFFSET {
+        return Some(1);
+    }
+    let is_list = first >= RLP_LIST_OFFSET;
+    let offset = if is_list {
+        RLP_LIST_OFFSET
+    } else {
+        RLP_STRING_OFFSET
+    };
+    let (header_len, payload_len) = match usize::from(first - offset) {
+        payload_len @ 0..=55 => (1, payload_len),
+        long => {
+            let len_bytes = rest.get(..long - 55)?;
+            if len_bytes[0] == 0 || len_bytes.len() > std::mem::size_of::<usize>() {
+                return None;
+            }
+            let payload_len = len_bytes
+                .iter()
+                .fold(0, |len, byte| (len << 8) | usize::from(*byte));
+            if payload_len < 56 {
+                return None;
+            }
+            (1 + len_bytes.len(), payload_len)
+        }
+    };
+    let payload = bytes.get(header_len..header_len.checked_add(payload_len)?)?;
+    if is_list {
+        let mut items = payload;
+        while !items.is_empty() {
+            items = &items[canonical_rlp_item_len(items)?..];
+        }
+    } else if payload_len == 1 && payload[0] < RLP_STRING_OFFSET {
+        return None;
+    }
+    Some(header_len + payload_len)
+}
+
+const fn is_keyof_u16(key: &[u8]) -> bool {
+    matches!(
+        reserved::lookup(key),
+        Some(ReservedKey {
+            validator: KeyValidator::Port,
+            ..
+        })
+    )
+}
+
+fn check_spec_reserved_keys<S: IdentityScheme>(key: &[u8], value: &[u8]) -> Result<(), EnrError> {
+    let validator = match reserved::lookup(key) {
+        Some(reserved) => reserved.validator,
+        None => return Ok(()),
+    };
+    match validator {
+        KeyValidator::Port => {
+            rlp::decode::<u16>(value).map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+        }
+        KeyValidator::Flags => {
+            rlp::decode::<u64>(value).map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+        }
+        KeyValidator::IdentityScheme => {
+            let id_bytes = rlp::decode::<Vec<u8>>(value)
+                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+            if id_bytes != S::ID.as_bytes() {
+                return Err(EnrError::UnsupportedIdentityScheme);
+            }
+        }
+        KeyValidator::Ipv4 => {
+            let ip4_bytes = rlp::decode::<Vec<u8>>(value)
+                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+            if ip4_bytes.len() != 4 {
+                return Err(EnrError::InvalidRlpData("Invalid Ipv4 size".to_string()));
+            }
+        }
+        KeyValidator::Ipv6 => {
+            let ip6_bytes = rlp::decode::<Vec<u8>>(value)
+                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+            if ip6_bytes.len() != 16 {
+                return Err(EnrError::InvalidRlpData("Invalid Ipv6 size".to_string()));
+            }
+        }
+        KeyValidator::Secp256k1 => check_public_key(KeyScheme::Secp256k1, value)?,
+        KeyValidator::Ed25519 => check_public_key(KeyScheme::Ed25519, value)?,
+        KeyValidator::Unchecked => return Ok(()),
+    };
+    Ok(())
+}
+
+/// Checks that `value` is an RLP encoded public key of `scheme`, with the length checked when a
+/// record is decoded.
+fn check_public_key(scheme: KeyScheme, value: &[u8]) -> Result<(), EnrError> {
+    let public_key =
+        rlp::decode::<Vec<u8>>(value).map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
+    scheme
+        .check_len(&public_key)
+        .map_err(|err| EnrError::InvalidRlpData(err.to_string()))
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::net::Ipv4Addr;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_vector_k256() {
+        let valid_record = hex::decode("f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f").unwrap();
+        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
+        let expected_pubkey =
+            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
+                .unwrap();
+
+        let enr = rlp::decode::<DefaultEnr>(&valid_record).unwrap();
+
+        let pubkey = enr.public_key().encode();
+
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
+        assert_eq!(enr.id(), Some(String::from("v4")));
+        assert_eq!(enr.udp4(), Some(30303));
+        assert_eq!(enr.tcp4(), None);
+        assert_eq!(enr.signature(), &signature[..]);
+        assert_eq!(pubkey.to_vec(), expected_pubkey);
+        assert!(enr.verify());
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_vector_2() {
+        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
+        let expected_pubkey =
+            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
+                .unwrap();
+        let expected_node_id =
+            hex::decode("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")
+                .unwrap();
+
+        let enr = text.parse::<DefaultEnr>().unwrap();
+        let pubkey = enr.public_key().encode();
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
+        assert_eq!(enr.ip6(), None);
+        assert_eq!(enr.id(), Some(String::from("v4")));
+        assert_eq!(enr.udp4(), Some(30303));
+        assert_eq!(enr.udp6(), None);
+        assert_eq!(enr.tcp4(), None);
+        assert_eq!(enr.tcp6(), None);
+        assert_eq!(enr.signature(), &signature[..]);
+        assert_eq!(pubkey.to_vec(), expected_pubkey);
+        assert_eq!(enr.node_id().raw().to_vec(), expected_node_id);
+
+        assert!(enr.verify());
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_vector_2_k256() {
+        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
+        let expected_pubkey =
+            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
+                .unwrap();
+        let expected_node_id =
+            hex::decode("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")
+                .unwrap();
+
+        let enr = text.parse::<Enr<k256::ecdsa::SigningKey>>().unwrap();
+        let pubkey = enr.public_key().encode();
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
+        assert_eq!(enr.ip6(), None);
+        assert_eq!(enr.id(), Some(String::from("v4")));
+        assert_eq!(enr.udp4(), Some(30303));
+        assert_eq!(enr.udp6(), None);
+        assert_eq!(enr.tcp4(), None);
+        assert_eq!(enr.tcp6(), None);
+        assert_eq!(enr.signature(), &signature[..]);
+        assert_eq!(pubkey.to_vec(), expected_pubkey);
+        assert_eq!(enr.node_id().raw().to_vec(), expected_node_id);
+
+        assert!(enr.verify());
+    }
+
+    // the values in the content are rlp lists
+    #[test]
+    fn test_rlp_list_value() {
+        let text = "enr:-Je4QH0uN2HkMRmscUp6yvyTOPGtOg9U6lCxBFvCGynyystnDNRJbfz5GhXXY2lcu9tsghMxRiYHoznBwG46GQ7dfm0og2V0aMfGhMvbiDiAgmlkgnY0gmlwhA6hJmuJc2VjcDI1NmsxoQJBP4kg9GNBurV3uVXgR72u1n-XIABibUZLT1WvJLKwvIN0Y3CCdyeDdWRwgncn";
+        let signature = hex::decode("7d2e3761e43119ac714a7acafc9338f1ad3a0f54ea50b1045bc21b29f2cacb670cd4496dfcf91a15d763695cbbdb6c821331462607a339c1c06e3a190edd7e6d").unwrap();
+        let expected_pubkey =
+            hex::decode("02413f8920f46341bab577b955e047bdaed67f972000626d464b4f55af24b2b0bc")
+                .unwrap();
+        let enr = text.parse::<DefaultEnr>().unwrap();
+
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(14, 161, 38, 107)));
+        assert_eq!(enr.id(), Some(String::from("v4")));
+        assert_eq!(enr.udp4(), Some(30503));
+        assert_eq!(enr.tcp4(), Some(30503));
+        assert_eq!(enr.seq(), 40);
+        assert_eq!(enr.signature(), &signature[..]);
+        assert_eq!(enr.public_key().encode().to_vec(), expected_pubkey);
+
+        assert!(enr.verify());
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_read_enr_base64url_decoding_enforce_no_pad_no_extra_trailingbits() {
+        let test_data = [
+            ("padded", "Invalid base64 encoding: InvalidPadding", "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8="),
+            ("extra trailing bits", "Invalid base64 encoding: InvalidLastSymbol(178, 57)", "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl9"),
+        ];
+        for (test_name, err, text) in test_data {
+            assert_eq!(
+                text.parse::<DefaultEnr>().unwrap_err().to_string(),
+                err,
+                "{test_name}",
+            );
+        }
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_read_enr_no_prefix() {
+        let text = "-Iu4QM-YJF2RRpMcZkFiWzMf2kRd1A5F1GIekPa4Sfi_v0DCLTDBfOMTMMWJhhawr1YLUPb5008CpnBKrgjY3sstjfgCgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQP8u1uyQFyJYuQUTyA1raXKhSw1HhhxNUQ2VE52LNHWMIN0Y3CCIyiDdWRwgiMo";
+        text.parse::<DefaultEnr>().unwrap();
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_read_enr_prefix() {
+        let text = "enr:-Iu4QM-YJF2RRpMcZkFiWzMf2kRd1A5F1GIekPa4Sfi_v0DCLTDBfOMTMMWJhhawr1YLUPb5008CpnBKrgjY3sstjfgCgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQP8u1uyQFyJYuQUTyA1raXKhSw1HhhxNUQ2VE52LNHWMIN0Y3CCIyiDdWRwgiMo";
+        text.parse::<DefaultEnr>().unwrap();
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_read_enr_reject_too_large_record() {
+        // 300-byte rlp encoded content, record creation should succeed.
+        let text = concat!("enr:-QEpuEDaLyrPP4gxBI9YL7QE9U1tZig_Nt8rue8bRIuYv_IMziFc8OEt3LQMwkwt6da-Z0Y8BaqkDalZbBq647UtV2ei",
+                           "AYJpZIJ2NIJpcIR_AAABiXNlY3AyNTZrMaEDymNMrg1JrLQB2KTGtv6MVbcNEVv0AHacwUAPMljNMTiDdWRwgnZferiieHh4",
+                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
+                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
+                           "eHh4eHh4eHh4eHh4eHh4");
+        let mut record = text.parse::<DefaultEnr>().unwrap();
+        // Ensures the size check when creating a record from string is
+        // consistent with the internal ones, such as when updating a record
+        // field.
+        let key_data =
+            hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap();
+        let key = k256::ecdsa::SigningKey::from_slice(&key_data).unwrap();
+        assert!(record.set_udp4(record.udp4().unwrap(), &key).is_ok());
+
+        // 301-byte rlp encoded content, record creation should fail.
+        let text = concat!("enr:-QEquEBxABglcZbIGKJ8RHDCp2Ft59tdf61RhV3XXf2BKTlKE2XwzNfihH-46hKkANsXaGRwH8Dp7a3lTrKiv2FMMaFY",
+                           "AYJpZIJ2NIJpcIR_AAABiXNlY3AyNTZrMaEDymNMrg1JrLQB2KTGtv6MVbcNEVv0AHacwUAPMljNMTiDdWRwgnZferijeHh4",
+                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
+                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
+                           "eHh4eHh4eHh4eHh4eHh4eA");
+        assert_eq!(
+            text.parse::<DefaultEnr>().unwrap_err(),
+            EnrParseError::TooLarge
+        );
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_read_enr_rlp_decoding_reject_extra_data() {
+        // Valid record
+        let record_hex1 = concat!(
+            "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
+            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
+            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
+            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
+            "75647082765f"
+        );
+        // Invalid record
+        // Replaces prefix "f884" with "f883", items payload length in bytes: 0x84 -> 0x83
+        let record_hex2 = concat!(
+            "f883b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
+            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
+            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
+            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
+            "75647082765f"
+        );
+        // Invalid record
+        // Appends one byte 252 (0xfc), 1-byte extra data
+        let record_hex3 = concat!(
+            "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
+            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
+            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
+            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
+            "75647082765ffc"
+        );
+
+        let valid_record = hex::decode(record_hex1).unwrap();
+        let expected_pubkey =
+            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
+                .unwrap();
+
+        let enr = rlp::decode::<DefaultEnr>(&valid_record).unwrap();
+        let pubkey = enr.public_key().encode();
+        assert_eq!(pubkey.to_vec(), expected_pubkey);
+        assert!(enr.verify());
+
+        let invalid_record = hex::decode(record_hex2).unwrap();
+        rlp::decode::<DefaultEnr>(&invalid_record).expect_err("should reject extra data");
+
+        let invalid_record = hex::decode(record_hex3).unwrap();
+        rlp::decode::<DefaultEnr>(&invalid_record).expect_err("should reject extra data");
+    }
+
+    /// Tests that RLP integers decoding rejects any item with leading zeroes.
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_rlp_integer_decoding() {
+        // Uses the example node from the ENR spec.
+        //
+        // We first replace "seq" 0x01 with 0x0001 for a leading zero byte,
+        // and then construct the RLP.
+        //
+        // ```
+        // seq = bytes.fromhex('0001')  # replaces 0x01
+        // rlp_data = encode(
+        //     [
+        //         0x7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c,
+        //         seq, 'id', 'v4', 'ip', 0x7f000001, 'secp256k1', bytes.fromhex(
+        //         '03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138'), 'udp', 0x765f])
+        // textual_form = "enr:" + urlsafe_b64encode(rlp_data).decode('utf-8').rstrip('=')
+        // print(textual_form)
+        // ```
+        let text = "enr:-Ia4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5yCAAGCaWSCdjSCaXCEfwAAAYlzZWNwMjU2azGhA8pjTK4NSay0Adikxrb-jFW3DRFb9AB2nMFADzJYzTE4g3VkcIJ2Xw";
+        assert_eq!(
+            text.parse::<DefaultEnr>().unwrap_err(),
+            DecoderError::RlpInvalidIndirection.into()
+        );
+    }
+
+    #[cfg(feature = "rust-secp256k1")]
+    #[test]
+    fn test_encode_decode_secp256k1() {
+        let mut rng = secp256k1::rand::thread_rng();
+        let key = secp256k1::SecretKey::new(&mut rng);
+        let ip = Ipv4Addr::new(127, 0, 0, 1);
+        let tcp = 3000;
+
+        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+
+        let encoded_enr = rlp::encode(&enr);
+
+        let decoded_enr = rlp::decode::<Enr<secp256k1::SecretKey>>(&encoded_enr).unwrap();
+
+        assert_eq!(decoded_enr.id(), Some("v4".into()));
+        assert_eq!(decoded_enr.ip4(), Some(ip));
+        assert_eq!(decoded_enr.tcp4(), Some(tcp));
+        // Must compare encoding as the public key itself can be different
+        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
+        assert!(decoded_enr.verify());
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_encode_decode_k256() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let ip = Ipv4Addr::new(127, 0, 0, 1);
+        let tcp = 3000;
+
+        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+
+        let encoded_enr = rlp::encode(&enr);
+
+        let decoded_enr = rlp::decode::<Enr<k256::ecdsa::SigningKey>>(&encoded_enr).unwrap();
+
+        assert_eq!(decoded_enr.id(), Some("v4".into()));
+        assert_eq!(decoded_enr.ip4(), Some(ip));
+        assert_eq!(decoded_enr.tcp4(), Some(tcp));
+        // Must compare encoding as the public key itself can be different
+        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
+        decoded_enr.public_key().encode_uncompressed();
+        assert!(decoded_enr.verify());
+    }
+
+    #[cfg(all(feature = "ed25519", feature = "k256"))]
+    #[test]
+    fn test_encode_decode_ed25519() {
+        let mut rng = rand::thread_rng();
+        let key = ed25519_dalek::SigningKey::generate(&mut rng);
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let tcp = 30303;
+
+        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+
+        let encoded_enr = rlp::encode(&enr);
+        let decoded_enr = rlp::decode::<Enr<CombinedKey>>(&encoded_enr).unwrap();
+
+        assert_eq!(decoded_enr.id(), Some("v4".into()));
+        assert_eq!(decoded_enr.ip4(), Some(ip));
+        assert_eq!(decoded_enr.tcp4(), Some(tcp));
+        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
+        assert!(decoded_enr.verify());
+    }
+
+    #[cfg(all(feature = "bls", feature = "ed25519", feature = "k256"))]
+    #[test]
+    fn test_encode_decode_bls() {
+        let key = CombinedKey::generate_bls381();
+        let enr = Enr::builder().udp4(9000).build(&key).unwrap();
+        assert!(enr.has_key_field(KeyScheme::Bls381));
+        assert_eq!(
+            enr.raw_public_key_field(KeyScheme::Bls381).map(<[u8]>::len),
+            Some(48)
+        );
+
+        let decoded = rlp::decode::<Enr<CombinedKey>>(&rlp::encode(&enr)).unwrap();
+        assert_eq!(decoded.public_key(), key.public());
+        assert_eq!(decoded.node_id(), enr.node_id());
+        assert!(decoded.verify());
+
+        let decoded: Enr<blst::min_pk::SecretKey> = enr.to_base64().parse().unwrap();
+        assert!(decoded.verify());
+    }
+
+    #[test]
+    fn test_add_key() {
+        let mut rng = rand::thread_rng();
+        let key = k256::ecdsa::SigningKey::random(&mut rng);
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let tcp = 30303;
+
+        let mut enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+
+        enr.insert("random", &Vec::new(), &key).unwrap();
+        assert!(enr.verify());
+    }
+
+    #[test]
+    fn test_set_ip() {
+        let mut rng = rand::thread_rng();
+        let key = k256::ecdsa::SigningKey::random(&mut rng);
+        let tcp = 30303;
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+
+        let mut enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
+
+        assert!(enr.set_ip(ip.into(), &key).is_ok());
+        assert_eq!(enr.id(), Some("v4".into()));
+        assert_eq!(enr.ip4(), Some(ip));
+        assert_eq!(enr.tcp4(), Some(tcp));
+        assert!(enr.verify());
+
+        // Compare the encoding as the key itself can be different
+        assert_eq!(enr.public_key().encode(), key.public().encode());
+    }
+
+    #[test]
+    fn ip_mutation_static_node_id() {
+        let mut rng = rand::thread_rng();
+        let key = k256::ecdsa::SigningKey::random(&mut rng);
+        let tcp = 30303;
+        let udp = 30304;
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+
+        let mut enr = Enr::builder()
+            .ip4(ip)
+            .tcp4(tcp)
+            .udp4(udp)
+            .build(&key)
+            .unwrap();
+
+        let node_id = enr.node_id();
+
+        enr.set_udp_socket("192.168.0.1:800".parse::<SocketAddr>().unwrap(), &key)
+            .unwrap();
+        assert_eq!(node_id, enr.node_id());
+        assert_eq!(
+            enr.udp4_socket(),
+            "192.168.0.1:800".parse::<SocketAddrV4>().unwrap().into()
+        );
+    }
+
+    #[cfg(all(feature = "ed25519", feature = "k256"))]
+    #[test]
+    fn combined_key_can_decode_all() {
+        // generate a random secp256k1 key
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let ip = Ipv4Addr::new(192, 168, 0, 1);
+        let enr_secp256k1 = Enr::builder().ip(ip.into()).tcp4(8000).build(&key).unwrap();
+
+        // encode to base64
+        let base64_string_secp256k1 = enr_secp256k1.to_base64();
+
+        // generate a random ed25519 key
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
+        let enr_ed25519 = Enr::builder().ip(ip.into()).tcp4(8000).build(&key).unwrap();
+
+        // encode to base64
+        let base64_string_ed25519 = enr_ed25519.to_base64();
+
+        // decode base64 strings of varying key types
+        // decode the secp256k1 with default Enr
+        let _decoded_enr_secp256k1: DefaultEnr = base64_string_secp256k1.parse().unwrap();
+        // decode ed25519 ENRs
+        let _decoded_enr_ed25519: Enr<ed25519_dalek::SigningKey> =
+            base64_string_ed25519.parse().unwrap();
+
+        // use the combined key to be able to decode either
+        let _decoded_enr: Enr<CombinedKey> = base64_string_secp256k1
+            .parse()
+            .expect("Can decode both secp");
+        let _decoded_enr: Enr<CombinedKey> = base64_string_ed25519.parse().unwrap();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_remove_insert() {
+        let mut rng = rand::thread_rng();
+        let key = k256::ecdsa::SigningKey::random(&mut rng);
+        let tcp = 30303;
+        let mut topics = Vec::new();
+        let mut s = RlpStream::new();
+        s.begin_list(2);
+        s.append(&"lighthouse");
+        s.append(&"eth_syncing");
+        topics.extend_from_slice(&s.out().freeze());
+
+        let mut enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
+
+        assert_eq!(enr.tcp4(), Some(tcp));
+        assert_eq!(enr.get("topics"), None);
+
+        let topics: &[u8] = &topics;
+
+        let (removed, inserted) = enr
+            .remove_insert([b"tcp"].iter(), vec![(b"topics", topics)].into_iter(), &key)
+            .unwrap();
+
+        assert_eq!(
+            removed[0],
+            Some(rlp::encode(&tcp.to_be_bytes().to_vec()).freeze())
+        );
+        assert_eq!(inserted[0], None);
+
+        assert_eq!(enr.tcp4(), None);
+        assert_eq!(enr.get("topics"), Some(topics));
+
+        // Compare the encoding as the key itself can be different
+        assert_eq!(enr.public_key().encode(), key.public().encode());
+    }
+
+    /// | n     | `rlp::encode(n.to_be_bytes())` | `rlp::encode::<u16>(n)` |
+    /// | ----- | ------------------------------ | ----------------------- |
+    /// | 0     | 0x820000                       | 0x80
+    /// | 30    | 0x82001e                       | 0x1e
+    /// | 255   | 0x8200ff                       | 0x81ff
+    /// | 30303 | 0x82765f                       | 0x82765f
+    const LOW_INT_PORTS: [u16; 4] = [0, 30, 255, 30303];
+
+    #[test]
+    fn test_low_integer_build() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        for tcp in LOW_INT_PORTS {
+            let enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
+
+            assert_tcp4(&enr, tcp);
+        }
+    }
+
+    #[test]
+    fn test_low_integer_set() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        for tcp in LOW_INT_PORTS {
+            let mut enr = Enr::empty(&key).unwrap();
+            enr.set_tcp4(tcp, &key).unwrap();
+            assert_tcp4(&enr, tcp);
+        }
+    }
+
+    #[test]
+    fn test_low_integer_set_socket() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
+
+        for tcp in LOW_INT_PORTS {
+            let mut enr = Enr::empty(&key).unwrap();
+            enr.set_socket(SocketAddr::V4(SocketAddrV4::new(ipv4, tcp)), &key, true)
+                .unwrap();
+            assert_tcp4(&enr, tcp);
+        }
+    }
+
+    #[test]
+    fn test_low_integer_insert() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        for tcp in LOW_INT_PORTS {
+            let mut enr = Enr::empty(&key).unwrap();
+
+            let res = enr.insert(b"tcp", &tcp.to_be_bytes().as_ref(), &key);
+            if u8::try_from(tcp).is_ok() {
+                assert_eq!(res.unwrap_err().to_string(), "invalid rlp data");
+            } else {
+                res.unwrap(); // integers above 255 are encoded correctly
+                assert_tcp4(&enr, tcp);
+            }
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_low_integer_remove_insert() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        for tcp in LOW_INT_PORTS {
+            let mut enr = Enr::empty(&key).unwrap();
+
+            let res = enr.remove_insert(
+                [b"none"].iter(),
+                vec![(b"tcp".as_slice(), tcp.to_be_bytes().as_slice())].into_iter(),
+                &key,
+            );
+            if u8::try_from(tcp).is_ok() {
+                assert_eq!(res.unwrap_err().to_string(), "invalid rlp data");
+            } else {
+                res.unwrap(); // integers above 255 are encoded correctly
+                assert_tcp4(&enr, tcp);
+            }
+        }
+    }
+
+    #[test]
+    fn test_low_integer_bad_enr() {
+        let vectors = vec![
+            (0, "enr:-Hy4QDMsoimQl2Qb9CuIWlNjyt0C0DmZC4QpAsJzgUHowOq2Nph9UbAtZ_qS_8fl6SU-eSWrswHiLCoMUGQfjhl_GW0BgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggAA"),
+            (30, "enr:-Hy4QCCgTB9tAEJL1DFwTTtwd79xxQx2hvi5RX9vWvcdKqbpS3SDzHHBivpOgxE40HGt6P0NtCE5QKzOQ5fzBwepDfMBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggAe"),
+            (255, "enr:-Hy4QOrU9C35gZyJigIi-u19sRP42eEjVEhzO-LnKXKM5VlDMZ45vnOIa3bqm15ap8pmLjq5kmRPzjuA0RUdzSsieqcBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggD_"),
+            (30303, "enr:-Hy4QF_mn4BuM6hY4CuLH8xDQd7U8kVZe9fyNgRB1vjdToGWQsQhetRvsByoJCWGQ6kf2aiWC0le24lkp0IPIJkLSTUBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwgnZf"),
+        ];
+
+        for (tcp, enr_str) in vectors {
+            let res = DefaultEnr::from_str(enr_str);
+            if u8::try_from(tcp).is_ok() {
+                assert_eq!(
+                    res.unwrap_err().to_string(),
+                    "Invalid ENR: RlpInvalidIndirection"
+                );
+            } else {
+                assert_tcp4(&res.unwrap(), tcp);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare_content() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let tcp = 30303;
+
+        let enr1 = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+
+        let mut enr2 = enr1.clone();
+        enr2.set_seq(1, &key).unwrap();
+        let mut enr3 = enr1.clone();
+        enr3.set_seq(2, &key).unwrap();
+
+        // Enr 1 & 2 should be equal, secpk256k1 should have different signatures for the same Enr content
+        assert_ne!(enr1.signature(), enr2.signature());
+        assert!(enr1.compare_content(&enr2));
+        assert_ne!(enr1, enr2);
+
+        // Enr 1 & 3 should not be equal, and have different signatures
+        assert_ne!(enr1.signature(), enr3.signature());
+        assert!(!enr1.compare_content(&enr3));
+        assert_ne!(enr1, enr3);
+    }
+
+    #[test]
+    fn test_verify_with_buffer() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr1 = Enr::builder().tcp4(30303).build(&key).unwrap();
+        let enr2 = Enr::builder().udp4(30303).build(&key).unwrap();
+
+        let mut buffer = BytesMut::new();
+        assert!(enr1.verify_with_buffer(&mut buffer));
+        assert_eq!(buffer, enr1.rlp_content());
+        assert!(enr2.verify_with_buffer(&mut buffer));
+        assert_eq!(buffer, enr2.rlp_content());
+
+        let mut tampered = enr1.clone();
+        tampered.seq += 1;
+        assert!(!tampered.verify_with_buffer(&mut buffer));
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_consistency_policy_reject() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let ip6 = Ipv6Addr::LOCALHOST;
+
+        assert_eq!(
+            DefaultEnr::builder()
+                .tcp6(30303)
+                .consistency_policy(ConsistencyPolicy::Reject)
+                .build(&key)
+                .unwrap_err(),
+            EnrError::OrphanPort("tcp6".into())
+        );
+
+        let mut enr = DefaultEnr::builder()
+            .consistency_policy(ConsistencyPolicy::Reject)
+            .build(&key)
+            .unwrap();
+        let enr_bkp = enr.clone();
+
+        assert_eq!(
+            enr.set_tcp6(30303, &key),
+            Err(EnrError::OrphanPort("tcp6".into()))
+        );
+        assert_eq!(
+            enr.set_ip(ip6.into(), &key),
+            Err(EnrError::OrphanIp("ip6".into()))
+        );
+        assert_eq!(enr, enr_bkp);
+        assert_eq!(enr.tcp6(), None);
+        assert_eq!(enr.ip6(), None);
+
+        enr.set_tcp_socket(SocketAddr::new(ip6.into(), 30303), &key)
+            .unwrap();
+        assert_eq!(enr.tcp6_socket(), Some(SocketAddrV6::new(ip6, 30303, 0, 0)));
+        assert!(enr.verify());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_consistency_policy_prune() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .tcp4(30303)
+            .udp4(30304)
+            .udp6(30305)
+            .consistency_policy(ConsistencyPolicy::Prune)
+            .build(&key)
+            .unwrap();
+
+        enr.remove_insert([b"ip"].iter(), std::iter::empty::<(&[u8], &[u8])>(), &key)
+            .unwrap();
+        assert_eq!(enr.ip4(), None);
+        assert_eq!(enr.tcp4(), None);
+        assert_eq!(enr.udp4(), None);
+        // orphans that were not caused by the removal are left untouched
+        assert_eq!(enr.udp6(), Some(30305));
+        assert!(enr.verify());
+    }
+
+    #[test]
+    fn test_signing_summary() {
+        let key_data =
+            hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap();
+        let key = k256::ecdsa::SigningKey::from_slice(&key_data).unwrap();
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(127, 0, 0, 1))
+            .udp4(30303)
+            .add_value("custom", &vec![1_u8, 2])
+            .build(&key)
+            .unwrap();
+
+        let summary = enr.signing_summary().unwrap();
+        assert_eq!(summary.digest(), enr.signing_digest().unwrap());
+        // the signature is over the digest
+        use k256::ecdsa::signature::hazmat::PrehashVerifier;
+        let signature = k256::ecdsa::Signature::try_from(enr.signature()).unwrap();
+        assert!(enr
+            .public_key()
+            .verify_prehash(&summary.digest(), &signature)
+            .is_ok());
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "digest: 0x{}\nseq: 1\ncustom: 0x820102\nid: v4\nip: 127.0.0.1\n\
+                 secp256k1: 0x03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138\n\
+                 udp: 30303",
+                hex::encode(summary.digest())
+            )
+        );
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_signing_digest_ed25519() {
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
+        let enr = Enr::builder().udp4(30303).build(&key).unwrap();
+        assert_eq!(
+            enr.signing_digest(),
+            Err(EnrError::UnsupportedIdentityScheme)
+        );
+    }
+
+    #[test]
+    fn test_set_endpoints() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+        assert!(enr.endpoints().is_empty());
+
+        let udp4 = Endpoint::new(Transport::Udp, "10.0.0.1:9000".parse().unwrap());
+        let tcp4 = Endpoint::new(Transport::Tcp, "10.0.0.1:9001".parse().unwrap());
+        let udp6 = Endpoint::new(Transport::Udp, "[::1]:9002".parse().unwrap());
+        enr.set_endpoints([udp6.clone(), tcp4.clone(), udp4.clone()], &key)
+            .unwrap();
+
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(
+            enr.endpoints(),
+            vec![udp4.clone(), tcp4.clone(), udp6.clone()]
+        );
+        assert_eq!(enr.endpoint_v4(Transport::Tcp), Some(tcp4.clone()));
+        assert_eq!(enr.port_v6(Transport::Udp), Some(9002));
+        assert_eq!(enr.udp6(), Some(9002));
+        assert_eq!(enr.udp4_socket(), Some("10.0.0.1:9000".parse().unwrap()));
+        assert_eq!(enr.tcp4_socket(), Some("10.0.0.1:9001".parse().unwrap()));
+        assert_eq!(enr.udp6_socket(), Some("[::1]:9002".parse().unwrap()));
+        assert!(enr.verify());
+
+        // endpoints of the same family can't have different ips
+        let enr_bkp = enr.clone();
+        let other_tcp4 = Endpoint::new(Transport::Tcp, "10.0.0.2:9001".parse().unwrap());
+        assert_eq!(
+            enr.set_endpoints([udp4.clone(), other_tcp4], &key),
+            Err(EnrError::ConflictingEndpoints)
+        );
+        assert_eq!(enr, enr_bkp);
+        assert_eq!(enr.endpoints(), vec![udp4, tcp4.clone(), udp6]);
+
+        // extra fields are written along with the endpoint
+        let quic4 = Endpoint::new(Transport::Quic, "10.0.0.1:9003".parse().unwrap())
+            .with_extra("quic-v", rlp::encode(&1_u8).freeze());
+        enr.set_endpoints([quic4], &key).unwrap();
+        assert_eq!(enr.quic4(), Some(9003));
+        assert_eq!(enr.get_decodable::<u8>("quic-v"), Some(Ok(1)));
+        assert!(enr
+            .endpoints()
+            .iter()
+            .all(|endpoint| endpoint.extra.is_empty()));
+    }
+
+    #[test]
+    fn test_set_endpoints_and_insert() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+
+        let udp6 = Endpoint::new(Transport::Udp, "[::1]:9000".parse().unwrap());
+        enr.set_endpoints_and_insert(
+            [udp6],
+            [("custom", rlp::encode(&b"value".as_ref()).freeze())],
+            &key,
+        )
+        .unwrap();
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.udp6_socket(), Some("[::1]:9000".parse().unwrap()));
+        assert_eq!(enr.get("custom"), Some(b"value".as_ref()));
+        assert!(enr.verify());
+
+        // an invalid field reverts the ipv6 endpoint as well
+        let enr_bkp = enr.clone();
+        let other_udp6 = Endpoint::new(Transport::Udp, "[::2]:9001".parse().unwrap());
+        assert!(enr
+            .set_endpoints_and_insert(
+                [other_udp6],
+                [("tcp6", rlp::encode(&b"not a port".as_ref()).freeze())],
+                &key,
+            )
+            .is_err());
+        assert_eq!(enr, enr_bkp);
+        assert_eq!(enr.udp6_socket(), Some("[::1]:9000".parse().unwrap()));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_remove_insert_reverts_invalid_port() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().udp4(9000).build(&key).unwrap();
+        let enr_bkp = enr.clone();
+
+        let result = enr.remove_insert(
+            [b"udp"].iter(),
+            vec![(b"tcp", [1_u8, 2, 3].as_ref())].into_iter(),
+            &key,
+        );
+        assert!(matches!(result, Err(EnrError::InvalidRlpData(_))));
+        assert_eq!(enr, enr_bkp);
+        assert_eq!(enr.udp4(), Some(9000));
+
+        // reserved keys are validated like any other batch update
+        let result = enr.remove_insert(
+            std::iter::empty::<&[u8]>(),
+            vec![(b"ip", [127_u8, 0, 0].as_ref())].into_iter(),
+            &key,
+        );
+        assert!(matches!(result, Err(EnrError::InvalidRlpData(_))));
+        let result = enr.remove_insert([b"id"].iter(), std::iter::empty::<(&[u8], &[u8])>(), &key);
+        assert_eq!(result, Err(EnrError::MandatoryKey("id".into())));
+        assert_eq!(enr, enr_bkp);
+    }
+
+    #[test]
+    fn test_similarity_hash() {
+        let build = |ip: Ipv4Addr, eth2: &[u8]| {
+            let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+            let mut builder = DefaultEnr::builder();
+            builder.ip4(ip).udp4(9000).tcp4(9000);
+            for i in 0..8_u8 {
+                builder.add_value([b'f', i], &[i; 4].as_ref());
+            }
+            builder.add_value("eth2", &eth2).build(&key).unwrap()
+        };
+
+        let enr1 = build(Ipv4Addr::new(10, 0, 0, 1), b"fork");
+        let enr2 = build(Ipv4Addr::new(10, 0, 0, 2), b"fork");
+        let enr3 = build(Ipv4Addr::new(10, 0, 0, 3), b"other fork");
+
+        // different keys and ips are excluded by default
+        assert_eq!(enr1.similarity_hash(), enr2.similarity_hash());
+        assert!(enr1
+            .similarity_hash()
+            .is_similar(&enr3.similarity_hash(), 24));
+        assert_ne!(enr1.similarity_hash(), enr3.similarity_hash());
+
+        let mut config = SimilarityConfig::default();
+        config.include("ip");
+        assert_ne!(
+            enr1.similarity_hash_with(&config),
+            enr2.similarity_hash_with(&config)
+        );
+        assert_ne!(
+            enr1.similarity_hash_with(&SimilarityConfig::all_fields()),
+            enr1.similarity_hash()
+        );
+    }
+
+    #[test]
+    fn test_approx_memory_usage() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let usage = enr.approx_memory_usage();
+        assert!(usage > std::mem::size_of::<DefaultEnr>() + enr.size());
+
+        enr.insert("custom", &vec![0_u8; 100], &key).unwrap();
+        assert!(enr.approx_memory_usage() > usage + 100);
+
+        let decoded = enr.to_base64().parse::<DefaultEnr>().unwrap();
+        assert_eq!(decoded.approx_memory_usage(), enr.approx_memory_usage());
+        assert_eq!(decoded.get("custom"), Some([0_u8; 100].as_ref()));
+    }
+
+    #[cfg(feature = "compact")]
+    #[test]
+    fn test_compact_inline_fields() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let decoded = enr.to_base64().parse::<DefaultEnr>().unwrap();
+
+        // the id, the public key and the port are all stored inline
+        let entry_size = std::mem::size_of::<Key>() + std::mem::size_of::<Value>();
+        assert_eq!(
+            decoded.approx_memory_usage(),
+            std::mem::size_of::<DefaultEnr>() + decoded.signature.capacity() + 3 * entry_size
+        );
+        assert!(decoded.content.values().all(|value| !value.spilled()));
+    }
+
+    #[test]
+    fn test_descriptor() {
+        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+        let enr = text.parse::<DefaultEnr>().unwrap();
+
+        let descriptor = enr.descriptor();
+        assert_eq!(
+            descriptor.to_string(),
+            "a448f24c6d18e575@secp256k1/udp=127.0.0.1:30303"
+        );
+        assert_eq!(
+            descriptor.to_string().parse::<NodeDescriptor>().unwrap(),
+            descriptor
+        );
+        assert!(descriptor.matches(&enr));
+
+        for matching in ["a448", "a448f24c@secp256k1", "a4/udp=127.0.0.1:30303"] {
+            assert!(matching.parse::<NodeDescriptor>().unwrap().matches(&enr));
+        }
+        for not_matching in [
+            "a449",
+            "a448@ed25519",
+            "a448/tcp=127.0.0.1:30303",
+            "a448/fork=01020304",
+        ] {
+            assert!(!not_matching
+                .parse::<NodeDescriptor>()
+                .unwrap()
+                .matches(&enr));
+        }
+        assert_eq!(
+            "".parse::<NodeDescriptor>(),
+            Err(DescriptorParseError::Empty)
+        );
+
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder()
+            .add_value(
+                "eth2",
+                &hex::decode("0102030400000000ffffffffffffffff").unwrap(),
+            )
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.descriptor().fork_digest(), Some([1, 2, 3, 4]));
+        assert!(enr
+            .descriptor()
+            .to_string()
+            .ends_with("@secp256k1/fork=01020304"));
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        for seq in [0, 1, 127, 128, 256, u64::MAX] {
+            for value_len in [0, 1, 55, 56, 150] {
+                let enr = DefaultEnr::builder()
+                    .seq(seq)
+                    .add_value("custom", &vec![0x80_u8; value_len])
+                    .build(&key)
+                    .unwrap();
+                let encoded = rlp::encode(&enr);
+                assert_eq!(enr.encoded_len(), encoded.len());
+
+                // encoding appends to existing data
+                let mut buffer = BytesMut::from(&b"prefix"[..]);
+                enr.encode_to(&mut buffer);
+                assert_eq!(&buffer[..6], b"prefix");
+                assert_eq!(&buffer[6..], &encoded[..]);
+                assert_eq!(rlp::encode(&&enr), encoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_context() {
+        // a record holding only an ed25519 key, which a secp256k1 ENR cannot verify
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&vec![0_u8; 64]);
+        stream.append(&1_u64);
+        stream.append(&"ed25519".as_bytes());
+        stream.append(&vec![1_u8; 32]);
+        stream.append(&"id".as_bytes());
+        stream.append(&"v4".as_bytes());
+        let encoded = stream.out();
+
+        let (error, context) = match DefaultEnr::decode_with_context(&encoded) {
+            Err(EnrParseError::Rlp { error, context }) => (error, context.unwrap()),
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(error, DecoderError::Custom("Unknown signature"));
+        assert_eq!(context.scheme(), Some("v4"));
+        assert_eq!(context.key_fields(), ["ed25519"]);
+        assert!(context.other_fields().is_empty());
+
+        let text = format!("enr:{}", URL_SAFE_NO_PAD.encode(&encoded));
+        assert_eq!(
+            text.parse::<DefaultEnr>().unwrap_err().to_string(),
+            "Invalid ENR: Custom(\"Unknown signature\") (id: v4, key fields: [ed25519], other fields: [])"
+        );
+
+        // malformed records carry no context
+        assert_eq!(
+            DefaultEnr::decode_with_context(&[0x80]).unwrap_err(),
+            DecoderError::RlpExpectedToBeList.into()
+        );
+    }
+
+    #[test]
+    fn test_map_accessors() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let mut enr = Enr::empty(&key).unwrap();
+        assert_eq!(enr.len(), 2);
+        assert!(!enr.is_empty());
+        assert_eq!(enr.optional_len(), 0);
+        assert!(enr.has_only_mandatory_fields());
+
+        enr.set_udp4(30303, &key).unwrap();
+        assert_eq!(enr.len(), 3);
+        assert_eq!(enr.optional_len(), 1);
+        assert!(!enr.has_only_mandatory_fields());
+        assert!(enr.contains_key("udp"));
+        assert!(!enr.contains_key("tcp"));
+        assert_eq!(
+            enr.keys().collect::<Vec<_>>(),
+            [b"id".as_slice(), b"secp256k1", b"udp"]
+        );
+        assert_eq!(enr.values().count(), 3);
+        assert_eq!(enr.values().last(), enr.get_raw_rlp("udp"));
+    }
+
+    #[test]
+    fn test_ownership_proof() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let mut enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+
+        let proof = enr.ownership_proof(b"challenge", &key).unwrap();
+        assert!(enr.verify_ownership_proof(b"challenge", &proof));
+        assert!(!enr.verify_ownership_proof(b"other challenge", &proof));
+        // proofs are domain separated from record signatures
+        let message = enr.ownership_message(b"challenge");
+        assert!(enr.public_key().verify_message(&message, &proof));
+        assert!(!enr.public_key().verify_v4(&message, &proof));
+
+        // the proof doesn't transfer to other records or record versions
+        let other_key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let other = DefaultEnr::builder().udp4(30303).build(&other_key).unwrap();
+        assert!(!other.verify_ownership_proof(b"challenge", &proof));
+        enr.set_udp4(30304, &key).unwrap();
+        assert!(!enr.verify_ownership_proof(b"challenge", &proof));
+
+        assert_eq!(
+            enr.ownership_proof(b"challenge", &other_key),
+            Err(EnrError::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn test_alias_table() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let mut aliases = AliasTable::new();
+        aliases.alias("udp", "legacy-udp");
+        let aliases = Arc::new(aliases);
+
+        // a record written by an old client
+        let mut enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .add_value("legacy-udp", &30303_u16)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.udp4(), None);
+
+        enr.set_alias_table(Some(aliases.clone()));
+        assert_eq!(enr.udp4(), Some(30303));
+        assert_eq!(
+            enr.udp4_socket(),
+            Some(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30303))
+        );
+
+        // values inserted under an alias are validated as values of the reserved key
+        assert!(enr.insert("legacy-udp", &vec![0_u8; 3], &key).is_err());
+
+        // a record written by an upgraded client can be read under the legacy name
+        let mut enr = DefaultEnr::builder().udp4(30304).build(&key).unwrap();
+        enr.set_alias_table(Some(aliases));
+        assert_eq!(enr.get_decodable::<u16>("legacy-udp"), Some(Ok(30304)));
+    }
+
+    #[test]
+    fn test_assert_sorted_edge_cases() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let enr = DefaultEnr::builder()
+            .add_value("", &1_u8)
+            .add_value([0_u8], &1_u8)
+            .add_value([0_u8, 0], &1_u8)
+            .add_value("tcp", &1_u16)
+            .add_value("tcp6", &1_u16)
+            .add_value("tcq", &1_u16)
+            .add_value([0x7f_u8], &1_u8)
+            .add_value([0x80_u8], &1_u8)
+            .build(&key)
+            .unwrap();
+        enr.assert_sorted();
+        let decoded = rlp::decode::<DefaultEnr>(&rlp::encode(&enr)).unwrap();
+        decoded.assert_sorted();
+        assert_eq!(decoded.to_base64(), enr.to_base64());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_encoded_keys_are_sorted(
+            keys in proptest::collection::btree_set(
+                proptest::collection::vec(
+                    proptest::sample::select(vec![0_u8, b'6', b'c', b'p', b't', 0x7f, 0x80, 0xff]),
+                    0..5,
+                ),
+                0..10,
+            )
+        ) {
+            let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+            let mut builder = DefaultEnr::builder();
+            for k in &keys {
+                builder.add_value(k, &1_u8);
+            }
+            let enr = builder.build(&key).unwrap();
+            enr.assert_sorted();
+
+            let mut expected: Vec<Vec<u8>> = keys.into_iter().collect();
+            expected.extend([b"id".to_vec(), b"secp256k1".to_vec()]);
+            expected.sort();
+            expected.dedup();
+            let decoded = rlp::decode::<DefaultEnr>(&rlp::encode(&enr)).unwrap();
+            proptest::prop_assert_eq!(decoded.keys().map(<[u8]>::to_vec).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[cfg(feature = "verification-cache")]
+    #[test]
+    fn test_verification_cache() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let encoded = rlp::encode(&enr);
+
+        // decoding verifies the record, populating the cache
+        let decoded = rlp::decode::<DefaultEnr>(&encoded).unwrap();
+        assert!(decoded.verify());
+
+        // a forged signature over the same content is not a cache hit
+        let mut forged = rlp::decode::<DefaultEnr>(&encoded).unwrap();
+        forged.signature[0] ^= 1;
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn test_iter_decoded() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(30303)
+            .tcp6(30304)
+            .add_value("quic", &9000_u16)
+            .add_value("eth2", &[1_u8, 2, 3, 4].as_ref())
+            .add_value("ip6", &[1_u8; 3].as_ref())
+            .add_value("custom", &7_u8)
+            .build(&key)
+            .unwrap();
+
+        let public_key = key.verifying_key().to_sec1_bytes();
+        let fields: Vec<FieldValue> = enr.iter_decoded().collect();
+        assert_eq!(
+            fields,
+            [
+                FieldValue::Unknown(b"custom", &[7]),
+                FieldValue::Eth2(&[1, 2, 3, 4]),
+                FieldValue::Id("v4".into()),
+                FieldValue::Ip4(Ipv4Addr::LOCALHOST),
+                FieldValue::Invalid(b"ip6", &[0x83, 1, 1, 1]),
+                FieldValue::Quic(9000),
+                FieldValue::PublicKey {
+                    scheme: "secp256k1",
+                    key: &public_key,
+                },
+                FieldValue::Tcp6(30304),
+                FieldValue::Udp(30303),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_field() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .tcp4(30303)
+            .add_value("attnets", &[0xff_u8; 8].as_ref())
+            .add_value("syncnets", &[0x0f_u8].as_ref())
+            .add_value("eth2", &[1_u8, 2, 3].as_ref())
+            .build(&key)
+            .unwrap();
+
+        assert_eq!(enr.get_field::<fields::Id>(), Some(Ok("v4".into())));
+        assert_eq!(
+            enr.get_field::<fields::Secp256k1>(),
+            Some(Ok(key.public().encode().into()))
+        );
+        assert_eq!(
+            enr.get_field::<fields::Ip>(),
+            Some(Ok(Ipv4Addr::new(10, 0, 0, 1)))
+        );
+        assert_eq!(enr.get_field::<fields::Tcp>(), Some(Ok(30303)));
+        assert_eq!(enr.get_field::<fields::Attnets>(), Some(Ok([0xff; 8])));
+        assert_eq!(enr.get_field::<fields::Syncnets>(), Some(Ok(0x0f)));
+        assert_eq!(enr.get_field::<fields::Udp>(), None);
+        assert_eq!(enr.get_field::<fields::Ip6>(), None);
+        // values of the wrong length are errors rather than absent
+        let enr = DefaultEnr::builder()
+            .add_value("attnets", &[0xff_u8; 4].as_ref())
+            .build(&key)
+            .unwrap();
+        assert_eq!(
+            enr.get_field::<fields::Attnets>(),
+            Some(Err(DecoderError::RlpInvalidLength))
+        );
     }
-}
 
-impl<K: EnrKey> IntoIterator for Enr<K> {
-    type Item = (Key, Bytes);
-
-    type IntoIter = EnrIntoIter;
-
-    fn into_iter(self) -> Self::IntoIter {
-        EnrIntoIter {
-            inner: self.content.into_iter(),
-        }
+    #[test]
+    fn test_utf8_and_uint_fields() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+
+        enr.insert_utf8("client", "lighthouse/v4", &key).unwrap();
+        enr.insert_uint("height", 0, &key).unwrap();
+        enr.insert_uint("weight", 1 << 40, &key).unwrap();
+        assert_eq!(enr.get_utf8("client"), Some(Ok("lighthouse/v4")));
+        assert_eq!(enr.get_uint("height"), Some(Ok(0)));
+        assert_eq!(enr.get_uint("weight"), Some(Ok(1 << 40)));
+        assert_eq!(enr.get_utf8("missing"), None);
+        assert_eq!(enr.get_uint("missing"), None);
+
+        // non-canonical and malformed values are rejected
+        enr.insert_raw_rlp("padded", Bytes::from_static(&[0x82, 0x00, 0x01]), &key)
+            .unwrap();
+        enr.insert_raw_rlp("long", rlp::encode(&[1_u8; 9].as_ref()).freeze(), &key)
+            .unwrap();
+        enr.insert_raw_rlp(
+            "binary",
+            rlp::encode(&[0xff_u8, 0xfe].as_ref()).freeze(),
+            &key,
+        )
+        .unwrap();
+        enr.insert_raw_rlp(
+            "list",
+            rlp::encode_list::<&str, _>(&["a", "b"]).freeze(),
+            &key,
+        )
+        .unwrap();
+        assert!(enr.get_uint("padded").unwrap().is_err());
+        assert!(enr.get_uint("long").unwrap().is_err());
+        assert!(enr.get_utf8("binary").unwrap().is_err());
+        assert!(enr.get_utf8("list").unwrap().is_err());
+        assert!(enr.get_uint("list").unwrap().is_err());
     }
-}
 
-pub(crate) fn digest(b: &[u8]) -> [u8; 32] {
-    let mut output = [0_u8; 32];
-    output.copy_from_slice(&Keccak256::digest(b));
-    output
-}
+    #[test]
+    fn test_custom_identity_scheme() {
+        /// "v4" signatures under another name, with node ids inverted.
+        struct Inverted;
 
-const fn is_keyof_u16(key: &[u8]) -> bool {
-    matches!(key, b"tcp" | b"tcp6" | b"udp" | b"udp6")
-}
+        impl IdentityScheme for Inverted {
+            const ID: &'static str = "inverted";
 
-fn check_spec_reserved_keys(key: &[u8], value: &[u8]) -> Result<(), EnrError> {
-    match key {
-        b"tcp" | b"tcp6" | b"udp" | b"udp6" => {
-            rlp::decode::<u16>(value).map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-        }
-        b"id" => {
-            let id_bytes = rlp::decode::<Vec<u8>>(value)
-                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-            if id_bytes != b"v4" {
-                return Err(EnrError::UnsupportedIdentityScheme);
+            fn node_id<K: EnrKey>(public_key: &K::PublicKey) -> NodeId {
+                let mut raw = V4Scheme::node_id::<K>(public_key).raw();
+                raw.iter_mut().for_each(|byte| *byte = !*byte);
+                NodeId::new(&raw)
             }
-        }
-        b"ip" => {
-            let ip4_bytes = rlp::decode::<Vec<u8>>(value)
-                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-            if ip4_bytes.len() != 4 {
-                return Err(EnrError::InvalidRlpData("Invalid Ipv4 size".to_string()));
+
+            fn sign<K: EnrKey>(key: &K, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+                V4Scheme::sign(key, content)
             }
-        }
-        b"ip6" => {
-            let ip6_bytes = rlp::decode::<Vec<u8>>(value)
-                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-            if ip6_bytes.len() != 16 {
-                return Err(EnrError::InvalidRlpData("Invalid Ipv6 size".to_string()));
+
+            fn verify<K: EnrKey>(public_key: &K::PublicKey, content: &[u8], sig: &[u8]) -> bool {
+                V4Scheme::verify::<K>(public_key, content, sig)
             }
         }
-        b"secp256k1" => {
-            rlp::decode::<Enr<k256::ecdsa::SigningKey>>(value)
-                .map_err(|err| EnrError::InvalidRlpData(err.to_string()))?;
-        }
-        _ => return Ok(()),
-    };
-    Ok(())
-}
 
-#[cfg(test)]
-#[cfg(feature = "k256")]
-mod tests {
-    use super::*;
-    use std::convert::TryFrom;
-    use std::net::Ipv4Addr;
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr: Enr<_, Inverted> = Enr::builder_with_scheme().udp4(30303).build(&key).unwrap();
+        assert_eq!(enr.id(), Some("inverted".into()));
+        assert_eq!(enr.node_id().raw()[0], !NodeId::from(key.public()).raw()[0]);
 
-    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+        enr.set_tcp4(30304, &key).unwrap();
+        assert!(enr.verify());
+        let decoded: Enr<k256::ecdsa::SigningKey, Inverted> = enr.to_base64().parse().unwrap();
+        assert_eq!(decoded, enr);
+        assert!(enr.signing_digest().is_err());
+
+        // the scheme is checked on decoding and cannot be changed
+        assert!(enr.to_base64().parse::<DefaultEnr>().is_err());
+        assert_eq!(
+            enr.insert("id", &"v4", &key).unwrap_err(),
+            EnrError::UnsupportedIdentityScheme
+        );
+    }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_vector_k256() {
-        let valid_record = hex::decode("f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f").unwrap();
-        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
-        let expected_pubkey =
-            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
-                .unwrap();
+    fn test_try_public_key() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::empty(&key).unwrap();
+        assert_eq!(enr.try_public_key().unwrap(), *key.verifying_key());
+        assert_eq!(enr.try_public_key().unwrap(), enr.public_key());
+    }
 
-        let enr = rlp::decode::<DefaultEnr>(&valid_record).unwrap();
+    #[test]
+    fn test_insert_public_key_field() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+        let public_key = rlp::encode(&key.public().encode().to_vec()).freeze();
+        enr.insert_raw_rlp("secp256k1", public_key, &key).unwrap();
+        assert!(enr.verify());
 
-        let pubkey = enr.public_key().encode();
+        let err = enr
+            .insert_raw_rlp(
+                "secp256k1",
+                rlp::encode(&[2_u8; 32].as_ref()).freeze(),
+                &key,
+            )
+            .unwrap_err();
+        assert!(matches!(err, EnrError::InvalidRlpData(msg) if msg.contains("not 33 bytes")));
+        let err = enr
+            .insert_raw_rlp("ed25519", rlp::encode(&[0_u8; 33].as_ref()).freeze(), &key)
+            .unwrap_err();
+        assert!(matches!(err, EnrError::InvalidRlpData(msg) if msg.contains("not 32 bytes")));
+    }
 
-        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
-        assert_eq!(enr.id(), Some(String::from("v4")));
-        assert_eq!(enr.udp4(), Some(30303));
-        assert_eq!(enr.tcp4(), None);
-        assert_eq!(enr.signature(), &signature[..]);
-        assert_eq!(pubkey.to_vec(), expected_pubkey);
+    #[test]
+    fn test_attestation() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let relay_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        assert!(!enr.verify_attestation(&relay_key.public()));
+
+        enr.attest(&relay_key, &key).unwrap();
+        assert_eq!(enr.seq(), 2);
         assert!(enr.verify());
+        assert!(enr.verify_attestation(&relay_key.public()));
+        assert!(!enr.verify_attestation(&key.public()));
+        // attestations are domain separated from record signatures
+        let message = attestation::message(&enr.node_id, &enr.content);
+        let signature = enr.get(ATTESTATION_KEY).unwrap();
+        assert!(!relay_key.public().verify_v4(&message, signature));
+
+        // the attestation survives encoding and re-attesting replaces it
+        let decoded: DefaultEnr = enr.to_base64().parse().unwrap();
+        assert!(decoded.verify_attestation(&relay_key.public()));
+        let other_relay = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        enr.attest(&other_relay, &key).unwrap();
+        assert!(enr.verify_attestation(&other_relay.public()));
+        assert!(!enr.verify_attestation(&relay_key.public()));
+
+        // the attestation covers the other fields, and only the record's key can re-sign it
+        enr.set_udp4(30304, &key).unwrap();
+        assert!(!enr.verify_attestation(&other_relay.public()));
+        assert_eq!(
+            enr.attest(&relay_key, &relay_key),
+            Err(EnrError::PublicKeyMismatch)
+        );
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_vector_2() {
-        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
-        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
-        let expected_pubkey =
-            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
-                .unwrap();
-        let expected_node_id =
-            hex::decode("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")
-                .unwrap();
+    fn test_builder_rejects_conflicting_values() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
 
-        let enr = text.parse::<DefaultEnr>().unwrap();
-        let pubkey = enr.public_key().encode();
-        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
-        assert_eq!(enr.ip6(), None);
-        assert_eq!(enr.id(), Some(String::from("v4")));
-        assert_eq!(enr.udp4(), Some(30303));
-        assert_eq!(enr.udp6(), None);
-        assert_eq!(enr.tcp4(), None);
-        assert_eq!(enr.tcp6(), None);
-        assert_eq!(enr.signature(), &signature[..]);
-        assert_eq!(pubkey.to_vec(), expected_pubkey);
-        assert_eq!(enr.node_id().raw().to_vec(), expected_node_id);
+        // setting a key twice to the same value is fine
+        let enr = DefaultEnr::builder()
+            .tcp4(30303)
+            .add_value("tcp", &30303_u16)
+            .add_value("id", &"v4")
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.tcp4(), Some(30303));
 
-        assert!(enr.verify());
+        assert_eq!(
+            DefaultEnr::builder()
+                .tcp4(30303)
+                .add_value("tcp", &30304_u16)
+                .build(&key)
+                .unwrap_err(),
+            EnrError::ConflictingValue("tcp".into())
+        );
+        assert_eq!(
+            DefaultEnr::builder()
+                .add_value("id", &"v5")
+                .build(&key)
+                .unwrap_err(),
+            EnrError::UnsupportedIdentityScheme
+        );
+        assert_eq!(
+            DefaultEnr::builder()
+                .add_value("custom", &[0_u8; 300].as_ref())
+                .build(&key)
+                .unwrap_err(),
+            EnrError::ValueTooLarge("custom".into())
+        );
+        // too large in total, although each value fits
+        assert_eq!(
+            DefaultEnr::builder()
+                .add_value("a", &[0_u8; 150].as_ref())
+                .add_value("b", &[0_u8; 150].as_ref())
+                .build(&key)
+                .unwrap_err(),
+            EnrError::ExceedsMaxSize
+        );
+
+        // a builder can be reused with another key
+        let mut builder = DefaultEnr::builder();
+        builder.udp4(30303);
+        let first = builder.build(&key).unwrap();
+        let other_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let second = builder.build(&other_key).unwrap();
+        assert_ne!(first.node_id(), second.node_id());
+        assert!(second.verify());
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_vector_2_k256() {
-        let text = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
-        let signature = hex::decode("7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c").unwrap();
-        let expected_pubkey =
-            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
-                .unwrap();
-        let expected_node_id =
-            hex::decode("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")
-                .unwrap();
+    fn test_builder_try_build() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let list = rlp::encode_list::<&[u8], _>(&[b"fork".as_ref(), b"digest".as_ref()]).freeze();
 
-        let enr = text.parse::<Enr<k256::ecdsa::SigningKey>>().unwrap();
-        let pubkey = enr.public_key().encode();
-        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(127, 0, 0, 1)));
-        assert_eq!(enr.ip6(), None);
-        assert_eq!(enr.id(), Some(String::from("v4")));
-        assert_eq!(enr.udp4(), Some(30303));
-        assert_eq!(enr.udp6(), None);
-        assert_eq!(enr.tcp4(), None);
-        assert_eq!(enr.tcp6(), None);
-        assert_eq!(enr.signature(), &signature[..]);
-        assert_eq!(pubkey.to_vec(), expected_pubkey);
-        assert_eq!(enr.node_id().raw().to_vec(), expected_node_id);
+        let enr = DefaultEnr::builder()
+            .udp4(30303)
+            .add_raw_rlp("list", list.clone())
+            .try_build(&key)
+            .unwrap();
+        assert_eq!(enr.seq(), 1);
+        assert_eq!(enr.get_raw_rlp("list"), Some(list.as_ref()));
+        let decoded: DefaultEnr = enr.to_base64().parse().unwrap();
+        assert_eq!(decoded.get_raw_rlp("list"), Some(list.as_ref()));
+
+        // trailing data after the item
+        let mut trailing = list.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            DefaultEnr::builder()
+                .add_raw_rlp("list", trailing.into())
+                .try_build(&key)
+                .unwrap_err(),
+            EnrError::InvalidRlpData("list".into())
+        );
 
-        assert!(enr.verify());
+        // reserved keys are checked
+        assert!(DefaultEnr::builder()
+            .add_raw_rlp("ip", rlp::encode(&[127_u8, 0, 0].as_ref()).freeze())
+            .try_build(&key)
+            .is_err());
+        assert_eq!(
+            DefaultEnr::builder()
+                .add_raw_rlp("id", rlp::encode(&"v5").freeze())
+                .try_build(&key)
+                .unwrap_err(),
+            EnrError::UnsupportedIdentityScheme
+        );
     }
 
-    // the values in the content are rlp lists
     #[test]
-    fn test_rlp_list_value() {
-        let text = "enr:-Je4QH0uN2HkMRmscUp6yvyTOPGtOg9U6lCxBFvCGynyystnDNRJbfz5GhXXY2lcu9tsghMxRiYHoznBwG46GQ7dfm0og2V0aMfGhMvbiDiAgmlkgnY0gmlwhA6hJmuJc2VjcDI1NmsxoQJBP4kg9GNBurV3uVXgR72u1n-XIABibUZLT1WvJLKwvIN0Y3CCdyeDdWRwgncn";
-        let signature = hex::decode("7d2e3761e43119ac714a7acafc9338f1ad3a0f54ea50b1045bc21b29f2cacb670cd4496dfcf91a15d763695cbbdb6c821331462607a339c1c06e3a190edd7e6d").unwrap();
-        let expected_pubkey =
-            hex::decode("02413f8920f46341bab577b955e047bdaed67f972000626d464b4f55af24b2b0bc")
-                .unwrap();
-        let enr = text.parse::<DefaultEnr>().unwrap();
+    #[allow(deprecated)]
+    fn test_genesis_fields() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .add_value("network", &5_u64)
+            .immutable_fields(["network", "chain"])
+            .build(&key)
+            .unwrap();
+        let first = enr.clone();
+        let genesis = enr.genesis().unwrap().unwrap();
+        assert_eq!(genesis.keys(), [b"chain".to_vec(), b"network".to_vec()]);
+        assert!(enr.verify_genesis());
+
+        // other fields can change
+        enr.set_udp4(30303, &key).unwrap();
+        assert!(enr.same_genesis(&first));
+
+        // designated fields, including absent ones, cannot
+        for result in [
+            enr.insert("network", &6_u64, &key),
+            enr.insert("chain", &1_u64, &key),
+            enr.insert(GENESIS_KEY, &"forged", &key),
+        ] {
+            assert_eq!(result.unwrap_err(), EnrError::ImmutableFieldChanged);
+        }
+        assert_eq!(
+            enr.remove_insert(
+                [GENESIS_KEY].iter(),
+                std::iter::empty::<(&[u8], &[u8])>(),
+                &key
+            )
+            .unwrap_err(),
+            EnrError::ImmutableFieldChanged
+        );
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.get_uint("network"), Some(Ok(5)));
+
+        let decoded: DefaultEnr = enr.to_base64().parse().unwrap();
+        assert!(decoded.same_genesis(&first));
+
+        // a record carrying a genesis it does not match
+        let forged = DefaultEnr::builder()
+            .add_value("network", &6_u64)
+            .add_value_rlp(
+                GENESIS_KEY,
+                first.get_raw_rlp(GENESIS_KEY).unwrap().to_vec().into(),
+            )
+            .build(&key)
+            .unwrap();
+        assert!(!forged.verify_genesis());
+        assert!(!forged.same_genesis(&first));
+
+        // records without a genesis
+        let plain = DefaultEnr::empty(&key).unwrap();
+        assert!(plain.genesis().is_none());
+        assert!(plain.verify_genesis());
+        assert!(!plain.same_genesis(&first));
+    }
 
-        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(14, 161, 38, 107)));
-        assert_eq!(enr.id(), Some(String::from("v4")));
-        assert_eq!(enr.udp4(), Some(30503));
-        assert_eq!(enr.tcp4(), Some(30503));
-        assert_eq!(enr.seq(), 40);
-        assert_eq!(enr.signature(), &signature[..]);
-        assert_eq!(enr.public_key().encode().to_vec(), expected_pubkey);
+    #[test]
+    fn test_encode_to_buf() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let small = DefaultEnr::builder().seq(0).build(&key).unwrap();
+        let large = DefaultEnr::builder()
+            .seq(u64::MAX)
+            .ip4(Ipv4Addr::LOCALHOST)
+            .add_value("padding", &vec![0xaa_u8; 100])
+            .build(&key)
+            .unwrap();
 
-        assert!(enr.verify());
+        for enr in [small, large] {
+            let expected = rlp::encode(&enr);
+
+            let mut vec = b"header".to_vec();
+            enr.encode_to_buf(&mut vec);
+            assert_eq!(&vec[6..], expected.as_ref());
+
+            let mut packet = [0_u8; 300];
+            let mut unfilled = &mut packet[..];
+            enr.encode_to_buf(&mut unfilled);
+            assert_eq!(300 - unfilled.len(), enr.encoded_len());
+            assert_eq!(&packet[..enr.encoded_len()], expected.as_ref());
+        }
     }
 
-    #[cfg(feature = "k256")]
+    #[cfg(feature = "test-determinism")]
     #[test]
-    fn test_read_enr_base64url_decoding_enforce_no_pad_no_extra_trailingbits() {
-        let test_data = [
-            ("padded", "Invalid base64 encoding: InvalidPadding", "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8="),
-            ("extra trailing bits", "Invalid base64 encoding: InvalidLastSymbol(178, 57)", "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl9"),
-        ];
-        for (test_name, err, text) in test_data {
-            assert_eq!(text.parse::<DefaultEnr>().unwrap_err(), err, "{test_name}",);
-        }
+    fn test_injected_entropy() {
+        let key = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let build = || DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+
+        crate::inject_entropy([9; 32]);
+        let first = build();
+        crate::inject_entropy([9; 32]);
+        assert_eq!(build().signature(), first.signature());
+        crate::clear_injected_entropy();
+        assert_ne!(build().signature(), first.signature());
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_read_enr_no_prefix() {
-        let text = "-Iu4QM-YJF2RRpMcZkFiWzMf2kRd1A5F1GIekPa4Sfi_v0DCLTDBfOMTMMWJhhawr1YLUPb5008CpnBKrgjY3sstjfgCgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQP8u1uyQFyJYuQUTyA1raXKhSw1HhhxNUQ2VE52LNHWMIN0Y3CCIyiDdWRwgiMo";
-        text.parse::<DefaultEnr>().unwrap();
+    #[allow(deprecated)]
+    fn test_diff() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr1 = Enr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .tcp4(30303)
+            .add_value("note", &"first")
+            .build(&key)
+            .unwrap();
+        let mut enr2 = enr1.clone();
+        enr2.set_tcp4(30304, &key).unwrap();
+        enr2.set_udp4(9000, &key).unwrap();
+        enr2.remove_insert([b"note"].iter(), std::iter::empty::<(&[u8], &[u8])>(), &key)
+            .unwrap();
+
+        let diff = enr1.diff(&enr2);
+        assert_eq!(diff.seq, (1, 4));
+        assert_eq!(diff.added, [(&b"udp"[..], FieldValue::Udp(9000))]);
+        assert_eq!(
+            diff.removed,
+            [(
+                &b"note"[..],
+                FieldValue::Unknown(b"note", &rlp::encode(&"first"))
+            )]
+        );
+        assert_eq!(
+            diff.modified,
+            [(&b"tcp"[..], FieldValue::Tcp(30303), FieldValue::Tcp(30304))]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "seq 1 -> 4\n+ udp 9000\n- note 0x856669727374\n~ tcp 30303 -> 30304\n"
+        );
+
+        let mut enr3 = enr1.clone();
+        enr3.set_seq(5, &key).unwrap();
+        assert!(enr1.diff(&enr3).is_empty());
+        assert!(!diff.is_empty());
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_read_enr_prefix() {
-        let text = "enr:-Iu4QM-YJF2RRpMcZkFiWzMf2kRd1A5F1GIekPa4Sfi_v0DCLTDBfOMTMMWJhhawr1YLUPb5008CpnBKrgjY3sstjfgCgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQP8u1uyQFyJYuQUTyA1raXKhSw1HhhxNUQ2VE52LNHWMIN0Y3CCIyiDdWRwgiMo";
-        text.parse::<DefaultEnr>().unwrap();
+    fn test_apply_changes() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .tcp4(30303)
+            .add_value("tmp_a", &1_u8)
+            .build(&key)
+            .unwrap();
+
+        let mut changes = FieldChanges::new();
+        changes
+            .remove("tcp")
+            .remove_if(|key| key.starts_with(b"tmp_") || key == b"id")
+            .insert("tcp", &1_u16)
+            .insert("udp", &30304_u16);
+        let previous = enr.apply_changes(&changes, &key).unwrap();
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.id(), Some("v4".into()));
+        assert_eq!(enr.tcp4(), Some(1));
+        assert_eq!(enr.udp4(), Some(30304));
+        assert_eq!(enr.get("tmp_a"), None);
+        // the value before the update is reported, not the intermediate removal
+        assert_eq!(previous.get_decodable::<u16>("tcp"), Some(Ok(30303)));
+        assert_eq!(previous.get_decodable::<u8>("tmp_a"), Some(Ok(1)));
+        assert_eq!(previous.get_raw_rlp("udp"), None);
+        assert_eq!(
+            previous.iter_decoded().collect::<Vec<_>>(),
+            [FieldValue::Tcp(30303), FieldValue::Unknown(b"tmp_a", &[1])]
+        );
+
+        // invalid values revert the whole update
+        let mut changes = FieldChanges::new();
+        changes.remove("udp").insert("tcp", &u64::MAX);
+        assert!(enr.apply_changes(&changes, &key).is_err());
+        assert_eq!(enr.udp4(), Some(30304));
+        assert_eq!(enr.seq(), 2);
+    }
+
+    /// Signs for one key with another, as a misbehaving remote signer might.
+    struct WrongSigner(k256::ecdsa::SigningKey, k256::ecdsa::SigningKey);
+
+    impl EnrSigner for WrongSigner {
+        type PublicKey = k256::ecdsa::VerifyingKey;
+
+        fn public_key(&self) -> Self::PublicKey {
+            self.0.public()
+        }
+
+        fn sign_v4(&self, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+            EnrKey::sign_v4(&self.1, content)
+        }
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_read_enr_reject_too_large_record() {
-        // 300-byte rlp encoded content, record creation should succeed.
-        let text = concat!("enr:-QEpuEDaLyrPP4gxBI9YL7QE9U1tZig_Nt8rue8bRIuYv_IMziFc8OEt3LQMwkwt6da-Z0Y8BaqkDalZbBq647UtV2ei",
-                           "AYJpZIJ2NIJpcIR_AAABiXNlY3AyNTZrMaEDymNMrg1JrLQB2KTGtv6MVbcNEVv0AHacwUAPMljNMTiDdWRwgnZferiieHh4",
-                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
-                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
-                           "eHh4eHh4eHh4eHh4eHh4");
-        let mut record = text.parse::<DefaultEnr>().unwrap();
-        // Ensures the size check when creating a record from string is
-        // consistent with the internal ones, such as when updating a record
-        // field.
-        let key_data =
-            hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
-                .unwrap();
-        let key = k256::ecdsa::SigningKey::from_slice(&key_data).unwrap();
-        assert!(record.set_udp4(record.udp4().unwrap(), &key).is_ok());
+    fn test_external_signer() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr: DefaultEnr = Enr::builder().udp4(9000).build_with_signer(&key).unwrap();
+        assert_eq!(enr.public_key(), key.public());
+
+        let mut changes = FieldChanges::new();
+        changes.insert("udp", &9001_u16);
+        enr.apply_changes_with_signer(&changes, &key).unwrap();
+        assert_eq!(enr.udp4(), Some(9001));
+        assert_eq!(enr.seq(), 2);
+        assert!(enr.verify());
+
+        // invalid signatures are rejected and the record is reverted
+        let other = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let wrong = WrongSigner(key.clone(), other);
+        assert_eq!(
+            DefaultEnr::builder().build_with_signer(&wrong).unwrap_err(),
+            EnrError::SigningError
+        );
+        changes.insert("udp", &9002_u16);
+        assert_eq!(
+            enr.apply_changes_with_signer(&changes, &wrong).unwrap_err(),
+            EnrError::SigningError
+        );
+        assert_eq!(enr.udp4(), Some(9001));
+        assert_eq!(enr.seq(), 2);
+    }
+
+    #[cfg(feature = "async-signing")]
+    #[tokio::test]
+    async fn test_async_signer() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr: DefaultEnr = Enr::builder().udp4(9000).build_async(&key).await.unwrap();
 
-        // 301-byte rlp encoded content, record creation should fail.
-        let text = concat!("enr:-QEquEBxABglcZbIGKJ8RHDCp2Ft59tdf61RhV3XXf2BKTlKE2XwzNfihH-46hKkANsXaGRwH8Dp7a3lTrKiv2FMMaFY",
-                           "AYJpZIJ2NIJpcIR_AAABiXNlY3AyNTZrMaEDymNMrg1JrLQB2KTGtv6MVbcNEVv0AHacwUAPMljNMTiDdWRwgnZferijeHh4",
-                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
-                           "eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4eHh4",
-                           "eHh4eHh4eHh4eHh4eHh4eA");
-        assert!(text
-            .parse::<DefaultEnr>()
-            .unwrap_err()
-            .contains("enr exceeds max size"));
+        let mut changes = FieldChanges::new();
+        changes.insert("tcp", &9001_u16);
+        enr.apply_changes_async(&changes, &key).await.unwrap();
+        assert_eq!(enr.tcp4(), Some(9001));
+        assert!(enr.verify());
+
+        let wrong = WrongSigner(
+            key,
+            k256::ecdsa::SigningKey::random(&mut rand::thread_rng()),
+        );
+        assert!(enr.apply_changes_async(&changes, &wrong).await.is_err());
+        assert_eq!(enr.seq(), 2);
     }
 
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_read_enr_rlp_decoding_reject_extra_data() {
-        // Valid record
-        let record_hex1 = concat!(
-            "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
-            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
-            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
-            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
-            "75647082765f"
+    // the cached encoding of a record is interior mutable, but not part of its hash
+    #[allow(clippy::mutable_key_type)]
+    fn test_fingerprint64() {
+        use std::collections::HashSet;
+
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().udp4(9000).build(&key).unwrap();
+        let first = enr.clone();
+        enr.set_udp4(9001, &key).unwrap();
+
+        assert_eq!(first.fingerprint64(), first.clone().fingerprint64());
+        assert_ne!(first.fingerprint64(), enr.fingerprint64());
+
+        // a record with different content under the same sequence number shares the fingerprint
+        // but is still a distinct element
+        let mut equivocation = first.clone();
+        equivocation.set_udp4(9002, &key).unwrap();
+        equivocation.set_seq(first.seq(), &key).unwrap();
+        assert_eq!(first.fingerprint64(), equivocation.fingerprint64());
+        assert_ne!(first, equivocation);
+
+        let set: HashSet<_> = [first.clone(), enr, equivocation, first]
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_remove() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(9000)
+            .tcp4(9001)
+            .add_value("note", &"a")
+            .build(&key)
+            .unwrap();
+
+        let removed = enr.remove(["note", "tcp", "missing"], &key).unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                Some(rlp::encode(&"a").freeze()),
+                Some(rlp::encode(&9001_u16).freeze()),
+                None
+            ]
         );
-        // Invalid record
-        // Replaces prefix "f884" with "f883", items payload length in bytes: 0x84 -> 0x83
-        let record_hex2 = concat!(
-            "f883b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
-            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
-            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
-            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
-            "75647082765f"
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.get("note"), None);
+        assert_eq!(enr.tcp4(), None);
+        assert!(enr.verify());
+
+        // mandatory keys are refused and leave the record unchanged
+        let before = enr.clone();
+        assert_eq!(
+            enr.remove(["udp", "secp256k1"], &key).unwrap_err(),
+            EnrError::MandatoryKey("secp256k1".into())
         );
-        // Invalid record
-        // Appends one byte 252 (0xfc), 1-byte extra data
-        let record_hex3 = concat!(
-            "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599",
-            "ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1",
-            "145ccb9c01826964827634826970847f00000189736563703235366b31a103ca",
-            "634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd313883",
-            "75647082765ffc"
+        assert_eq!(
+            enr.remove(["id"], &key).unwrap_err(),
+            EnrError::MandatoryKey("id".into())
         );
+        assert_eq!(enr, before);
+        assert_eq!(enr.udp4(), Some(9000));
+    }
 
-        let valid_record = hex::decode(record_hex1).unwrap();
-        let expected_pubkey =
-            hex::decode("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")
-                .unwrap();
+    #[test]
+    fn test_update_transaction() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .ip6(Ipv6Addr::LOCALHOST)
+            .udp6(9000)
+            .build(&key)
+            .unwrap();
 
-        let enr = rlp::decode::<DefaultEnr>(&valid_record).unwrap();
-        let pubkey = enr.public_key().encode();
-        assert_eq!(pubkey.to_vec(), expected_pubkey);
+        let previous = enr
+            .update(&key, |tx| {
+                tx.set_tcp_socket(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 9001))
+                    .insert("attnets", &[0xff_u8; 8].as_ref())
+                    .remove("udp6")
+                    .set_udp6(9002);
+            })
+            .unwrap();
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.tcp4(), Some(9001));
+        assert_eq!(enr.udp6(), Some(9002));
+        assert_eq!(previous.get_decodable::<u16>("udp6"), Some(Ok(9000)));
+        assert_eq!(previous.len(), 1);
         assert!(enr.verify());
 
-        let invalid_record = hex::decode(record_hex2).unwrap();
-        rlp::decode::<DefaultEnr>(&invalid_record).expect_err("should reject extra data");
+        // any invalid modification reverts the whole update
+        let before = enr.clone();
+        let conflicting = enr.update(&key, |tx| {
+            tx.remove("attnets")
+                .set_udp_socket(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 9003))
+                .set_tcp_socket(SocketAddr::new(Ipv4Addr::new(10, 0, 0, 1).into(), 9004));
+        });
+        assert_eq!(conflicting.unwrap_err(), EnrError::ConflictingEndpoints);
+        assert_eq!(
+            enr.update(&key, |tx| {
+                tx.set_tcp4(9005).remove("secp256k1");
+            })
+            .unwrap_err(),
+            EnrError::MandatoryKey("secp256k1".into())
+        );
+        assert_eq!(
+            enr.update(&key, |tx| {
+                tx.insert("x", &vec![0_u8; 300]);
+            })
+            .unwrap_err(),
+            EnrError::ExceedsMaxSize
+        );
+        assert_eq!(enr, before);
+        assert!(enr.get_raw_rlp("attnets").is_some());
+    }
 
-        let invalid_record = hex::decode(record_hex3).unwrap();
-        rlp::decode::<DefaultEnr>(&invalid_record).expect_err("should reject extra data");
+    #[test]
+    fn test_key_fields() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::empty(&key).unwrap();
+        assert!(enr.has_key_field(KeyScheme::Secp256k1));
+        assert!(!enr.has_key_field(KeyScheme::Ed25519));
+        assert_eq!(
+            enr.raw_public_key_field(KeyScheme::Secp256k1),
+            Some(key.public().encode().as_ref())
+        );
+        assert_eq!(enr.raw_public_key_field(KeyScheme::Ed25519), None);
+
+        // key fields of the wrong length are reported as such, before verifying the signature
+        for (scheme, len, error) in [
+            (
+                KeyScheme::Secp256k1,
+                32,
+                "secp256k1 public key is not 33 bytes",
+            ),
+            (
+                KeyScheme::Secp256k1,
+                65,
+                "secp256k1 public key is not 33 bytes",
+            ),
+            (KeyScheme::Ed25519, 33, "ed25519 public key is not 32 bytes"),
+        ] {
+            let mut stream = RlpStream::new_list(6);
+            stream.append(&vec![0_u8; 64]);
+            stream.append(&1_u64);
+            stream.append(&"id");
+            stream.append(&"v4");
+            stream.append(&scheme.enr_key());
+            stream.append(&vec![2_u8; len]);
+            assert_eq!(
+                rlp::decode::<DefaultEnr>(&stream.out()).unwrap_err(),
+                DecoderError::Custom(error)
+            );
+        }
     }
 
-    /// Tests that RLP integers decoding rejects any item with leading zeroes.
-    #[cfg(feature = "k256")]
     #[test]
-    fn test_rlp_integer_decoding() {
-        // Uses the example node from the ENR spec.
-        //
-        // We first replace "seq" 0x01 with 0x0001 for a leading zero byte,
-        // and then construct the RLP.
-        //
-        // ```
-        // seq = bytes.fromhex('0001')  # replaces 0x01
-        // rlp_data = encode(
-        //     [
-        //         0x7098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c,
-        //         seq, 'id', 'v4', 'ip', 0x7f000001, 'secp256k1', bytes.fromhex(
-        //         '03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138'), 'udp', 0x765f])
-        // textual_form = "enr:" + urlsafe_b64encode(rlp_data).decode('utf-8').rstrip('=')
-        // print(textual_form)
-        // ```
-        let text = "enr:-Ia4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5yCAAGCaWSCdjSCaXCEfwAAAYlzZWNwMjU2azGhA8pjTK4NSay0Adikxrb-jFW3DRFb9AB2nMFADzJYzTE4g3VkcIJ2Xw";
+    fn test_compact() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mapped: Ipv6Addr = "::ffff:10.0.0.1".parse().unwrap();
+        let mut enr = DefaultEnr::builder()
+            .ip6(mapped)
+            .udp6(9000)
+            .tcp4(9001)
+            .add_value("attnets", &[0xff_u8; 8].as_ref())
+            .add_value("subnets", &[0xff_u8; 8].as_ref())
+            .build(&key)
+            .unwrap();
+        // duplicates are only known through aliases
+        assert_eq!(enr.compaction_report().compactions.len(), 1);
+
+        let mut aliases = AliasTable::new();
+        aliases.alias("subnets", "attnets");
+        enr.set_alias_table(Some(Arc::new(aliases)));
+        let report = enr.compaction_report();
         assert_eq!(
-            text.parse::<DefaultEnr>().unwrap_err(),
-            "Invalid ENR: RlpInvalidIndirection"
+            report.compactions,
+            vec![
+                Compaction::Ipv4MappedIpv6 {
+                    address: mapped,
+                    saving: 14
+                },
+                Compaction::DuplicateAlias {
+                    key: "attnets".into(),
+                    alias: "subnets".into(),
+                    saving: 17
+                }
+            ]
         );
+
+        let size = enr.size();
+        assert_eq!(enr.compact(&key).unwrap(), report);
+        assert_eq!(enr.seq(), 2);
+        assert!(size - enr.size() >= report.savings());
+        assert_eq!(enr.ip6(), None);
+        assert_eq!(enr.udp4_socket(), Some("10.0.0.1:9000".parse().unwrap()));
+        assert_eq!(enr.tcp4(), Some(9001));
+        assert_eq!(enr.get_raw_rlp("subnets"), enr.get_raw_rlp("attnets"));
+        assert!(enr.compaction_report().is_empty());
+
+        // nothing to compact leaves the record unchanged
+        assert!(enr.compact(&key).unwrap().is_empty());
+        assert_eq!(enr.seq(), 2);
     }
 
-    #[cfg(feature = "rust-secp256k1")]
     #[test]
-    fn test_encode_decode_secp256k1() {
-        let mut rng = secp256k1::rand::thread_rng();
-        let key = secp256k1::SecretKey::new(&mut rng);
-        let ip = Ipv4Addr::new(127, 0, 0, 1);
-        let tcp = 3000;
+    fn test_record_flags() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = Enr::builder()
+            .flags(RecordFlags::ARCHIVE_NODE)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.flags(), Some(RecordFlags::ARCHIVE_NODE));
 
-        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+        let seq = enr.seq();
+        let flags = enr
+            .update_flags(
+                RecordFlags::RELAY_CAPABLE | RecordFlags::user(31),
+                RecordFlags::ARCHIVE_NODE,
+                &key,
+            )
+            .unwrap();
+        assert_eq!(flags, RecordFlags::RELAY_CAPABLE | RecordFlags::user(31));
+        assert_eq!(enr.seq(), seq + 1);
+        assert!(enr.verify());
 
-        let encoded_enr = rlp::encode(&enr);
+        // unknown bits survive a round trip
+        enr.set_flags(RecordFlags::from_bits_retain(1 << 20), &key)
+            .unwrap();
+        let decoded = rlp::decode::<DefaultEnr>(&rlp::encode(&enr)).unwrap();
+        assert_eq!(
+            decoded.flags(),
+            Some(RecordFlags::from_bits_retain(1 << 20))
+        );
 
-        let decoded_enr = rlp::decode::<Enr<secp256k1::SecretKey>>(&encoded_enr).unwrap();
+        let mut policy = policy::Policy::new(policy::Action::Deny);
+        policy.rule(
+            policy::Action::Allow,
+            policy::Condition::HasFlags(RecordFlags::from_bits_retain(1 << 20)),
+        );
+        assert!(policy.evaluate(&decoded).is_allowed());
+        enr.update_flags(RecordFlags::ARCHIVE_NODE, RecordFlags::all(), &key)
+            .unwrap();
+        assert!(!policy.evaluate(&enr).is_allowed());
 
-        assert_eq!(decoded_enr.id(), Some("v4".into()));
-        assert_eq!(decoded_enr.ip4(), Some(ip));
-        assert_eq!(decoded_enr.tcp4(), Some(tcp));
-        // Must compare encoding as the public key itself can be different
-        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
-        assert!(decoded_enr.verify());
+        // a flags field that is not a `u64` is rejected
+        assert!(enr.insert(FLAGS_KEY, &"archive node", &key).is_err());
     }
 
-    #[cfg(feature = "rust-secp256k1")]
     #[test]
-    fn test_secp256k1_sign_ecdsa_with_mock_noncedata() {
-        // Uses the example record from the ENR spec.
-        //
-        // The feature "rust-secp256k1" creates ECDSA signatures with additional random data.
-        // Under the unit testing environment, the mock value `MOCK_ECDSA_NONCE_ADDITIONAL_DATA`
-        // is always used.
-        //
-        // The expected ENR textual form `expected_enr_base64` is constructed by a Python script:
-        // ```
-        // key = SigningKey.from_secret_exponent(
-        //     0xb71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291, curve=SECP256k1)
-        //
-        // # Builds content RLP
-        // rlp_data = encode([1, 'id', 'v4', 'ip', 0x7f000001, 'secp256k1', bytes.fromhex(
-        //     '03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138'), 'udp', 0x765f])
-        // rlp_data_hash = keccak(rlp_data)
-        //
-        // # Signs the content RLP **with** the additional data.
-        // additional_data = bytes.fromhex(
-        //     'baaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaadbaaaaaad')
-        // content_signature = key.sign_digest_deterministic(rlp_data_hash, hashfunc=sha256,
-        //                                                   sigencode=sigencode_string_canonize,
-        //                                                   extra_entropy=additional_data)
-        // rlp_with_signature = encode(
-        //     [content_signature, 1, 'id', 'v4', 'ip', 0x7f000001, 'secp256k1', bytes.fromhex(
-        //         '03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138'), 'udp', 0x765f])
-        // textual_form = "enr:" + urlsafe_b64encode(rlp_with_signature).decode('utf-8').rstrip('=')
-        // ```
-        let expected_enr_base64 = "enr:-IS4QLJYdRwxdy-AbzWC6wL9ooB6O6uvCvJsJ36rbJztiAs1JzPY0__YkgFzZwNUuNhm1BDN6c4-UVRCJP9bXNCmoDYBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+    fn test_signature_mode() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let standard = Enr::builder().udp4(30303).build(&key).unwrap();
+        assert_eq!(standard.signature_mode(), SignatureMode::Standard);
+        assert_eq!(
+            standard.recover_public_key(),
+            Err(EnrError::UnrecoverableSignature)
+        );
 
-        let key_data =
-            hex::decode("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+        for mode in [
+            SignatureMode::Recoverable,
+            SignatureMode::RecoverableWithoutPublicKey,
+        ] {
+            let mut enr: DefaultEnr = Enr::builder()
+                .signature_mode(mode)
+                .udp4(30303)
+                .build(&key)
                 .unwrap();
-        let ip = Ipv4Addr::new(127, 0, 0, 1);
-        let udp = 30303;
+            assert_eq!(enr.signature_mode(), mode);
+            assert_eq!(enr.signature().len(), 65);
+            assert_eq!(enr.recover_public_key().unwrap(), *key.verifying_key());
+            assert_eq!(enr.public_key(), *key.verifying_key());
+            assert_eq!(enr.node_id(), standard.node_id());
+            assert!(enr.verify());
+
+            // the mode is kept across updates
+            enr.set_udp4(30304, &key).unwrap();
+            assert_eq!(enr.signature_mode(), mode);
+            let decoded = rlp::decode::<DefaultEnr>(&rlp::encode(&enr)).unwrap();
+            assert_eq!(decoded, enr);
+            assert_eq!(decoded.node_id(), standard.node_id());
+        }
 
-        let key = secp256k1::SecretKey::from_slice(&key_data).unwrap();
-        let enr = Enr::builder().ip4(ip).udp4(udp).build(&key).unwrap();
-        let enr_base64 = enr.to_base64();
-        assert_eq!(enr_base64, expected_enr_base64);
+        let stripped: DefaultEnr = Enr::builder()
+            .signature_mode(SignatureMode::RecoverableWithoutPublicKey)
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
+        assert!(stripped.get_raw_rlp("secp256k1").is_none());
+        assert!(stripped.size() + 40 < standard.size());
+
+        // a record without a public key field must carry a recoverable signature
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&&stripped.signature()[..64]);
+        stream.append(&stripped.seq());
+        stream.append(&"id");
+        stream.append(&"v4");
+        stream.append(&"udp");
+        stream.append(&30303_u16);
+        assert!(rlp::decode::<DefaultEnr>(&stream.out()).is_err());
+    }
 
-        let enr = enr_base64.parse::<Enr<secp256k1::SecretKey>>().unwrap();
-        assert!(enr.verify());
+    #[cfg(feature = "eth2")]
+    #[test]
+    fn test_subnets() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .add_value("attnets", &[0b101_u8, 0, 0, 0, 0, 0, 0, 0].as_ref())
+            .build(&key)
+            .unwrap();
+        assert!(enr.is_subscribed_to_attnet(0));
+        assert!(!enr.is_subscribed_to_attnet(1));
+        assert!(enr.is_subscribed_to_attnet(2));
+        assert!(!enr.is_subscribed_to_attnet(64));
+        assert_eq!(enr.sync_committee_subnets(), None);
+        assert!(!enr.is_subscribed_to_syncnet(0));
+
+        let mut syncnets = SyncCommitteeSubnets::new();
+        syncnets.set(1, true);
+        assert_eq!(enr.set_sync_committee_subnets(syncnets, &key), Ok(None));
+        assert!(enr.is_subscribed_to_syncnet(1));
+        assert_eq!(enr.get_field::<fields::Syncnets>(), Some(Ok(0b10)));
+
+        let previous = enr
+            .set_attestation_subnets(AttestationSubnets::new(), &key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(previous.subnets().collect::<Vec<_>>(), [0, 2]);
+        assert!(!enr.is_subscribed_to_attnet(0));
+
+        // malformed bitfields subscribe to no subnet
+        enr.insert("syncnets", &[0xff_u8].as_ref(), &key).unwrap();
+        assert!(matches!(enr.sync_committee_subnets(), Some(Err(_))));
+        assert!(!enr.is_subscribed_to_syncnet(1));
     }
 
-    #[cfg(feature = "k256")]
+    #[cfg(feature = "eth2")]
     #[test]
-    fn test_encode_decode_k256() {
-        let key = k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng);
-        let ip = Ipv4Addr::new(127, 0, 0, 1);
-        let tcp = 3000;
+    fn test_eth2_fork_id() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let fork_id = EnrForkId {
+            fork_digest: [0x6a, 0x95, 0xa1, 0xa9],
+            next_fork_version: [4, 0, 0, 0],
+            next_fork_epoch: u64::MAX,
+        };
+        let mut enr = DefaultEnr::builder()
+            .eth2_fork_id(fork_id)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.eth2_fork_id(), Some(Ok(fork_id)));
+        assert_eq!(
+            enr.get_raw_rlp(ETH2_KEY).unwrap()[1..],
+            fork_id.to_ssz_bytes()
+        );
 
-        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+        let next = EnrForkId {
+            next_fork_epoch: 269_568,
+            ..fork_id
+        };
+        assert_eq!(enr.set_eth2_fork_id(next, &key), Ok(Some(fork_id)));
+        assert_eq!(enr.eth2_fork_id(), Some(Ok(next)));
 
-        let encoded_enr = rlp::encode(&enr);
+        // a truncated fork id is an error rather than absent
+        enr.insert(ETH2_KEY, &[1_u8, 2, 3, 4].as_ref(), &key)
+            .unwrap();
+        assert_eq!(
+            enr.eth2_fork_id(),
+            Some(Err(DecoderError::RlpInvalidLength))
+        );
+    }
 
-        let decoded_enr = rlp::decode::<Enr<k256::ecdsa::SigningKey>>(&encoded_enr).unwrap();
+    #[cfg(feature = "eth2")]
+    #[test]
+    fn test_is_compatible_fork() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let fork_id = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            next_fork_version: [2, 0, 0, 0],
+            next_fork_epoch: 1000,
+        };
+        let build = |fork_id: EnrForkId| {
+            DefaultEnr::builder()
+                .eth2_fork_id(fork_id)
+                .build(&key)
+                .unwrap()
+        };
+        let local = build(fork_id);
 
-        assert_eq!(decoded_enr.id(), Some("v4".into()));
-        assert_eq!(decoded_enr.ip4(), Some(ip));
-        assert_eq!(decoded_enr.tcp4(), Some(tcp));
-        // Must compare encoding as the public key itself can be different
-        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
-        decoded_enr.public_key().encode_uncompressed();
-        assert!(decoded_enr.verify());
+        assert_eq!(
+            local.is_compatible_fork(&build(fork_id)),
+            ForkCompatibility::Compatible
+        );
+
+        let rescheduled = EnrForkId {
+            next_fork_epoch: u64::MAX,
+            ..fork_id
+        };
+        let compatibility = local.is_compatible_fork(&build(rescheduled));
+        assert_eq!(
+            compatibility,
+            ForkCompatibility::NextForkMismatch {
+                local: fork_id,
+                remote: rescheduled,
+            }
+        );
+        assert!(compatibility.is_compatible());
+
+        let forked = EnrForkId {
+            fork_digest: [5, 6, 7, 8],
+            ..fork_id
+        };
+        let compatibility = local.is_compatible_fork(&build(forked));
+        assert!(!compatibility.is_compatible());
+        assert_eq!(
+            compatibility.to_string(),
+            "fork digest mismatch: local 0x01020304, remote 0x05060708"
+        );
+
+        let without_eth2 = DefaultEnr::empty(&key).unwrap();
+        assert_eq!(
+            local.is_compatible_fork(&without_eth2),
+            ForkCompatibility::MissingForkId
+        );
+        assert_eq!(
+            without_eth2.is_compatible_fork(&local),
+            ForkCompatibility::MissingForkId
+        );
     }
 
-    #[cfg(all(feature = "ed25519", feature = "k256"))]
     #[test]
-    fn test_encode_decode_ed25519() {
-        let mut rng = rand::thread_rng();
-        let key = ed25519_dalek::SigningKey::generate(&mut rng);
-        let ip = Ipv4Addr::new(10, 0, 0, 1);
-        let tcp = 30303;
+    fn test_quic_builder() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let quic4: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let quic6: SocketAddr = "[::1]:9001".parse().unwrap();
+        let enr = DefaultEnr::builder()
+            .quic_socket(quic4)
+            .quic_socket(quic6)
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.seq(), 1);
+        assert_eq!(enr.quic4(), Some(9000));
+        assert_eq!(enr.quic6(), Some(9001));
+        assert_eq!(enr.quic4_socket().map(SocketAddr::V4), Some(quic4));
+        assert_eq!(enr.quic6_socket().map(SocketAddr::V6), Some(quic6));
+        assert_eq!(
+            enr.endpoints(),
+            [
+                Endpoint::new(Transport::Udp, "10.0.0.1:30303".parse().unwrap()),
+                Endpoint::new(Transport::Quic, quic4),
+                Endpoint::new(Transport::Quic, quic6),
+            ]
+        );
 
-        let enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+        let enr = DefaultEnr::builder().quic6(9001).build(&key).unwrap();
+        assert_eq!(enr.quic6(), Some(9001));
+        assert_eq!(enr.quic6_socket(), None);
+        // a QUIC port without an IP address is an orphan
+        assert_eq!(
+            DefaultEnr::builder()
+                .quic4(9000)
+                .consistency_policy(ConsistencyPolicy::Reject)
+                .build(&key),
+            Err(EnrError::OrphanPort("quic".into()))
+        );
+    }
 
-        let encoded_enr = rlp::encode(&enr);
-        let decoded_enr = rlp::decode::<Enr<CombinedKey>>(&encoded_enr).unwrap();
+    #[test]
+    fn test_set_sockets() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let sockets: [(Transport, SocketAddr); 4] = [
+            (Transport::Udp, "10.0.0.1:9000".parse().unwrap()),
+            (Transport::Tcp, "10.0.0.1:9001".parse().unwrap()),
+            (Transport::Udp, "[::1]:9000".parse().unwrap()),
+            (Transport::Quic, "[::1]:9002".parse().unwrap()),
+        ];
+        let expected: Vec<_> = sockets
+            .iter()
+            .map(|(transport, socket)| Endpoint::new(*transport, *socket))
+            .collect();
+
+        let built = DefaultEnr::builder().sockets(&sockets).build(&key).unwrap();
+        assert_eq!(built.endpoints(), expected);
+
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+        enr.set_sockets(&sockets, &key).unwrap();
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(enr.endpoints(), expected);
+
+        // sockets of one family with different IP addresses conflict
+        let conflicting = [
+            (Transport::Udp, "10.0.0.1:9000".parse().unwrap()),
+            (Transport::Tcp, "10.0.0.2:9001".parse().unwrap()),
+        ];
+        assert_eq!(
+            enr.set_sockets(&conflicting, &key),
+            Err(EnrError::ConflictingEndpoints)
+        );
+        assert_eq!(enr.seq(), 2);
+        assert_eq!(
+            DefaultEnr::builder().sockets(&conflicting).build(&key),
+            Err(EnrError::ConflictingEndpoints)
+        );
+    }
 
-        assert_eq!(decoded_enr.id(), Some("v4".into()));
-        assert_eq!(decoded_enr.ip4(), Some(ip));
-        assert_eq!(decoded_enr.tcp4(), Some(tcp));
-        assert_eq!(decoded_enr.public_key().encode(), key.public().encode());
-        assert!(decoded_enr.verify());
+    #[test]
+    fn test_enode_url() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let public_key = hex::encode(key.public().encode_uncompressed());
+
+        let url = format!("enode://{public_key}@127.0.0.1:30303?discport=30301");
+        let enr = DefaultEnr::try_from_enode_url(&url, 5, &key).unwrap();
+        assert_eq!(enr.seq(), 5);
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::LOCALHOST));
+        assert_eq!(enr.tcp4(), Some(30303));
+        assert_eq!(enr.udp4(), Some(30301));
+        assert_eq!(enr.to_enode_url().unwrap(), url);
+
+        let url = format!("enode://{public_key}@[::1]:30303");
+        let enr = DefaultEnr::try_from_enode_url(&url, 1, &key).unwrap();
+        assert_eq!(enr.udp6(), Some(30303));
+        assert_eq!(enr.to_enode_url().unwrap(), url);
+
+        let other_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        assert_eq!(
+            DefaultEnr::try_from_enode_url(&url, 1, &other_key),
+            Err(EnodeError::PublicKeyMismatch)
+        );
+        assert!(matches!(
+            DefaultEnr::try_from_enode_url("enode://00@127.0.0.1:30303", 1, &key),
+            Err(EnodeError::InvalidUrl(_))
+        ));
+        let bad_discport = format!("enode://{public_key}@127.0.0.1:30303?discport=x");
+        assert!(matches!(
+            DefaultEnr::try_from_enode_url(&bad_discport, 1, &key),
+            Err(EnodeError::InvalidUrl(_))
+        ));
+
+        let enr = DefaultEnr::builder().tcp4(30303).build(&key).unwrap();
+        assert_eq!(enr.to_enode_url(), Err(EnodeError::MissingIp));
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.to_enode_url(), Err(EnodeError::MissingTcpPort));
+    }
+
+    #[test]
+    fn test_multiaddr() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .multiaddr("/ip4/127.0.0.1/tcp/9000/udp/9001/quic-v1")
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::LOCALHOST));
+        assert_eq!(enr.tcp4(), Some(9000));
+        assert_eq!(enr.quic4(), Some(9001));
+        assert_eq!(enr.udp4(), None);
+
+        enr.set_from_multiaddr("/ip6/::1/udp/9002", &key).unwrap();
+        assert_eq!(enr.ip6(), Some(Ipv6Addr::LOCALHOST));
+        assert_eq!(enr.udp6(), Some(9002));
+        assert_eq!(enr.seq(), 2);
+
+        let err = enr
+            .set_from_multiaddr("/dns4/example.org/tcp/9000", &key)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            EnrError::InvalidMultiaddr("unsupported protocol `/dns4`".into())
+        );
+        assert_eq!(enr.seq(), 2);
+        assert!(matches!(
+            DefaultEnr::builder()
+                .multiaddr("/ip4/127.0.0.1/sctp/5000")
+                .build(&key),
+            Err(EnrError::InvalidMultiaddr(_))
+        ));
+    }
+
+    #[cfg(all(feature = "raw", feature = "k256"))]
+    #[test]
+    fn test_from_parts_unchecked() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().tcp4(30303).build(&key).unwrap();
+        let rebuilt = DefaultEnr::from_parts_unchecked(
+            enr.seq(),
+            enr.content().clone(),
+            enr.signature().to_vec(),
+            enr.node_id(),
+        );
+        assert_eq!(rebuilt, enr);
+        assert!(rebuilt.verify());
+
+        // content changed without re-signing
+        let mut content = enr.content().clone();
+        content.insert(b"tcp".to_vec(), rlp::encode(&30304_u16).freeze());
+        let tampered = DefaultEnr::from_parts_unchecked(
+            enr.seq(),
+            content,
+            enr.signature().to_vec(),
+            enr.node_id(),
+        );
+        assert_eq!(tampered.tcp4(), Some(30304));
+        assert!(!tampered.verify());
+        assert!(rlp::decode::<DefaultEnr>(&rlp::encode(&tampered)).is_err());
+
+        // no public key
+        let mut content = enr.content().clone();
+        content.remove(b"secp256k1".as_ref());
+        let keyless = DefaultEnr::from_parts_unchecked(
+            enr.seq(),
+            content,
+            enr.signature().to_vec(),
+            enr.node_id(),
+        );
+        assert!(keyless.try_public_key().is_err());
+        assert!(!keyless.verify());
+        assert!(!keyless.verify_strict());
     }
 
     #[test]
-    fn test_add_key() {
-        let mut rng = rand::thread_rng();
-        let key = k256::ecdsa::SigningKey::random(&mut rng);
-        let ip = Ipv4Addr::new(10, 0, 0, 1);
-        let tcp = 30303;
-
-        let mut enr = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
-
-        enr.insert("random", &Vec::new(), &key).unwrap();
-        assert!(enr.verify());
+    fn test_is_canonical_rlp() {
+        for canonical in [
+            &[0x05][..],
+            &[0x80],
+            &[0x81, 0x80],
+            &[0xc0],
+            &[0xc4, 0x05, 0x81, 0x80, 0xc0],
+            &rlp::encode(&vec![7_u8; 56]),
+        ] {
+            assert!(is_canonical_rlp(canonical), "{}", hex::encode(canonical));
+        }
+        for non_canonical in [
+            // a single byte below 0x80 with a header
+            &[0x81, 0x05][..],
+            &[0xc2, 0x81, 0x05],
+            // a long header for a short payload
+            &[0xb8, 0x01, 0x80],
+            &[0xf8, 0x01, 0x05],
+            // a length with a leading zero
+            &[0xb9, 0x00, 0x38],
+            // truncated items and trailing bytes
+            &[],
+            &[0x82, 0x01],
+            &[0xc2, 0x05],
+            &[0x05, 0x05],
+        ] {
+            assert!(
+                !is_canonical_rlp(non_canonical),
+                "{}",
+                hex::encode(non_canonical)
+            );
+        }
     }
 
+    #[cfg(feature = "k256")]
     #[test]
-    fn test_set_ip() {
-        let mut rng = rand::thread_rng();
-        let key = k256::ecdsa::SigningKey::random(&mut rng);
-        let tcp = 30303;
-        let ip = Ipv4Addr::new(10, 0, 0, 1);
-
-        let mut enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
+    fn test_verify_strict() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().tcp4(30303).build(&key).unwrap();
+        assert!(enr.verify_strict());
 
-        assert!(enr.set_ip(ip.into(), &key).is_ok());
-        assert_eq!(enr.id(), Some("v4".into()));
-        assert_eq!(enr.ip4(), Some(ip));
-        assert_eq!(enr.tcp4(), Some(tcp));
+        // the list item is not minimally encoded, which decoding does not check
+        let value = Bytes::from_static(&[0xc2, 0x81, 0x05]);
+        enr.insert_raw_rlp("list", value, &key).unwrap();
+        let enr: DefaultEnr = rlp::decode(&rlp::encode(&enr)).unwrap();
         assert!(enr.verify());
-
-        // Compare the encoding as the key itself can be different
-        assert_eq!(enr.public_key().encode(), key.public().encode());
+        assert!(!enr.verify_strict());
     }
 
     #[test]
-    fn ip_mutation_static_node_id() {
-        let mut rng = rand::thread_rng();
-        let key = k256::ecdsa::SigningKey::random(&mut rng);
-        let tcp = 30303;
-        let udp = 30304;
-        let ip = Ipv4Addr::new(10, 0, 0, 1);
+    fn test_supersedes() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let old = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let mut new = old.clone();
+        new.set_udp4(30304, &key).unwrap();
 
-        let mut enr = Enr::builder()
-            .ip4(ip)
-            .tcp4(tcp)
-            .udp4(udp)
-            .build(&key)
-            .unwrap();
+        assert_eq!(new.supersedes(&old), Ok(Supersedes::Newer));
+        assert_eq!(old.supersedes(&new), Ok(Supersedes::Older));
+        assert_eq!(old.supersedes(&old.clone()), Ok(Supersedes::Same));
 
-        let node_id = enr.node_id();
+        // the same content signed again
+        let resigned = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        assert_eq!(resigned.supersedes(&old), Ok(Supersedes::Same));
 
-        enr.set_udp_socket("192.168.0.1:800".parse::<SocketAddr>().unwrap(), &key)
-            .unwrap();
-        assert_eq!(node_id, enr.node_id());
+        // conflicting content at the same sequence number
+        let conflicting = DefaultEnr::builder().udp4(30305).build(&key).unwrap();
+        assert_eq!(conflicting.supersedes(&old), Ok(Supersedes::Conflict));
+        assert_eq!(old.supersedes(&conflicting), Ok(Supersedes::Conflict));
+
+        let other =
+            DefaultEnr::empty(&k256::ecdsa::SigningKey::random(&mut rand::thread_rng())).unwrap();
         assert_eq!(
-            enr.udp4_socket(),
-            "192.168.0.1:800".parse::<SocketAddrV4>().unwrap().into()
+            new.supersedes(&other),
+            Err(WrongNode {
+                expected: new.node_id(),
+                found: other.node_id(),
+            })
         );
     }
 
-    #[cfg(all(feature = "ed25519", feature = "k256"))]
     #[test]
-    fn combined_key_can_decode_all() {
-        // generate a random secp256k1 key
-        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-        let ip = Ipv4Addr::new(192, 168, 0, 1);
-        let enr_secp256k1 = Enr::builder().ip(ip.into()).tcp4(8000).build(&key).unwrap();
-
-        // encode to base64
-        let base64_string_secp256k1 = enr_secp256k1.to_base64();
+    fn test_parse_error_kinds() {
+        let valid_record = hex::decode("f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f").unwrap();
+        let text = |bytes: &[u8]| format!("enr:{}", URL_SAFE_NO_PAD.encode(bytes));
 
-        // generate a random ed25519 key
-        let key = ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
-        let enr_ed25519 = Enr::builder().ip(ip.into()).tcp4(8000).build(&key).unwrap();
+        assert_eq!(
+            "enr".parse::<DefaultEnr>(),
+            Err(EnrParseError::InvalidString)
+        );
+        assert!(matches!(
+            "enr:!!!!".parse::<DefaultEnr>(),
+            Err(EnrParseError::Base64(_))
+        ));
 
-        // encode to base64
-        let base64_string_ed25519 = enr_ed25519.to_base64();
+        let mut tampered = valid_record.clone();
+        tampered[10] ^= 0x01;
+        assert_eq!(
+            text(&tampered).parse::<DefaultEnr>(),
+            Err(EnrParseError::InvalidSignature)
+        );
 
-        // decode base64 strings of varying key types
-        // decode the secp256k1 with default Enr
-        let _decoded_enr_secp256k1: DefaultEnr = base64_string_secp256k1.parse().unwrap();
-        // decode ed25519 ENRs
-        let _decoded_enr_ed25519: Enr<ed25519_dalek::SigningKey> =
-            base64_string_ed25519.parse().unwrap();
+        let public_key = rlp::Rlp::new(&valid_record)
+            .at(7)
+            .unwrap()
+            .data()
+            .unwrap()
+            .to_vec();
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&vec![0_u8; 64]).append(&1_u64);
+        stream.append(&"id").append(&"v5");
+        stream.append(&"secp256k1").append(&public_key);
+        assert_eq!(
+            text(&stream.out()).parse::<DefaultEnr>(),
+            Err(EnrParseError::UnsupportedScheme(Some("v5".into())))
+        );
 
-        // use the combined key to be able to decode either
-        let _decoded_enr: Enr<CombinedKey> = base64_string_secp256k1
-            .parse()
-            .expect("Can decode both secp");
-        let _decoded_enr: Enr<CombinedKey> = base64_string_ed25519.parse().unwrap();
+        // the error converts into the message returned before errors were typed
+        let message: String = EnrParseError::InvalidString.into();
+        assert_eq!(message, "Invalid ENR string");
     }
 
     #[test]
-    fn test_remove_insert() {
-        let mut rng = rand::thread_rng();
-        let key = k256::ecdsa::SigningKey::random(&mut rng);
-        let tcp = 30303;
-        let mut topics = Vec::new();
-        let mut s = RlpStream::new();
-        s.begin_list(2);
-        s.append(&"lighthouse");
-        s.append(&"eth_syncing");
-        topics.extend_from_slice(&s.out().freeze());
-
-        let mut enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
-
-        assert_eq!(enr.tcp4(), Some(tcp));
-        assert_eq!(enr.get("topics"), None);
-
-        let topics: &[u8] = &topics;
-
-        let (removed, inserted) = enr
-            .remove_insert([b"tcp"].iter(), vec![(b"topics", topics)].into_iter(), &key)
+    fn test_seq_policy() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder()
+            .seq(5)
+            .seq_policy(SeqPolicy::Explicit(10))
+            .build(&key)
             .unwrap();
+        assert_eq!(enr.seq(), 10);
+        assert_eq!(enr.seq_policy(), SeqPolicy::Explicit(10));
 
+        // an explicit sequence number must increase, and a failed update leaves the record as is
         assert_eq!(
-            removed[0],
-            Some(rlp::encode(&tcp.to_be_bytes().to_vec()).freeze())
+            enr.insert("foo", &1_u8, &key),
+            Err(EnrError::SequenceNumberNotIncreasing(10))
         );
-        assert_eq!(inserted[0], None);
+        assert_eq!(enr.get("foo"), None);
+        enr.set_seq_policy(SeqPolicy::Explicit(20));
+        enr.set_udp4(30303, &key).unwrap();
+        assert_eq!(enr.seq(), 20);
+
+        enr.set_seq_policy(SeqPolicy::UnixTimestamp);
+        enr.insert("foo", &1_u8, &key).unwrap();
+        let timestamp = enr.seq();
+        assert!(timestamp > 1_600_000_000);
+        enr.update(&key, |tx| {
+            tx.set_tcp4(30303);
+        })
+        .unwrap();
+        assert!(enr.seq() > timestamp);
+        assert!(enr.verify());
 
-        assert_eq!(enr.tcp4(), None);
-        assert_eq!(enr.get("topics"), Some(topics));
+        enr.set_seq_policy(SeqPolicy::Increment);
+        let seq = enr.seq();
+        enr.remove(["foo"], &key).unwrap();
+        assert_eq!(enr.seq(), seq + 1);
 
-        // Compare the encoding as the key itself can be different
-        assert_eq!(enr.public_key().encode(), key.public().encode());
+        let enr = DefaultEnr::builder()
+            .seq_policy(SeqPolicy::UnixTimestamp)
+            .build(&key)
+            .unwrap();
+        assert!(enr.seq() > 1_600_000_000);
+        assert!(enr.verify());
     }
 
-    /// | n     | `rlp::encode(n.to_be_bytes())` | `rlp::encode::<u16>(n)` |
-    /// | ----- | ------------------------------ | ----------------------- |
-    /// | 0     | 0x820000                       | 0x80
-    /// | 30    | 0x82001e                       | 0x1e
-    /// | 255   | 0x8200ff                       | 0x81ff
-    /// | 30303 | 0x82765f                       | 0x82765f
-    const LOW_INT_PORTS: [u16; 4] = [0, 30, 255, 30303];
+    #[test]
+    fn test_socket_addresses() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let ip4 = Ipv4Addr::new(10, 0, 0, 1);
+        let ip6 = Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        assert!(enr.socket_addresses().is_empty());
+
+        let enr = DefaultEnr::builder()
+            .ip4(ip4)
+            .udp4(30303)
+            .tcp4(30304)
+            .ip6(ip6)
+            .udp6(9000)
+            .quic6(9001)
+            .build(&key)
+            .unwrap();
+        assert_eq!(
+            enr.socket_addresses(),
+            vec![
+                (Transport::Udp, SocketAddr::new(ip4.into(), 30303)),
+                (Transport::Tcp, SocketAddr::new(ip4.into(), 30304)),
+                (Transport::Udp, SocketAddr::new(ip6.into(), 9000)),
+                // the IPv4 port applies to IPv6 without an IPv6-specific port
+                (Transport::Tcp, SocketAddr::new(ip6.into(), 30304)),
+                (Transport::Quic, SocketAddr::new(ip6.into(), 9001)),
+            ]
+        );
+        assert_eq!(enr.endpoints().len(), 4);
+        assert_eq!(
+            enr.socket_addresses_of(Transport::Tcp),
+            vec![
+                SocketAddr::new(ip4.into(), 30304),
+                SocketAddr::new(ip6.into(), 30304),
+            ]
+        );
+        assert_eq!(
+            enr.socket_addresses_of(Transport::Quic),
+            vec![SocketAddr::new(ip6.into(), 9001)]
+        );
+    }
 
     #[test]
-    fn test_low_integer_build() {
+    fn test_ip_sanity_filters() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
 
-        for tcp in LOW_INT_PORTS {
-            let enr = Enr::builder().tcp4(tcp).build(&key).unwrap();
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        assert!(!enr.has_global_ip());
+        assert!(!enr.is_loopback());
+        assert!(!enr.is_private());
 
-            assert_tcp4(&enr, tcp);
-        }
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(30303)
+            .ip6(Ipv6Addr::LOCALHOST)
+            .udp6(30303)
+            .build(&key)
+            .unwrap();
+        assert!(enr.is_loopback());
+        assert!(!enr.is_private());
+        assert!(!enr.has_global_ip());
+        assert_eq!(enr.udp4_socket_global(), None);
+        assert_eq!(enr.udp6_socket_global(), None);
+
+        // a node behind a NAT with a global IPv6 address
+        let ip6 = "2a01:4f8::1".parse().unwrap();
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(192, 168, 1, 10))
+            .udp4(30303)
+            .tcp4(30303)
+            .ip6(ip6)
+            .tcp6(30304)
+            .build(&key)
+            .unwrap();
+        assert!(enr.has_global_ip());
+        assert!(!enr.is_loopback());
+        assert!(!enr.is_private());
+        assert_eq!(enr.udp4_socket_global(), None);
+        assert_eq!(enr.tcp4_socket_global(), None);
+        assert!(enr.tcp4_socket().is_some());
+        assert_eq!(
+            enr.tcp6_socket_global(),
+            Some(SocketAddrV6::new(ip6, 30304, 0, 0))
+        );
+        assert_eq!(enr.quic6_socket_global(), None);
+
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .build(&key)
+            .unwrap();
+        assert!(enr.is_private());
     }
 
     #[test]
-    fn test_low_integer_set() {
+    fn test_encoded_cache() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+
+        let encoded = enr.encoded().clone();
+        assert_eq!(encoded.len(), enr.size());
+        // the cached encoding is shared rather than re-encoded
+        assert_eq!(enr.encoded().as_ptr(), encoded.as_ptr());
+        assert_eq!(enr.clone().encoded().as_ptr(), encoded.as_ptr());
+        assert_eq!(rlp::encode(&enr), encoded);
+
+        enr.set_udp4(30304, &key).unwrap();
+        assert_ne!(enr.encoded(), &encoded);
+        enr.set_seq(10, &key).unwrap();
+        let expected = {
+            let mut buffer = Vec::new();
+            enr.encode_to_buf(&mut buffer);
+            buffer
+        };
+        assert_eq!(enr.encoded(), &expected);
+        assert_eq!(enr.to_base64().parse::<DefaultEnr>().unwrap(), enr);
 
-        for tcp in LOW_INT_PORTS {
-            let mut enr = Enr::empty(&key).unwrap();
-            enr.set_tcp4(tcp, &key).unwrap();
-            assert_tcp4(&enr, tcp);
-        }
+        // a failed update keeps the record and its encoding
+        let encoded = enr.encoded().clone();
+        assert!(enr.insert("udp", &[0_u8; 300].as_ref(), &key).is_err());
+        assert_eq!(enr.encoded(), &encoded);
     }
 
+    #[cfg(feature = "k256")]
     #[test]
-    fn test_low_integer_set_socket() {
+    fn test_content_hash() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-        let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
+        let mut enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
 
-        for tcp in LOW_INT_PORTS {
-            let mut enr = Enr::empty(&key).unwrap();
-            enr.set_socket(SocketAddr::V4(SocketAddrV4::new(ipv4, tcp)), &key, true)
-                .unwrap();
-            assert_tcp4(&enr, tcp);
-        }
+        let hash = enr.content_hash();
+        assert_eq!(hash, digest(&rlp::encode(&enr)));
+        assert_eq!(enr.record_id::<Keccak256>()[..], hash);
+        assert_eq!(
+            enr.record_id::<sha3::Sha3_256>(),
+            sha3::Sha3_256::digest(enr.encoded())
+        );
+        let decoded: DefaultEnr = rlp::decode(enr.encoded()).unwrap();
+        assert_eq!(decoded.content_hash(), hash);
+
+        // a new version of the record has a new hash, the node id is unchanged
+        enr.set_udp4(30304, &key).unwrap();
+        assert_ne!(enr.content_hash(), hash);
+        assert_eq!(enr.content_hash(), digest(&rlp::encode(&enr)));
     }
 
+    #[cfg(feature = "k256")]
     #[test]
-    fn test_low_integer_insert() {
+    fn test_try_from_bytes() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-
-        for tcp in LOW_INT_PORTS {
-            let mut enr = Enr::empty(&key).unwrap();
-
-            let res = enr.insert(b"tcp", &tcp.to_be_bytes().as_ref(), &key);
-            if u8::try_from(tcp).is_ok() {
-                assert_eq!(res.unwrap_err().to_string(), "invalid rlp data");
-            } else {
-                res.unwrap(); // integers above 255 are encoded correctly
-                assert_tcp4(&enr, tcp);
-            }
-        }
+        let enr: DefaultEnr = Enr::builder().udp4(30303).build(&key).unwrap();
+
+        let bytes = enr.to_rlp_bytes();
+        assert_eq!(bytes, rlp::encode(&enr).freeze());
+        assert_eq!(DefaultEnr::try_from(bytes.as_ref()), Ok(enr.clone()));
+        assert_eq!(DefaultEnr::try_from(bytes.to_vec()), Ok(enr.clone()));
+        assert_eq!(DefaultEnr::try_from(bytes.clone()), Ok(enr));
+
+        let mut tampered = bytes.to_vec();
+        // a byte of the signature
+        tampered[5] ^= 0x01;
+        assert!(DefaultEnr::try_from(tampered).is_err());
+        assert!(DefaultEnr::try_from(&[][..]).is_err());
     }
 
+    #[cfg(all(feature = "k256", feature = "rust-secp256k1"))]
     #[test]
-    fn test_low_integer_remove_insert() {
+    fn test_into_key_type() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr: DefaultEnr = Enr::builder().udp4(30303).build(&key).unwrap();
 
-        for tcp in LOW_INT_PORTS {
-            let mut enr = Enr::empty(&key).unwrap();
+        let secp_enr: Enr<secp256k1::SecretKey> = enr.clone().into_key_type().unwrap();
+        assert_eq!(secp_enr.node_id(), enr.node_id());
+        assert_eq!(secp_enr.encoded(), enr.encoded());
+        assert_eq!(
+            secp_enr.public_key(),
+            CombinedSecpKey::secp256k1_public_from_k256(&enr.public_key())
+        );
 
-            let res = enr.remove_insert(
-                [b"none"].iter(),
-                vec![(b"tcp".as_slice(), tcp.to_be_bytes().as_slice())].into_iter(),
-                &key,
-            );
-            if u8::try_from(tcp).is_ok() {
-                assert_eq!(res.unwrap_err().to_string(), "invalid rlp data");
-            } else {
-                res.unwrap(); // integers above 255 are encoded correctly
-                assert_tcp4(&enr, tcp);
-            }
-        }
+        // a record of either backend is signed and verified as a `CombinedSecpKey` record
+        let mut combined: Enr<CombinedSecpKey> = secp_enr.into_key_type().unwrap();
+        let secp_key = CombinedSecpKey::from(CombinedSecpKey::secp256k1_from_k256(&key));
+        combined.set_udp4(30304, &secp_key).unwrap();
+        let enr: DefaultEnr = combined.into_key_type().unwrap();
+        assert_eq!(enr.udp4(), Some(30304));
+        assert!(enr.verify());
     }
 
+    #[cfg(all(feature = "ed25519", feature = "k256"))]
     #[test]
-    fn test_low_integer_bad_enr() {
-        let vectors = vec![
-            (0, "enr:-Hy4QDMsoimQl2Qb9CuIWlNjyt0C0DmZC4QpAsJzgUHowOq2Nph9UbAtZ_qS_8fl6SU-eSWrswHiLCoMUGQfjhl_GW0BgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggAA"),
-            (30, "enr:-Hy4QCCgTB9tAEJL1DFwTTtwd79xxQx2hvi5RX9vWvcdKqbpS3SDzHHBivpOgxE40HGt6P0NtCE5QKzOQ5fzBwepDfMBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggAe"),
-            (255, "enr:-Hy4QOrU9C35gZyJigIi-u19sRP42eEjVEhzO-LnKXKM5VlDMZ45vnOIa3bqm15ap8pmLjq5kmRPzjuA0RUdzSsieqcBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwggD_"),
-            (30303, "enr:-Hy4QF_mn4BuM6hY4CuLH8xDQd7U8kVZe9fyNgRB1vjdToGWQsQhetRvsByoJCWGQ6kf2aiWC0le24lkp0IPIJkLSTUBgmlkgnY0iXNlY3AyNTZrMaECMoYV0PAXMueQz19FHpBO0jGBoLYCWhfSxGf5kQgk9KqDdGNwgnZf"),
-        ];
-
-        for (tcp, enr_str) in vectors {
-            let res = DefaultEnr::from_str(enr_str);
-            if u8::try_from(tcp).is_ok() {
-                assert_eq!(
-                    res.unwrap_err().to_string(),
-                    "Invalid ENR: RlpInvalidIndirection"
-                );
-            } else {
-                assert_tcp4(&res.unwrap(), tcp);
-            }
-        }
+    fn test_into_key_type_unsupported() {
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
+        let enr: Enr<ed25519_dalek::SigningKey> = Enr::builder().build(&key).unwrap();
+        assert!(enr.into_key_type::<k256::ecdsa::SigningKey>().is_err());
     }
 
     #[test]
-    fn test_compare_content() {
+    fn test_batch_updates_share_validation() {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-        let ip = Ipv4Addr::new(10, 0, 0, 1);
-        let tcp = 30303;
+        let mut enr = DefaultEnr::builder()
+            .udp4(9000)
+            .add_value("tmp", &1_u8)
+            .build(&key)
+            .unwrap();
 
-        let enr1 = Enr::builder().ip4(ip).tcp4(tcp).build(&key).unwrap();
+        // predicates skip the mandatory keys
+        let previous = enr.update(&key, |tx| {
+            tx.remove_if(|_| true);
+        });
+        assert_eq!(previous.unwrap().len(), 2);
+        assert_eq!(enr.id(), Some("v4".into()));
+        assert_eq!(enr.public_key(), key.public());
 
-        let mut enr2 = enr1.clone();
-        enr2.set_seq(1, &key).unwrap();
-        let mut enr3 = enr1.clone();
-        enr3.set_seq(2, &key).unwrap();
+        // explicit removals of mandatory keys fail alike on every batch path
+        let mut changes = FieldChanges::new();
+        changes.remove("id");
+        assert_eq!(
+            enr.apply_changes(&changes, &key).unwrap_err(),
+            EnrError::MandatoryKey("id".into())
+        );
+        assert_eq!(
+            enr.update(&key, |tx| {
+                tx.apply_changes(&changes);
+            })
+            .unwrap_err(),
+            EnrError::MandatoryKey("id".into())
+        );
+        assert_eq!(
+            enr.remove(["id"], &key).unwrap_err(),
+            EnrError::MandatoryKey("id".into())
+        );
+        assert_eq!(enr.seq(), 2);
+    }
 
-        // Enr 1 & 2 should be equal, secpk256k1 should have different signatures for the same Enr content
-        assert_ne!(enr1.signature(), enr2.signature());
-        assert!(enr1.compare_content(&enr2));
-        assert_ne!(enr1, enr2);
+    #[test]
+    fn test_core_conversion() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(30303)
+            .build(&key)
+            .unwrap();
 
-        // Enr 1 & 3 should not be equal, and have different signatures
-        assert_ne!(enr1.signature(), enr3.signature());
-        assert!(!enr1.compare_content(&enr3));
-        assert_ne!(enr1, enr3);
+        let core = enr_core::Enr::try_from(&enr).unwrap();
+        assert_eq!(core.node_id(), enr.node_id());
+        assert_eq!(rlp::encode(&core), enr.encoded());
+        assert_eq!(DefaultEnr::try_from(core).unwrap(), enr);
+
+        // records created by the core crate decode here
+        let mut content = BTreeMap::new();
+        content.insert(b"udp".to_vec(), rlp::encode(&30303_u16).freeze());
+        let core = enr_core::Enr::new(&key, 5, content).unwrap();
+        let enr = DefaultEnr::try_from(core).unwrap();
+        assert_eq!(enr.seq(), 5);
+        assert_eq!(enr.udp4(), Some(30303));
     }
 
     fn assert_tcp4(enr: &DefaultEnr, tcp: u16) {
@@ -1723,12 +5350,13 @@ mod tests {
         let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
 
         let mut huge_enr = Enr::empty(&key).unwrap();
-        let large_vec: Vec<u8> = std::iter::repeat(0).take(MAX_ENR_SIZE).collect();
+        let large_vec: Vec<u8> = vec![0; MAX_ENR_SIZE];
         let large_vec_encoded = rlp::encode(&large_vec).freeze();
 
-        huge_enr
-            .content
-            .insert(b"large vec".to_vec(), large_vec_encoded);
+        huge_enr.content.insert(
+            Key::from(b"large vec".as_ref()),
+            storage::value(large_vec_encoded),
+        );
         huge_enr.sign(&key).unwrap();
 
         assert!(huge_enr.verify());
@@ -0,0 +1,160 @@
+//! The [`enr!`](crate::enr!) construction macro and its support code.
+//!
+//! Items in this module are implementation details of the macro and not part of the public API.
+
+use crate::EnrError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Converts the value of an `ip4` field, parsing string literals.
+pub trait IntoIpv4 {
+    fn into_ipv4(self) -> Result<Ipv4Addr, EnrError>;
+}
+
+impl IntoIpv4 for Ipv4Addr {
+    fn into_ipv4(self) -> Result<Ipv4Addr, EnrError> {
+        Ok(self)
+    }
+}
+
+impl IntoIpv4 for &str {
+    fn into_ipv4(self) -> Result<Ipv4Addr, EnrError> {
+        self.parse()
+            .map_err(|_| EnrError::InvalidIpAddress(self.to_string()))
+    }
+}
+
+/// Converts the value of an `ip6` field, parsing string literals.
+pub trait IntoIpv6 {
+    fn into_ipv6(self) -> Result<Ipv6Addr, EnrError>;
+}
+
+impl IntoIpv6 for Ipv6Addr {
+    fn into_ipv6(self) -> Result<Ipv6Addr, EnrError> {
+        Ok(self)
+    }
+}
+
+impl IntoIpv6 for &str {
+    fn into_ipv6(self) -> Result<Ipv6Addr, EnrError> {
+        self.parse()
+            .map_err(|_| EnrError::InvalidIpAddress(self.to_string()))
+    }
+}
+
+/// Converts the value of an `ip` field, parsing string literals.
+pub trait IntoIp {
+    fn into_ip(self) -> Result<IpAddr, EnrError>;
+}
+
+impl IntoIp for IpAddr {
+    fn into_ip(self) -> Result<IpAddr, EnrError> {
+        Ok(self)
+    }
+}
+
+impl IntoIp for Ipv4Addr {
+    fn into_ip(self) -> Result<IpAddr, EnrError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoIp for Ipv6Addr {
+    fn into_ip(self) -> Result<IpAddr, EnrError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoIp for &str {
+    fn into_ip(self) -> Result<IpAddr, EnrError> {
+        self.parse()
+            .map_err(|_| EnrError::InvalidIpAddress(self.to_string()))
+    }
+}
+
+/// Builds and signs an [`Enr`](crate::Enr).
+///
+/// The first argument is the signing key, followed by the fields of the record. Known fields
+/// (`ip`, `ip4`, `ip6`, `tcp4`, `tcp6`, `udp4`, `udp6`, `id` and `seq`) are set with `name =
+/// value`; IP addresses may be given as string literals, which fail the macro with
+/// [`EnrError::InvalidIpAddress`](crate::EnrError::InvalidIpAddress) if malformed. Custom fields are set with
+/// `"key" => value`, where the value is any [`rlp::Encodable`]. Unknown field names and custom
+/// fields using a reserved key are rejected at compile time.
+///
+/// Evaluates to a `Result<Enr<K>, EnrError>`.
+///
+/// ```
+/// # #[cfg(feature = "k256")] {
+/// use enr::{enr, k256::ecdsa::SigningKey};
+///
+/// let key = SigningKey::random(&mut rand::thread_rng());
+/// let enr = enr!(&key; ip4 = "10.0.0.1", tcp4 = 30303, "custom" => b"value".to_vec()).unwrap();
+///
+/// assert_eq!(enr.ip4(), Some("10.0.0.1".parse().unwrap()));
+/// assert_eq!(enr.tcp4(), Some(30303));
+/// assert_eq!(enr.get("custom"), Some(b"value".as_ref()));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! enr {
+    ($key:expr $(; $($fields:tt)*)?) => {{
+        let mut builder = $crate::Enr::builder();
+        let fields: ::std::result::Result<(), $crate::EnrError> = (|| {
+            $crate::__enr_fields!(builder; $($($fields)*)?);
+            Ok(())
+        })();
+        fields.and_then(|()| builder.build($key))
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enr_fields {
+    ($builder:ident;) => {};
+    ($builder:ident; $field:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__enr_field!($builder, $field, $value);
+        $crate::__enr_fields!($builder; $($($rest)*)?);
+    };
+    ($builder:ident; $key:literal => $value:expr $(, $($rest:tt)*)?) => {
+        const _: () = assert!(
+            !$crate::is_reserved_key($key),
+            "reserved keys must be set through their named field"
+        );
+        $builder.add_value($key, &$value);
+        $crate::__enr_fields!($builder; $($($rest)*)?);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enr_field {
+    ($builder:ident, ip, $value:expr) => {
+        $builder.ip($crate::macros::IntoIp::into_ip($value)?);
+    };
+    ($builder:ident, ip4, $value:expr) => {
+        $builder.ip4($crate::macros::IntoIpv4::into_ipv4($value)?);
+    };
+    ($builder:ident, ip6, $value:expr) => {
+        $builder.ip6($crate::macros::IntoIpv6::into_ipv6($value)?);
+    };
+    ($builder:ident, tcp4, $value:expr) => {
+        $builder.tcp4($value);
+    };
+    ($builder:ident, tcp6, $value:expr) => {
+        $builder.tcp6($value);
+    };
+    ($builder:ident, udp4, $value:expr) => {
+        $builder.udp4($value);
+    };
+    ($builder:ident, udp6, $value:expr) => {
+        $builder.udp6($value);
+    };
+    ($builder:ident, id, $value:expr) => {
+        $builder.id($value);
+    };
+    ($builder:ident, seq, $value:expr) => {
+        $builder.seq($value);
+    };
+    ($builder:ident, $field:ident, $value:expr) => {
+        compile_error!(concat!("unknown ENR field `", stringify!($field), "`"));
+    };
+}
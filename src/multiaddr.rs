@@ -0,0 +1,136 @@
+//! Record fields from libp2p multiaddrs.
+//!
+//! Consensus clients configure their listening addresses as multiaddrs such as
+//! `/ip4/203.0.113.7/tcp/9000` or `/ip6/2001:db8::1/udp/9001/quic-v1`. [`Builder::multiaddr`] and
+//! [`Enr::set_from_multiaddr`] map the components of a multiaddr to the `ip`, `ip6`, `tcp`,
+//! `tcp6`, `udp`, `udp6`, `quic` and `quic6` fields:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder()
+//!     .multiaddr("/ip4/203.0.113.7/tcp/9000/udp/9001/quic-v1")
+//!     .build(&key)
+//!     .unwrap();
+//! assert_eq!(enr.tcp4(), Some(9000));
+//! assert_eq!(enr.quic4(), Some(9001));
+//! # }
+//! ```
+//!
+//! [`Builder::multiaddr`]: crate::builder::Builder::multiaddr
+//! [`Enr::set_from_multiaddr`]: crate::Enr::set_from_multiaddr
+
+use crate::{EnrError, Transport};
+use std::net::{IpAddr, SocketAddr};
+
+/// The sockets of a multiaddr, in order.
+type Sockets = Vec<(Transport, SocketAddr)>;
+
+/// The sockets of the text form of a multiaddr, see [`parse`]. A `/p2p` component is skipped, as
+/// the peer id is given by the key signing the record.
+pub(crate) fn sockets(multiaddr: &str) -> Result<Sockets, EnrError> {
+    parse(multiaddr)
+        .map(|(sockets, _)| sockets)
+        .map_err(EnrError::InvalidMultiaddr)
+}
+
+/// The sockets and the peer id of the text form of a multiaddr, or the reason it is invalid.
+///
+/// Each `/tcp` or `/udp` component is a socket at the IP address of the preceding `/ip4` or
+/// `/ip6` component, and a `/udp` component followed by `/quic-v1` is a QUIC socket. The peer id
+/// is the value of the last `/p2p` component. Other protocols are rejected.
+pub(crate) fn parse(multiaddr: &str) -> Result<(Sockets, Option<&str>), String> {
+    let mut components = multiaddr
+        .strip_prefix('/')
+        .ok_or_else(|| format!("`{multiaddr}` does not start with `/`"))?
+        .split('/')
+        .peekable();
+    let mut ip = None;
+    let mut sockets = Vec::new();
+    let mut peer_id = None;
+    while let Some(protocol) = components.next() {
+        let value = components
+            .next()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("`/{protocol}` has no value"));
+        match protocol {
+            "ip4" | "ip6" => {
+                let value = value?;
+                let address: IpAddr = value
+                    .parse()
+                    .map_err(|e| format!("invalid address `{value}`: {e}"))?;
+                if address.is_ipv4() != (protocol == "ip4") {
+                    return Err(format!("`{address}` is not an `{protocol}` address"));
+                }
+                ip = Some(address);
+            }
+            "tcp" | "udp" => {
+                let value = value?;
+                let ip =
+                    ip.ok_or_else(|| format!("`/{protocol}` is not preceded by an IP address"))?;
+                let port = value
+                    .parse()
+                    .map_err(|e| format!("invalid {protocol} port `{value}`: {e}"))?;
+                let transport = if protocol == "tcp" {
+                    Transport::Tcp
+                } else if components.next_if_eq(&"quic-v1").is_some() {
+                    Transport::Quic
+                } else {
+                    Transport::Udp
+                };
+                sockets.push((transport, SocketAddr::new(ip, port)));
+            }
+            "p2p" => peer_id = Some(value?),
+            _ => return Err(format!("unsupported protocol `/{protocol}`")),
+        }
+    }
+    if sockets.is_empty() {
+        return Err(format!("`{multiaddr}` has no tcp, udp or quic-v1 socket"));
+    }
+    Ok((sockets, peer_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sockets() {
+        assert_eq!(
+            sockets("/ip4/127.0.0.1/tcp/9000/udp/9001/quic-v1/ip6/::1/udp/9002/p2p/16Uiu2HAm"),
+            Ok(vec![
+                (Transport::Tcp, "127.0.0.1:9000".parse().unwrap()),
+                (Transport::Quic, "127.0.0.1:9001".parse().unwrap()),
+                (Transport::Udp, "[::1]:9002".parse().unwrap()),
+            ])
+        );
+
+        assert_eq!(
+            parse("/ip4/127.0.0.1/udp/9000/p2p/16Uiu2HAm").map(|(_, peer_id)| peer_id),
+            Ok(Some("16Uiu2HAm"))
+        );
+        assert_eq!(
+            parse("/ip4/127.0.0.1/udp/9000").map(|(_, peer_id)| peer_id),
+            Ok(None)
+        );
+
+        for multiaddr in [
+            "ip4/127.0.0.1/tcp/9000",
+            "/ip4/127.0.0.1",
+            "/tcp/9000",
+            "/ip4/::1/tcp/9000",
+            "/ip4/127.0.0.1/tcp/70000",
+            "/ip4/127.0.0.1/tcp",
+            "/ip4/127.0.0.1/udp/9000/quic",
+            "/dns4/example.org/tcp/9000",
+        ] {
+            assert!(
+                matches!(sockets(multiaddr), Err(EnrError::InvalidMultiaddr(_))),
+                "{}",
+                multiaddr
+            );
+        }
+    }
+}
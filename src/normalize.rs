@@ -0,0 +1,265 @@
+//! Canonicalization of values written under reserved keys.
+//!
+//! [`Enr::insert_normalized`] accepts some non-canonical inputs that [`Enr::insert_raw_rlp`]
+//! rejects or stores as given, adjusts them to their canonical form and reports each adjustment
+//! in a [`Normalized`], so callers know their input was stored differently:
+//!
+//! - IPv4-mapped IPv6 addresses written to `ip6` are stored as IPv4 addresses under `ip`, and
+//!   the IPv6 ports of the record move to their IPv4 counterparts.
+//! - Ports with leading zero bytes are stored with the minimal encoding the specification
+//!   requires.
+//!
+//! Identity schemes are case-sensitive, so a mixed-case `id` is rejected rather than lowercased.
+//!
+//! [`Enr::insert_normalized`]: crate::Enr::insert_normalized
+//! [`Enr::insert_raw_rlp`]: crate::Enr::insert_raw_rlp
+
+use crate::{
+    compaction::PORT_KEYS, is_keyof_u16, EnrError, IdentityScheme, Key, Transaction, Value,
+};
+use bytes::Bytes;
+use rlp::Rlp;
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// An adjustment made to a value before it was stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// An IPv4-mapped IPv6 address was stored as an IPv4 address under `ip` instead of `ip6`,
+    /// and the `udp6`, `tcp6` and `quic6` ports of the record moved to their IPv4 counterparts.
+    ///
+    /// The address is stored under `ip6` as given if the record already has an `ip` address or
+    /// the IPv4 counterpart of one of its IPv6 ports.
+    Ipv4MappedIpv6 {
+        /// The address as given.
+        from: Ipv6Addr,
+        /// The address as stored.
+        to: Ipv4Addr,
+    },
+    /// Leading zero bytes were stripped from the port stored under the given key.
+    StrippedLeadingZeros(String),
+}
+
+/// The outcome of [`Enr::insert_normalized`](crate::Enr::insert_normalized).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalized {
+    /// The key the value was stored under.
+    pub key: Vec<u8>,
+    /// The previous RLP encoded value stored under the key, if any.
+    pub previous: Option<Bytes>,
+    /// The adjustment made to the input, if it was not stored as given.
+    pub normalization: Option<Normalization>,
+}
+
+/// How a value written to a record is stored, as decided by [`normalize`].
+pub(crate) struct Normalizing {
+    /// The key to store the value under.
+    pub(crate) key: Vec<u8>,
+    /// The RLP encoded value to store.
+    pub(crate) value: Bytes,
+    /// The adjustment made to the input, if any.
+    pub(crate) normalization: Option<Normalization>,
+    /// Fields moved along with the value, with the key they move from and to.
+    moves: Vec<(&'static str, &'static str, Bytes)>,
+}
+
+impl Normalizing {
+    fn new(key: &[u8], value: Bytes, normalization: Option<Normalization>) -> Self {
+        Self {
+            key: key.to_vec(),
+            value,
+            normalization,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Adds the modifications storing the value to `tx`.
+    pub(crate) fn apply<S: IdentityScheme>(&self, tx: &mut Transaction<'_, S>) {
+        if let Some(Normalization::Ipv4MappedIpv6 { .. }) = self.normalization {
+            tx.remove("ip6");
+        }
+        for (from, to, value) in &self.moves {
+            tx.remove(from).insert_raw_rlp(to, value.clone());
+        }
+        tx.insert_raw_rlp(&self.key, self.value.clone());
+    }
+}
+
+/// Canonicalizes a raw RLP value written under `key` of a record holding `content`.
+pub(crate) fn normalize(
+    key: &[u8],
+    value: Bytes,
+    content: &BTreeMap<Key, Value>,
+) -> Result<Normalizing, EnrError> {
+    let invalid = |err: rlp::DecoderError| EnrError::InvalidRlpData(err.to_string());
+    let rlp = Rlp::new(&value);
+
+    if key == b"id" {
+        let id = rlp.data().map_err(invalid)?;
+        if id.iter().any(u8::is_ascii_uppercase) {
+            return Err(EnrError::InvalidRlpData(
+                "identity schemes are lowercase".into(),
+            ));
+        }
+    } else if key == b"ip6" {
+        let mapped = <[u8; 16]>::try_from(rlp.data().map_err(invalid)?)
+            .ok()
+            .map(Ipv6Addr::from)
+            .and_then(|from| from.to_ipv4_mapped().map(|to| (from, to)));
+        let ports: Vec<_> = PORT_KEYS
+            .iter()
+            .filter_map(|(v6, v4)| Some((*v6, *v4, content.get(v6.as_bytes())?)))
+            .collect();
+        // the address and its ports must not overwrite IPv4 fields of the record
+        let vacant = !content.contains_key(b"ip".as_ref())
+            && ports
+                .iter()
+                .all(|(_, v4, _)| !content.contains_key(v4.as_bytes()));
+        if let (Some((from, to)), true) = (mapped, vacant) {
+            let value = rlp::encode(&to.octets().as_ref()).freeze();
+            let mut normalizing = Normalizing::new(
+                b"ip",
+                value,
+                Some(Normalization::Ipv4MappedIpv6 { from, to }),
+            );
+            normalizing.moves = ports
+                .into_iter()
+                .map(|(v6, v4, port)| (v6, v4, Bytes::copy_from_slice(port)))
+                .collect();
+            return Ok(normalizing);
+        }
+    } else if is_keyof_u16(key) {
+        let port = rlp.data().map_err(invalid)?;
+        if port.first() == Some(&0) {
+            let stripped: Vec<u8> = port.iter().copied().skip_while(|b| *b == 0).collect();
+            let value = rlp::encode(&stripped).freeze();
+            let normalization =
+                Normalization::StrippedLeadingZeros(String::from_utf8_lossy(key).into());
+            return Ok(Normalizing::new(key, value, Some(normalization)));
+        }
+    }
+    Ok(Normalizing::new(key, value, None))
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use crate::Enr;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_insert_normalized() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+
+        let mapped: Ipv6Addr = "::ffff:10.0.0.1".parse().unwrap();
+        let normalized = enr
+            .insert_normalized("ip6", rlp::encode(&mapped.octets().as_ref()).freeze(), &key)
+            .unwrap();
+        assert_eq!(normalized.key, b"ip");
+        assert_eq!(
+            normalized.normalization,
+            Some(Normalization::Ipv4MappedIpv6 {
+                from: mapped,
+                to: Ipv4Addr::new(10, 0, 0, 1)
+            })
+        );
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(enr.ip6(), None);
+
+        // a two byte big-endian port of 30
+        let normalized = enr
+            .insert_normalized("udp", Bytes::from_static(&[0x82, 0x00, 0x1e]), &key)
+            .unwrap();
+        assert_eq!(
+            normalized.normalization,
+            Some(Normalization::StrippedLeadingZeros("udp".into()))
+        );
+        assert_eq!(enr.udp4(), Some(30));
+        assert_eq!(enr.get_raw_rlp("udp"), Some([0x1e].as_ref()));
+
+        // canonical values are stored as given
+        let normalized = enr
+            .insert_normalized("tcp", rlp::encode(&9000_u16).freeze(), &key)
+            .unwrap();
+        assert_eq!(normalized.normalization, None);
+        assert_eq!(enr.tcp4(), Some(9000));
+        assert_eq!(enr.seq(), 4);
+    }
+
+    #[test]
+    fn test_mapped_ipv6_moves() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mapped: Ipv6Addr = "::ffff:10.0.0.1".parse().unwrap();
+        let value = rlp::encode(&mapped.octets().as_ref()).freeze();
+
+        // the ports of the IPv6 address move along with it
+        let mut enr = DefaultEnr::builder()
+            .ip6("2001:db8::1".parse().unwrap())
+            .udp6(30303)
+            .tcp6(30304)
+            .build(&key)
+            .unwrap();
+        let normalized = enr.insert_normalized("ip6", value.clone(), &key).unwrap();
+        assert_eq!(normalized.key, b"ip");
+        assert_eq!(normalized.previous, None);
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(enr.udp4(), Some(30303));
+        assert_eq!(enr.tcp4(), Some(30304));
+        assert_eq!(enr.ip6(), None);
+        assert_eq!(enr.udp6(), None);
+        assert_eq!(enr.tcp6(), None);
+        assert_eq!(enr.seq(), 2);
+
+        // an existing IPv4 address is not overwritten
+        let mut enr = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(192, 0, 2, 1))
+            .udp4(9000)
+            .build(&key)
+            .unwrap();
+        let normalized = enr.insert_normalized("ip6", value.clone(), &key).unwrap();
+        assert_eq!(normalized.key, b"ip6");
+        assert_eq!(normalized.normalization, None);
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(enr.ip6(), Some(mapped));
+
+        // nor is an existing IPv4 port
+        let mut enr = DefaultEnr::builder()
+            .ip6("2001:db8::1".parse().unwrap())
+            .udp6(30303)
+            .udp4(9000)
+            .build(&key)
+            .unwrap();
+        let normalized = enr.insert_normalized("ip6", value, &key).unwrap();
+        assert_eq!(normalized.normalization, None);
+        assert_eq!(enr.udp4(), Some(9000));
+        assert_eq!(enr.udp6(), Some(30303));
+        assert_eq!(enr.ip6(), Some(mapped));
+    }
+
+    #[test]
+    fn test_rejected() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut enr = DefaultEnr::empty(&key).unwrap();
+
+        assert_eq!(
+            enr.insert_normalized("id", rlp::encode(&"V4").freeze(), &key)
+                .unwrap_err(),
+            EnrError::InvalidRlpData("identity schemes are lowercase".into())
+        );
+        // still too large for a port after stripping
+        assert!(enr
+            .insert_normalized(
+                "udp",
+                Bytes::from_static(&[0x84, 0x00, 0x01, 0x00, 0x00]),
+                &key
+            )
+            .is_err());
+        assert_eq!(enr.seq(), 1);
+    }
+}
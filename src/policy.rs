@@ -0,0 +1,500 @@
+//! Allowlist/denylist evaluation of ENRs.
+//!
+//! A [`Policy`] is an ordered list of [`Rule`]s, each pairing an [`Action`] with a [`Condition`]
+//! on the content of a record. Evaluating a record against a policy applies the action of the
+//! first rule whose condition matches, falling back to the policy's default action. The resulting
+//! [`Verdict`] carries an explanation of why the record was allowed or denied.
+//!
+//! With the `serde` feature, policies can be loaded from configuration files:
+//!
+//! ```rust
+//! # #[cfg(feature = "serde")] {
+//! use enr::policy::Policy;
+//!
+//! let policy: Policy = serde_json::from_str(r#"{
+//!     "default": "allow",
+//!     "rules": [
+//!         { "action": "deny", "condition": { "ip_range": "10.0.0.0/8" } },
+//!         { "action": "deny", "condition": { "missing_field": "eth2" } }
+//!     ]
+//! }"#).unwrap();
+//! # }
+//! ```
+
+use crate::{descriptor::fork_digest, Enr, EnrKey, EnrPublicKey, RecordFlags};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    error::Error,
+    fmt,
+    net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+/// The action taken on a record.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// The record is allowed.
+    Allow,
+    /// The record is denied.
+    Deny,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// A condition on the content of a record.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// The node id starts with the given bytes.
+    NodeIdPrefix(#[cfg_attr(feature = "serde", serde(with = "enr_core::serde_hex_prfx"))] Vec<u8>),
+    /// The public key is stored under the given ENR key, e.g. `secp256k1`.
+    KeyScheme(String),
+    /// The `ip` or `ip6` address is within the given range.
+    IpRange(IpCidr),
+    /// The given key is present in the record.
+    HasField(String),
+    /// The given key is absent from the record.
+    MissingField(String),
+    /// The `flags` field has all of the given flags set.
+    HasFlags(RecordFlags),
+    /// The fork digest of the `eth2` field equals the given digest.
+    ForkDigest(#[cfg_attr(feature = "serde", serde(with = "enr_core::serde_hex_prfx"))] [u8; 4]),
+    /// All of the conditions match.
+    All(Vec<Condition>),
+    /// Any of the conditions match.
+    Any(Vec<Condition>),
+    /// The condition does not match.
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Evaluates the condition, returning a description of the match if it matches.
+    fn evaluate<K: EnrKey>(&self, enr: &Enr<K>) -> Option<String> {
+        match self {
+            Self::NodeIdPrefix(prefix) => enr
+                .node_id()
+                .raw()
+                .starts_with(prefix)
+                .then(|| format!("node id starts with 0x{}", hex::encode(prefix))),
+            Self::KeyScheme(scheme) => (enr.public_key().enr_key() == scheme.as_bytes())
+                .then(|| format!("key scheme is {scheme}")),
+            Self::IpRange(range) => {
+                let ip4 = enr.ip4().map(IpAddr::from);
+                let ip6 = enr.ip6().map(IpAddr::from);
+                ip4.into_iter()
+                    .chain(ip6)
+                    .find(|ip| range.contains(ip))
+                    .map(|ip| format!("ip {ip} is in {range}"))
+            }
+            Self::HasField(key) => enr
+                .get_raw_rlp(key)
+                .is_some()
+                .then(|| format!("field {key} is present")),
+            Self::MissingField(key) => enr
+                .get_raw_rlp(key)
+                .is_none()
+                .then(|| format!("field {key} is missing")),
+            Self::HasFlags(flags) => enr
+                .flags()
+                .is_some_and(|set| set.contains(*flags))
+                .then(|| format!("flags {flags:?} are set")),
+            Self::ForkDigest(digest) => (fork_digest(enr) == Some(*digest))
+                .then(|| format!("fork digest is 0x{}", hex::encode(digest))),
+            Self::All(conditions) => conditions
+                .iter()
+                .map(|condition| condition.evaluate(enr))
+                .collect::<Option<Vec<_>>>()
+                .map(|reasons| reasons.join(" and ")),
+            Self::Any(conditions) => conditions
+                .iter()
+                .find_map(|condition| condition.evaluate(enr)),
+            Self::Not(condition) => match condition.evaluate(enr) {
+                Some(_) => None,
+                None => Some(format!("not ({condition})")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |conditions: &[Self], separator: &str| {
+            conditions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        match self {
+            Self::NodeIdPrefix(prefix) => {
+                write!(f, "node id starts with 0x{}", hex::encode(prefix))
+            }
+            Self::KeyScheme(scheme) => write!(f, "key scheme is {scheme}"),
+            Self::IpRange(range) => write!(f, "ip is in {range}"),
+            Self::HasField(key) => write!(f, "field {key} is present"),
+            Self::MissingField(key) => write!(f, "field {key} is missing"),
+            Self::HasFlags(flags) => write!(f, "flags {flags:?} are set"),
+            Self::ForkDigest(digest) => write!(f, "fork digest is 0x{}", hex::encode(digest)),
+            Self::All(conditions) => write!(f, "{}", join(conditions, " and ")),
+            Self::Any(conditions) => write!(f, "{}", join(conditions, " or ")),
+            Self::Not(condition) => write!(f, "not ({condition})"),
+        }
+    }
+}
+
+/// A rule applying an [`Action`] to records matching a [`Condition`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    /// The action taken on matching records.
+    pub action: Action,
+    /// The condition records must match.
+    pub condition: Condition,
+}
+
+/// An ordered list of [`Rule`]s with a default [`Action`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// The action taken on records that match no rule.
+    pub default: Action,
+    /// The rules, in the order they are evaluated.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    /// Creates a policy without rules.
+    #[must_use]
+    pub const fn new(default: Action) -> Self {
+        Self {
+            default,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Appends a rule to the policy.
+    pub fn rule(&mut self, action: Action, condition: Condition) -> &mut Self {
+        self.rules.push(Rule { action, condition });
+        self
+    }
+
+    /// Evaluates a record against the policy. The action of the first matching rule is applied,
+    /// or the default action if no rule matches.
+    #[must_use]
+    pub fn evaluate<K: EnrKey>(&self, enr: &Enr<K>) -> Verdict {
+        self.rules
+            .iter()
+            .enumerate()
+            .find_map(|(index, rule)| {
+                rule.condition.evaluate(enr).map(|reason| Verdict {
+                    action: rule.action,
+                    rule: Some(index),
+                    explanation: format!("rule {index} ({}): {reason}", rule.action),
+                })
+            })
+            .unwrap_or_else(|| Verdict {
+                action: self.default,
+                rule: None,
+                explanation: format!("no rule matched, default ({})", self.default),
+            })
+    }
+}
+
+/// The outcome of evaluating a record against a [`Policy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verdict {
+    /// The action taken on the record.
+    pub action: Action,
+    /// The index of the rule that matched, if any.
+    pub rule: Option<usize>,
+    /// A human readable explanation of the verdict.
+    pub explanation: String,
+}
+
+impl Verdict {
+    /// Returns whether the record is allowed.
+    #[must_use]
+    pub fn is_allowed(&self) -> bool {
+        self.action == Action::Allow
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.explanation)
+    }
+}
+
+/// An IPv4 or IPv6 address range in CIDR notation, e.g. `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    /// The network address.
+    addr: IpAddr,
+    /// The length of the network prefix in bits.
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Creates a new range. Fails if the prefix length exceeds the address length.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, IpCidrError> {
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_len {
+            return Err(IpCidrError::PrefixTooLong(prefix_len));
+        }
+        Ok(Self { addr, prefix_len })
+    }
+
+    /// Returns whether the address is within the range.
+    #[must_use]
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = IpCidrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (
+                addr,
+                Some(
+                    prefix_len
+                        .parse::<u8>()
+                        .map_err(IpCidrError::PrefixLength)?,
+                ),
+            ),
+            None => (s, None),
+        };
+        let addr = addr.parse::<IpAddr>().map_err(IpCidrError::Address)?;
+        let prefix_len = prefix_len.unwrap_or(match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        });
+        Self::new(addr, prefix_len)
+    }
+}
+
+/// An error creating or parsing an [`IpCidr`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IpCidrError {
+    /// The network address is not an IP address.
+    Address(AddrParseError),
+    /// The prefix length is not a number.
+    PrefixLength(ParseIntError),
+    /// The prefix length exceeds the length of the address.
+    PrefixTooLong(u8),
+}
+
+impl fmt::Display for IpCidrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(e) => write!(f, "Invalid ip address: {e}"),
+            Self::PrefixLength(e) => write!(f, "Invalid prefix length: {e}"),
+            Self::PrefixTooLong(prefix_len) => write!(f, "Invalid prefix length: {prefix_len}"),
+        }
+    }
+}
+
+impl Error for IpCidrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Address(e) => Some(e),
+            Self::PrefixLength(e) => Some(e),
+            Self::PrefixTooLong(_) => None,
+        }
+    }
+}
+
+impl From<Ipv4Addr> for IpCidr {
+    fn from(addr: Ipv4Addr) -> Self {
+        Self {
+            addr: addr.into(),
+            prefix_len: 32,
+        }
+    }
+}
+
+impl From<Ipv6Addr> for IpCidr {
+    fn from(addr: Ipv6Addr) -> Self {
+        Self {
+            addr: addr.into(),
+            prefix_len: 128,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for IpCidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_ip_cidr() {
+        let range = "10.0.0.0/8".parse::<IpCidr>().unwrap();
+        assert!(range.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(!range.contains(&"11.0.0.1".parse().unwrap()));
+        assert!(!range.contains(&"::1".parse().unwrap()));
+
+        let range = "fd00::/8".parse::<IpCidr>().unwrap();
+        assert!(range.contains(&"fd12::1".parse().unwrap()));
+        assert!(!range.contains(&"fe80::1".parse().unwrap()));
+
+        assert!("0.0.0.0/0"
+            .parse::<IpCidr>()
+            .unwrap()
+            .contains(&"1.2.3.4".parse().unwrap()));
+        assert_eq!(
+            "10.0.0.0/33".parse::<IpCidr>(),
+            Err(IpCidrError::PrefixTooLong(33))
+        );
+    }
+
+    #[test]
+    fn test_policy_evaluate() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let private = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .add_value("eth2", &vec![1_u8, 2, 3, 4])
+            .build(&key)
+            .unwrap();
+        let public = DefaultEnr::builder()
+            .ip4(Ipv4Addr::new(1, 2, 3, 4))
+            .build(&key)
+            .unwrap();
+
+        let mut policy = Policy::new(Action::Allow);
+        policy
+            .rule(
+                Action::Deny,
+                Condition::IpRange("10.0.0.0/8".parse().unwrap()),
+            )
+            .rule(
+                Action::Deny,
+                Condition::All(vec![
+                    Condition::KeyScheme("secp256k1".into()),
+                    Condition::MissingField("eth2".into()),
+                ]),
+            );
+
+        let verdict = policy.evaluate(&private);
+        assert!(!verdict.is_allowed());
+        assert_eq!(verdict.rule, Some(0));
+        assert_eq!(
+            verdict.explanation,
+            "rule 0 (deny): ip 10.0.0.1 is in 10.0.0.0/8"
+        );
+
+        let verdict = policy.evaluate(&public);
+        assert!(!verdict.is_allowed());
+        assert_eq!(
+            verdict.explanation,
+            "rule 1 (deny): key scheme is secp256k1 and field eth2 is missing"
+        );
+
+        let policy = Policy {
+            default: Action::Deny,
+            rules: vec![Rule {
+                action: Action::Allow,
+                condition: Condition::ForkDigest([1, 2, 3, 4]),
+            }],
+        };
+        assert!(policy.evaluate(&private).is_allowed());
+        let verdict = policy.evaluate(&public);
+        assert!(!verdict.is_allowed());
+        assert_eq!(verdict.explanation, "no rule matched, default (deny)");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_policy_serde() {
+        let policy: Policy = serde_json::from_str(
+            r#"{
+                "default": "deny",
+                "rules": [
+                    { "action": "allow", "condition": { "node_id_prefix": "0xa448" } },
+                    { "action": "allow", "condition": { "fork_digest": "0x01020304" } },
+                    { "action": "allow", "condition": { "not": { "ip_range": "::/0" } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(policy.default, Action::Deny);
+        assert_eq!(
+            policy.rules[0].condition,
+            Condition::NodeIdPrefix(vec![0xa4, 0x48])
+        );
+        assert_eq!(
+            policy.rules[1].condition,
+            Condition::ForkDigest([1, 2, 3, 4])
+        );
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(serde_json::from_str::<Policy>(&json).unwrap(), policy);
+    }
+}
@@ -0,0 +1,207 @@
+//! A multi-line, human readable rendering of an ENR for CLIs and logs.
+
+#[cfg(feature = "eth2")]
+use crate::{
+    AttestationSubnets, EnrForkId, SubnetBitfield, SyncCommitteeSubnets, ATTNETS_KEY, ETH2_KEY,
+    SYNCNETS_KEY,
+};
+use crate::{Enr, EnrKey, FieldValue, IdentityScheme};
+use std::fmt;
+
+/// A table of the decoded fields of a record, as returned by [`Enr::display_pretty`] and rendered
+/// by the alternate form of the record's [`Display`](fmt::Display) implementation, `{:#}`.
+///
+/// Every field is rendered on its own line as `key  value`, with the keys aligned. Fields known to
+/// this crate are decoded, see [`FieldValue`]; additionally, with the `eth2` feature, the `eth2`
+/// fork id is split into its parts and the `attnets` and `syncnets` bitfields are listed as the
+/// subnets they subscribe to.
+/// Values that can't be decoded are rendered as hex.
+pub struct PrettyEnr<'a, K: EnrKey, S: IdentityScheme> {
+    enr: &'a Enr<K, S>,
+    base64: bool,
+}
+
+impl<K: EnrKey, S: IdentityScheme> Clone for PrettyEnr<'_, K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            enr: self.enr,
+            base64: self.base64,
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> fmt::Debug for PrettyEnr<'_, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PrettyEnr")
+            .field("enr", self.enr)
+            .field("base64", &self.base64)
+            .finish()
+    }
+}
+
+impl<'a, K: EnrKey, S: IdentityScheme> PrettyEnr<'a, K, S> {
+    pub(crate) const fn new(enr: &'a Enr<K, S>) -> Self {
+        Self { enr, base64: true }
+    }
+
+    /// Omits the trailing line with the base64 text form of the record.
+    #[must_use]
+    pub const fn without_base64(mut self) -> Self {
+        self.base64 = false;
+        self
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> fmt::Display for PrettyEnr<'_, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = ["node_id", "seq", "signature", "base64"];
+        let width = self
+            .enr
+            .keys()
+            .map(|key| String::from_utf8_lossy(key).chars().count())
+            .chain(header.iter().map(|label| label.len()))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{:width$}  {}", "node_id", self.enr.node_id())?;
+        writeln!(f, "{:width$}  {}", "seq", self.enr.seq())?;
+        write!(
+            f,
+            "{:width$}  0x{}",
+            "signature",
+            hex::encode(self.enr.signature())
+        )?;
+        for (key, value) in self.enr.iter() {
+            let name = String::from_utf8_lossy(key);
+            write!(f, "\n{name:width$}  ")?;
+            format_value(f, key, value)?;
+        }
+        if self.base64 {
+            write!(f, "\n{:width$}  {}", "base64", self.enr.to_base64())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the decoded raw RLP value of the field stored under `key`.
+fn format_value(f: &mut fmt::Formatter, key: &[u8], value: &[u8]) -> fmt::Result {
+    #[cfg(feature = "eth2")]
+    if let Some(result) = format_consensus_value(f, key, value) {
+        return result;
+    }
+    write!(f, "{}", FieldValue::decode(key, value))
+}
+
+/// Writes the decoded raw RLP value of a consensus layer field, if `key` is one and its value is
+/// well-formed.
+#[cfg(feature = "eth2")]
+fn format_consensus_value(f: &mut fmt::Formatter, key: &[u8], value: &[u8]) -> Option<fmt::Result> {
+    let data = rlp::Rlp::new(value).data().ok()?;
+    if key == ETH2_KEY.as_bytes() {
+        let fork_id = EnrForkId::from_ssz_bytes(data).ok()?;
+        Some(write!(
+            f,
+            "fork_digest 0x{}, next_fork_version 0x{}, next_fork_epoch {}",
+            hex::encode(fork_id.fork_digest),
+            hex::encode(fork_id.next_fork_version),
+            fork_id.next_fork_epoch
+        ))
+    } else if key == ATTNETS_KEY.as_bytes() {
+        let subnets = AttestationSubnets::from_ssz_bytes(data).ok()?;
+        Some(format_subnets(f, subnets))
+    } else if key == SYNCNETS_KEY.as_bytes() {
+        let subnets = SyncCommitteeSubnets::from_ssz_bytes(data).ok()?;
+        Some(format_subnets(f, subnets))
+    } else {
+        None
+    }
+}
+
+/// Writes the subnets of a bitfield as a list, e.g. `[0, 5]`.
+#[cfg(feature = "eth2")]
+fn format_subnets<const N: usize>(
+    f: &mut fmt::Formatter,
+    subnets: SubnetBitfield<N>,
+) -> fmt::Result {
+    f.debug_list().entries(subnets.subnets()).finish()
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_display_pretty() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .quic4(9001)
+            .add_value("custom", &0x1234_u16)
+            .build(&key)
+            .unwrap();
+
+        let pretty = enr.display_pretty().to_string();
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert_eq!(lines[0], format!("node_id    {}", enr.node_id()));
+        assert_eq!(lines[1], format!("seq        {}", enr.seq()));
+        assert!(lines.contains(&"custom     0x821234"));
+        assert!(lines.contains(&"ip         10.0.0.1"));
+        assert!(lines.contains(&"quic       9001"));
+        assert_eq!(
+            lines.last(),
+            Some(&format!("base64     {}", enr.to_base64()).as_str())
+        );
+        assert_eq!(format!("{enr:#}"), pretty);
+        assert_eq!(format!("{enr}"), enr.to_base64());
+
+        let without_base64 = enr.display_pretty().without_base64().to_string();
+        assert_eq!(without_base64.lines().count(), lines.len() - 1);
+        assert!(!without_base64.contains("enr:"));
+    }
+
+    #[cfg(feature = "eth2")]
+    #[test]
+    fn test_display_pretty_consensus_fields() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let fork_id = EnrForkId {
+            fork_digest: [0xb5, 0x30, 0x3f, 0x2a],
+            next_fork_version: [4, 0, 0, 0],
+            next_fork_epoch: u64::MAX,
+        };
+        let mut attnets = AttestationSubnets::new();
+        attnets.set(0, true);
+        attnets.set(63, true);
+        let mut syncnets = SyncCommitteeSubnets::new();
+        syncnets.set(2, true);
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .add_value(ETH2_KEY, &fork_id.to_ssz_bytes().as_ref())
+            .add_value(ATTNETS_KEY, &attnets)
+            .add_value(SYNCNETS_KEY, &syncnets)
+            .build(&key)
+            .unwrap();
+
+        let pretty = enr.display_pretty().to_string();
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert!(lines.contains(&"attnets    [0, 63]"));
+        assert!(lines.contains(&"eth2       fork_digest 0xb5303f2a, next_fork_version 0x04000000, next_fork_epoch 18446744073709551615"));
+        assert!(lines.contains(&"syncnets   [2]"));
+    }
+
+    #[test]
+    fn test_display_pretty_invalid_values() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr: Enr<DefaultKey> = Enr::builder()
+            .add_value("eth2", &[1_u8, 2, 3].as_ref())
+            .add_value("attnets", &[1_u8].as_ref())
+            .build(&key)
+            .unwrap();
+
+        let pretty = enr.display_pretty().to_string();
+        assert!(pretty.contains("\nattnets    0x01"));
+        assert!(pretty.contains("\neth2       0x010203"));
+    }
+}
@@ -0,0 +1,202 @@
+//! A protobuf representation of records for gRPC APIs.
+//!
+//! [`Enr`] is the `prost` message of the schema [`ENR_PROTO`], which is also found at
+//! `proto/enr.proto` in the repository for generating code in other languages. It carries the
+//! record in both its binary and text form along with its node id and sequence number, so clients
+//! can display and index records without decoding them.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, proto, Enr};
+//! use prost::Message;
+//! use std::convert::TryFrom;
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//!
+//! let bytes = proto::Enr::from(&enr).encode_to_vec();
+//! let message = proto::Enr::decode(bytes.as_slice()).unwrap();
+//! assert_eq!(message.seq, 1);
+//! assert_eq!(Enr::<SigningKey>::try_from(message).unwrap(), enr);
+//! # }
+//! ```
+
+use crate::{EnrKey, EnrParseError, IdentityScheme};
+use std::{convert::TryFrom, error::Error, fmt};
+
+/// The protobuf schema of [`Enr`], in the package `enr.v1`.
+pub const ENR_PROTO: &str = include_str!("../proto/enr.proto");
+
+/// The protobuf message of a record.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Enr {
+    /// The RLP encoding of the record.
+    #[prost(bytes = "vec", tag = "1")]
+    pub rlp: Vec<u8>,
+    /// The text form of the record.
+    #[prost(string, tag = "2")]
+    pub base64: String,
+    /// The node id of the record.
+    #[prost(bytes = "vec", tag = "3")]
+    pub node_id: Vec<u8>,
+    /// The sequence number of the record.
+    #[prost(uint64, tag = "4")]
+    pub seq: u64,
+}
+
+/// An error converting an [`Enr`] message into a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProtoError {
+    /// Neither the `rlp` nor the `base64` field is set.
+    MissingRecord,
+    /// The `rlp` field is not a valid record.
+    InvalidRlp(EnrParseError),
+    /// The `base64` field is not a valid record.
+    InvalidBase64(EnrParseError),
+    /// The fields describe different records.
+    Inconsistent,
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRecord => write!(f, "the message holds no record"),
+            Self::InvalidRlp(e) => write!(f, "invalid rlp field: {e}"),
+            Self::InvalidBase64(e) => write!(f, "invalid base64 field: {e}"),
+            Self::Inconsistent => write!(f, "the fields of the message describe different records"),
+        }
+    }
+}
+
+impl Error for ProtoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidRlp(e) => Some(e),
+            Self::InvalidBase64(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> From<&crate::Enr<K, S>> for Enr {
+    fn from(enr: &crate::Enr<K, S>) -> Self {
+        Self {
+            rlp: rlp::encode(enr).to_vec(),
+            base64: enr.to_base64(),
+            node_id: enr.node_id().raw().to_vec(),
+            seq: enr.seq(),
+        }
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> From<crate::Enr<K, S>> for Enr {
+    fn from(enr: crate::Enr<K, S>) -> Self {
+        Self::from(&enr)
+    }
+}
+
+/// Decodes and verifies the record of a message.
+///
+/// The record is decoded from the `rlp` field, or from the `base64` field if `rlp` is empty. The
+/// other fields are optional, but if set they must agree with the record: `base64` must be its text
+/// form and `node_id` and a non-zero `seq` its node id and sequence number.
+impl<K: EnrKey, S: IdentityScheme> TryFrom<Enr> for crate::Enr<K, S> {
+    type Error = ProtoError;
+
+    fn try_from(message: Enr) -> Result<Self, Self::Error> {
+        let enr: Self = if !message.rlp.is_empty() {
+            Self::decode_with_context(&message.rlp).map_err(ProtoError::InvalidRlp)?
+        } else if !message.base64.is_empty() {
+            message.base64.parse().map_err(ProtoError::InvalidBase64)?
+        } else {
+            return Err(ProtoError::MissingRecord);
+        };
+
+        let consistent = (message.base64.is_empty() || message.base64 == enr.to_base64())
+            && (message.node_id.is_empty() || message.node_id == enr.node_id().raw())
+            && (message.seq == 0 || message.seq == enr.seq());
+        if !consistent {
+            return Err(ProtoError::Inconsistent);
+        }
+        Ok(enr)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    type DefaultEnr = crate::Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_proto_round_trip() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+
+        let message = Enr::from(&enr);
+        assert_eq!(message.node_id, enr.node_id().raw());
+        let decoded = Enr::decode(message.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(DefaultEnr::try_from(decoded), Ok(enr.clone()));
+
+        // either form of the record suffices
+        let rlp_only = Enr {
+            rlp: message.rlp.clone(),
+            ..Enr::default()
+        };
+        assert_eq!(DefaultEnr::try_from(rlp_only), Ok(enr.clone()));
+        let base64_only = Enr {
+            base64: message.base64.clone(),
+            ..Enr::default()
+        };
+        assert_eq!(DefaultEnr::try_from(base64_only), Ok(enr));
+    }
+
+    #[test]
+    fn test_proto_invalid() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let message = Enr::from(&enr);
+
+        assert_eq!(
+            DefaultEnr::try_from(Enr::default()),
+            Err(ProtoError::MissingRecord)
+        );
+        let wrong_seq = Enr {
+            seq: 2,
+            ..message.clone()
+        };
+        assert_eq!(
+            DefaultEnr::try_from(wrong_seq),
+            Err(ProtoError::Inconsistent)
+        );
+        let other = DefaultEnr::empty(&key).unwrap();
+        let wrong_base64 = Enr {
+            base64: other.to_base64(),
+            ..message.clone()
+        };
+        assert_eq!(
+            DefaultEnr::try_from(wrong_base64),
+            Err(ProtoError::Inconsistent)
+        );
+        let invalid_rlp = Enr {
+            rlp: vec![0xc0],
+            ..message
+        };
+        assert!(matches!(
+            DefaultEnr::try_from(invalid_rlp),
+            Err(ProtoError::InvalidRlp(_))
+        ));
+    }
+
+    #[test]
+    fn test_proto_schema() {
+        assert!(ENR_PROTO.contains("package enr.v1;"));
+        assert!(ENR_PROTO.contains("bytes rlp = 1;"));
+        assert!(ENR_PROTO.contains("string base64 = 2;"));
+        assert!(ENR_PROTO.contains("bytes node_id = 3;"));
+        assert!(ENR_PROTO.contains("uint64 seq = 4;"));
+    }
+}
@@ -0,0 +1,397 @@
+//! A byte-exact, unverified representation of an ENR.
+//!
+//! A [`RawRecord`] keeps the exact RLP encoding it was decoded from. It is structurally sanitized
+//! (size limit, sorted keys, well-formed values) but neither the signature nor the identity
+//! scheme is verified. This allows relays and storage layers to hold and forward records of
+//! identity schemes this crate does not (yet) support, without modifying them. A conversion into
+//! a verified [`Enr`] can be attempted at any time with [`RawRecord::to_enr`].
+//!
+//! Under its alias [`UnverifiedEnr`], the type serves as the first stage of a two-stage decode:
+//! crawlers can inspect the fields of possibly bogus records, e.g. for metrics, and only pay for
+//! signature verification with [`UnverifiedEnr::verify`] when they keep the record.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, UnverifiedEnr};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//! let bytes = rlp::encode(&enr);
+//!
+//! let unverified = UnverifiedEnr::decode(&bytes).unwrap();
+//! assert_eq!(unverified.get_decodable::<u16>("udp"), Some(Ok(30303)));
+//! assert_eq!(unverified.verify::<SigningKey>().unwrap(), enr);
+//! # }
+//! ```
+
+#[cfg(feature = "k256")]
+use crate::EnrError;
+use crate::{decode_record_fields, trace, Enr, EnrKey, EnrParseError, Key, Value};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use bytes::Bytes;
+use rlp::{Decodable, DecoderError, Rlp, RlpStream};
+use std::{collections::BTreeMap, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Whether the public key stored in a record must match the key recovered from its signature.
+#[cfg(feature = "k256")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RecoveryMode {
+    /// A `secp256k1` public key stored in the record must match the recovered key.
+    #[default]
+    Strict,
+    /// Any `secp256k1` public key stored in the record is ignored.
+    Lenient,
+}
+
+/// An ENR whose signature and identity scheme have not been verified.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RawRecord {
+    /// The exact RLP encoding of the record.
+    raw: Bytes,
+
+    /// The sequence number of the record.
+    seq: u64,
+
+    /// The signature of the record.
+    signature: Vec<u8>,
+
+    /// Key-value contents of the record, stored as raw RLP bytes.
+    content: BTreeMap<Key, Value>,
+}
+
+/// A record decoded without verifying its signature, see [`RawRecord`].
+pub type UnverifiedEnr = RawRecord;
+
+impl RawRecord {
+    /// Decodes a record from its RLP encoding without verifying its signature.
+    pub fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+        rlp::decode(raw)
+    }
+
+    /// The exact RLP encoding of the record.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The signature of the record.
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The `id` (identity scheme) of the record if it is defined.
+    #[must_use]
+    pub fn id(&self) -> Option<String> {
+        self.get_raw_rlp("id")
+            .and_then(|rlp_data| Rlp::new(rlp_data).data().ok())
+            .map(|id_bytes| String::from_utf8_lossy(id_bytes).to_string())
+    }
+
+    /// Reads a key from the record if it exists as raw RLP bytes.
+    pub fn get_raw_rlp(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.content.get(key.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// Decodes the value of a key from the record if it exists.
+    pub fn get_decodable<T: Decodable>(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Option<Result<T, DecoderError>> {
+        self.get_raw_rlp(key).map(rlp::decode)
+    }
+
+    /// Returns an iterator over all key/value pairs in the record.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.content.iter().map(|(k, v)| (k.as_slice(), v.as_ref()))
+    }
+
+    /// Provides the URL-safe base64 encoded "text" version of the record prefixed by "enr:".
+    ///
+    /// This is identical to the text form the record was originally received in.
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        let hex = URL_SAFE_NO_PAD.encode(&self.raw);
+        format!("enr:{hex}")
+    }
+
+    /// Attempts to convert the record into a verified [`Enr`].
+    ///
+    /// This fails if the identity scheme or key type is not supported by `K`, or if the signature
+    /// is invalid.
+    pub fn to_enr<K: EnrKey>(&self) -> Result<Enr<K>, DecoderError> {
+        rlp::decode(&self.raw)
+    }
+
+    /// Verifies the record, consuming it into an [`Enr`]. Fails like [`RawRecord::to_enr`].
+    pub fn verify<K: EnrKey>(self) -> Result<Enr<K>, DecoderError> {
+        self.to_enr()
+    }
+
+    /// Recovers the secp256k1 public key from a recoverable signature.
+    ///
+    /// Some tooling signs records with a 65 byte recoverable signature (`r || s || v`) instead of
+    /// the 64 byte signature of the v4 identity scheme, allowing the public key field to be
+    /// omitted (see [`SignatureMode`](crate::SignatureMode)). The signer of such records can be
+    /// recovered here without verifying the record. The recovery id `v` may be given either as
+    /// `0..=3` or offset by 27.
+    ///
+    /// In [`RecoveryMode::Strict`], a `secp256k1` public key stored in the record must match the
+    /// recovered key, otherwise [`EnrError::PublicKeyMismatch`] is returned.
+    #[cfg(feature = "k256")]
+    pub fn recover_public_key(
+        &self,
+        mode: RecoveryMode,
+    ) -> Result<k256::ecdsa::VerifyingKey, EnrError> {
+        use k256::ecdsa::{SigningKey, VerifyingKey};
+
+        let mut stream = RlpStream::new_list(self.content.len() * 2 + 1);
+        stream.append(&self.seq);
+        for (key, value) in &self.content {
+            stream.append(&key.as_slice());
+            stream.append_raw(value, 1);
+        }
+        let recovered = SigningKey::recover_v4(&stream.out(), &self.signature)
+            .ok_or(EnrError::UnrecoverableSignature)?;
+
+        if mode == RecoveryMode::Strict {
+            if let Some(public_key) = self.get_raw_rlp("secp256k1") {
+                let public_key = Rlp::new(public_key)
+                    .data()
+                    .ok()
+                    .and_then(|bytes| VerifyingKey::from_sec1_bytes(bytes).ok());
+                if public_key != Some(recovered) {
+                    return Err(EnrError::PublicKeyMismatch);
+                }
+            }
+        }
+        Ok(recovered)
+    }
+}
+
+impl<K: EnrKey> From<&Enr<K>> for RawRecord {
+    fn from(enr: &Enr<K>) -> Self {
+        Self {
+            raw: rlp::encode(enr).freeze(),
+            seq: enr.seq(),
+            signature: enr.signature().to_vec(),
+            content: enr.content.clone(),
+        }
+    }
+}
+
+impl<K: EnrKey> From<Enr<K>> for RawRecord {
+    fn from(enr: Enr<K>) -> Self {
+        Self::from(&enr)
+    }
+}
+
+impl std::fmt::Display for RawRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_base64())
+    }
+}
+
+impl std::fmt::Debug for RawRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RawRecord")
+            .field("id", &self.id())
+            .field("seq", &self.seq)
+            .field("signature", &hex::encode(&self.signature))
+            .field("raw", &hex::encode(&self.raw))
+            .finish()
+    }
+}
+
+/// Convert a URL-SAFE base64 encoded ENR into a [`RawRecord`].
+impl FromStr for RawRecord {
+    type Err = EnrParseError;
+
+    fn from_str(base64_string: &str) -> Result<Self, Self::Err> {
+        let decode_string = base64_string.strip_prefix("enr:").unwrap_or(base64_string);
+        let bytes = URL_SAFE_NO_PAD
+            .decode(decode_string)
+            .map_err(EnrParseError::Base64)?;
+        Ok(rlp::decode(&bytes)?)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for RawRecord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_base64())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RawRecord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+impl rlp::Encodable for RawRecord {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        stream.append_raw(&self.raw, 1);
+    }
+}
+
+impl rlp::Decodable for RawRecord {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let (signature, seq, content) = decode_record_fields(rlp, false)
+            .inspect_err(|cause| trace::decode_failed(rlp.as_raw().len(), cause))?;
+        Ok(Self {
+            raw: Bytes::copy_from_slice(rlp.as_raw()),
+            seq,
+            signature,
+            content,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use crate::digest;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[test]
+    fn test_raw_record_round_trip() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+
+        let raw = RawRecord::from(&enr);
+        assert_eq!(raw.as_bytes(), rlp::encode(&enr).as_ref());
+        assert_eq!(raw.to_base64(), enr.to_base64());
+        assert_eq!(raw.seq(), enr.seq());
+        assert_eq!(raw.id(), Some("v4".into()));
+
+        let decoded = raw.to_base64().parse::<RawRecord>().unwrap();
+        assert_eq!(decoded, raw);
+        assert_eq!(decoded.to_enr::<k256::ecdsa::SigningKey>().unwrap(), enr);
+    }
+
+    #[test]
+    fn test_unverified_enr() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let enr = DefaultEnr::builder().udp4(30303).build(&key).unwrap();
+        let mut bytes = rlp::encode(&enr).to_vec();
+
+        let unverified = UnverifiedEnr::decode(&bytes).unwrap();
+        assert_eq!(unverified.get_decodable::<u16>("udp"), Some(Ok(30303)));
+        assert_eq!(unverified.get_decodable::<u16>("tcp"), None);
+        assert_eq!(unverified.verify::<k256::ecdsa::SigningKey>().unwrap(), enr);
+
+        // corrupt the signature: the record still decodes, but fails verification
+        bytes[5] ^= 1;
+        let unverified = UnverifiedEnr::decode(&bytes).unwrap();
+        assert_eq!(unverified.get_decodable::<u16>("udp"), Some(Ok(30303)));
+        assert!(unverified.verify::<k256::ecdsa::SigningKey>().is_err());
+    }
+
+    #[test]
+    fn test_raw_record_unknown_scheme() {
+        // a record with the identity scheme "v5" and a fake signature
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&vec![0_u8; 64]);
+        stream.append(&1_u64);
+        stream.append(&"id");
+        stream.append(&"v5");
+        stream.append(&"secp256k1");
+        stream.append(&vec![2_u8; 33]);
+        let encoded = stream.out();
+
+        let raw = RawRecord::decode(&encoded).unwrap();
+        assert_eq!(raw.id(), Some("v5".into()));
+        assert_eq!(raw.as_bytes(), encoded.as_ref());
+        assert_eq!(rlp::encode(&raw), encoded);
+        assert!(raw.to_enr::<k256::ecdsa::SigningKey>().is_err());
+    }
+
+    /// Encodes a record signed with a recoverable signature.
+    fn recoverable_record(key: &k256::ecdsa::SigningKey, public_key: Option<&[u8]>) -> RawRecord {
+        let mut content = vec![("id", rlp::encode(&"v4"))];
+        if let Some(public_key) = public_key {
+            content.push(("secp256k1", rlp::encode(&public_key)));
+        }
+        content.push(("udp", rlp::encode(&30303_u16)));
+        let append_content = |stream: &mut RlpStream| {
+            stream.append(&1_u64);
+            for (k, v) in &content {
+                stream.append(k);
+                stream.append_raw(v, 1);
+            }
+        };
+
+        let mut stream = RlpStream::new_list(content.len() * 2 + 1);
+        append_content(&mut stream);
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(&digest(&stream.out()))
+            .unwrap();
+        let mut signature = signature.to_vec();
+        signature.push(recovery_id.to_byte() + 27);
+
+        let mut stream = RlpStream::new_list(content.len() * 2 + 2);
+        stream.append(&signature);
+        append_content(&mut stream);
+        RawRecord::decode(&stream.out()).unwrap()
+    }
+
+    #[test]
+    fn test_recover_public_key() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let public_key = *key.verifying_key();
+
+        // without a public key field
+        let raw = recoverable_record(&key, None);
+        assert_eq!(
+            raw.recover_public_key(RecoveryMode::Strict).unwrap(),
+            public_key
+        );
+
+        // with a matching public key field
+        let encoded = public_key.to_sec1_bytes();
+        let raw = recoverable_record(&key, Some(&encoded));
+        assert_eq!(
+            raw.recover_public_key(RecoveryMode::Strict).unwrap(),
+            public_key
+        );
+
+        // with a mismatching public key field
+        let other = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let raw = recoverable_record(&key, Some(&other.verifying_key().to_sec1_bytes()));
+        assert_eq!(
+            raw.recover_public_key(RecoveryMode::Strict),
+            Err(EnrError::PublicKeyMismatch)
+        );
+        assert_eq!(
+            raw.recover_public_key(RecoveryMode::Lenient).unwrap(),
+            public_key
+        );
+
+        // standard v4 signatures are not recoverable
+        let enr = DefaultEnr::builder().build(&key).unwrap();
+        assert_eq!(
+            RawRecord::from(&enr).recover_public_key(RecoveryMode::Lenient),
+            Err(EnrError::UnrecoverableSignature)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Lists of records, as carried in the `records` field of discv5 `NODES` messages.
+//!
+//! A `NODES` response carries an RLP list of ENRs. A single invalid record from a misbehaving peer
+//! should not cause the valid records of the response to be discarded, so [`decode_list`] decodes
+//! each record independently. [`encode_list`] encodes as many records as fit into the space left
+//! in a packet.
+
+use crate::{rlp_header_len, Enr, EnrKey};
+use bytes::BytesMut;
+use rlp::{DecoderError, Rlp, RlpStream};
+
+/// Decodes an RLP list of records, decoding and verifying each record independently.
+///
+/// Fails only if `bytes` is not an RLP list. Otherwise, the result of decoding each item is
+/// returned in order.
+pub fn decode_list<K: EnrKey>(
+    bytes: &[u8],
+) -> Result<Vec<Result<Enr<K>, DecoderError>>, DecoderError> {
+    let rlp = Rlp::new(bytes);
+    if !rlp.is_list() {
+        return Err(DecoderError::RlpExpectedToBeList);
+    }
+    let payload_info = rlp.payload_info()?;
+    if bytes.len() != payload_info.header_len + payload_info.value_len {
+        return Err(DecoderError::RlpInconsistentLengthAndData);
+    }
+    Ok(rlp.iter().map(|item| item.as_val()).collect())
+}
+
+/// Encodes records as an RLP list of at most `max_size` bytes, in order, stopping before the
+/// first record that doesn't fit.
+///
+/// Returns the encoded list and the number of records it contains.
+pub fn encode_list<'a, K: EnrKey + 'a>(
+    records: impl IntoIterator<Item = &'a Enr<K>>,
+    max_size: usize,
+) -> (BytesMut, usize) {
+    let mut payload = BytesMut::new();
+    let mut count = 0;
+    for enr in records {
+        let len = enr.encoded_len();
+        if rlp_header_len(payload.len() + len) + payload.len() + len > max_size {
+            break;
+        }
+        enr.encode_to(&mut payload);
+        count += 1;
+    }
+
+    let mut stream = RlpStream::new_list(count);
+    stream.append_raw(&payload, count);
+    (stream.out(), count)
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    fn records(count: u16) -> Vec<DefaultEnr> {
+        (0..count)
+            .map(|i| {
+                let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+                DefaultEnr::builder().udp4(30303 + i).build(&key).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let records = records(3);
+        let (encoded, count) = encode_list(&records, usize::MAX);
+        assert_eq!(count, 3);
+        assert_eq!(encoded, rlp::encode_list(&records));
+
+        let decoded = decode_list::<k256::ecdsa::SigningKey>(&encoded).unwrap();
+        assert_eq!(
+            decoded.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+            records
+        );
+    }
+
+    #[test]
+    fn test_invalid_item() {
+        let records = records(2);
+        let mut stream = RlpStream::new_list(3);
+        stream.append(&records[0]);
+        stream.append(&"not a record");
+        stream.append(&records[1]);
+
+        let decoded = decode_list::<k256::ecdsa::SigningKey>(&stream.out()).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].as_ref().unwrap(), &records[0]);
+        assert!(decoded[1].is_err());
+        assert_eq!(decoded[2].as_ref().unwrap(), &records[1]);
+
+        assert!(decode_list::<k256::ecdsa::SigningKey>(&rlp::encode(&"not a list")).is_err());
+    }
+
+    #[test]
+    fn test_capped() {
+        let records = records(5);
+        let full = encode_list(&records, usize::MAX).0;
+
+        // one byte short of fitting all records drops the last one
+        let (encoded, count) = encode_list(&records, full.len() - 1);
+        assert_eq!(count, 4);
+        assert!(encoded.len() < full.len());
+        assert_eq!(encoded, rlp::encode_list(&records[..4]));
+
+        let (encoded, count) = encode_list(&records, 1);
+        assert_eq!(count, 0);
+        assert_eq!(encoded.as_ref(), [0xc0]);
+    }
+}
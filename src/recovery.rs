@@ -0,0 +1,93 @@
+//! Records signed with recoverable signatures.
+//!
+//! Under the `v4` identity scheme, a record stores its public key under the `secp256k1` key and
+//! is signed with a 64 byte signature. A recoverable signature appends a one byte recovery id,
+//! from which the public key can be recovered. Records signed this way may omit the public key
+//! field, saving over 40 bytes, which compact discovery protocols use to fit more records in a
+//! packet. Note that implementations without public key recovery cannot decode such records.
+//!
+//! The [`SignatureMode`] of a record is chosen when it is built and kept when it is updated:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, SignatureMode};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey> = Enr::builder()
+//!     .signature_mode(SignatureMode::RecoverableWithoutPublicKey)
+//!     .udp4(30303)
+//!     .build(&key)
+//!     .unwrap();
+//! assert!(enr.get_raw_rlp("secp256k1").is_none());
+//! assert_eq!(enr.recover_public_key().unwrap(), *key.verifying_key());
+//!
+//! // the public key and node id are recovered when decoding
+//! let decoded: Enr<SigningKey> = enr.to_base64().parse().unwrap();
+//! assert_eq!(decoded.node_id(), enr.node_id());
+//! assert_eq!(decoded.signature_mode(), SignatureMode::RecoverableWithoutPublicKey);
+//! # }
+//! ```
+
+use crate::{EnrError, EnrKey, IdentityScheme, Key, KeyScheme, V4Scheme, Value};
+use std::collections::BTreeMap;
+
+/// The length of a recoverable signature, `r || s || v`.
+const RECOVERABLE_SIGNATURE_LEN: usize = 65;
+
+/// How a record is signed and whether it stores its public key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SignatureMode {
+    /// The signature of the identity scheme, with the public key stored in the record.
+    #[default]
+    Standard,
+    /// A `v4` signature followed by its recovery id, with the public key stored in the record.
+    Recoverable,
+    /// A `v4` signature followed by its recovery id, with the public key omitted from the record.
+    RecoverableWithoutPublicKey,
+}
+
+impl SignatureMode {
+    /// The mode of a record of the identity scheme `S` with the given signature and content.
+    pub(crate) fn of<S: IdentityScheme>(signature: &[u8], content: &BTreeMap<Key, Value>) -> Self {
+        if S::ID != V4Scheme::ID || signature.len() != RECOVERABLE_SIGNATURE_LEN {
+            Self::Standard
+        } else if KeyScheme::ALL
+            .iter()
+            .any(|scheme| content.contains_key(scheme.enr_key().as_bytes()))
+        {
+            Self::Recoverable
+        } else {
+            Self::RecoverableWithoutPublicKey
+        }
+    }
+
+    /// Whether records of this mode store their public key.
+    pub(crate) const fn stores_public_key(self) -> bool {
+        !matches!(self, Self::RecoverableWithoutPublicKey)
+    }
+
+    /// Signs the RLP encoded content of a record of the identity scheme `S`. Recoverable
+    /// signatures are only supported by the `v4` identity scheme.
+    pub(crate) fn sign<K: EnrKey, S: IdentityScheme>(
+        self,
+        key: &K,
+        content: &[u8],
+    ) -> Result<Vec<u8>, EnrError> {
+        match self {
+            Self::Standard => S::sign(key, content).map_err(|_| EnrError::SigningError),
+            _ if S::ID != V4Scheme::ID => Err(EnrError::UnsupportedIdentityScheme),
+            _ => key
+                .sign_v4_recoverable(content)
+                .map_err(|_| EnrError::SigningError),
+        }
+    }
+
+    /// Checks that a signature produced by an external signer fits this mode.
+    pub(crate) fn check(self, signature: &[u8]) -> Result<(), EnrError> {
+        if self == Self::Standard || signature.len() == RECOVERABLE_SIGNATURE_LEN {
+            Ok(())
+        } else {
+            Err(EnrError::UnrecoverableSignature)
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Registry of the ENR keys with a reserved meaning.
+//!
+//! Keys defined by [EIP-778](https://eips.ethereum.org/EIPS/eip-778) are always reserved. Crate
+//! features that give meaning to further keys register them here along with the validator applied
+//! to their values on insertion. The registry is checked at compile time so that no two
+//! registrations assign different validators to the same key. Downstream crates defining their own
+//! fields can check against [`reserved_keys`] to avoid collisions.
+
+use std::fmt;
+
+/// How the value stored under a reserved key is validated on insertion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyValidator {
+    /// The value is the name of a supported identity scheme.
+    IdentityScheme,
+    /// The value is a 4 byte IPv4 address.
+    Ipv4,
+    /// The value is a 16 byte IPv6 address.
+    Ipv6,
+    /// The value is an RLP encoded `u16`.
+    Port,
+    /// The value is an RLP encoded `u64` of [`RecordFlags`](crate::RecordFlags).
+    Flags,
+    /// The value is a secp256k1 public key.
+    Secp256k1,
+    /// The value is an ed25519 public key.
+    Ed25519,
+    /// The value is not validated.
+    Unchecked,
+}
+
+/// A key with a reserved meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReservedKey {
+    /// The ENR key.
+    pub key: &'static str,
+    /// The validator applied to the key's value on insertion.
+    pub validator: KeyValidator,
+    /// The specification or crate feature that reserves the key.
+    pub registered_by: &'static str,
+}
+
+impl ReservedKey {
+    const fn new(key: &'static str, validator: KeyValidator, registered_by: &'static str) -> Self {
+        Self {
+            key,
+            validator,
+            registered_by,
+        }
+    }
+}
+
+impl fmt::Display for ReservedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.key, self.registered_by)
+    }
+}
+
+/// Every reserved key. Features add their keys as `#[cfg]` gated entries.
+const RESERVED_KEYS: &[ReservedKey] = &[
+    ReservedKey::new("id", KeyValidator::IdentityScheme, "eip-778"),
+    ReservedKey::new("secp256k1", KeyValidator::Secp256k1, "eip-778"),
+    ReservedKey::new("ed25519", KeyValidator::Ed25519, "eip-778"),
+    ReservedKey::new("ip", KeyValidator::Ipv4, "eip-778"),
+    ReservedKey::new("ip6", KeyValidator::Ipv6, "eip-778"),
+    ReservedKey::new("tcp", KeyValidator::Port, "eip-778"),
+    ReservedKey::new("tcp6", KeyValidator::Port, "eip-778"),
+    ReservedKey::new("udp", KeyValidator::Port, "eip-778"),
+    ReservedKey::new("udp6", KeyValidator::Port, "eip-778"),
+    ReservedKey::new("quic", KeyValidator::Port, "enr"),
+    ReservedKey::new("quic6", KeyValidator::Port, "enr"),
+    ReservedKey::new("genesis", KeyValidator::Unchecked, "enr"),
+    ReservedKey::new("flags", KeyValidator::Flags, "enr"),
+];
+
+const _: () = assert!(
+    find_conflict(RESERVED_KEYS).is_none(),
+    "a reserved key is registered with conflicting validators"
+);
+
+/// The keys reserved by the specification and the enabled features of this crate.
+#[must_use]
+pub const fn reserved_keys() -> &'static [ReservedKey] {
+    RESERVED_KEYS
+}
+
+/// Returns whether the key is reserved. Usable in constant expressions.
+#[must_use]
+pub const fn is_reserved_key(key: &str) -> bool {
+    lookup(key.as_bytes()).is_some()
+}
+
+/// The registration of a key, if it is reserved.
+pub(crate) const fn lookup(key: &[u8]) -> Option<&'static ReservedKey> {
+    let mut i = 0;
+    while i < RESERVED_KEYS.len() {
+        if bytes_eq(RESERVED_KEYS[i].key.as_bytes(), key) {
+            return Some(&RESERVED_KEYS[i]);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Finds two registrations of the same key with different validators.
+const fn find_conflict(keys: &[ReservedKey]) -> Option<(usize, usize)> {
+    let mut i = 0;
+    while i < keys.len() {
+        let mut j = i + 1;
+        while j < keys.len() {
+            if bytes_eq(keys[i].key.as_bytes(), keys[j].key.as_bytes())
+                && keys[i].validator as u8 != keys[j].validator as u8
+            {
+                return Some((i, j));
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_conflict() {
+        let keys = [
+            ReservedKey::new("tcp", KeyValidator::Port, "eip-778"),
+            ReservedKey::new("quic", KeyValidator::Port, "a"),
+            ReservedKey::new("quic", KeyValidator::Port, "b"),
+        ];
+        assert_eq!(find_conflict(&keys), None);
+
+        let keys = [
+            ReservedKey::new("quic", KeyValidator::Port, "a"),
+            ReservedKey::new("tcp", KeyValidator::Port, "eip-778"),
+            ReservedKey::new("quic", KeyValidator::Unchecked, "b"),
+        ];
+        assert_eq!(find_conflict(&keys), Some((0, 2)));
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup(b"udp6").unwrap().validator, KeyValidator::Port);
+        assert_eq!(lookup(b"quic6").unwrap().validator, KeyValidator::Port);
+        assert!(lookup(b"eth2").is_none());
+        assert!(is_reserved_key("ip6"));
+        assert!(!is_reserved_key("eth2"));
+    }
+}
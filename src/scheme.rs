@@ -0,0 +1,112 @@
+//! Identity schemes defining how records are signed and how node ids are derived.
+//!
+//! The identity scheme of a record is named by its `id` field. [`Enr`] is generic over an
+//! [`IdentityScheme`], defaulting to the [`V4Scheme`] of the specification. Experimental or
+//! private-network schemes can be plugged in by implementing the trait, and records of such a
+//! scheme are built with [`Enr::builder_with_scheme`]:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, EnrKey, EnrPublicKey, IdentityScheme, NodeId, SigningError};
+//! use sha3::{Digest, Keccak256};
+//!
+//! /// Like "v4", but with node ids derived from the compressed public key.
+//! struct Compressed;
+//!
+//! impl IdentityScheme for Compressed {
+//!     const ID: &'static str = "compressed";
+//!
+//!     fn node_id<K: EnrKey>(public_key: &K::PublicKey) -> NodeId {
+//!         NodeId::new(&Keccak256::digest(public_key.encode().as_ref()).into())
+//!     }
+//!
+//!     fn sign<K: EnrKey>(key: &K, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+//!         key.sign_v4(content)
+//!     }
+//!
+//!     fn verify<K: EnrKey>(public_key: &K::PublicKey, content: &[u8], signature: &[u8]) -> bool {
+//!         public_key.verify_v4(content, signature)
+//!     }
+//! }
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr: Enr<SigningKey, Compressed> = Enr::builder_with_scheme()
+//!     .udp4(30303)
+//!     .build(&key)
+//!     .unwrap();
+//! assert_eq!(enr.id(), Some("compressed".into()));
+//!
+//! // records of another scheme are rejected
+//! assert!(enr.to_base64().parse::<Enr<SigningKey>>().is_err());
+//! # }
+//! ```
+//!
+//! Schemes of networks with other identities, e.g. SHA3-256 based ones, can derive node ids from
+//! the uncompressed public key with any [`NodeIdHasher`]:
+//!
+//! ```rust
+//! use enr::{EnrKey, EnrPublicKey, IdentityScheme, NodeId, SigningError};
+//! use sha3::Sha3_256;
+//!
+//! struct Sha3Identity;
+//!
+//! impl IdentityScheme for Sha3Identity {
+//!     const ID: &'static str = "sha3";
+//!
+//!     fn node_id<K: EnrKey>(public_key: &K::PublicKey) -> NodeId {
+//!         NodeId::from_public_key_with::<Sha3_256>(public_key)
+//!     }
+//!
+//!     fn sign<K: EnrKey>(key: &K, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+//!         key.sign_v4(content)
+//!     }
+//!
+//!     fn verify<K: EnrKey>(public_key: &K::PublicKey, content: &[u8], signature: &[u8]) -> bool {
+//!         public_key.verify_v4(content, signature)
+//!     }
+//! }
+//! ```
+//!
+//! [`Enr`]: crate::Enr
+//! [`NodeIdHasher`]: crate::NodeIdHasher
+//! [`Enr::builder_with_scheme`]: crate::Enr::builder_with_scheme
+
+use crate::{EnrKey, EnrPublicKey, NodeId, SigningError};
+
+/// The node id derivation, signing and verification of an identity scheme.
+pub trait IdentityScheme {
+    /// The name of the scheme, stored under the `id` key of its records.
+    const ID: &'static str;
+
+    /// Derives the node id of a public key.
+    fn node_id<K: EnrKey>(public_key: &K::PublicKey) -> NodeId;
+
+    /// Signs the RLP encoded content of a record.
+    fn sign<K: EnrKey>(key: &K, content: &[u8]) -> Result<Vec<u8>, SigningError>;
+
+    /// Verifies the signature over the RLP encoded content of a record.
+    fn verify<K: EnrKey>(public_key: &K::PublicKey, content: &[u8], signature: &[u8]) -> bool;
+}
+
+/// The "v4" identity scheme of the specification.
+///
+/// Node ids are the keccak256 hash of the uncompressed public key, and the content is signed by
+/// [`EnrKey::sign_v4`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct V4Scheme;
+
+impl IdentityScheme for V4Scheme {
+    const ID: &'static str = "v4";
+
+    fn node_id<K: EnrKey>(public_key: &K::PublicKey) -> NodeId {
+        NodeId::from(public_key.clone())
+    }
+
+    fn sign<K: EnrKey>(key: &K, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+        key.sign_v4(content)
+    }
+
+    fn verify<K: EnrKey>(public_key: &K::PublicKey, content: &[u8], signature: &[u8]) -> bool {
+        public_key.verify_v4(content, signature)
+    }
+}
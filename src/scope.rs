@@ -0,0 +1,135 @@
+//! Classification of the IP addresses advertised in an ENR.
+//!
+//! Records may advertise addresses peers cannot route to, e.g. a private address of a node behind
+//! a NAT or the loopback address of a misconfigured node. The special-purpose ranges of the IANA
+//! IPv4 and IPv6 registries (RFC 6890) determine whether an address is globally reachable.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Whether `ip` is reachable from the public internet, i.e. not in any special-purpose range
+/// that is private, local, reserved for documentation, benchmarking or future use, or multicast.
+pub(crate) fn is_global(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_global_v4(ip),
+        IpAddr::V6(ip) => is_global_v6(ip),
+    }
+}
+
+/// Whether `ip` is a loopback address, `127.0.0.0/8` or `::1`, also as an IPv4-mapped address.
+pub(crate) fn is_loopback(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback(),
+        IpAddr::V6(ip) => ip
+            .to_ipv4_mapped()
+            .map_or(ip.is_loopback(), |ip| ip.is_loopback()),
+    }
+}
+
+/// Whether `ip` is an address of a private network: `10.0.0.0/8`, `172.16.0.0/12` and
+/// `192.168.0.0/16` (RFC 1918), the shared address space `100.64.0.0/10` (RFC 6598) and the
+/// unique local addresses `fc00::/7` (RFC 4193), also as IPv4-mapped addresses.
+pub(crate) fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_v4(ip),
+            None => ip.segments()[0] & 0xfe00 == 0xfc00,
+        },
+    }
+}
+
+/// Whether `ip` is an IPv4 address of a private network.
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private() || (a == 100 && b & 0xc0 == 64)
+}
+
+/// Whether `ip` is a globally reachable IPv4 address.
+fn is_global_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(a == 0 // "this network"
+        || is_private_v4(ip)
+        || ip.is_loopback()
+        || ip.is_link_local()
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        || ip.is_documentation()
+        // benchmarking
+        || (a == 198 && b & 0xfe == 18)
+        || ip.is_multicast()
+        // reserved, including the broadcast address
+        || a >= 240)
+}
+
+/// Whether `ip` is a globally reachable IPv6 address, or IPv4-mapped address.
+fn is_global_v6(ip: Ipv6Addr) -> bool {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_global_v4(ip);
+    }
+    let segments = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local
+        || segments[0] & 0xfe00 == 0xfc00
+        // link-local and the deprecated site-local
+        || segments[0] & 0xffc0 == 0xfe80
+        || segments[0] & 0xffc0 == 0xfec0
+        // documentation
+        || (segments[0] == 0x2001 && segments[1] == 0xdb8)
+        // discard-only
+        || (segments[0] == 0x100 && segments[1..4] == [0, 0, 0])
+        // local-use IPv4/IPv6 translation
+        || (segments[0] == 0x64 && segments[1] == 0xff9b && segments[2] == 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_scopes() {
+        let global = ["1.1.1.1", "100.128.0.1", "2a01:4f8::1", "::ffff:8.8.8.8"];
+        for ip in global {
+            assert!(is_global(ip.parse().unwrap()), "{}", ip);
+        }
+        let not_global = [
+            "0.1.2.3",
+            "10.1.2.3",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.1.1",
+            "172.31.255.255",
+            "192.0.0.8",
+            "192.0.2.1",
+            "192.168.1.1",
+            "198.19.0.1",
+            "203.0.113.9",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "::ffff:192.168.1.1",
+            "100::1",
+            "2001:db8::1",
+            "64:ff9b:1::1",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+        ];
+        for ip in not_global {
+            assert!(!is_global(ip.parse().unwrap()), "{}", ip);
+        }
+
+        assert!(is_loopback("127.0.0.2".parse().unwrap()));
+        assert!(is_loopback("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!is_loopback("fe80::1".parse().unwrap()));
+
+        for ip in ["10.0.0.1", "172.16.0.1", "100.127.255.255", "fc00::1"] {
+            assert!(is_private(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["127.0.0.1", "169.254.1.1", "100.128.0.1", "fe80::1"] {
+            assert!(!is_private(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
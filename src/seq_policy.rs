@@ -0,0 +1,121 @@
+//! Policies for choosing the sequence number of an updated record.
+//!
+//! By default, every modification of an [`Enr`] increments its sequence number. A node that does
+//! not persist its record must then persist the sequence number, or peers ignore its records
+//! after a restart. With [`SeqPolicy::UnixTimestamp`] the sequence number follows the system
+//! clock instead, so a restarted node publishes records superseding the ones it published before.
+//!
+//! A [`SeqPolicy`] can be set when building an [`Enr`], or on an existing [`Enr`], and applies to
+//! every modification re-signing the record.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, SeqPolicy};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder()
+//!     .seq_policy(SeqPolicy::UnixTimestamp)
+//!     .build(&key)
+//!     .unwrap();
+//! assert!(enr.seq() > 1_600_000_000);
+//!
+//! let seq = enr.seq();
+//! enr.set_udp4(30303, &key).unwrap();
+//! assert!(enr.seq() > seq);
+//!
+//! enr.set_seq_policy(SeqPolicy::Explicit(u64::MAX));
+//! enr.set_tcp4(30303, &key).unwrap();
+//! assert_eq!(enr.seq(), u64::MAX);
+//! # }
+//! ```
+//!
+//! [`Enr`]: crate::Enr
+
+use crate::EnrError;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Defines the sequence number a record is re-signed with when it is modified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SeqPolicy {
+    /// The sequence number is incremented by one.
+    #[default]
+    Increment,
+    /// The sequence number is the current unix time in seconds, or the incremented sequence number
+    /// if that is larger, e.g. after several modifications within a second or if the clock went
+    /// backwards.
+    UnixTimestamp,
+    /// The sequence number is the given value, which must be larger than the current one.
+    Explicit(u64),
+}
+
+impl SeqPolicy {
+    /// The sequence number following `seq` under the policy.
+    ///
+    /// Fails with [`EnrError::SequenceNumberTooHigh`] if `seq` cannot be incremented and with
+    /// [`EnrError::SequenceNumberNotIncreasing`] if an explicit sequence number does not exceed
+    /// `seq`.
+    pub fn next(self, seq: u64) -> Result<u64, EnrError> {
+        match self {
+            Self::Increment => seq.checked_add(1).ok_or(EnrError::SequenceNumberTooHigh),
+            Self::UnixTimestamp => {
+                let incremented = seq.checked_add(1).ok_or(EnrError::SequenceNumberTooHigh)?;
+                Ok(incremented.max(unix_time()))
+            }
+            Self::Explicit(next) if next > seq => Ok(next),
+            Self::Explicit(next) => Err(EnrError::SequenceNumberNotIncreasing(next)),
+        }
+    }
+
+    /// The sequence number of a new record built with the starting sequence number `seq`.
+    pub(crate) fn initial(self, seq: u64) -> u64 {
+        match self {
+            Self::Increment => seq,
+            Self::UnixTimestamp => seq.max(unix_time()),
+            Self::Explicit(initial) => initial,
+        }
+    }
+}
+
+/// The current unix time in seconds, or 0 if the system clock is before the unix epoch.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seq_policy_next() {
+        assert_eq!(SeqPolicy::Increment.next(1), Ok(2));
+        assert_eq!(
+            SeqPolicy::Increment.next(u64::MAX),
+            Err(EnrError::SequenceNumberTooHigh)
+        );
+
+        let now = unix_time();
+        assert!(SeqPolicy::UnixTimestamp.next(1).unwrap() >= now);
+        // never goes backwards
+        assert_eq!(SeqPolicy::UnixTimestamp.next(now + 10), Ok(now + 11));
+        assert_eq!(
+            SeqPolicy::UnixTimestamp.next(u64::MAX),
+            Err(EnrError::SequenceNumberTooHigh)
+        );
+
+        assert_eq!(SeqPolicy::Explicit(7).next(6), Ok(7));
+        assert_eq!(
+            SeqPolicy::Explicit(7).next(7),
+            Err(EnrError::SequenceNumberNotIncreasing(7))
+        );
+    }
+
+    #[test]
+    fn test_seq_policy_initial() {
+        assert_eq!(SeqPolicy::Increment.initial(1), 1);
+        assert!(SeqPolicy::UnixTimestamp.initial(1) >= unix_time());
+        assert_eq!(SeqPolicy::UnixTimestamp.initial(u64::MAX), u64::MAX);
+        assert_eq!(SeqPolicy::Explicit(5).initial(1), 5);
+    }
+}
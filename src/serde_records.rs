@@ -0,0 +1,275 @@
+//! Serde helpers for collections of records, for use with `#[serde(with = "...")]`.
+//!
+//! Human readable formats store records in their base64 text form, binary formats store their raw
+//! RLP encoding, which is about a quarter smaller and avoids the base64 round trip.
+//!
+//! - [`vec`] (de)serializes a `Vec<Enr<K>>` as a sequence of records.
+//! - [`map`] (de)serializes a `HashMap<NodeId, Enr<K>>`. Human readable formats store a map from
+//!   the hex encoded node id to the record, and loading fails if a key does not match the node id
+//!   of its record. Binary formats store only the records, as the keys are derived from them.
+//!   When a binary encoding holds several records of the same node, the one with the highest
+//!   sequence number is kept.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr, NodeId};
+//! use serde::{Deserialize, Serialize};
+//! use std::collections::HashMap;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct PeerStore {
+//!     #[serde(with = "enr::serde_records::map")]
+//!     peers: HashMap<NodeId, Enr<SigningKey>>,
+//!     #[serde(with = "enr::serde_records::vec")]
+//!     bootnodes: Vec<Enr<SigningKey>>,
+//! }
+//!
+//! let enr: Enr<SigningKey> = Enr::empty(&SigningKey::random(&mut rand::thread_rng())).unwrap();
+//! let store = PeerStore {
+//!     peers: HashMap::from([(enr.node_id(), enr.clone())]),
+//!     bootnodes: vec![enr],
+//! };
+//! let json = serde_json::to_string(&store).unwrap();
+//! let loaded: PeerStore = serde_json::from_str(&json).unwrap();
+//! assert_eq!(loaded.peers, store.peers);
+//! # }
+//! ```
+
+use crate::{Enr, EnrKey, IdentityScheme, NodeId};
+use serde::{
+    de::{self, Deserializer, Visitor},
+    Deserialize, Serialize, Serializer,
+};
+use std::{convert::TryFrom, fmt, marker::PhantomData, str::FromStr};
+
+/// Serializes a record by reference, in the text form or as raw RLP depending on the format.
+struct SerializeRecord<'a, K: EnrKey, S: IdentityScheme>(&'a Enr<K, S>);
+
+impl<K: EnrKey, S: IdentityScheme> Serialize for SerializeRecord<'_, K, S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.0.to_base64())
+        } else {
+            serializer.serialize_bytes(&rlp::encode(self.0))
+        }
+    }
+}
+
+/// Deserializes a record serialized by [`SerializeRecord`].
+struct DeserializeRecord<K: EnrKey, S: IdentityScheme>(Enr<K, S>);
+
+impl<'de, K: EnrKey, S: IdentityScheme> Deserialize<'de> for DeserializeRecord<K, S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Enr::from_str(&s).map(Self).map_err(de::Error::custom)
+        } else {
+            deserializer.deserialize_bytes(RlpVisitor(PhantomData))
+        }
+    }
+}
+
+/// Decodes a record from its raw RLP encoding.
+struct RlpVisitor<K: EnrKey, S: IdentityScheme>(PhantomData<fn() -> (K, S)>);
+
+impl<K: EnrKey, S: IdentityScheme> Visitor<'_> for RlpVisitor<K, S> {
+    type Value = DeserializeRecord<K, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the RLP encoding of a record")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        rlp::decode(v).map(DeserializeRecord).map_err(E::custom)
+    }
+}
+
+/// (De)serializes a `Vec<Enr<K>>`.
+pub mod vec {
+    use super::{DeserializeRecord, SerializeRecord};
+    use crate::{Enr, EnrKey, IdentityScheme};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes the records as a sequence.
+    pub fn serialize<K: EnrKey, S: IdentityScheme, T: Serializer>(
+        records: &[Enr<K, S>],
+        serializer: T,
+    ) -> Result<T::Ok, T::Error> {
+        serializer.collect_seq(records.iter().map(SerializeRecord))
+    }
+
+    /// Deserializes a sequence of records, verifying each.
+    pub fn deserialize<'de, K: EnrKey, S: IdentityScheme, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Enr<K, S>>, D::Error> {
+        let records = Vec::<DeserializeRecord<K, S>>::deserialize(deserializer)?;
+        Ok(records.into_iter().map(|record| record.0).collect())
+    }
+}
+
+/// (De)serializes a `HashMap<NodeId, Enr<K>>`.
+pub mod map {
+    use super::{DeserializeRecord, SerializeRecord};
+    use crate::{Enr, EnrKey, IdentityScheme, NodeId};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::{
+        collections::{hash_map::Entry, HashMap},
+        hash::BuildHasher,
+    };
+
+    /// Serializes the records, keyed by node id in human readable formats.
+    pub fn serialize<K: EnrKey, S: IdentityScheme, H, T: Serializer>(
+        records: &HashMap<NodeId, Enr<K, S>, H>,
+        serializer: T,
+    ) -> Result<T::Ok, T::Error> {
+        // sorted, so that equal maps serialize identically
+        let mut sorted: Vec<_> = records.iter().collect();
+        sorted.sort_unstable_by_key(|(node_id, _)| node_id.raw());
+        if serializer.is_human_readable() {
+            serializer.collect_map(
+                sorted
+                    .into_iter()
+                    .map(|(node_id, enr)| (super::NodeIdKey(*node_id), SerializeRecord(enr))),
+            )
+        } else {
+            serializer.collect_seq(sorted.into_iter().map(|(_, enr)| SerializeRecord(enr)))
+        }
+    }
+
+    /// Deserializes the records, verifying each and checking that every key matches the node id of
+    /// its record.
+    pub fn deserialize<'de, K, S, H, D>(
+        deserializer: D,
+    ) -> Result<HashMap<NodeId, Enr<K, S>, H>, D::Error>
+    where
+        K: EnrKey,
+        S: IdentityScheme,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        let mut records = HashMap::default();
+        if deserializer.is_human_readable() {
+            let entries =
+                HashMap::<super::NodeIdKey, DeserializeRecord<K, S>>::deserialize(deserializer)?;
+            for (node_id, record) in entries {
+                if node_id.0 != record.0.node_id() {
+                    return Err(de::Error::custom(format!(
+                        "key {} does not match the node id of its record",
+                        node_id.0
+                    )));
+                }
+                records.insert(node_id.0, record.0);
+            }
+        } else {
+            for DeserializeRecord(enr) in Vec::<DeserializeRecord<K, S>>::deserialize(deserializer)?
+            {
+                match records.entry(enr.node_id()) {
+                    Entry::Occupied(mut entry) => {
+                        if enr.seq() > entry.get().seq() {
+                            entry.insert(enr);
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(enr);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// A node id map key, in its full `0x` prefixed hex form.
+#[derive(PartialEq, Eq, Hash)]
+struct NodeIdKey(NodeId);
+
+impl Serialize for NodeIdKey {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(self.0.raw())))
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeIdKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let raw = hex::decode(s.trim_start_matches("0x")).map_err(de::Error::custom)?;
+        let raw = <[u8; 32]>::try_from(raw.as_slice())
+            .map_err(|_| de::Error::custom("node ids are 32 bytes"))?;
+        Ok(Self(NodeId::new(&raw)))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    type DefaultEnr = Enr<k256::ecdsa::SigningKey>;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Store {
+        #[serde(with = "map")]
+        peers: HashMap<NodeId, DefaultEnr>,
+        #[serde(with = "vec")]
+        bootnodes: Vec<DefaultEnr>,
+    }
+
+    fn store() -> Store {
+        let records: Vec<DefaultEnr> = (0..3)
+            .map(|port| {
+                let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+                Enr::builder().udp4(9000 + port).build(&key).unwrap()
+            })
+            .collect();
+        Store {
+            peers: records
+                .iter()
+                .map(|enr| (enr.node_id(), enr.clone()))
+                .collect(),
+            bootnodes: records,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let store = store();
+
+        let json = serde_json::to_string(&store).unwrap();
+        assert!(json.contains(&store.bootnodes[0].to_base64()));
+        assert_eq!(serde_json::from_str::<Store>(&json).unwrap(), store);
+
+        let binary = bincode::serialize(&store).unwrap();
+        assert!(binary.len() < json.len());
+        assert_eq!(bincode::deserialize::<Store>(&binary).unwrap(), store);
+    }
+
+    #[test]
+    fn test_mismatched_key() {
+        let mut store = store();
+        let (first, second) = (store.bootnodes[0].clone(), store.bootnodes[1].clone());
+        store.peers.insert(first.node_id(), second);
+
+        let json = serde_json::to_string(&store).unwrap();
+        let err = serde_json::from_str::<Store>(&json).unwrap_err();
+        assert!(err.to_string().contains("does not match the node id"));
+    }
+
+    #[test]
+    fn test_highest_seq_kept() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let old: DefaultEnr = Enr::builder().udp4(9000).build(&key).unwrap();
+        let mut new = old.clone();
+        new.set_udp4(9001, &key).unwrap();
+
+        #[derive(Deserialize)]
+        struct Peers(#[serde(with = "map")] HashMap<NodeId, DefaultEnr>);
+
+        // the binary form of a map is the sequence of its records
+        let binary =
+            bincode::serialize(&vec![SerializeRecord(&new), SerializeRecord(&old)]).unwrap();
+        let peers = bincode::deserialize::<Peers>(&binary).unwrap().0;
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[&key.public().into()], new);
+    }
+}
@@ -0,0 +1,108 @@
+//! Signing records with keys that are not held in memory.
+//!
+//! An [`EnrSigner`] signs the content of a record on behalf of a public key, for example through
+//! an HSM, a remote KMS or a threshold signing protocol. Every [`EnrKey`] is a signer. Records of
+//! the "v4" identity scheme are built by a signer with
+//! [`Builder::build_with_signer`](crate::builder::Builder::build_with_signer) and updated with
+//! [`Enr::apply_changes_with_signer`](crate::Enr::apply_changes_with_signer). Signatures returned
+//! by a signer are verified against its public key before they are accepted.
+//!
+//! With the `async-signing` feature, [`AsyncEnrSigner`] provides the same for signers that sign
+//! asynchronously, through `build_async` and `apply_changes_async`.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::{SigningKey, VerifyingKey}, Enr, EnrKey, EnrSigner, SigningError};
+//!
+//! /// Stands in for a remote signing service.
+//! struct Remote(SigningKey);
+//!
+//! impl EnrSigner for Remote {
+//!     type PublicKey = VerifyingKey;
+//!
+//!     fn public_key(&self) -> VerifyingKey {
+//!         self.0.public()
+//!     }
+//!
+//!     fn sign_v4(&self, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+//!         EnrKey::sign_v4(&self.0, content)
+//!     }
+//! }
+//!
+//! let remote = Remote(SigningKey::random(&mut rand::thread_rng()));
+//! let enr: Enr<SigningKey> = Enr::builder().udp4(9000).build_with_signer(&remote).unwrap();
+//! assert_eq!(enr.public_key(), remote.public_key());
+//! # }
+//! ```
+
+use crate::{EnrError, EnrKey, EnrPublicKey, SigningError};
+#[cfg(feature = "async-signing")]
+use std::future::Future;
+
+/// Signs the content of records of the "v4" identity scheme for a public key.
+pub trait EnrSigner {
+    /// The public key the signer signs for.
+    type PublicKey: EnrPublicKey;
+
+    /// The public key the signer signs for.
+    fn public_key(&self) -> Self::PublicKey;
+
+    /// Signs the RLP encoded content of a record like [`EnrKey::sign_v4`].
+    fn sign_v4(&self, content: &[u8]) -> Result<Vec<u8>, SigningError>;
+}
+
+impl<K: EnrKey> EnrSigner for K {
+    type PublicKey = K::PublicKey;
+
+    fn public_key(&self) -> Self::PublicKey {
+        self.public()
+    }
+
+    fn sign_v4(&self, content: &[u8]) -> Result<Vec<u8>, SigningError> {
+        EnrKey::sign_v4(self, content)
+    }
+}
+
+/// Signs the content of records of the "v4" identity scheme for a public key, asynchronously.
+///
+/// Every [`EnrSigner`] that is `Sync` is an asynchronous signer.
+#[cfg(feature = "async-signing")]
+pub trait AsyncEnrSigner: Sync {
+    /// The public key the signer signs for.
+    type PublicKey: EnrPublicKey;
+
+    /// The public key the signer signs for.
+    fn public_key(&self) -> Self::PublicKey;
+
+    /// Signs the RLP encoded content of a record like [`EnrKey::sign_v4`].
+    fn sign_v4(&self, content: &[u8])
+        -> impl Future<Output = Result<Vec<u8>, SigningError>> + Send;
+}
+
+#[cfg(feature = "async-signing")]
+impl<T: EnrSigner + Sync> AsyncEnrSigner for T {
+    type PublicKey = T::PublicKey;
+
+    fn public_key(&self) -> Self::PublicKey {
+        EnrSigner::public_key(self)
+    }
+
+    fn sign_v4(
+        &self,
+        content: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, SigningError>> + Send {
+        std::future::ready(EnrSigner::sign_v4(self, content))
+    }
+}
+
+/// Accepts a signature returned by a signer if it is valid for `public_key`.
+pub(crate) fn check_signature<P: EnrPublicKey>(
+    public_key: &P,
+    content: &[u8],
+    signature: Result<Vec<u8>, SigningError>,
+) -> Result<Vec<u8>, EnrError> {
+    match signature {
+        Ok(signature) if public_key.verify_v4(content, &signature) => Ok(signature),
+        _ => Err(EnrError::SigningError),
+    }
+}
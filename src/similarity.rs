@@ -0,0 +1,127 @@
+//! Locality-sensitive hashing of ENR content.
+//!
+//! A [`SimilarityHash`] is a 64-bit [SimHash](https://en.wikipedia.org/wiki/SimHash) of a record's
+//! key/value pairs. Records that share most of their fields produce hashes with a small hamming
+//! distance, allowing crawlers to cluster near-identical records advertised under different node
+//! ids. Fields that are expected to differ between otherwise identical nodes, such as the public
+//! key and IP addresses, can be excluded through a [`SimilarityConfig`].
+
+use crate::{digest, Key, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Configures which fields of a record contribute to its [`SimilarityHash`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimilarityConfig {
+    /// Keys that are ignored when computing the hash.
+    excluded_keys: BTreeSet<Vec<u8>>,
+}
+
+impl Default for SimilarityConfig {
+    /// Excludes the public key and IP address fields.
+    fn default() -> Self {
+        Self {
+            excluded_keys: ["secp256k1", "ed25519", "ip", "ip6"]
+                .iter()
+                .map(|key| key.as_bytes().to_vec())
+                .collect(),
+        }
+    }
+}
+
+impl SimilarityConfig {
+    /// A configuration where every field contributes to the hash.
+    #[must_use]
+    pub fn all_fields() -> Self {
+        Self {
+            excluded_keys: BTreeSet::new(),
+        }
+    }
+
+    /// Excludes a key from the hash.
+    pub fn exclude(&mut self, key: impl AsRef<[u8]>) -> &mut Self {
+        self.excluded_keys.insert(key.as_ref().to_vec());
+        self
+    }
+
+    /// Includes a previously excluded key in the hash.
+    pub fn include(&mut self, key: impl AsRef<[u8]>) -> &mut Self {
+        self.excluded_keys.remove(key.as_ref());
+        self
+    }
+
+    /// Returns whether the key is excluded from the hash.
+    #[must_use]
+    pub fn is_excluded(&self, key: impl AsRef<[u8]>) -> bool {
+        self.excluded_keys.contains(key.as_ref())
+    }
+}
+
+/// A 64-bit locality-sensitive hash of a record's content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SimilarityHash(u64);
+
+impl SimilarityHash {
+    /// Computes the hash of the given content, ignoring keys excluded by the config.
+    pub(crate) fn new(content: &BTreeMap<Key, Value>, config: &SimilarityConfig) -> Self {
+        let mut weights = [0_i32; 64];
+        for (key, value) in content {
+            if config.is_excluded(key) {
+                continue;
+            }
+            // each key/value pair is a feature of the record
+            let mut feature = Vec::with_capacity(key.len() + value.len() + 1);
+            feature.extend_from_slice(key);
+            feature.push(0);
+            feature.extend_from_slice(value);
+            let mut feature_hash = [0_u8; 8];
+            feature_hash.copy_from_slice(&digest(&feature)[..8]);
+            let feature_hash = u64::from_be_bytes(feature_hash);
+
+            for (bit, weight) in weights.iter_mut().enumerate() {
+                if feature_hash & (1 << bit) == 0 {
+                    *weight -= 1;
+                } else {
+                    *weight += 1;
+                }
+            }
+        }
+
+        let hash = weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0_u64, |hash, (bit, _)| hash | (1 << bit));
+        Self(hash)
+    }
+
+    /// The raw 64-bit value of the hash.
+    #[must_use]
+    pub const fn raw(&self) -> u64 {
+        self.0
+    }
+
+    /// The number of differing bits between two hashes. The lower the distance, the more similar
+    /// the records.
+    #[must_use]
+    pub const fn hamming_distance(&self, other: &Self) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// Returns whether the hamming distance between two hashes is at most `max_distance`.
+    #[must_use]
+    pub const fn is_similar(&self, other: &Self, max_distance: u32) -> bool {
+        self.hamming_distance(other) <= max_distance
+    }
+}
+
+impl From<u64> for SimilarityHash {
+    fn from(raw: u64) -> Self {
+        Self(raw)
+    }
+}
+
+impl std::fmt::Display for SimilarityHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
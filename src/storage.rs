@@ -0,0 +1,141 @@
+//! The storage of the keys and raw RLP encoded values of a record's content.
+//!
+//! By default, keys are vectors and values are [`Bytes`]. With the `compact` feature, they are
+//! [`SmallVec`]s instead, which store keys of up to [`INLINE_KEY_LEN`] bytes and values of up to
+//! [`INLINE_VALUE_LEN`] bytes inline rather than in an allocation of their own. This covers the
+//! keys of the specification and the values of most fields, including `v4` public keys, so that
+//! a typical record allocates little beyond the nodes of its map.
+
+use crate::EnrKey;
+#[cfg(feature = "compact")]
+use crate::KeyScheme;
+use bytes::Bytes;
+use rlp::DecoderError;
+#[cfg(feature = "compact")]
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
+
+/// The longest key stored inline with the `compact` feature.
+#[cfg(feature = "compact")]
+pub const INLINE_KEY_LEN: usize = 16;
+
+/// The longest value stored inline with the `compact` feature, which fits an RLP encoded
+/// compressed `secp256k1` public key.
+#[cfg(feature = "compact")]
+pub const INLINE_VALUE_LEN: usize = 36;
+
+/// The "key" in an ENR record can be arbitrary bytes.
+#[cfg(not(feature = "compact"))]
+pub type Key = Vec<u8>;
+/// The "key" in an ENR record can be arbitrary bytes.
+#[cfg(feature = "compact")]
+pub type Key = SmallVec<[u8; INLINE_KEY_LEN]>;
+
+/// A raw RLP encoded value of a record.
+#[cfg(not(feature = "compact"))]
+pub type Value = Bytes;
+/// A raw RLP encoded value of a record.
+#[cfg(feature = "compact")]
+pub type Value = SmallVec<[u8; INLINE_VALUE_LEN]>;
+
+/// Stores a raw RLP encoded value.
+#[cfg(not(feature = "compact"))]
+pub fn value(raw: Bytes) -> Value {
+    raw
+}
+
+/// Stores a raw RLP encoded value.
+#[cfg(feature = "compact")]
+pub fn value(raw: Bytes) -> Value {
+    SmallVec::from_slice(&raw)
+}
+
+/// Copies a raw RLP encoded value into storage.
+#[cfg(not(feature = "compact"))]
+pub fn copy_value(raw: &[u8]) -> Value {
+    Bytes::copy_from_slice(raw)
+}
+
+/// Copies a raw RLP encoded value into storage.
+#[cfg(feature = "compact")]
+pub fn copy_value(raw: &[u8]) -> Value {
+    SmallVec::from_slice(raw)
+}
+
+/// A stored value as [`Bytes`], e.g. to return it from the public API.
+#[cfg(not(feature = "compact"))]
+pub fn into_bytes(value: Value) -> Bytes {
+    value
+}
+
+/// A stored value as [`Bytes`], e.g. to return it from the public API.
+#[cfg(feature = "compact")]
+pub fn into_bytes(value: Value) -> Bytes {
+    Bytes::copy_from_slice(&value)
+}
+
+/// The bytes a key allocates on the heap.
+#[cfg(not(feature = "compact"))]
+pub fn key_heap_size(key: &Key) -> usize {
+    key.capacity()
+}
+
+/// The bytes a key allocates on the heap.
+#[cfg(feature = "compact")]
+pub fn key_heap_size(key: &Key) -> usize {
+    if key.spilled() {
+        key.capacity()
+    } else {
+        0
+    }
+}
+
+/// The bytes a value allocates on the heap.
+#[cfg(not(feature = "compact"))]
+pub fn value_heap_size(value: &Value) -> usize {
+    value.len()
+}
+
+/// The bytes a value allocates on the heap.
+#[cfg(feature = "compact")]
+pub fn value_heap_size(value: &Value) -> usize {
+    if value.spilled() {
+        value.capacity()
+    } else {
+        0
+    }
+}
+
+/// The public key stored in `content`, as read by [`EnrKey::enr_to_public`]. With the `compact`
+/// feature, the public key fields known to this crate, see [`KeyScheme`], are copied into the
+/// map the trait takes.
+pub fn enr_to_public<K: EnrKey>(
+    content: &BTreeMap<Key, Value>,
+) -> Result<K::PublicKey, DecoderError> {
+    #[cfg(feature = "compact")]
+    let content: &BTreeMap<Vec<u8>, Bytes> = &content
+        .iter()
+        .filter(|(key, _)| KeyScheme::from_enr_key(key).is_some())
+        .map(|(key, value)| (key.to_vec(), Bytes::copy_from_slice(value)))
+        .collect();
+    K::enr_to_public(content)
+}
+
+/// The content of a record with keys as vectors and values as [`Bytes`], as exposed by the
+/// public API.
+#[cfg(feature = "raw")]
+pub fn export(content: &BTreeMap<Key, Value>) -> BTreeMap<Vec<u8>, Bytes> {
+    content
+        .iter()
+        .map(|(key, value)| (key.to_vec(), into_bytes(value.clone())))
+        .collect()
+}
+
+/// Stores content exposed by the public API, the reverse of [`export`].
+#[cfg(feature = "raw")]
+pub fn import(content: BTreeMap<Vec<u8>, Bytes>) -> BTreeMap<Key, Value> {
+    content
+        .into_iter()
+        .map(|(key, value)| (Key::from(key), self::value(value)))
+        .collect()
+}
@@ -0,0 +1,450 @@
+//! A store keeping the newest valid record of each node.
+//!
+//! Every discovery implementation keeps a table of the records it has seen, replacing a node's
+//! record only by a valid record with a higher sequence number. [`EnrStore`] implements this
+//! rule once, indexed by [`NodeId`], and writes every change through to a [`StoreBackend`]:
+//!
+//! - [`MemoryBackend`] persists nothing.
+//! - [`JsonFileBackend`] persists the records as a JSON array of their text form, which is easy to
+//!   inspect and edit by hand.
+//! - `SledBackend`, with the `store-sled` feature, persists the records in a `sled` tree.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, store::EnrStore, Enr};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder().udp4(30303).build(&key).unwrap();
+//! let old = enr.clone();
+//! enr.set_udp4(30304, &key).unwrap();
+//!
+//! let mut store = EnrStore::new();
+//! assert!(store.insert(enr.clone()).unwrap());
+//! // an older record of the same node does not replace the newer one
+//! assert!(!store.insert(old).unwrap());
+//! assert_eq!(store.get(&enr.node_id()), Some(&enr));
+//! # }
+//! ```
+
+use crate::{ConflictingRecord, Enr, EnrKey, NodeId, Supersedes};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Where an [`EnrStore`] persists its records.
+pub trait StoreBackend<K: EnrKey> {
+    /// Loads all persisted records.
+    fn load(&mut self) -> io::Result<Vec<Enr<K>>>;
+
+    /// Persists `enr`, replacing the persisted record of the same node.
+    fn save(&mut self, enr: &Enr<K>) -> io::Result<()>;
+
+    /// Removes the persisted record of a node.
+    fn remove(&mut self, node_id: &NodeId) -> io::Result<()>;
+}
+
+/// A backend persisting nothing, for stores that only live in memory.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryBackend;
+
+impl<K: EnrKey> StoreBackend<K> for MemoryBackend {
+    fn load(&mut self) -> io::Result<Vec<Enr<K>>> {
+        Ok(Vec::new())
+    }
+
+    fn save(&mut self, _enr: &Enr<K>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn remove(&mut self, _node_id: &NodeId) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A backend persisting the records as a JSON array of their base64 text form.
+///
+/// The whole file is rewritten on every change, through a temporary file next to it that replaces
+/// the file once written, so a crash never leaves a partially written file behind. This suits
+/// stores of up to a few thousand records.
+#[derive(Clone, Debug)]
+pub struct JsonFileBackend {
+    path: PathBuf,
+    /// The text form of the persisted records.
+    records: HashMap<NodeId, String>,
+}
+
+impl JsonFileBackend {
+    /// A backend persisting to the file at `path`, which is created on the first change if it
+    /// doesn't exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            records: HashMap::new(),
+        }
+    }
+
+    /// The path of the file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes all records, ordered by node id so that the file only changes where records do.
+    fn write(&self) -> io::Result<()> {
+        let mut records: Vec<_> = self.records.iter().collect();
+        records.sort_unstable_by_key(|(node_id, _)| node_id.raw());
+        let records: Vec<_> = records.into_iter().map(|(_, enr)| enr).collect();
+        let json = serde_json::to_vec_pretty(&records)?;
+
+        let mut temp_path = OsString::from(&self.path);
+        temp_path.push(".tmp");
+        fs::write(&temp_path, json)?;
+        fs::rename(&temp_path, &self.path)
+    }
+}
+
+impl<K: EnrKey> StoreBackend<K> for JsonFileBackend {
+    fn load(&mut self) -> io::Result<Vec<Enr<K>>> {
+        let json = match fs::read(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let records: Vec<String> = serde_json::from_slice(&json)?;
+        let mut records = records
+            .iter()
+            .map(|text| {
+                text.parse::<Enr<K>>()
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        // of several records of a node, the one with the highest sequence number is kept
+        records.sort_unstable_by_key(Enr::seq);
+        self.records = records
+            .iter()
+            .map(|enr| (enr.node_id(), enr.to_base64()))
+            .collect();
+        Ok(records)
+    }
+
+    fn save(&mut self, enr: &Enr<K>) -> io::Result<()> {
+        self.records.insert(enr.node_id(), enr.to_base64());
+        self.write()
+    }
+
+    fn remove(&mut self, node_id: &NodeId) -> io::Result<()> {
+        if self.records.remove(node_id).is_some() {
+            self.write()?;
+        }
+        Ok(())
+    }
+}
+
+/// A backend persisting the RLP encoded records in a `sled` tree, keyed by node id.
+#[cfg(feature = "store-sled")]
+#[derive(Clone, Debug)]
+pub struct SledBackend {
+    tree: sled::Tree,
+}
+
+#[cfg(feature = "store-sled")]
+impl SledBackend {
+    /// A backend persisting to the `enr` tree of the `sled` database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(sled::open(path)?.open_tree("enr")?))
+    }
+
+    /// A backend persisting to `tree`, which should hold no other data.
+    #[must_use]
+    pub const fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+}
+
+#[cfg(feature = "store-sled")]
+impl<K: EnrKey> StoreBackend<K> for SledBackend {
+    fn load(&mut self) -> io::Result<Vec<Enr<K>>> {
+        self.tree
+            .iter()
+            .values()
+            .map(|value| {
+                rlp::decode(&value?).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+            })
+            .collect()
+    }
+
+    fn save(&mut self, enr: &Enr<K>) -> io::Result<()> {
+        self.tree
+            .insert(enr.node_id().raw(), rlp::encode(enr).as_ref())?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    fn remove(&mut self, node_id: &NodeId) -> io::Result<()> {
+        self.tree.remove(node_id.raw())?;
+        self.tree.flush()?;
+        Ok(())
+    }
+}
+
+/// The newest valid record of each node, persisted to a [`StoreBackend`].
+pub struct EnrStore<K: EnrKey, B: StoreBackend<K> = MemoryBackend> {
+    records: HashMap<NodeId, Enr<K>>,
+    backend: B,
+}
+
+impl<K: EnrKey> EnrStore<K> {
+    /// An empty store that persists nothing.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            records: HashMap::new(),
+            backend: MemoryBackend,
+        }
+    }
+}
+
+impl<K: EnrKey> Default for EnrStore<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: EnrKey, B: StoreBackend<K>> EnrStore<K, B> {
+    /// Opens a store, loading the records persisted in `backend`.
+    ///
+    /// Loaded records are verified when decoded. If several records of a node are loaded, the one
+    /// with the highest sequence number is kept. Fails with a [`ConflictingRecord`] error of kind
+    /// [`ErrorKind::InvalidData`] if two loaded records of a node conflict.
+    pub fn open(mut backend: B) -> io::Result<Self> {
+        let mut records: HashMap<NodeId, Enr<K>> = HashMap::new();
+        for enr in backend.load()? {
+            let relation = records
+                .get(&enr.node_id())
+                .map(|existing| enr.supersedes(existing));
+            match relation {
+                None | Some(Ok(Supersedes::Newer)) => {
+                    records.insert(enr.node_id(), enr);
+                }
+                Some(Ok(Supersedes::Conflict)) => return Err(conflict(&enr)),
+                Some(_) => {}
+            }
+        }
+        Ok(Self { records, backend })
+    }
+
+    /// Stores a record if its signature is valid and it is newer than the stored record of the
+    /// same node, persisting it to the backend.
+    ///
+    /// Returns whether the record was stored. If persisting fails, the store is left unchanged. A
+    /// valid record with the sequence number of the stored record but different content is
+    /// rejected with a [`ConflictingRecord`] error of kind [`ErrorKind::InvalidData`].
+    pub fn insert(&mut self, enr: Enr<K>) -> io::Result<bool> {
+        if let Some(existing) = self.records.get(&enr.node_id()) {
+            match enr.supersedes(existing) {
+                Ok(Supersedes::Newer) => {}
+                Ok(Supersedes::Conflict) if enr.verify() => return Err(conflict(&enr)),
+                _ => return Ok(false),
+            }
+        }
+        if !enr.verify() {
+            return Ok(false);
+        }
+        self.backend.save(&enr)?;
+        self.records.insert(enr.node_id(), enr);
+        Ok(true)
+    }
+
+    /// Removes the record of a node, returning it if it was stored.
+    pub fn remove(&mut self, node_id: &NodeId) -> io::Result<Option<Enr<K>>> {
+        if !self.records.contains_key(node_id) {
+            return Ok(None);
+        }
+        self.backend.remove(node_id)?;
+        Ok(self.records.remove(node_id))
+    }
+
+    /// The stored record of a node.
+    #[must_use]
+    pub fn get(&self, node_id: &NodeId) -> Option<&Enr<K>> {
+        self.records.get(node_id)
+    }
+
+    /// Returns whether a record of the node is stored.
+    #[must_use]
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.records.contains_key(node_id)
+    }
+
+    /// The node ids of all stored records, in arbitrary order.
+    pub fn node_ids(&self) -> impl Iterator<Item = &NodeId> {
+        self.records.keys()
+    }
+
+    /// All stored records, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &Enr<K>> {
+        self.records.values()
+    }
+
+    /// The number of stored records.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns whether no records are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The backend the records are persisted to.
+    #[must_use]
+    pub const fn backend(&self) -> &B {
+        &self.backend
+    }
+}
+
+/// The error of a record conflicting with the stored record of its node.
+fn conflict<K: EnrKey>(enr: &Enr<K>) -> io::Error {
+    let conflict = ConflictingRecord {
+        node_id: enr.node_id(),
+        seq: enr.seq(),
+    };
+    io::Error::new(ErrorKind::InvalidData, conflict)
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    /// A path in the temporary directory that is removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            let name = format!("enr-store-{}-{}", std::process::id(), rand::random::<u64>());
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn records() -> (DefaultKey, Enr<DefaultKey>, Enr<DefaultKey>) {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let old = Enr::builder().udp4(30303).build(&key).unwrap();
+        let mut new = old.clone();
+        new.set_udp4(30304, &key).unwrap();
+        (key, old, new)
+    }
+
+    #[test]
+    fn test_keeps_newest() {
+        let (_, old, new) = records();
+        let mut store = EnrStore::new();
+        assert!(store.insert(old.clone()).unwrap());
+        assert!(store.insert(new.clone()).unwrap());
+        assert!(!store.insert(old).unwrap());
+        assert!(!store.insert(new.clone()).unwrap());
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get(&new.node_id()), Some(&new));
+
+        assert_eq!(store.remove(&new.node_id()).unwrap(), Some(new.clone()));
+        assert_eq!(store.remove(&new.node_id()).unwrap(), None);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_conflicting_record() {
+        let (key, old, _) = records();
+        let conflicting = Enr::builder().udp4(30305).build(&key).unwrap();
+        let mut store = EnrStore::new();
+        store.insert(old.clone()).unwrap();
+
+        // the same content signed again is not a conflict
+        let resigned = Enr::builder().udp4(30303).build(&key).unwrap();
+        assert!(!store.insert(resigned).unwrap());
+
+        let err = store.insert(conflicting).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref(),
+            Some(&ConflictingRecord {
+                node_id: old.node_id(),
+                seq: old.seq(),
+            })
+        );
+        assert_eq!(store.get(&old.node_id()), Some(&old));
+    }
+
+    #[cfg(feature = "raw")]
+    #[test]
+    fn test_rejects_invalid_signature() {
+        let (_, old, new) = records();
+        let forged = Enr::from_parts_unchecked(
+            new.seq(),
+            new.content().clone(),
+            old.signature().to_vec(),
+            new.node_id(),
+        );
+        let mut store = EnrStore::new();
+        store.insert(old.clone()).unwrap();
+        assert!(!store.insert(forged).unwrap());
+        assert_eq!(store.get(&old.node_id()), Some(&old));
+    }
+
+    #[test]
+    fn test_json_file_backend() {
+        let path = TempPath::new();
+        let (_, old, new) = records();
+        let other = Enr::empty(&DefaultKey::random(&mut rand::thread_rng())).unwrap();
+
+        let mut store = EnrStore::<DefaultKey, _>::open(JsonFileBackend::new(&path.0)).unwrap();
+        assert!(store.is_empty());
+        store.insert(old).unwrap();
+        store.insert(new.clone()).unwrap();
+        store.insert(other.clone()).unwrap();
+        drop(store);
+
+        let mut store = EnrStore::<DefaultKey, _>::open(JsonFileBackend::new(&path.0)).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&new.node_id()), Some(&new));
+        store.remove(&other.node_id()).unwrap();
+        drop(store);
+
+        let store = EnrStore::<DefaultKey, _>::open(JsonFileBackend::new(&path.0)).unwrap();
+        assert_eq!(store.iter().collect::<Vec<_>>(), vec![&new]);
+
+        fs::write(&path.0, "[\"enr:-invalid\"]").unwrap();
+        let err = EnrStore::<DefaultKey, _>::open(JsonFileBackend::new(&path.0)).err();
+        assert_eq!(err.map(|e| e.kind()), Some(ErrorKind::InvalidData));
+    }
+
+    #[cfg(feature = "store-sled")]
+    #[test]
+    fn test_sled_backend() {
+        let path = TempPath::new();
+        let (_, old, new) = records();
+
+        let mut store =
+            EnrStore::<DefaultKey, _>::open(SledBackend::open(&path.0).unwrap()).unwrap();
+        store.insert(old).unwrap();
+        store.insert(new.clone()).unwrap();
+        drop(store);
+
+        let store = EnrStore::<DefaultKey, _>::open(SledBackend::open(&path.0).unwrap()).unwrap();
+        assert_eq!(store.iter().collect::<Vec<_>>(), vec![&new]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Subnet subscriptions of the Ethereum consensus layer.
+//!
+//! Consensus clients advertise the attestation and sync committee subnets they subscribe to
+//! under `attnets` and `syncnets`, as an SSZ `Bitvector[64]` and `Bitvector[4]`. A
+//! [`SubnetBitfield`] decodes these once, so that callers check subscriptions by subnet id
+//! instead of by bit position:
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, AttestationSubnets, Enr};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder().build(&key).unwrap();
+//!
+//! let mut subnets = AttestationSubnets::new();
+//! subnets.set(3, true);
+//! subnets.set(40, true);
+//! enr.set_attestation_subnets(subnets, &key).unwrap();
+//!
+//! assert!(enr.is_subscribed_to_attnet(40));
+//! assert!(!enr.is_subscribed_to_attnet(41));
+//! assert_eq!(enr.attestation_subnets(), Some(Ok(subnets)));
+//! # }
+//! ```
+
+use rlp::{DecoderError, Encodable, Rlp, RlpStream};
+
+/// The ENR key of the attestation subnet bitfield.
+pub const ATTNETS_KEY: &str = "attnets";
+
+/// The ENR key of the sync committee subnet bitfield.
+pub const SYNCNETS_KEY: &str = "syncnets";
+
+/// The attestation subnets a node subscribes to, stored under `attnets`.
+pub type AttestationSubnets = SubnetBitfield<64>;
+
+/// The sync committee subnets a node subscribes to, stored under `syncnets`.
+pub type SyncCommitteeSubnets = SubnetBitfield<4>;
+
+/// An SSZ `Bitvector[N]` of subnet subscriptions, for `N` up to 64.
+///
+/// Subnet `i` is bit `i % 8` of byte `i / 8` of the SSZ encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SubnetBitfield<const N: usize> {
+    /// Bit `i` is set if the node subscribes to subnet `i`.
+    bits: u64,
+}
+
+impl<const N: usize> SubnetBitfield<N> {
+    /// The length of the SSZ encoding in bytes.
+    const BYTES: usize = {
+        assert!(N <= 64, "subnet bitfields hold at most 64 subnets");
+        N.div_ceil(8)
+    };
+
+    /// A bitfield without subscriptions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// The number of subnets.
+    #[must_use]
+    pub const fn len(self) -> usize {
+        N
+    }
+
+    /// Whether the bitfield holds no subnets, i.e. `N` is zero.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        N == 0
+    }
+
+    /// Whether the node subscribes to `subnet`. Subnets out of range are never subscribed to.
+    #[must_use]
+    pub const fn get(self, subnet: usize) -> bool {
+        subnet < N && self.bits & (1 << subnet) != 0
+    }
+
+    /// Sets whether the node subscribes to `subnet`.
+    ///
+    /// # Panics
+    /// Panics if `subnet` is not below `N`.
+    pub fn set(&mut self, subnet: usize, subscribed: bool) {
+        assert!(
+            subnet < N,
+            "subnet {} out of range for {} subnets",
+            subnet,
+            N
+        );
+        if subscribed {
+            self.bits |= 1 << subnet;
+        } else {
+            self.bits &= !(1 << subnet);
+        }
+    }
+
+    /// The subnets the node subscribes to, in ascending order.
+    pub fn subnets(self) -> impl Iterator<Item = usize> {
+        (0..N).filter(move |&subnet| self.get(subnet))
+    }
+
+    /// The SSZ encoding of the bitfield.
+    #[must_use]
+    pub fn to_ssz_bytes(self) -> Vec<u8> {
+        self.bits.to_le_bytes()[..Self::BYTES].to_vec()
+    }
+
+    /// Decodes the SSZ encoding of the bitfield, rejecting encodings of the wrong length or with
+    /// bits set beyond the last subnet.
+    pub fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecoderError> {
+        if bytes.len() != Self::BYTES {
+            return Err(DecoderError::RlpInvalidLength);
+        }
+        let mut le_bytes = [0; 8];
+        le_bytes[..bytes.len()].copy_from_slice(bytes);
+        let bits = u64::from_le_bytes(le_bytes);
+        if N < 64 && bits >> N != 0 {
+            return Err(DecoderError::Custom("subnet bitfield has excess bits set"));
+        }
+        Ok(Self { bits })
+    }
+}
+
+impl<const N: usize> Encodable for SubnetBitfield<N> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append(&self.to_ssz_bytes());
+    }
+}
+
+impl<const N: usize> rlp::Decodable for SubnetBitfield<N> {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Self::from_ssz_bytes(rlp.data()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssz_encoding() {
+        let mut subnets = SyncCommitteeSubnets::new();
+        subnets.set(0, true);
+        subnets.set(3, true);
+        assert_eq!(subnets.to_ssz_bytes(), [0b1001]);
+        assert_eq!(subnets.subnets().collect::<Vec<_>>(), [0, 3]);
+        assert!(!subnets.get(4));
+
+        let mut subnets = AttestationSubnets::new();
+        subnets.set(9, true);
+        subnets.set(63, true);
+        let bytes = subnets.to_ssz_bytes();
+        assert_eq!(bytes, [0, 0b10, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(AttestationSubnets::from_ssz_bytes(&bytes), Ok(subnets));
+        assert_eq!(rlp::decode(&rlp::encode(&subnets)), Ok(subnets));
+
+        assert_eq!(
+            AttestationSubnets::from_ssz_bytes(&[0xff; 4]),
+            Err(DecoderError::RlpInvalidLength)
+        );
+        assert!(SyncCommitteeSubnets::from_ssz_bytes(&[0x10]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! A human-auditable rendering of the content of an ENR that is covered by its signature.
+//!
+//! External signers, such as hardware wallets, are only handed a digest to sign. A
+//! [`SigningSummary`] pairs that digest with the decoded fields that produced it, so users can be
+//! shown what they are signing.
+
+use crate::{FieldValue, Key, Value};
+use std::{collections::BTreeMap, fmt};
+
+/// The digest signed for a record along with the decoded content it was computed from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SigningSummary {
+    /// The digest that is signed.
+    digest: [u8; 32],
+    /// The sequence number of the record.
+    seq: u64,
+    /// The (key, decoded value) pairs of the record, in the order they are signed.
+    fields: Vec<(String, String)>,
+}
+
+impl SigningSummary {
+    pub(crate) fn new(digest: [u8; 32], seq: u64, content: &BTreeMap<Key, Value>) -> Self {
+        let fields = content
+            .iter()
+            .map(|(key, value)| {
+                (
+                    String::from_utf8_lossy(key).into(),
+                    format_value(key, value),
+                )
+            })
+            .collect();
+        Self {
+            digest,
+            seq,
+            fields,
+        }
+    }
+
+    /// The digest that is signed.
+    #[must_use]
+    pub const fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+
+    /// The sequence number of the record.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The (key, decoded value) pairs of the record, in the order they are signed.
+    #[must_use]
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+}
+
+impl fmt::Display for SigningSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "digest: 0x{}", hex::encode(self.digest))?;
+        write!(f, "seq: {}", self.seq)?;
+        for (key, value) in &self.fields {
+            write!(f, "\n{key}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the raw RLP value of a record field in a human readable form.
+///
+/// Known fields are decoded to their typed representation. Any value that can't be decoded is
+/// rendered as the hex encoding of its raw RLP bytes.
+pub(crate) fn format_value(key: &[u8], value: &[u8]) -> String {
+    FieldValue::decode(key, value).to_string()
+}
@@ -0,0 +1,259 @@
+//! Background tasks for long-running nodes.
+//!
+//! [`republisher`] builds the loop found in most node binaries: a task that periodically
+//! re-signs the node's shared record and hands the encoded record to the networking layer.
+//!
+//! ```
+//! # #[cfg(feature = "k256")]
+//! # async fn example() {
+//! use enr::{k256::ecdsa::SigningKey, tasks, Enr};
+//! use std::{sync::{Arc, RwLock}, time::Duration};
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr = Arc::new(RwLock::new(Enr::empty(&key).unwrap()));
+//!
+//! let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+//! let task = tasks::republisher(enr, Arc::new(key), Duration::from_secs(300), |encoded| {
+//!     // advertise the encoded record to peers
+//! })
+//! .jitter(Duration::from_secs(30))
+//! .run(async {
+//!     shutdown_rx.await.ok();
+//! });
+//! tokio::spawn(task);
+//! # }
+//! ```
+
+use crate::{Enr, EnrKey, SeqPolicy};
+use bytes::{Bytes, BytesMut};
+use log::debug;
+use rand::Rng;
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// A record shared between the republisher and the rest of the node.
+pub type SharedEnr<K> = Arc<RwLock<Enr<K>>>;
+
+/// How the sequence number of the record is updated before each republish.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RefreshSeqPolicy {
+    /// The sequence number is advanced by the record's own [`SeqPolicy`] and the record re-signed
+    /// on every republish.
+    #[default]
+    Record,
+    /// The sequence number is advanced by the given [`SeqPolicy`] instead of the record's and the
+    /// record re-signed on every republish.
+    Advance(SeqPolicy),
+    /// The record is republished as is. Changes made through the shared record are still
+    /// published, as every mutation already increments the sequence number.
+    Keep,
+}
+
+/// A periodic republish task, created by [`republisher`].
+pub struct Republisher<K: EnrKey, F> {
+    /// The record to republish.
+    shared_enr: SharedEnr<K>,
+    /// The key used to re-sign the record.
+    key: Arc<K>,
+    /// The base interval between republishes.
+    interval: Duration,
+    /// The maximum random delay added to each interval.
+    jitter: Duration,
+    /// How the sequence number is updated.
+    seq_policy: RefreshSeqPolicy,
+    /// Receives the RLP encoding of the record on every republish.
+    publish_fn: F,
+}
+
+/// Creates a task that republishes the shared record every `interval`, passing its RLP encoding
+/// to `publish_fn`. The record is published once immediately when the task starts.
+pub fn republisher<K, F>(
+    shared_enr: SharedEnr<K>,
+    key: Arc<K>,
+    interval: Duration,
+    publish_fn: F,
+) -> Republisher<K, F>
+where
+    K: EnrKey,
+    F: FnMut(Bytes),
+{
+    Republisher {
+        shared_enr,
+        key,
+        interval,
+        jitter: Duration::ZERO,
+        seq_policy: RefreshSeqPolicy::default(),
+        publish_fn,
+    }
+}
+
+impl<K, F> Republisher<K, F>
+where
+    K: EnrKey,
+    F: FnMut(Bytes),
+{
+    /// Adds a random delay of up to `jitter` to each interval, so that nodes started together
+    /// don't republish in lockstep.
+    #[must_use]
+    pub const fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets how the sequence number is updated before each republish.
+    #[must_use]
+    pub const fn seq_policy(mut self, seq_policy: RefreshSeqPolicy) -> Self {
+        self.seq_policy = seq_policy;
+        self
+    }
+
+    /// Runs the task until `shutdown` completes.
+    ///
+    /// The returned future is cancellation safe: the record is never left partially updated, so
+    /// dropping it or aborting the spawned task is an alternative to `shutdown`. The task also
+    /// stops if the shared record's lock is poisoned.
+    pub async fn run(mut self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        let mut buffer = BytesMut::new();
+        if !self.publish(RefreshSeqPolicy::Keep, &mut buffer) {
+            return;
+        }
+        loop {
+            let delay = if self.jitter.is_zero() {
+                self.interval
+            } else {
+                self.interval + rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+            };
+            tokio::select! {
+                () = &mut shutdown => return,
+                () = tokio::time::sleep(delay) => {}
+            }
+            if !self.publish(self.seq_policy, &mut buffer) {
+                return;
+            }
+        }
+    }
+
+    /// Publishes the record, first updating its sequence number as given by `seq_policy`. Returns
+    /// `false` if the shared record can no longer be accessed.
+    fn publish(&mut self, seq_policy: RefreshSeqPolicy, buffer: &mut BytesMut) -> bool {
+        {
+            let Ok(mut enr) = self.shared_enr.write() else {
+                debug!("Shared ENR lock poisoned, stopping republisher");
+                return false;
+            };
+            let advance = match seq_policy {
+                RefreshSeqPolicy::Record => Some(enr.seq_policy()),
+                RefreshSeqPolicy::Advance(policy) => Some(policy),
+                RefreshSeqPolicy::Keep => None,
+            };
+            if let Some(policy) = advance {
+                let key = &self.key;
+                let bumped = policy.next(enr.seq()).and_then(|seq| enr.set_seq(seq, key));
+                if let Err(e) = bumped {
+                    debug!("Failed to re-sign ENR, republishing unchanged: {e}");
+                }
+            }
+            buffer.clear();
+            enr.encode_to(buffer);
+        }
+        (self.publish_fn)(buffer.split().freeze());
+        true
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    type DefaultKey = k256::ecdsa::SigningKey;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_republisher() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Arc::new(RwLock::new(Enr::empty(&key).unwrap()));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let task = tokio::spawn(
+            republisher(
+                enr.clone(),
+                Arc::new(key),
+                Duration::from_secs(60),
+                move |encoded| {
+                    tx.send(encoded).unwrap();
+                },
+            )
+            .jitter(Duration::from_secs(5))
+            .run(async {
+                shutdown_rx.await.ok();
+            }),
+        );
+
+        for seq in 1..=3 {
+            let encoded = rx.recv().await.unwrap();
+            let published: Enr<DefaultKey> = rlp::decode(&encoded).unwrap();
+            assert_eq!(published.seq(), seq);
+            assert_eq!(published, *enr.read().unwrap());
+        }
+
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_republisher_keep_seq() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Arc::new(RwLock::new(Enr::empty(&key).unwrap()));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let task = tokio::spawn(
+            republisher(
+                enr,
+                Arc::new(key),
+                Duration::from_secs(60),
+                move |encoded| {
+                    tx.send(encoded).unwrap();
+                },
+            )
+            .seq_policy(RefreshSeqPolicy::Keep)
+            .run(std::future::pending()),
+        );
+
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(first, second);
+        task.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_republisher_advance_seq() {
+        let key = DefaultKey::random(&mut rand::thread_rng());
+        let enr = Arc::new(RwLock::new(Enr::empty(&key).unwrap()));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let task = tokio::spawn(
+            republisher(
+                enr,
+                Arc::new(key),
+                Duration::from_secs(60),
+                move |encoded| {
+                    tx.send(encoded).unwrap();
+                },
+            )
+            .seq_policy(RefreshSeqPolicy::Advance(SeqPolicy::UnixTimestamp))
+            .run(std::future::pending()),
+        );
+
+        let first: Enr<DefaultKey> = rlp::decode(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(first.seq(), 1);
+        let second: Enr<DefaultKey> = rlp::decode(&rx.recv().await.unwrap()).unwrap();
+        assert!(second.seq() > 1_600_000_000);
+        task.abort();
+    }
+}
@@ -0,0 +1,390 @@
+//! Record templates with placeholders resolved when the record is signed.
+//!
+//! Deployments driven by configuration ship a [`RecordTemplate`] rather than a record, as some
+//! values are only known at runtime. A template holds fixed values and [`Placeholder`]s, which are
+//! filled in by a [`TemplateProviders`] implementation when the template is built into a signed
+//! [`Enr`], so every runtime value goes through one code path.
+//!
+//! Templates can be parsed from a text form with one `key = value` field per line. Values of the
+//! form `${NAME}` are placeholders. Other values are stored as an unsigned integer if they parse as
+//! one, as address octets if they parse as an IP address, as bytes if they are hex with a `0x`
+//! prefix, and as a UTF-8 string otherwise. Empty lines and lines starting with `#` are ignored.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, RecordTemplate, TemplateProviders};
+//! use std::net::{IpAddr, Ipv4Addr};
+//!
+//! struct Node;
+//!
+//! impl TemplateProviders for Node {
+//!     fn external_ip(&self) -> Option<IpAddr> {
+//!         Some(Ipv4Addr::new(203, 0, 113, 7).into())
+//!     }
+//! }
+//!
+//! let template: RecordTemplate = "
+//!     ip = ${EXTERNAL_IP}
+//!     udp = 9000
+//!     started = ${NOW_TS}
+//! "
+//! .parse()
+//! .unwrap();
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let enr = template.build(&Node, &key).unwrap();
+//! assert_eq!(enr.ip4(), Some(Ipv4Addr::new(203, 0, 113, 7)));
+//! assert_eq!(enr.udp4(), Some(9000));
+//! # }
+//! ```
+
+use crate::{Enr, EnrError, EnrKey};
+use bytes::Bytes;
+use rlp::Encodable;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A runtime value of a [`RecordTemplate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    /// The external IP address of the node, stored as its octets.
+    ExternalIp,
+    /// The current UNIX timestamp in seconds, stored as an unsigned integer.
+    NowTs,
+    /// The 4 byte fork digest of the node's current fork.
+    ForkDigest,
+}
+
+impl Placeholder {
+    /// Every placeholder.
+    pub const ALL: [Self; 3] = [Self::ExternalIp, Self::NowTs, Self::ForkDigest];
+
+    /// The name of the placeholder in the text form of templates.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ExternalIp => "EXTERNAL_IP",
+            Self::NowTs => "NOW_TS",
+            Self::ForkDigest => "FORK_DIGEST",
+        }
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${{{}}}", self.name())
+    }
+}
+
+/// Supplies the values of [`Placeholder`]s when a [`RecordTemplate`] is built.
+pub trait TemplateProviders {
+    /// The value of [`Placeholder::ExternalIp`]. Unresolved by default.
+    fn external_ip(&self) -> Option<IpAddr> {
+        None
+    }
+
+    /// The value of [`Placeholder::NowTs`]. The system time by default.
+    fn now_ts(&self) -> Option<u64> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|now| now.as_secs())
+    }
+
+    /// The value of [`Placeholder::ForkDigest`]. Unresolved by default.
+    fn fork_digest(&self) -> Option<[u8; 4]> {
+        None
+    }
+}
+
+/// The value of a template field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplateValue {
+    /// A raw RLP encoded value.
+    Fixed(Bytes),
+    /// A value resolved when the template is built.
+    Placeholder(Placeholder),
+}
+
+/// The fields of a record, some of which are resolved when the record is built.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecordTemplate {
+    fields: BTreeMap<Vec<u8>, TemplateValue>,
+}
+
+impl RecordTemplate {
+    /// Creates an empty template.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a field to a fixed value.
+    pub fn fixed<T: Encodable>(&mut self, key: impl AsRef<[u8]>, value: &T) -> &mut Self {
+        self.fields.insert(
+            key.as_ref().to_vec(),
+            TemplateValue::Fixed(rlp::encode(value).freeze()),
+        );
+        self
+    }
+
+    /// Sets a field to a placeholder resolved when the template is built.
+    pub fn placeholder(&mut self, key: impl AsRef<[u8]>, placeholder: Placeholder) -> &mut Self {
+        self.fields.insert(
+            key.as_ref().to_vec(),
+            TemplateValue::Placeholder(placeholder),
+        );
+        self
+    }
+
+    /// The keys of the template and the placeholders they hold.
+    pub fn placeholders(&self) -> impl Iterator<Item = (&Vec<u8>, Placeholder)> {
+        self.fields.iter().filter_map(|(key, value)| match value {
+            TemplateValue::Placeholder(placeholder) => Some((key, *placeholder)),
+            TemplateValue::Fixed(_) => None,
+        })
+    }
+
+    /// Resolves the placeholders of the template with `providers` and builds a record signed by
+    /// `key`.
+    ///
+    /// Values are validated like [`Enr::insert`] does. Fails with
+    /// [`EnrError::UnresolvedPlaceholder`] if a provider has no value for a placeholder.
+    pub fn build<K: EnrKey>(
+        &self,
+        providers: &impl TemplateProviders,
+        key: &K,
+    ) -> Result<Enr<K>, EnrError> {
+        let mut builder = Enr::builder();
+        for (field, value) in &self.fields {
+            let value = match value {
+                TemplateValue::Fixed(value) => value.clone(),
+                TemplateValue::Placeholder(placeholder) => resolve(*placeholder, providers)
+                    .ok_or_else(|| EnrError::UnresolvedPlaceholder(placeholder.name().into()))?,
+            };
+            builder.add_value_rlp(field, value);
+        }
+        builder.try_build(key)
+    }
+}
+
+/// The RLP encoded value of a placeholder.
+fn resolve(placeholder: Placeholder, providers: &impl TemplateProviders) -> Option<Bytes> {
+    let value = match placeholder {
+        Placeholder::ExternalIp => match providers.external_ip()? {
+            IpAddr::V4(ip) => rlp::encode(&ip.octets().as_ref()),
+            IpAddr::V6(ip) => rlp::encode(&ip.octets().as_ref()),
+        },
+        Placeholder::NowTs => rlp::encode(&providers.now_ts()?),
+        Placeholder::ForkDigest => rlp::encode(&providers.fork_digest()?.as_ref()),
+    };
+    Some(value.freeze())
+}
+
+/// Parses the text form of a fixed value into its RLP encoding.
+pub(crate) fn parse_value(value: &str) -> Result<Bytes, hex::FromHexError> {
+    let encoded = if let Ok(uint) = value.parse::<u64>() {
+        rlp::encode(&uint)
+    } else if let Ok(ip) = value.parse::<IpAddr>() {
+        match ip {
+            IpAddr::V4(ip) => rlp::encode(&ip.octets().as_ref()),
+            IpAddr::V6(ip) => rlp::encode(&ip.octets().as_ref()),
+        }
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        rlp::encode(&hex::decode(hex)?)
+    } else {
+        rlp::encode(&value)
+    };
+    Ok(encoded.freeze())
+}
+
+impl FromStr for RecordTemplate {
+    type Err = TemplateParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut template = Self::new();
+        for (index, line) in s.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(TemplateParseError::MissingSeparator { line: number })?;
+            let (key, value) = (key.trim(), value.trim());
+
+            if let Some(name) = value.strip_prefix("${").and_then(|v| v.strip_suffix('}')) {
+                let placeholder = Placeholder::ALL
+                    .iter()
+                    .copied()
+                    .find(|placeholder| placeholder.name() == name)
+                    .ok_or_else(|| TemplateParseError::UnknownPlaceholder {
+                        line: number,
+                        name: name.into(),
+                    })?;
+                template.placeholder(key, placeholder);
+            } else {
+                let value = parse_value(value).map_err(|error| TemplateParseError::InvalidHex {
+                    line: number,
+                    error,
+                })?;
+                template
+                    .fields
+                    .insert(key.into(), TemplateValue::Fixed(value));
+            }
+        }
+        Ok(template)
+    }
+}
+
+/// An error parsing the text form of a [`RecordTemplate`], with the number of the offending line,
+/// counted from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateParseError {
+    /// The line is not of the form `key = value`.
+    MissingSeparator {
+        /// The number of the line.
+        line: usize,
+    },
+    /// The line holds a placeholder of an unknown name.
+    UnknownPlaceholder {
+        /// The number of the line.
+        line: usize,
+        /// The name of the placeholder.
+        name: String,
+    },
+    /// The line holds a value with a `0x` prefix that is not valid hex.
+    InvalidHex {
+        /// The number of the line.
+        line: usize,
+        /// The error decoding the value.
+        error: hex::FromHexError,
+    },
+}
+
+impl fmt::Display for TemplateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator { line } => write!(f, "line {line}: expected `key = value`"),
+            Self::UnknownPlaceholder { line, name } => {
+                write!(f, "line {line}: unknown placeholder `{name}`")
+            }
+            Self::InvalidHex { line, error } => write!(f, "line {line}: invalid hex: {error}"),
+        }
+    }
+}
+
+impl Error for TemplateParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidHex { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    struct Fixed;
+
+    impl TemplateProviders for Fixed {
+        fn external_ip(&self) -> Option<IpAddr> {
+            Some(Ipv6Addr::LOCALHOST.into())
+        }
+
+        fn now_ts(&self) -> Option<u64> {
+            Some(1_700_000_000)
+        }
+
+        fn fork_digest(&self) -> Option<[u8; 4]> {
+            Some([1, 2, 3, 4])
+        }
+    }
+
+    struct Unresolved;
+
+    impl TemplateProviders for Unresolved {}
+
+    #[test]
+    fn test_build() {
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut template = RecordTemplate::new();
+        template
+            .placeholder("ip6", Placeholder::ExternalIp)
+            .placeholder("ts", Placeholder::NowTs)
+            .placeholder("fork", Placeholder::ForkDigest)
+            .fixed("udp6", &9000_u16);
+
+        let enr = template.build(&Fixed, &key).unwrap();
+        assert_eq!(enr.ip6(), Some(Ipv6Addr::LOCALHOST));
+        assert_eq!(enr.udp6(), Some(9000));
+        assert_eq!(enr.get_uint("ts"), Some(Ok(1_700_000_000)));
+        assert_eq!(enr.get("fork"), Some([1, 2, 3, 4].as_ref()));
+
+        assert_eq!(
+            template.build(&Unresolved, &key).unwrap_err(),
+            EnrError::UnresolvedPlaceholder("FORK_DIGEST".into())
+        );
+    }
+
+    #[test]
+    fn test_placeholder_validation() {
+        // an IPv6 address cannot be stored under `ip`
+        let key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let mut template = RecordTemplate::new();
+        template.placeholder("ip", Placeholder::ExternalIp);
+        assert!(template.build(&Fixed, &key).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let template: RecordTemplate = "
+            # comment
+            ip6 = ${EXTERNAL_IP}
+            fork = ${FORK_DIGEST}
+            tcp6 = 30303
+            ip = 10.0.0.1
+            raw = 0x0102
+            name = boot node
+        "
+        .parse()
+        .unwrap();
+
+        let mut expected = RecordTemplate::new();
+        expected
+            .placeholder("ip6", Placeholder::ExternalIp)
+            .placeholder("fork", Placeholder::ForkDigest)
+            .fixed("tcp6", &30303_u64)
+            .fixed("ip", &[10_u8, 0, 0, 1].as_ref())
+            .fixed("raw", &vec![1_u8, 2])
+            .fixed("name", &"boot node");
+        assert_eq!(template, expected);
+        assert_eq!(
+            template.placeholders().map(|(_, p)| p).collect::<Vec<_>>(),
+            [Placeholder::ForkDigest, Placeholder::ExternalIp]
+        );
+
+        assert_eq!(
+            "ip = ${NOPE}".parse::<RecordTemplate>().unwrap_err(),
+            TemplateParseError::UnknownPlaceholder {
+                line: 1,
+                name: "NOPE".into(),
+            }
+        );
+        assert_eq!(
+            "\nno separator".parse::<RecordTemplate>().unwrap_err(),
+            TemplateParseError::MissingSeparator { line: 2 }
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Generators of valid, signed records for testing code that handles records.
+//!
+//! [`TestEnrGenerator`] produces a reproducible sequence of records from a seed: the same seed
+//! and configuration always yield the same keys, fields and signatures. The `Arbitrary`
+//! implementation of `Enr<SigningKey>` provides a proptest strategy covering the same field
+//! combinations.
+//!
+//! ```rust
+//! use enr::test_utils::{IpFamily, TestEnrGenerator};
+//!
+//! let mut generator = TestEnrGenerator::new(7).ip(IpFamily::Both).quic(true).eth2(true);
+//! let (_key, enr) = generator.generate();
+//! assert!(enr.udp4_socket().is_some() && enr.udp6_socket().is_some());
+//!
+//! // the same seed yields the same records
+//! let again = TestEnrGenerator::new(7).ip(IpFamily::Both).quic(true).eth2(true).next();
+//! assert_eq!(again, Some(enr));
+//! ```
+//!
+//! ```rust
+//! use enr::{k256::ecdsa::SigningKey, Enr};
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(enr in any::<Enr<SigningKey>>())| {
+//!     prop_assert_eq!(rlp::decode::<Enr<SigningKey>>(&rlp::encode(&enr)).unwrap(), enr);
+//! });
+//! ```
+//!
+//! Signatures are made reproducible with [`inject_entropy`](crate::inject_entropy), which
+//! replaces any entropy injected by the calling thread.
+
+use crate::{clear_injected_entropy, inject_entropy, Enr, MAX_ENR_SIZE};
+use bytes::Bytes;
+use k256::ecdsa::SigningKey;
+use proptest::{
+    arbitrary::Arbitrary,
+    strategy::{BoxedStrategy, Strategy},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rlp::Encodable;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// The key of the field padding records to near the maximum size.
+const PADDING_KEY: &str = "padding";
+
+/// The IP address families a generated record has endpoints for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IpFamily {
+    /// No IP address or port.
+    None,
+    /// An IPv4 address with UDP and TCP ports.
+    #[default]
+    V4,
+    /// An IPv6 address with UDP and TCP ports.
+    V6,
+    /// Both an IPv4 and an IPv6 address, with UDP and TCP ports each.
+    Both,
+}
+
+impl IpFamily {
+    /// All IP address family combinations.
+    pub const ALL: [Self; 4] = [Self::None, Self::V4, Self::V6, Self::Both];
+
+    /// Whether records have an IPv4 endpoint.
+    const fn has_v4(self) -> bool {
+        matches!(self, Self::V4 | Self::Both)
+    }
+
+    /// Whether records have an IPv6 endpoint.
+    const fn has_v6(self) -> bool {
+        matches!(self, Self::V6 | Self::Both)
+    }
+}
+
+/// Generates a reproducible sequence of valid, signed records from a seed.
+#[derive(Clone, Debug)]
+pub struct TestEnrGenerator {
+    /// The source of keys, addresses and signing entropy.
+    rng: StdRng,
+    /// The IP address families of the records.
+    ip: IpFamily,
+    /// Whether records have QUIC ports.
+    quic: bool,
+    /// Whether records have an `eth2` field.
+    eth2: bool,
+    /// Fields added to every record, with their RLP encoded values.
+    custom: Vec<(Vec<u8>, Bytes)>,
+    /// Whether records are padded to near the maximum size.
+    near_max_size: bool,
+}
+
+impl TestEnrGenerator {
+    /// Creates a generator of records with an IPv4 endpoint.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            ip: IpFamily::V4,
+            quic: false,
+            eth2: false,
+            custom: Vec::new(),
+            near_max_size: false,
+        }
+    }
+
+    /// Sets the IP address families of the records.
+    #[must_use]
+    pub const fn ip(mut self, ip: IpFamily) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    /// Adds `quic`/`quic6` ports for each IP address family of the records.
+    #[must_use]
+    pub const fn quic(mut self, quic: bool) -> Self {
+        self.quic = quic;
+        self
+    }
+
+    /// Adds a random 16 byte `eth2` fork id to the records.
+    #[must_use]
+    pub const fn eth2(mut self, eth2: bool) -> Self {
+        self.eth2 = eth2;
+        self
+    }
+
+    /// Adds `key` with the RLP encoding of `value` to every record.
+    #[must_use]
+    pub fn custom<T: Encodable>(mut self, key: impl AsRef<[u8]>, value: &T) -> Self {
+        self.custom
+            .push((key.as_ref().to_vec(), rlp::encode(value).freeze()));
+        self
+    }
+
+    /// Pads the records with a `padding` field to within a few bytes of the maximum record size.
+    #[must_use]
+    pub const fn near_max_size(mut self, near_max_size: bool) -> Self {
+        self.near_max_size = near_max_size;
+        self
+    }
+
+    /// Generates the next record and the key it is signed with.
+    ///
+    /// # Panics
+    /// Panics if the custom fields make the record exceed the maximum size.
+    pub fn generate(&mut self) -> (SigningKey, Enr<SigningKey>) {
+        let key = SigningKey::random(&mut self.rng);
+        let mut builder = Enr::builder();
+        if self.ip.has_v4() {
+            builder
+                .ip4(Ipv4Addr::from(self.rng.gen::<u32>()))
+                .udp4(self.rng.gen_range(1..=u16::MAX))
+                .tcp4(self.rng.gen_range(1..=u16::MAX));
+            if self.quic {
+                builder.add_value("quic", &self.rng.gen_range(1..=u16::MAX));
+            }
+        }
+        if self.ip.has_v6() {
+            builder
+                .ip6(Ipv6Addr::from(self.rng.gen::<u128>()))
+                .udp6(self.rng.gen_range(1..=u16::MAX))
+                .tcp6(self.rng.gen_range(1..=u16::MAX));
+            if self.quic {
+                builder.add_value("quic6", &self.rng.gen_range(1..=u16::MAX));
+            }
+        }
+        if self.eth2 {
+            builder.add_value("eth2", &self.rng.gen::<[u8; 16]>().as_ref());
+        }
+        for (field, value) in &self.custom {
+            builder.add_value_rlp(field, value.clone());
+        }
+        let entropy = self.rng.gen();
+
+        let sign = |builder: &mut crate::builder::Builder<SigningKey>| {
+            inject_entropy(entropy);
+            let enr = builder.build(&key);
+            clear_injected_entropy();
+            enr
+        };
+        let mut enr = sign(&mut builder).expect("custom fields fit in a record");
+        if self.near_max_size {
+            // shrink the padding until the record fits, the first attempt is at most a few bytes
+            // too large
+            let mut padding = MAX_ENR_SIZE.saturating_sub(enr.size());
+            while padding > 0 {
+                let mut padded_builder = builder.clone();
+                padded_builder.add_value(PADDING_KEY, &vec![0_u8; padding]);
+                if let Ok(padded) = sign(&mut padded_builder) {
+                    enr = padded;
+                    break;
+                }
+                padding -= 1;
+            }
+        }
+        (key, enr)
+    }
+}
+
+impl Iterator for TestEnrGenerator {
+    type Item = Enr<SigningKey>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.generate().1)
+    }
+}
+
+impl Arbitrary for Enr<SigningKey> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Records with any combination of IP address families, QUIC ports, `eth2` field and
+    /// padding to near the maximum size.
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        (
+            proptest::num::u64::ANY,
+            proptest::sample::select(IpFamily::ALL.to_vec()),
+            proptest::bool::ANY,
+            proptest::bool::ANY,
+            proptest::bool::ANY,
+        )
+            .prop_map(|(seed, ip, quic, eth2, near_max_size)| {
+                TestEnrGenerator::new(seed)
+                    .ip(ip)
+                    .quic(quic)
+                    .eth2(eth2)
+                    .near_max_size(near_max_size)
+                    .generate()
+                    .1
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let generator = TestEnrGenerator::new(1)
+            .ip(IpFamily::Both)
+            .custom("note", &"a");
+        let first: Vec<_> = generator.clone().take(3).collect();
+        let second: Vec<_> = generator.take(3).collect();
+        assert_eq!(first, second);
+        assert_ne!(first[0], first[1]);
+        assert_ne!(
+            first[0],
+            TestEnrGenerator::new(2).ip(IpFamily::Both).next().unwrap()
+        );
+        assert_eq!(
+            first[0].get_decodable::<String>("note"),
+            Some(Ok("a".into()))
+        );
+    }
+
+    #[test]
+    fn test_field_combinations() {
+        for ip in IpFamily::ALL {
+            let (key, enr) = TestEnrGenerator::new(3)
+                .ip(ip)
+                .quic(true)
+                .eth2(true)
+                .near_max_size(true)
+                .generate();
+            assert!(enr.verify());
+            assert_eq!(enr.public_key(), *key.verifying_key());
+            assert_eq!(enr.udp4_socket().is_some(), ip.has_v4());
+            assert_eq!(enr.tcp6_socket().is_some(), ip.has_v6());
+            assert_eq!(enr.get_raw_rlp("quic").is_some(), ip.has_v4());
+            assert_eq!(enr.get_raw_rlp("quic6").is_some(), ip.has_v6());
+            assert!(enr.get_raw_rlp("eth2").is_some());
+            assert!(enr.size() <= MAX_ENR_SIZE && enr.size() > MAX_ENR_SIZE - 8);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Diagnostics of the decode, verify and update paths of records.
+//!
+//! With the `tracing` feature, these are structured `tracing` events, and decoding, verifying and
+//! signing updates of records enter the `enr_decode`, `enr_verify` and `enr_update` spans.
+//! Otherwise they are logged with `log` at the debug level.
+
+use crate::{EnrError, Key, KeyScheme, Value};
+use rlp::DecoderError;
+use std::collections::BTreeMap;
+
+/// Reports a record of `record_size` bytes whose structure could not be decoded.
+pub(crate) fn decode_failed(record_size: usize, cause: &DecoderError) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(record_size, %cause, "Failed to decode ENR");
+    #[cfg(not(feature = "tracing"))]
+    log::debug!("Failed to decode ENR of {record_size} bytes: {cause}");
+}
+
+/// Reports a well-formed record whose public key or signature could not be verified.
+pub(crate) fn verify_failed(seq: u64, content: &BTreeMap<Key, Value>, cause: &DecoderError) {
+    let key_scheme = key_scheme(content);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(seq, key_scheme, %cause, "Failed to verify ENR");
+    #[cfg(not(feature = "tracing"))]
+    log::debug!("Failed to verify ENR with seq {seq} and key scheme {key_scheme:?}: {cause}");
+}
+
+/// Reports a record that was updated and signed with the sequence number `seq`.
+pub(crate) fn updated(seq: u64, record_size: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(seq, record_size, "Updated ENR");
+    #[cfg(not(feature = "tracing"))]
+    log::debug!("Updated ENR to seq {seq}, {record_size} bytes");
+}
+
+/// Reports an update of the record with the sequence number `seq` that was rolled back.
+pub(crate) fn update_failed(seq: u64, cause: &EnrError) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(seq, %cause, "Failed to update ENR");
+    #[cfg(not(feature = "tracing"))]
+    log::debug!("Failed to update ENR with seq {seq}: {cause}");
+}
+
+/// The ENR key of the first known public key field of the record, if any.
+fn key_scheme(content: &BTreeMap<Key, Value>) -> Option<&'static str> {
+    content
+        .keys()
+        .find_map(|key| KeyScheme::from_enr_key(key))
+        .map(KeyScheme::enr_key)
+}
@@ -0,0 +1,243 @@
+//! Atomic multi-field updates of a record.
+//!
+//! [`Enr::update`] hands a [`Transaction`] to a closure that modifies the record's fields. Once
+//! the closure returns, all modifications are signed under a single increment of the sequence
+//! number. If any modification is invalid, or the resulting record is too large or inconsistent,
+//! the record is left exactly as it was.
+//!
+//! ```rust
+//! # #[cfg(feature = "k256")] {
+//! use enr::{k256::ecdsa::SigningKey, Enr};
+//! use std::net::Ipv4Addr;
+//!
+//! let key = SigningKey::random(&mut rand::thread_rng());
+//! let mut enr: Enr<SigningKey> = Enr::builder()
+//!     .ip6("::1".parse().unwrap())
+//!     .udp6(9000)
+//!     .build(&key)
+//!     .unwrap();
+//!
+//! enr.update(&key, |tx| {
+//!     tx.set_ip(Ipv4Addr::LOCALHOST.into())
+//!         .set_tcp4(9000)
+//!         .insert("attnets", &[0xff_u8; 8].as_ref())
+//!         .remove("udp6");
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(enr.tcp4(), Some(9000));
+//! assert_eq!(enr.udp6(), None);
+//! assert_eq!(enr.seq(), 2);
+//!
+//! // an invalid modification reverts the whole update
+//! let invalid = enr.update(&key, |tx| {
+//!     tx.set_udp4(9001).insert("tcp", &u32::MAX);
+//! });
+//! assert!(invalid.is_err());
+//! assert_eq!(enr.udp4(), None);
+//! assert_eq!(enr.seq(), 2);
+//! # }
+//! ```
+//!
+//! [`Enr::update`]: crate::Enr::update
+
+use crate::{
+    check_spec_reserved_keys, storage, AliasTable, Endpoint, EnrError, FieldChanges,
+    IdentityScheme, Key, PreviousValues, RecordFlags, Transport, Value, FLAGS_KEY,
+};
+use bytes::Bytes;
+use rlp::Encodable;
+use std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+};
+
+/// The modifications of a record applied by [`Enr::update`].
+///
+/// Modifications apply in the order they are made. The first invalid modification is reported
+/// when the transaction completes and the remaining ones are ignored.
+///
+/// [`Enr::update`]: crate::Enr::update
+pub struct Transaction<'a, S: IdentityScheme> {
+    /// The content of the record being updated.
+    content: &'a mut BTreeMap<Key, Value>,
+    /// The aliases of the record, validated like the keys they alias.
+    alias_table: Option<&'a AliasTable>,
+    /// The key of the public key the record is signed with.
+    public_key: Key,
+    /// The IP addresses set by endpoints in this transaction, by key.
+    endpoint_ips: BTreeMap<&'static str, IpAddr>,
+    /// The values of the fields removed or overwritten by the transaction.
+    previous: PreviousValues,
+    /// The first invalid modification.
+    error: Option<EnrError>,
+    /// Marker to pin the identity scheme.
+    phantom: PhantomData<fn() -> S>,
+}
+
+impl<'a, S: IdentityScheme> Transaction<'a, S> {
+    /// Starts a transaction on the content of a record signed with the public key stored under
+    /// `public_key`.
+    pub(crate) fn new(
+        content: &'a mut BTreeMap<Key, Value>,
+        alias_table: Option<&'a AliasTable>,
+        public_key: Key,
+    ) -> Self {
+        Self {
+            content,
+            alias_table,
+            public_key,
+            endpoint_ips: BTreeMap::new(),
+            previous: PreviousValues::default(),
+            error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Completes the transaction, returning the previous values of the removed and overwritten
+    /// fields or the first invalid modification.
+    pub(crate) fn finish(self) -> Result<PreviousValues, EnrError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.previous),
+        }
+    }
+
+    /// Runs `modify` unless an earlier modification failed, recording its error.
+    fn apply(&mut self, modify: impl FnOnce(&mut Self) -> Result<(), EnrError>) -> &mut Self {
+        if self.error.is_none() {
+            if let Err(error) = modify(self) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+
+    /// Adds or overwrites `key` with the RLP encoding of `value`.
+    pub fn insert<T: Encodable>(&mut self, key: impl AsRef<[u8]>, value: &T) -> &mut Self {
+        self.insert_raw_rlp(key, rlp::encode(value).freeze())
+    }
+
+    /// Adds or overwrites `key` with a raw RLP encoded value.
+    pub fn insert_raw_rlp(&mut self, key: impl AsRef<[u8]>, value: Bytes) -> &mut Self {
+        let key = key.as_ref();
+        self.apply(|tx| {
+            check_spec_reserved_keys::<S>(key, &value)?;
+            if let Some(alias_table) = tx.alias_table {
+                for alias in alias_table.aliases_of(key) {
+                    check_spec_reserved_keys::<S>(alias, &value)?;
+                }
+            }
+            let previous = tx.content.insert(Key::from(key), storage::value(value));
+            tx.previous.record(key, previous.map(storage::into_bytes));
+            Ok(())
+        })
+    }
+
+    /// Removes `key`. The `id` key and the key of the public key cannot be removed and fail with
+    /// [`EnrError::MandatoryKey`].
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> &mut Self {
+        let key = key.as_ref();
+        self.apply(|tx| {
+            if tx.is_mandatory(key) {
+                return Err(EnrError::MandatoryKey(
+                    String::from_utf8_lossy(key).into_owned(),
+                ));
+            }
+            let previous = tx.content.remove(key);
+            tx.previous.record(key, previous.map(storage::into_bytes));
+            Ok(())
+        })
+    }
+
+    /// Removes every key matching `predicate`. The `id` key and the key of the public key are
+    /// never removed by a predicate.
+    pub fn remove_if(&mut self, predicate: impl Fn(&[u8]) -> bool) -> &mut Self {
+        self.apply(|tx| {
+            let removed: Vec<Key> = tx
+                .content
+                .keys()
+                .filter(|key| !tx.is_mandatory(key) && predicate(key))
+                .cloned()
+                .collect();
+            for key in removed {
+                let previous = tx.content.remove(&key);
+                tx.previous.record(&key, previous.map(storage::into_bytes));
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies a batch of [`FieldChanges`], removals before insertions.
+    pub fn apply_changes(&mut self, changes: &FieldChanges) -> &mut Self {
+        changes.apply(self);
+        self
+    }
+
+    /// Sets the IP address under `ip` or `ip6`, depending on its family.
+    pub fn set_ip(&mut self, ip: IpAddr) -> &mut Self {
+        match ip {
+            IpAddr::V4(addr) => self.insert("ip", &addr.octets().as_ref()),
+            IpAddr::V6(addr) => self.insert("ip6", &addr.octets().as_ref()),
+        }
+    }
+
+    /// Sets the `udp` port.
+    pub fn set_udp4(&mut self, udp: u16) -> &mut Self {
+        self.insert("udp", &udp)
+    }
+
+    /// Sets the `udp6` port.
+    pub fn set_udp6(&mut self, udp: u16) -> &mut Self {
+        self.insert("udp6", &udp)
+    }
+
+    /// Sets the `tcp` port.
+    pub fn set_tcp4(&mut self, tcp: u16) -> &mut Self {
+        self.insert("tcp", &tcp)
+    }
+
+    /// Sets the `tcp6` port.
+    pub fn set_tcp6(&mut self, tcp: u16) -> &mut Self {
+        self.insert("tcp6", &tcp)
+    }
+
+    /// Sets the `flags` field.
+    pub fn set_flags(&mut self, flags: RecordFlags) -> &mut Self {
+        self.insert(FLAGS_KEY, &flags.bits())
+    }
+
+    /// Sets the IP address and UDP port of `socket`.
+    pub fn set_udp_socket(&mut self, socket: SocketAddr) -> &mut Self {
+        self.set_endpoint(Endpoint::new(Transport::Udp, socket))
+    }
+
+    /// Sets the IP address and TCP port of `socket`.
+    pub fn set_tcp_socket(&mut self, socket: SocketAddr) -> &mut Self {
+        self.set_endpoint(Endpoint::new(Transport::Tcp, socket))
+    }
+
+    /// Sets the IP address, port and extra fields of `endpoint`. Endpoints of the same IP address
+    /// family set in one transaction must share the same IP address, or fail with
+    /// [`EnrError::ConflictingEndpoints`].
+    pub fn set_endpoint(&mut self, endpoint: Endpoint) -> &mut Self {
+        self.apply(
+            |tx| match tx.endpoint_ips.insert(endpoint.ip_key(), endpoint.ip) {
+                Some(ip) if ip != endpoint.ip => Err(EnrError::ConflictingEndpoints),
+                _ => Ok(()),
+            },
+        )
+        .set_ip(endpoint.ip)
+        .insert(endpoint.port_key(), &endpoint.port);
+        for (key, value) in endpoint.extra {
+            self.insert_raw_rlp(key, value);
+        }
+        self
+    }
+
+    /// Whether `key` must be kept in the record: the `id` key and the key of the public key.
+    fn is_mandatory(&self, key: &[u8]) -> bool {
+        key == b"id" || key == self.public_key.as_slice()
+    }
+}
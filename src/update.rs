@@ -0,0 +1,157 @@
+//! Transactional modification of a record.
+//!
+//! An [`UpdateGuard`] snapshots a record before it is modified. Unless the update is completed
+//! with [`UpdateGuard::sign`], dropping the guard restores the snapshot, so every early return on
+//! an error path rolls the record back without having to undo each modification by hand.
+
+use crate::{
+    genesis, storage, trace, Enr, EnrError, EnrKey, EnrPublicKey, IdentityScheme, Key,
+    PreviousValues, Transaction, GENESIS_KEY, MAX_ENR_SIZE,
+};
+use std::ops::{Deref, DerefMut};
+
+/// A pending modification of a record, reverted when dropped unless signed.
+pub(crate) struct UpdateGuard<'a, K: EnrKey, S: IdentityScheme> {
+    enr: &'a mut Enr<K, S>,
+    /// The record before the update. `None` once the update is complete.
+    backup: Option<Enr<K, S>>,
+}
+
+impl<'a, K: EnrKey, S: IdentityScheme> UpdateGuard<'a, K, S> {
+    /// Starts an update of the record.
+    pub(crate) fn new(enr: &'a mut Enr<K, S>) -> Self {
+        let backup = Some(enr.clone());
+        enr.clear_encoded();
+        Self { enr, backup }
+    }
+
+    /// Modifies the content of the record through a [`Transaction`] for the holder of
+    /// `public_key`, then prunes the ports of removed IP addresses.
+    ///
+    /// Returns the values of the removed and overwritten fields before the update.
+    pub(crate) fn transact(
+        &mut self,
+        public_key: &K::PublicKey,
+        modify: impl FnOnce(&mut Transaction<'_, S>),
+    ) -> Result<PreviousValues, EnrError> {
+        let enr = &mut *self.enr;
+        let mut tx = Transaction::new(
+            &mut enr.content,
+            enr.alias_table.as_deref(),
+            Key::from(public_key.enr_key()),
+        );
+        modify(&mut tx);
+        let previous = tx.finish()?;
+        self.prune();
+        Ok(previous)
+    }
+
+    /// Removes the ports of any IP address family whose IP address was removed by the update, if
+    /// the consistency policy of the record requires it.
+    pub(crate) fn prune(&mut self) {
+        let original = self
+            .backup
+            .as_ref()
+            .expect("the backup is kept until the update completes");
+        self.enr
+            .consistency_policy
+            .prune(&original.content, &mut self.enr.content);
+    }
+
+    /// Completes the update: checks the size and consistency of the modified record, sets the
+    /// public key of `key`, advances the sequence number by the record's
+    /// [`SeqPolicy`](crate::SeqPolicy) and signs the record.
+    ///
+    /// On error, the record is reverted to its state before the update.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "enr_update", skip_all, fields(seq = self.enr.seq))
+    )]
+    pub(crate) fn sign(mut self, key: &K) -> Result<(), EnrError> {
+        self.prepare(&key.public())?;
+        let signature = self.enr.compute_signature(key)?;
+        self.complete(signature)
+    }
+
+    /// Sets `public_key`, checks the size and consistency of the modified record and advances
+    /// the sequence number, leaving the record to be signed and passed to
+    /// [`UpdateGuard::complete`].
+    pub(crate) fn prepare(&mut self, public_key: &K::PublicKey) -> Result<(), EnrError> {
+        self.try_prepare(public_key)
+            .inspect_err(|cause| trace::update_failed(self.original().seq, cause))
+    }
+
+    fn try_prepare(&mut self, public_key: &K::PublicKey) -> Result<(), EnrError> {
+        if self.enr.signature_mode().stores_public_key() {
+            self.enr.content.insert(
+                Key::from(public_key.enr_key()),
+                storage::value(rlp::encode(&public_key.encode().as_ref()).freeze()),
+            );
+        }
+
+        if self.enr.size() > MAX_ENR_SIZE {
+            return Err(EnrError::ExceedsMaxSize);
+        }
+        self.enr.consistency_policy.check(&self.enr.content)?;
+        genesis::check_update(
+            self.original().content.get(GENESIS_KEY.as_bytes()),
+            &self.enr.content,
+        )?;
+
+        self.enr.seq = self.enr.seq_policy.next(self.enr.seq)?;
+        self.enr.node_id = S::node_id::<K>(public_key);
+        Ok(())
+    }
+
+    /// Completes a prepared update with the signature of the record's content, which must keep
+    /// the [`SignatureMode`](crate::SignatureMode) of the record.
+    pub(crate) fn complete(mut self, signature: Vec<u8>) -> Result<(), EnrError> {
+        if let Err(cause) = self.try_complete(signature) {
+            trace::update_failed(self.original().seq, &cause);
+            return Err(cause);
+        }
+        self.backup = None;
+        trace::updated(self.enr.seq, self.enr.size());
+        Ok(())
+    }
+
+    fn try_complete(&mut self, signature: Vec<u8>) -> Result<(), EnrError> {
+        self.enr.signature_mode().check(&signature)?;
+        self.enr.signature = signature;
+
+        // in case the signature size changes
+        if self.enr.size() > MAX_ENR_SIZE {
+            return Err(EnrError::ExceedsMaxSize);
+        }
+        Ok(())
+    }
+
+    /// The record before the update.
+    fn original(&self) -> &Enr<K, S> {
+        self.backup
+            .as_ref()
+            .expect("the backup is kept until the update completes")
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Deref for UpdateGuard<'_, K, S> {
+    type Target = Enr<K, S>;
+
+    fn deref(&self) -> &Enr<K, S> {
+        self.enr
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> DerefMut for UpdateGuard<'_, K, S> {
+    fn deref_mut(&mut self) -> &mut Enr<K, S> {
+        self.enr
+    }
+}
+
+impl<K: EnrKey, S: IdentityScheme> Drop for UpdateGuard<'_, K, S> {
+    fn drop(&mut self) {
+        if let Some(backup) = self.backup.take() {
+            *self.enr = backup;
+        }
+    }
+}
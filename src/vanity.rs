@@ -0,0 +1,242 @@
+//! Generation of keys whose [`NodeId`] has a chosen shape.
+//!
+//! Testnet topologies and adversarial tests often need nodes at specific positions in the Kademlia
+//! keyspace. The functions in this module grind through random keys until the derived node id
+//! matches a [`Target`]: either a fixed prefix, or a bucket relative to a base node id. The search
+//! is spread over several threads, using the [`rayon`] thread pool with the `parallel` feature.
+//!
+//! Each additional bit of the target halves the chance of a random key matching, so targets of
+//! more than roughly 24 bits are impractical.
+
+use crate::{EnrKey, NodeId};
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex,
+};
+
+/// The number of attempts of a worker between progress reports.
+const PROGRESS_INTERVAL: u64 = 1024;
+
+/// The shape of the node id to search for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// The first `bits` bits of the node id equal those of `prefix`.
+    Prefix {
+        /// The node id whose leading bits are matched.
+        prefix: NodeId,
+        /// The number of leading bits to match, at most 256.
+        bits: u32,
+    },
+    /// The log2 distance of the node id to `base`, as used for the buckets of discv5 routing
+    /// tables, equals `distance`.
+    Bucket {
+        /// The node id distances are measured from.
+        base: NodeId,
+        /// The log2 distance, from 1 to 256.
+        distance: u32,
+    },
+}
+
+impl Target {
+    /// Checks that some node id can match the target.
+    pub fn check(&self) -> Result<(), InvalidTarget> {
+        match self {
+            Self::Bucket { distance, .. } if !(1..=256).contains(distance) => {
+                Err(InvalidTarget::Distance(*distance))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether the node id matches the target.
+    #[must_use]
+    pub fn matches(&self, node_id: &NodeId) -> bool {
+        match self {
+            Self::Prefix { prefix, bits } => {
+                leading_zeros(&xor(&prefix.raw(), &node_id.raw())) >= (*bits).min(256)
+            }
+            Self::Bucket { base, distance } => {
+                log2_distance(&base.raw(), &node_id.raw()) == Some(*distance)
+            }
+        }
+    }
+}
+
+/// A [`Target`] no node id can match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidTarget {
+    /// The bucket distance is outside of 1 to 256.
+    Distance(u32),
+}
+
+impl fmt::Display for InvalidTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Distance(distance) => write!(f, "log2 distance {distance} is not in 1..=256"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidTarget {}
+
+/// Searches for a `k256` key whose node id matches the target, using `parallelism` threads.
+///
+/// Fails without searching if no node id can match the target.
+#[cfg(feature = "k256")]
+pub fn search(
+    target: &Target,
+    parallelism: usize,
+) -> Result<(k256::ecdsa::SigningKey, NodeId), InvalidTarget> {
+    search_with(
+        target,
+        parallelism,
+        || k256::ecdsa::SigningKey::random(&mut rand::thread_rng()),
+        |_| {},
+    )
+}
+
+/// Searches for a key produced by `generate` whose node id matches the target, using
+/// `parallelism` threads.
+///
+/// `progress` is called periodically from the searching threads with the total number of keys
+/// tried so far. Fails without searching if no node id can match the target.
+pub fn search_with<K: EnrKey>(
+    target: &Target,
+    parallelism: usize,
+    generate: impl Fn() -> K + Sync,
+    progress: impl Fn(u64) + Sync,
+) -> Result<(K, NodeId), InvalidTarget> {
+    target.check()?;
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let result = Mutex::new(None);
+
+    let worker = || {
+        let mut since_report = 0;
+        while !found.load(Ordering::Relaxed) {
+            let key = generate();
+            let node_id = NodeId::from(key.public());
+            since_report += 1;
+            if target.matches(&node_id) {
+                if !found.swap(true, Ordering::Relaxed) {
+                    *result.lock().expect("no worker panicked") = Some((key, node_id));
+                }
+                break;
+            }
+            if since_report == PROGRESS_INTERVAL {
+                progress(attempts.fetch_add(since_report, Ordering::Relaxed) + since_report);
+                since_report = 0;
+            }
+        }
+        attempts.fetch_add(since_report, Ordering::Relaxed);
+    };
+    run_workers(parallelism.max(1), &worker);
+
+    Ok(result
+        .into_inner()
+        .expect("no worker panicked")
+        .expect("workers only stop once a key is found"))
+}
+
+#[cfg(feature = "parallel")]
+fn run_workers(parallelism: usize, worker: &(impl Fn() + Sync)) {
+    rayon::scope(|scope| {
+        for _ in 0..parallelism {
+            scope.spawn(|_| worker());
+        }
+    });
+}
+
+#[cfg(not(feature = "parallel"))]
+fn run_workers(parallelism: usize, worker: &(impl Fn() + Sync)) {
+    std::thread::scope(|scope| {
+        for _ in 0..parallelism {
+            scope.spawn(worker);
+        }
+    });
+}
+
+fn xor(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0_u8; 32];
+    for (out, (a, b)) in out.iter_mut().zip(a.iter().zip(b)) {
+        *out = a ^ b;
+    }
+    out
+}
+
+fn leading_zeros(bytes: &[u8; 32]) -> u32 {
+    let mut zeros = 0;
+    for byte in bytes {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
+/// The log2 distance between two node ids, or `None` if they are equal.
+fn log2_distance(a: &[u8; 32], b: &[u8; 32]) -> Option<u32> {
+    let zeros = leading_zeros(&xor(a, b));
+    (zeros < 256).then(|| 256 - zeros)
+}
+
+#[cfg(test)]
+#[cfg(feature = "k256")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix() {
+        let prefix = NodeId::new(&[0xab; 32]);
+        let target = Target::Prefix { prefix, bits: 8 };
+        let (key, node_id) = search(&target, 2).unwrap();
+        assert_eq!(node_id.raw()[0], 0xab);
+        assert_eq!(NodeId::from(key.public()), node_id);
+    }
+
+    #[test]
+    fn test_bucket() {
+        let base = NodeId::random();
+        let target = Target::Bucket {
+            base,
+            distance: 252,
+        };
+        let (_, node_id) = search_with(
+            &target,
+            4,
+            || k256::ecdsa::SigningKey::random(&mut rand::thread_rng()),
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(log2_distance(&base.raw(), &node_id.raw()), Some(252));
+        assert!(target.matches(&node_id));
+    }
+
+    #[test]
+    fn test_unreachable_bucket() {
+        for distance in [0, 257] {
+            let target = Target::Bucket {
+                base: NodeId::random(),
+                distance,
+            };
+            assert_eq!(target.check(), Err(InvalidTarget::Distance(distance)));
+            assert_eq!(
+                search(&target, 1).unwrap_err(),
+                InvalidTarget::Distance(distance)
+            );
+        }
+    }
+
+    #[test]
+    fn test_log2_distance() {
+        let a = [0_u8; 32];
+        let mut b = [0_u8; 32];
+        assert_eq!(log2_distance(&a, &b), None);
+        b[31] = 1;
+        assert_eq!(log2_distance(&a, &b), Some(1));
+        b[0] = 0x80;
+        assert_eq!(log2_distance(&a, &b), Some(256));
+    }
+}
@@ -0,0 +1,196 @@
+//! A process-wide cache of successful signature verifications.
+//!
+//! Nodes repeatedly receive the same records from bootnodes and well-connected peers. With the
+//! `verification-cache` feature, [`Enr::verify`](crate::Enr::verify) and decoding consult an LRU
+//! cache of records whose signature has already been verified, skipping the expensive signature
+//! check for records seen before. Entries are keyed by a hash of the public key, the signature and
+//! the signed content, so a record only hits the cache if it is byte-for-byte identical to a
+//! previously verified one. Only successful verifications are cached.
+
+use crate::digest;
+use lru::LruCache;
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, OnceLock, PoisonError,
+    },
+};
+
+/// The number of entries held by the cache unless configured otherwise.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Counters describing the effectiveness of the verification cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of verifications answered by the cache.
+    pub hits: u64,
+    /// The number of verifications that had to check the signature.
+    pub misses: u64,
+    /// The number of entries currently cached.
+    pub len: usize,
+    /// The maximum number of entries. A capacity of zero disables the cache.
+    pub capacity: usize,
+}
+
+struct VerificationCache {
+    /// Hashes of verified records. `None` if the cache is disabled.
+    entries: Mutex<Option<LruCache<[u8; 32], ()>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+fn cache() -> &'static VerificationCache {
+    static CACHE: OnceLock<VerificationCache> = OnceLock::new();
+    CACHE.get_or_init(|| VerificationCache::new(DEFAULT_CAPACITY))
+}
+
+/// Sets the maximum number of cached verifications, evicting the least recently used entries if
+/// the cache shrinks. A capacity of zero disables the cache.
+pub fn set_capacity(capacity: usize) {
+    cache().set_capacity(capacity);
+}
+
+/// Removes all cached verifications and resets the hit and miss counters.
+pub fn clear() {
+    cache().clear();
+}
+
+/// The current statistics of the cache.
+#[must_use]
+pub fn stats() -> CacheStats {
+    cache().stats()
+}
+
+/// Returns the cached result for the given record if present, otherwise runs `verify` and caches
+/// a successful result.
+pub(crate) fn verify_cached(
+    public_key: &[u8],
+    signature: &[u8],
+    content: &[u8],
+    verify: impl FnOnce() -> bool,
+) -> bool {
+    cache().verify(public_key, signature, content, verify)
+}
+
+impl VerificationCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(NonZeroUsize::new(capacity).map(LruCache::new)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn set_capacity(&self, capacity: usize) {
+        let mut entries = self.lock_entries();
+        match (NonZeroUsize::new(capacity), entries.as_mut()) {
+            (Some(capacity), Some(lru)) => lru.resize(capacity),
+            (capacity, _) => *entries = capacity.map(LruCache::new),
+        }
+    }
+
+    fn clear(&self) {
+        if let Some(lru) = self.lock_entries().as_mut() {
+            lru.clear();
+        }
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> CacheStats {
+        let entries = self.lock_entries();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            len: entries.as_ref().map_or(0, LruCache::len),
+            capacity: entries.as_ref().map_or(0, |lru| lru.cap().get()),
+        }
+    }
+
+    fn verify(
+        &self,
+        public_key: &[u8],
+        signature: &[u8],
+        content: &[u8],
+        verify: impl FnOnce() -> bool,
+    ) -> bool {
+        let mut preimage =
+            Vec::with_capacity(public_key.len() + signature.len() + content.len() + 2);
+        // length prefixes keep the boundaries between the fields unambiguous
+        preimage.push(public_key.len() as u8);
+        preimage.extend_from_slice(public_key);
+        preimage.push(signature.len() as u8);
+        preimage.extend_from_slice(signature);
+        preimage.extend_from_slice(content);
+        let entry = digest(&preimage);
+
+        if let Some(lru) = self.lock_entries().as_mut() {
+            if lru.get(&entry).is_some() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        } else {
+            return verify();
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // the signature is checked without holding the lock
+        let verified = verify();
+        if verified {
+            if let Some(lru) = self.lock_entries().as_mut() {
+                lru.put(entry, ());
+            }
+        }
+        verified
+    }
+
+    fn lock_entries(&self) -> MutexGuard<'_, Option<LruCache<[u8; 32], ()>>> {
+        // the cache holds no invariants a panic could break
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let cache = VerificationCache::new(2);
+        let verify = |cache: &VerificationCache, signature: &[u8], valid: bool| {
+            let mut checked = false;
+            let verified = cache.verify(b"key", signature, b"content", || {
+                checked = true;
+                valid
+            });
+            (verified, checked)
+        };
+
+        assert_eq!(verify(&cache, b"sig", true), (true, true));
+        // the verification is cached
+        assert_eq!(verify(&cache, b"sig", true), (true, false));
+        // a different signature over the same content is checked, and failures are not cached
+        assert_eq!(verify(&cache, b"forged", false), (false, true));
+        assert_eq!(verify(&cache, b"forged", false), (false, true));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                len: 1,
+                capacity: 2,
+            }
+        );
+
+        cache.clear();
+        assert_eq!(cache.stats().len, 0);
+        assert_eq!(verify(&cache, b"sig", true), (true, true));
+
+        // a disabled cache checks every signature
+        cache.set_capacity(0);
+        assert_eq!(verify(&cache, b"sig", true), (true, true));
+        assert_eq!(verify(&cache, b"sig", true), (true, true));
+        assert_eq!(cache.stats().capacity, 0);
+    }
+}
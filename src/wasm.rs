@@ -0,0 +1,186 @@
+//! JavaScript bindings for decoding, verifying and building `secp256k1` records in the browser.
+//!
+//! With the `wasm` feature, the crate builds for `wasm32-unknown-unknown` and exposes a small
+//! [`wasm_bindgen`] surface. Randomness for signing is drawn from `crypto.getRandomValues`.
+//!
+//! ```text
+//! import { decode, build } from "enr";
+//!
+//! const enr = decode("enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04j...");
+//! console.log(enr.verify(), enr.nodeId, enr.ip4, enr.udp4);
+//!
+//! const built = build(secretKey, { ip: "127.0.0.1", udp: "30303" });
+//! console.log(built.toBase64());
+//! ```
+//!
+//! Field values passed to [`build`] are parsed like the fixed values of a
+//! [`RecordTemplate`](crate::RecordTemplate).
+
+use crate::{
+    check_spec_reserved_keys, k256::ecdsa::SigningKey, template::parse_value, Enr, V4Scheme,
+};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// A verified `secp256k1` record.
+#[wasm_bindgen(js_name = Enr)]
+pub struct JsEnr(Enr<SigningKey>);
+
+#[wasm_bindgen(js_class = Enr)]
+impl JsEnr {
+    /// Verifies the signature of the record.
+    pub fn verify(&self) -> bool {
+        self.0.verify()
+    }
+
+    /// The text form of the record, prefixed by `enr:`.
+    #[wasm_bindgen(js_name = toBase64)]
+    pub fn to_base64(&self) -> String {
+        self.0.to_base64()
+    }
+
+    /// The raw RLP encoded value of `key`, if set.
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.0.get_raw_rlp(key).map(<[u8]>::to_vec)
+    }
+
+    /// The keys of the record, in order.
+    pub fn keys(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|(key, _)| String::from_utf8_lossy(key).into_owned())
+            .collect()
+    }
+
+    /// The sequence number of the record.
+    #[wasm_bindgen(getter)]
+    pub fn seq(&self) -> u64 {
+        self.0.seq()
+    }
+
+    /// The hex encoded node id of the record.
+    #[wasm_bindgen(getter, js_name = nodeId)]
+    pub fn node_id(&self) -> String {
+        hex::encode(self.0.node_id().raw())
+    }
+
+    /// The hex encoded compressed public key of the record.
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> String {
+        hex::encode(self.0.public_key().to_sec1_bytes())
+    }
+
+    /// The identity scheme of the record.
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> Option<String> {
+        self.0.id()
+    }
+
+    /// The IPv4 address of the record.
+    #[wasm_bindgen(getter)]
+    pub fn ip4(&self) -> Option<String> {
+        self.0.ip4().map(|ip| ip.to_string())
+    }
+
+    /// The IPv6 address of the record.
+    #[wasm_bindgen(getter)]
+    pub fn ip6(&self) -> Option<String> {
+        self.0.ip6().map(|ip| ip.to_string())
+    }
+
+    /// The UDP port of the record.
+    #[wasm_bindgen(getter)]
+    pub fn udp4(&self) -> Option<u16> {
+        self.0.udp4()
+    }
+
+    /// The IPv6-specific UDP port of the record.
+    #[wasm_bindgen(getter)]
+    pub fn udp6(&self) -> Option<u16> {
+        self.0.udp6()
+    }
+
+    /// The TCP port of the record.
+    #[wasm_bindgen(getter)]
+    pub fn tcp4(&self) -> Option<u16> {
+        self.0.tcp4()
+    }
+
+    /// The IPv6-specific TCP port of the record.
+    #[wasm_bindgen(getter)]
+    pub fn tcp6(&self) -> Option<u16> {
+        self.0.tcp6()
+    }
+}
+
+/// Decodes and verifies the text form of a record.
+#[wasm_bindgen]
+pub fn decode(base64: &str) -> Result<JsEnr, JsError> {
+    decode_enr(base64).map(JsEnr).map_err(|e| JsError::new(&e))
+}
+
+/// Builds a record signed by the 32 byte `secret_key`, with the fields of the object `fields`.
+/// Each property of `fields` is a key of the record, with a string value.
+#[wasm_bindgen]
+pub fn build(secret_key: &[u8], fields: &Object) -> Result<JsEnr, JsError> {
+    let fields = Object::entries(fields)
+        .iter()
+        .map(|entry| {
+            let entry = Array::from(&entry);
+            match (entry.get(0).as_string(), entry.get(1).as_string()) {
+                (Some(key), Some(value)) => Ok((key, value)),
+                _ => Err(JsError::new("field values must be strings")),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    build_enr(secret_key, fields)
+        .map(JsEnr)
+        .map_err(|e| JsError::new(&e))
+}
+
+fn decode_enr(base64: &str) -> Result<Enr<SigningKey>, String> {
+    Ok(base64.parse()?)
+}
+
+fn build_enr(
+    secret_key: &[u8],
+    fields: impl IntoIterator<Item = (String, String)>,
+) -> Result<Enr<SigningKey>, String> {
+    let key =
+        SigningKey::from_slice(secret_key).map_err(|e| format!("invalid private key: {e}"))?;
+    let mut builder = Enr::builder();
+    for (field, value) in fields {
+        let value = parse_value(&value).map_err(|e| format!("invalid hex: {e}"))?;
+        check_spec_reserved_keys::<V4Scheme>(field.as_bytes(), &value)
+            .map_err(|e| format!("invalid value for `{field}`: {e}"))?;
+        builder.add_value_rlp(field, value);
+    }
+    builder
+        .build(&key)
+        .map_err(|e| format!("failed to build the record: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_and_decode() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let fields = [("ip", "127.0.0.1"), ("udp", "30303"), ("note", "hello")]
+            .map(|(key, value)| (key.to_string(), value.to_string()));
+        let enr = build_enr(&key.to_bytes(), fields).unwrap();
+
+        let decoded = JsEnr(decode_enr(&enr.to_base64()).unwrap());
+        assert!(decoded.verify());
+        assert_eq!(decoded.ip4().as_deref(), Some("127.0.0.1"));
+        assert_eq!(decoded.udp4(), Some(30303));
+        assert_eq!(decoded.keys(), ["id", "ip", "note", "secp256k1", "udp"]);
+        assert_eq!(decoded.node_id(), hex::encode(enr.node_id().raw()));
+
+        assert!(build_enr(&[0; 32], []).is_err());
+        let invalid_port = [("udp".to_string(), "70000".to_string())];
+        assert!(build_enr(&key.to_bytes(), invalid_port).is_err());
+        assert!(decode_enr("enr:invalid").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+#![cfg(all(feature = "macros", feature = "k256"))]
+
+use enr::{enr, k256::ecdsa::SigningKey, Enr, EnrError};
+use std::net::Ipv6Addr;
+
+#[test]
+fn test_enr_macro() {
+    let key = SigningKey::random(&mut rand::thread_rng());
+    let enr: Enr<SigningKey> = enr!(&key;
+        ip4 = "10.0.0.1",
+        ip6 = Ipv6Addr::LOCALHOST,
+        tcp4 = 30303,
+        udp6 = 9000,
+        seq = 5,
+        "custom" => b"value".to_vec(),
+    )
+    .unwrap();
+
+    let expected = Enr::builder()
+        .ip4("10.0.0.1".parse().unwrap())
+        .ip6(Ipv6Addr::LOCALHOST)
+        .tcp4(30303)
+        .udp6(9000)
+        .seq(5)
+        .add_value("custom", &b"value".to_vec())
+        .build(&key)
+        .unwrap();
+    assert!(enr.compare_content(&expected));
+    assert_eq!(enr.seq(), 5);
+
+    let empty: Enr<SigningKey> = enr!(&key).unwrap();
+    assert_eq!(empty.len(), 2);
+
+    let invalid: Result<Enr<SigningKey>, _> = enr!(&key; ip4 = "10.0.0", tcp4 = 30303);
+    assert_eq!(invalid, Err(EnrError::InvalidIpAddress("10.0.0".into())));
+}
+
+#[test]
+fn test_enr_macro_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/macros/ui/*.rs");
+}
@@ -0,0 +1,6 @@
+use enr::{enr, k256::ecdsa::SigningKey};
+
+fn main() {
+    let key = SigningKey::random(&mut rand::thread_rng());
+    let _ = enr!(&key; tcp = 30303);
+}